@@ -8,6 +8,7 @@
 //
 
 use std::ffi::OsString;
+use std::process::ExitCode;
 
 use bc_util::{
     interpreter::{ExecutionResult, Interpreter},
@@ -31,25 +32,30 @@ struct Args {
     files: Vec<OsString>,
 }
 
-fn print_output_or_error(result: ExecutionResult<String>) {
+/// Print the output of a program, and any error to stderr.
+/// Returns `false` if there was an error.
+fn print_output_or_error(result: ExecutionResult<String>) -> bool {
     match result {
         Ok(output) => {
             print!("{}", output);
+            true
         }
         Err(e) => {
             print!("{}", e.partial_output());
-            println!("{}", e);
+            eprintln!("{}", e);
+            false
         }
     }
 }
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     setlocale(LocaleCategory::LcAll, "");
     textdomain(PROJECT_NAME)?;
     bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
 
     let args = Args::parse();
     let mut interpreter = Interpreter::default();
+    let mut ok = true;
 
     if args.define_math_functions {
         let lib = parse_program(include_str!("bc_util/math_functions.bc"), None)
@@ -62,57 +68,70 @@ fn main() -> Result<()> {
     for file in args.files {
         match std::fs::read_to_string(&file) {
             Ok(s) => match parse_program(&s, file.to_str()) {
-                Ok(program) => print_output_or_error(interpreter.exec(program)),
-                Err(e) => println!("{}", e),
+                Ok(program) => ok &= print_output_or_error(interpreter.exec(program)),
+                Err(e) => {
+                    eprint!("{}", e);
+                    ok = false;
+                }
             },
-            Err(_) => {
-                eprintln!("Could not read file: {}", file.to_string_lossy());
-                return Ok(());
+            Err(e) => {
+                eprintln!("bc: {}: {}", file.to_string_lossy(), e);
+                return Ok(ExitCode::FAILURE);
             }
         };
         if interpreter.has_quit() {
-            return Ok(());
+            return Ok(exit_code(ok));
         }
     }
 
     let mut repl = DefaultEditor::new()?;
     let mut line_buffer = String::new();
     while !interpreter.has_quit() {
-        let line = if line_buffer.is_empty() {
-            repl.readline(">> ")
-        } else {
-            repl.readline(".. ")
-        };
-        match line {
+        match repl.readline("") {
             Ok(line) => {
                 line_buffer.push_str(&line);
                 line_buffer.push('\n');
                 match parse_program(&line_buffer, None) {
                     Ok(program) => {
-                        print_output_or_error(interpreter.exec(program));
+                        ok &= print_output_or_error(interpreter.exec(program));
                         line_buffer.clear();
                     }
                     Err(e) if !e.is_incomplete => {
-                        println!("{}", e);
+                        eprint!("{}", e);
+                        ok = false;
                         line_buffer.clear();
                     }
                     _ => {}
                 }
                 repl.add_history_entry(line)?;
             }
-            Err(ReadlineError::Eof) => {
-                println!("CTRL-D");
-                break;
-            }
+            Err(ReadlineError::Eof) => break,
             Err(ReadlineError::Interrupted) => {
-                println!("CTRL-C");
-                break;
+                // discard the statement being entered
+                line_buffer.clear();
             }
             Err(e) => {
-                eprintln!("Error: {:?}", e);
-                break;
+                eprintln!("bc: {}", e);
+                return Ok(ExitCode::FAILURE);
             }
         }
     }
-    Ok(())
+
+    // input ended in the middle of a statement
+    if !line_buffer.is_empty() {
+        if let Err(e) = parse_program(&line_buffer, Some("stdin")) {
+            eprint!("{}", e);
+            ok = false;
+        }
+    }
+
+    Ok(exit_code(ok))
+}
+
+fn exit_code(ok: bool) -> ExitCode {
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
@@ -126,6 +126,24 @@ fn should_print(expr: &ExprInstruction) -> bool {
     )
 }
 
+/// The longest output line, including the backslash that continues it.
+const LINE_LENGTH: usize = 70;
+
+/// Split a printed number across lines of `LINE_LENGTH` characters,
+/// ending each continued line with a backslash.
+fn split_long_number(number: &str) -> String {
+    let chars: Vec<char> = number.chars().collect();
+    let mut result = String::with_capacity(number.len() + number.len() / LINE_LENGTH * 2);
+    let mut lines = chars.chunks(LINE_LENGTH - 1).peekable();
+    while let Some(line) = lines.next() {
+        result.extend(line);
+        if lines.peek().is_some() {
+            result.push_str("\\\n");
+        }
+    }
+    result
+}
+
 fn get_or_extend(array: &mut Vec<Number>, index: usize) -> &mut Number {
     if index >= array.len() {
         array.resize_with(index + 1, Number::zero);
@@ -507,8 +525,12 @@ impl Interpreter {
                 let value = self.eval_expr(expr)?;
                 if should_print(expr) {
                     // this should never fail
-                    writeln!(&mut self.output, "{}", value.to_string(self.obase))
-                        .expect("error appending to string");
+                    writeln!(
+                        &mut self.output,
+                        "{}",
+                        split_long_number(&value.to_string(self.obase))
+                    )
+                    .expect("error appending to string");
                 }
             }
             StmtInstruction::DefineFunction { .. } => {
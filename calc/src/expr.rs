@@ -9,34 +9,35 @@
 
 extern crate plib;
 
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::regex::bre_to_regex;
 use plib::PROJECT_NAME;
 use regex::Regex;
+use std::process::ExitCode;
 
-#[derive(Clone, Debug, PartialEq)]
-enum Token {
-    LParen,
-    RParen,
-    OpMul,
-    OpDiv,
-    OpRem,
-    OpAdd,
-    OpSub,
-    OpEq,
-    OpGT,
-    OpLT,
-    OpGE,
-    OpLE,
-    OpNE,
-    OpAnd,
-    OpOr,
-    OpMatch,
-    Integer(i64),
-    Str(String),
+// exit status for an invalid expression
+const EXIT_INVALID: u8 = 2;
+
+// exit status for an error while evaluating the expression
+const EXIT_ERROR: u8 = 3;
+
+#[derive(Debug)]
+enum ExprError {
+    // the expression is not valid
+    Invalid(String),
+
+    // the expression is valid, but could not be evaluated
+    Eval(String),
+}
+
+impl ExprError {
+    fn syntax() -> ExprError {
+        ExprError::Invalid(gettext("syntax error"))
+    }
 }
 
 // comparison operators
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 enum CmpOp {
     EQ,
     NE,
@@ -46,341 +47,250 @@ enum CmpOp {
     LE,
 }
 
-// integer operations
-#[derive(Clone, Debug)]
-enum IntOp {
-    Add,
-    Sub,
-    Mul,
-    Div,
-    Rem,
-}
-
-// convert an lval to a string
-fn token_display(t: &Token) -> String {
-    match t {
-        Token::Integer(val) => val.to_string(),
-        Token::Str(val) => String::from(val),
-        _ => {
-            panic!("BUG: not an lval");
+impl CmpOp {
+    fn parse(s: &str) -> Option<CmpOp> {
+        match s {
+            "=" => Some(CmpOp::EQ),
+            "!=" => Some(CmpOp::NE),
+            ">" => Some(CmpOp::GT),
+            "<" => Some(CmpOp::LT),
+            ">=" => Some(CmpOp::GE),
+            "<=" => Some(CmpOp::LE),
+            _ => None,
         }
     }
-}
 
-// is token an lval?
-fn token_is_lval(t: &Token) -> bool {
-    match t {
-        Token::Integer(_) => true,
-        Token::Str(_) => true,
-        _ => false,
+    fn apply<T: PartialOrd>(self, lhs: T, rhs: T) -> bool {
+        match self {
+            CmpOp::EQ => lhs == rhs,
+            CmpOp::NE => lhs != rhs,
+            CmpOp::GT => lhs > rhs,
+            CmpOp::LT => lhs < rhs,
+            CmpOp::GE => lhs >= rhs,
+            CmpOp::LE => lhs <= rhs,
+        }
     }
 }
 
-// is token zero?
-fn token_is_zero(t: &Token) -> bool {
-    match t {
-        Token::Integer(val) => *val == 0,
-        Token::Str(s) => s.is_empty(),
-        _ => false,
+// an integer, as POSIX defines one for expr: an optional minus sign
+// followed by decimal digits
+fn parse_int(s: &str) -> Option<i64> {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
     }
-}
 
-// convert token to string
-fn token_to_string(t: &Token) -> Result<String, &'static str> {
-    match t {
-        Token::Integer(val) => Ok(val.to_string()),
-        Token::Str(val) => Ok(String::from(val)),
-        _ => Err("syntax error: not a string"),
-    }
+    s.parse().ok()
 }
 
-// convert token to integer
-fn token_to_int(t: &Token) -> Option<i64> {
-    match t {
-        Token::Integer(val) => Some(*val),
-        _ => None,
-    }
+// an integer argument of an arithmetic operator
+fn int_operand(s: &str) -> Result<i64, ExprError> {
+    parse_int(s).ok_or_else(|| ExprError::Invalid(gettext("non-integer argument")))
 }
 
-// convert token to integer, returning an error if not an integer
-fn token_to_int_req(t: &Token) -> Result<i64, &'static str> {
-    match token_to_int(t) {
-        Some(val) => Ok(val),
-        None => Err("syntax error: not an integer"),
-    }
+// is the value null or zero?
+fn is_null(s: &str) -> bool {
+    s.is_empty() || parse_int(s) == Some(0)
 }
 
-// parse a single token
-fn parse_token(s: &str) -> Token {
-    match s {
-        "(" => Token::LParen,
-        ")" => Token::RParen,
-        "*" => Token::OpMul,
-        "/" => Token::OpDiv,
-        "%" => Token::OpRem,
-        "+" => Token::OpAdd,
-        "-" => Token::OpSub,
-        "=" => Token::OpEq,
-        ">" => Token::OpGT,
-        "<" => Token::OpLT,
-        ">=" => Token::OpGE,
-        "<=" => Token::OpLE,
-        "!=" => Token::OpNE,
-        "&" => Token::OpAnd,
-        "|" => Token::OpOr,
-        ":" => Token::OpMatch,
-        _ => match s.parse::<i64>() {
-            Ok(n) => Token::Integer(n),
-            Err(_) => Token::Str(String::from(s)),
-        },
-    }
+fn bool_value(b: bool) -> String {
+    String::from(if b { "1" } else { "0" })
 }
 
-// tokenize the command line arguments, all in a single pass
-fn tokenize() -> Vec<Token> {
-    // collect program's command line args
-    let mut args: Vec<String> = std::env::args().collect();
-    args.remove(0); // remove 1st value, the unnecessary program name
-
-    // parse each arg into a Token
-    let mut tokens = Vec::new();
-    for arg in &args {
-        tokens.push(parse_token(arg));
+// compare as integers if both sides are integers, otherwise as strings
+fn cmpop(lhs: &str, rhs: &str, op: CmpOp) -> String {
+    match (parse_int(lhs), parse_int(rhs)) {
+        (Some(l), Some(r)) => bool_value(op.apply(l, r)),
+        _ => bool_value(op.apply(lhs, rhs)),
     }
-
-    tokens
 }
 
-// compare two integers
-fn cmpint(lhs: i64, rhs: i64, op: CmpOp) -> Token {
-    let result: bool = match op {
-        CmpOp::EQ => lhs == rhs,
-        CmpOp::NE => lhs != rhs,
-        CmpOp::GT => lhs > rhs,
-        CmpOp::LT => lhs < rhs,
-        CmpOp::GE => lhs >= rhs,
-        CmpOp::LE => lhs <= rhs,
-    };
+// integer arithmetic, checked for overflow and division by zero
+fn intop(lhs: &str, rhs: &str, op: &str) -> Result<String, ExprError> {
+    let i1 = int_operand(lhs)?;
+    let i2 = int_operand(rhs)?;
 
-    Token::Integer(result as i64)
-}
+    if (op == "/" || op == "%") && i2 == 0 {
+        return Err(ExprError::Eval(gettext("division by zero")));
+    }
 
-// compare two strings
-fn cmpstr(lhs: &Token, rhs: &Token, op: CmpOp) -> Result<Token, &'static str> {
-    let lhs = token_to_string(lhs)?;
-    let rhs = token_to_string(rhs)?;
-
-    let result: bool = match op {
-        CmpOp::EQ => lhs == rhs,
-        CmpOp::NE => lhs != rhs,
-        CmpOp::GT => lhs > rhs,
-        CmpOp::LT => lhs < rhs,
-        CmpOp::GE => lhs >= rhs,
-        CmpOp::LE => lhs <= rhs,
+    let result = match op {
+        "+" => i1.checked_add(i2),
+        "-" => i1.checked_sub(i2),
+        "*" => i1.checked_mul(i2),
+        "/" => i1.checked_div(i2),
+        "%" => i1.checked_rem(i2),
+        _ => unreachable!(),
     };
 
-    Ok(Token::Integer(result as i64))
+    match result {
+        Some(n) => Ok(n.to_string()),
+        None => Err(ExprError::Eval(gettext("integer overflow"))),
+    }
 }
 
-// perform a comparison operation
-fn cmpop(lhs: &Token, rhs: &Token, op: CmpOp) -> Result<Token, &'static str> {
-    let lhs_int = token_to_int(lhs);
-    let rhs_int = token_to_int(rhs);
-
-    // if both are integers, perform int comparison
-    if lhs_int.is_some() && rhs_int.is_some() {
-        Ok(cmpint(lhs_int.unwrap(), rhs_int.unwrap(), op))
-
-    // otherwise, convert int to string, and perform string compare
-    } else if let Some(val) = lhs_int {
-        let tmp = Token::Str(val.to_string());
-        cmpstr(&tmp, rhs, op)
-    } else if let Some(val) = rhs_int {
-        let tmp = Token::Str(val.to_string());
-        cmpstr(lhs, &tmp, op)
+// match a string against a basic regular expression anchored at the start
+// of the string.  With a subexpression, the result is the text matched by
+// the first one; otherwise it is the number of characters matched.
+fn matchop(lhs: &str, rhs: &str) -> Result<String, ExprError> {
+    let pattern = format!("^(?s:{})", bre_to_regex(rhs));
+    let re = Regex::new(&pattern).map_err(|e| ExprError::Eval(e.to_string()))?;
+
+    let caps = re.captures(lhs);
+    if re.captures_len() > 1 {
+        Ok(caps
+            .and_then(|caps| caps.get(1))
+            .map(|m| String::from(m.as_str()))
+            .unwrap_or_default())
     } else {
-        cmpstr(lhs, rhs, op)
+        let len = caps.map(|caps| caps[0].chars().count()).unwrap_or(0);
+        Ok(len.to_string())
     }
 }
 
-// perform an integer math operation
-fn intop(lhs: &Token, rhs: &Token, op: IntOp) -> Result<Token, &'static str> {
-    let i1 = token_to_int_req(lhs)?;
-    let i2 = token_to_int_req(rhs)?;
-
-    match op {
-        IntOp::Add => Ok(Token::Integer(i1 + i2)),
-        IntOp::Sub => Ok(Token::Integer(i1 - i2)),
-        IntOp::Mul => Ok(Token::Integer(i1 * i2)),
-        IntOp::Div => Ok(Token::Integer(i1 / i2)),
-        IntOp::Rem => Ok(Token::Integer(i1 % i2)),
-    }
+// recursive descent parser and evaluator over the command line arguments,
+// from the lowest precedence operator to the highest.  All binary
+// operators are left-associative.
+struct Parser<'a> {
+    args: &'a [String],
+    pos: usize,
 }
 
-// logical and/or operation
-fn logop(lhs: &Token, rhs: &Token, is_and: bool) -> Token {
-    let lhs_zero = token_is_zero(lhs);
-    let rhs_zero = token_is_zero(rhs);
-
-    if is_and {
-        if !lhs_zero && !rhs_zero {
-            lhs.clone()
-        } else {
-            Token::Integer(0)
-        }
-    } else {
-        if !lhs_zero {
-            lhs.clone()
-        } else if !rhs_zero {
-            rhs.clone()
-        } else {
-            Token::Integer(0)
-        }
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.args.get(self.pos).map(|s| s.as_str())
     }
-}
 
-// regex match operation
-fn matchop(lhs: &Token, rhs: &Token) -> Result<Token, &'static str> {
-    let lhs = token_to_string(lhs)?;
-    let rhs = token_to_string(rhs)?;
+    fn next_if(&mut self, ops: &[&str]) -> Option<&'a str> {
+        let op = self.peek().filter(|op| ops.contains(op))?;
+        self.pos += 1;
+        Some(op)
+    }
 
-    let re = match Regex::new(&rhs) {
-        Ok(re_res) => re_res,
-        Err(_) => {
-            return Err("invalid regex");
+    // expr1 | expr2
+    fn parse_or(&mut self) -> Result<String, ExprError> {
+        let mut lhs = self.parse_and()?;
+        while self.next_if(&["|"]).is_some() {
+            let rhs = self.parse_and()?;
+            if is_null(&lhs) {
+                lhs = if is_null(&rhs) {
+                    String::from("0")
+                } else {
+                    rhs
+                };
+            }
         }
-    };
-
-    match re.captures(&lhs) {
-        // no regex match: zero
-        None => Ok(Token::Integer(0)),
-
-        // regex matched
-        Some(caps) => {
-            let cap1 = caps.get(1);
-
-            // if regex subexpression #1 matched, return as string
-            if let Some(mtch) = cap1 {
-                Ok(Token::Str(String::from(mtch.as_str())))
+        Ok(lhs)
+    }
 
-            // otherwise, return length of overall match as int
-            } else {
-                Ok(Token::Integer(caps.get(0).unwrap().len() as i64))
+    // expr1 & expr2
+    fn parse_and(&mut self) -> Result<String, ExprError> {
+        let mut lhs = self.parse_cmp()?;
+        while self.next_if(&["&"]).is_some() {
+            let rhs = self.parse_cmp()?;
+            if is_null(&lhs) || is_null(&rhs) {
+                lhs = String::from("0");
             }
         }
+        Ok(lhs)
     }
-}
 
-// find closing right paren
-fn find_matching_paren(tokens: &[Token]) -> Option<usize> {
-    let mut depth = 0;
-    for (i, token) in tokens.iter().enumerate() {
-        match token {
-            Token::LParen => depth += 1,
-            Token::RParen => {
-                if depth == 0 {
-                    return None;
-                }
-                depth -= 1;
-                if depth == 0 {
-                    return Some(i);
-                }
-            }
-            _ => {}
+    // expr1 = expr2, and the other comparisons
+    fn parse_cmp(&mut self) -> Result<String, ExprError> {
+        let mut lhs = self.parse_add()?;
+        while let Some(op) = self.next_if(&["=", "!=", ">", "<", ">=", "<="]) {
+            let rhs = self.parse_add()?;
+            lhs = cmpop(&lhs, &rhs, CmpOp::parse(op).unwrap());
         }
+        Ok(lhs)
     }
 
-    None
-}
+    // expr1 + expr2, expr1 - expr2
+    fn parse_add(&mut self) -> Result<String, ExprError> {
+        let mut lhs = self.parse_mul()?;
+        while let Some(op) = self.next_if(&["+", "-"]) {
+            let rhs = self.parse_mul()?;
+            lhs = intop(&lhs, &rhs, op)?;
+        }
+        Ok(lhs)
+    }
 
-// evaluate an expression
-fn eval_expression(tokens: &[Token]) -> Result<Token, &'static str> {
-    let mut tokens = tokens.to_vec();
-
-    // continually consume tokens until only one remains
-    while tokens.len() >= 3 {
-        // handle nested expressions: left hand side
-        if tokens[0] == Token::LParen {
-            if let Some(i) = find_matching_paren(&tokens) {
-                let subexpr = &tokens[1..i];
-                let result = eval_expression(subexpr)?;
-                tokens.splice(0..=i, vec![result]);
-                continue;
-            } else {
-                return Err("syntax error EP0: unmatched left paren");
-            }
+    // expr1 * expr2, expr1 / expr2, expr1 % expr2
+    fn parse_mul(&mut self) -> Result<String, ExprError> {
+        let mut lhs = self.parse_match()?;
+        while let Some(op) = self.next_if(&["*", "/", "%"]) {
+            let rhs = self.parse_match()?;
+            lhs = intop(&lhs, &rhs, op)?;
         }
-        // handle nested expressions: right hand side
-        if tokens[2] == Token::LParen {
-            if let Some(i) = find_matching_paren(&tokens[2..]) {
-                let subexpr = &tokens[3..i + 2];
-                let result = eval_expression(subexpr)?;
-                tokens.splice(2..=i + 2, vec![result]);
-                continue;
-            } else {
-                return Err("syntax error EP1: unmatched left paren");
-            }
+        Ok(lhs)
+    }
+
+    // expr1 : expr2
+    fn parse_match(&mut self) -> Result<String, ExprError> {
+        let mut lhs = self.parse_primary()?;
+        while self.next_if(&[":"]).is_some() {
+            let rhs = self.parse_primary()?;
+            lhs = matchop(&lhs, &rhs)?;
         }
+        Ok(lhs)
+    }
+
+    // ( expr ), or a string operand
+    fn parse_primary(&mut self) -> Result<String, ExprError> {
+        let arg = self.peek().ok_or_else(ExprError::syntax)?;
+        self.pos += 1;
 
-        // extract our left hand side, operator, and right hand side
-        let lhs = &tokens[0];
-        let operator = &tokens[1];
-        let rhs = &tokens[2];
-
-        // dispatch to the appropriate operation
-        let result = match operator {
-            Token::OpAdd => intop(lhs, rhs, IntOp::Add)?,
-            Token::OpSub => intop(lhs, rhs, IntOp::Sub)?,
-            Token::OpMul => intop(lhs, rhs, IntOp::Mul)?,
-            Token::OpDiv => intop(lhs, rhs, IntOp::Div)?,
-            Token::OpRem => intop(lhs, rhs, IntOp::Rem)?,
-
-            Token::OpEq => cmpop(lhs, rhs, CmpOp::EQ)?,
-            Token::OpNE => cmpop(lhs, rhs, CmpOp::NE)?,
-            Token::OpGT => cmpop(lhs, rhs, CmpOp::GT)?,
-            Token::OpLT => cmpop(lhs, rhs, CmpOp::LT)?,
-            Token::OpGE => cmpop(lhs, rhs, CmpOp::GE)?,
-            Token::OpLE => cmpop(lhs, rhs, CmpOp::LE)?,
-
-            Token::OpAnd => logop(lhs, rhs, true),
-            Token::OpOr => logop(lhs, rhs, false),
-
-            Token::OpMatch => matchop(lhs, rhs)?,
-
-            Token::LParen | Token::RParen | Token::Integer(_) | Token::Str(_) => {
-                return Err("syntax error: wanted operator");
+        if arg == "(" && self.peek().is_some() {
+            let value = self.parse_or()?;
+            if self.next_if(&[")"]).is_none() {
+                return Err(ExprError::syntax());
             }
-        };
+            return Ok(value);
+        }
 
-        // replace the lhs, operator, and rhs with the result
-        tokens.splice(0..=2, vec![result]);
+        Ok(String::from(arg))
     }
+}
 
-    // final result should be a single token
-    if tokens.len() == 1 {
-        let lhs = &tokens[0];
-        if token_is_lval(lhs) {
-            Ok(lhs.clone())
-        } else {
-            Err("syntax error: E1")
-        }
-    } else {
-        Err("syntax error: E2")
+// evaluate an expression given as a list of arguments
+fn eval_expression(args: &[String]) -> Result<String, ExprError> {
+    let mut parser = Parser { args, pos: 0 };
+    let value = parser.parse_or()?;
+    if parser.pos != args.len() {
+        return Err(ExprError::syntax());
     }
+
+    Ok(value)
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> ExitCode {
     // initialize translations
     setlocale(LocaleCategory::LcAll, "");
-    textdomain(PROJECT_NAME)?;
-    bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
-
-    // tokenize and evaluate the expression
-    let arg_tokens = tokenize();
-    let final_val = eval_expression(&arg_tokens)?;
+    textdomain(PROJECT_NAME).unwrap();
+    bind_textdomain_codeset(PROJECT_NAME, "UTF-8").unwrap();
 
-    // display the result
-    println!("{}", token_display(&final_val));
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("expr: {}", gettext("missing operand"));
+        return ExitCode::from(EXIT_INVALID);
+    }
 
-    Ok(())
+    match eval_expression(&args) {
+        Ok(value) => {
+            println!("{}", value);
+            if is_null(&value) {
+                ExitCode::from(1)
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Err(ExprError::Invalid(msg)) => {
+            eprintln!("expr: {}", msg);
+            ExitCode::from(EXIT_INVALID)
+        }
+        Err(ExprError::Eval(msg)) => {
+            eprintln!("expr: {}", msg);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
 }
@@ -10,6 +10,10 @@
 use plib::{run_test, TestPlan};
 
 fn expr_test(args: &[&str], expected_output: &str) {
+    expr_status_test(args, expected_output, 0);
+}
+
+fn expr_status_test(args: &[&str], expected_output: &str, expected_exit_code: i32) {
     let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
 
     run_test(TestPlan {
@@ -18,7 +22,22 @@ fn expr_test(args: &[&str], expected_output: &str) {
         stdin_data: String::new(),
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
-        expected_exit_code: 0,
+        expected_exit_code,
+        ..Default::default()
+    });
+}
+
+fn expr_error_test(args: &[&str], expected_error: &str, expected_exit_code: i32) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("expr"),
+        args: str_args,
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::from(expected_error),
+        expected_exit_code,
+        ..Default::default()
     });
 }
 
@@ -30,6 +49,7 @@ fn test_bc(program: &str, expected_output: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -41,6 +61,7 @@ fn test_bc_with_math_library(program: &str, expected_output: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -64,10 +85,12 @@ macro_rules! test_bc_l {
 
 #[test]
 fn test_expr_logops() {
-    expr_test(&["4", "|", "5", "+", "1"], "5\n");
+    expr_test(&["4", "|", "5", "+", "1"], "4\n");
     expr_test(&["0", "|", "5", "+", "1"], "6\n");
-    expr_test(&["4", "&", "5", "+", "1"], "5\n");
-    expr_test(&["4", "&", "0", "+", "1"], "1\n");
+    expr_status_test(&["", "|", "0"], "0\n", 1);
+    expr_test(&["4", "&", "5", "+", "1"], "4\n");
+    expr_test(&["4", "&", "0", "+", "1"], "4\n");
+    expr_status_test(&["4", "&", "0"], "0\n", 1);
     expr_test(&["0", "%", "5", "+", "1"], "1\n");
 }
 
@@ -78,26 +101,63 @@ fn test_expr_intops() {
     expr_test(&["4", "*", "4", "+", "1"], "17\n");
     expr_test(&["4", "/", "4", "+", "1"], "2\n");
     expr_test(&["4", "%", "4", "+", "1"], "1\n");
+    expr_test(&["10", "-", "2", "-", "3"], "5\n");
+    expr_test(&["-7", "/", "2"], "-3\n");
+}
+
+#[test]
+fn test_expr_precedence() {
+    expr_test(&["2", "+", "3", "*", "4"], "14\n");
+    expr_test(&["(", "2", "+", "3", ")", "*", "4"], "20\n");
+    expr_test(&["1", "|", "0", "&", "0"], "1\n");
+    expr_test(&["3", "=", "1", "+", "2"], "1\n");
+    expr_test(&["abc", ":", "a.", "+", "1"], "3\n");
 }
 
 #[test]
 fn test_expr_cmpint() {
-    expr_test(&["4", "<", "5", "+", "1"], "2\n");
-    expr_test(&["4", ">", "5", "+", "1"], "1\n");
-    expr_test(&["4", "<=", "5", "+", "1"], "2\n");
-    expr_test(&["4", ">=", "5", "+", "1"], "1\n");
-    expr_test(&["4", "=", "5", "+", "1"], "1\n");
-    expr_test(&["4", "!=", "5", "+", "1"], "2\n");
+    expr_test(&["4", "<", "5", "+", "1"], "1\n");
+    expr_status_test(&["4", ">", "5", "+", "1"], "0\n", 1);
+    expr_test(&["4", "<=", "5", "+", "1"], "1\n");
+    expr_status_test(&["4", ">=", "5", "+", "1"], "0\n", 1);
+    expr_status_test(&["4", "=", "5", "+", "1"], "0\n", 1);
+    expr_test(&["4", "!=", "5", "+", "1"], "1\n");
+    expr_test(&["10", ">", "9"], "1\n");
 }
 
 #[test]
 fn test_expr_cmpstr() {
-    expr_test(&["aaa", "<", "bbb", "+", "1"], "2\n");
-    expr_test(&["aaa", ">", "bbb", "+", "1"], "1\n");
-    expr_test(&["aaa", "<=", "bbb", "+", "1"], "2\n");
-    expr_test(&["aaa", ">=", "bbb", "+", "1"], "1\n");
-    expr_test(&["aaa", "=", "bbb", "+", "1"], "1\n");
-    expr_test(&["aaa", "!=", "bbb", "+", "1"], "2\n");
+    expr_test(&["aaa", "<", "bbb"], "1\n");
+    expr_status_test(&["aaa", ">", "bbb"], "0\n", 1);
+    expr_test(&["aaa", "<=", "bbb"], "1\n");
+    expr_status_test(&["aaa", ">=", "bbb"], "0\n", 1);
+    expr_status_test(&["aaa", "=", "bbb"], "0\n", 1);
+    expr_test(&["aaa", "!=", "bbb"], "1\n");
+    expr_status_test(&["10", ">", "9a"], "0\n", 1);
+}
+
+#[test]
+fn test_expr_match() {
+    expr_test(&["abcdef", ":", "abc"], "3\n");
+    expr_status_test(&["abcdef", ":", "bc"], "0\n", 1);
+    expr_test(&["abcdef", ":", ".*"], "6\n");
+    expr_test(&["abcdef", ":", r"a\(b.\)"], "bc\n");
+    expr_status_test(&["abcdef", ":", r"x\(b.\)"], "\n", 1);
+    expr_test(&["a.c", ":", r"a\.c"], "3\n");
+}
+
+#[test]
+fn test_expr_errors() {
+    expr_error_test(&["1", "+"], "expr: syntax error\n", 2);
+    expr_error_test(&["(", "1"], "expr: syntax error\n", 2);
+    expr_error_test(&["1", "2"], "expr: syntax error\n", 2);
+    expr_error_test(&["a", "+", "1"], "expr: non-integer argument\n", 2);
+    expr_error_test(&["1", "/", "0"], "expr: division by zero\n", 3);
+    expr_error_test(
+        &["9223372036854775807", "+", "1"],
+        "expr: integer overflow\n",
+        3,
+    );
 }
 
 #[test]
@@ -195,6 +255,29 @@ fn test_bc_length() {
     test_bc!(length)
 }
 
+#[test]
+fn test_bc_long_numbers_are_split() {
+    test_bc!(long_numbers_are_split)
+}
+
+#[test]
+fn test_bc_end_of_input_without_quit() {
+    test_bc("1 + 2\nscale = 2\n1 / 3\n", "3\n0.33\n");
+}
+
+#[test]
+fn test_bc_runtime_error() {
+    run_test(TestPlan {
+        cmd: String::from("bc"),
+        args: vec![],
+        stdin_data: String::from("1 / 0\n2\n"),
+        expected_out: String::from("2\n"),
+        expected_err: String::from("runtime error (line 1): division by zero\n"),
+        expected_exit_code: 1,
+        ..Default::default()
+    });
+}
+
 #[test]
 fn test_bc_mod() {
     test_bc!(mod)
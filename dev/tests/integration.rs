@@ -17,6 +17,7 @@ fn ar_compare_test(
         expected_out: stdout.to_string(),
         expected_err: stderr.to_string(),
         expected_exit_code: 0,
+        ..Default::default()
     });
     let result = fs::read(result_file).expect("could not open result file");
     if previous_contents.is_empty() {
@@ -45,6 +46,7 @@ fn ar_compare_approx_test(
         expected_out: stdout.to_string(),
         expected_err: stderr.to_string(),
         expected_exit_code: 0,
+        ..Default::default()
     });
 
     let result = fs::read(result_file).expect("could not open result file");
@@ -73,6 +75,7 @@ fn ar_print_test(args: &[&str], stdout_bytes: &[u8]) {
             expected_out: "".to_string(),
             expected_err: "".to_string(),
             expected_exit_code: 0,
+            ..Default::default()
         },
         |_, output| {
             assert!(output.status.success());
@@ -89,6 +92,7 @@ fn strip_file(path: &str, previous_contents: &[u8]) -> Vec<u8> {
         expected_out: "".to_string(),
         expected_err: "".to_string(),
         expected_exit_code: 0,
+        ..Default::default()
     });
     let result = fs::read(path).expect("could not open result file");
     fs::write(path, previous_contents).expect("could not reset result file");
@@ -103,6 +107,7 @@ fn strings_test(args: &[&str], stdout: &str) {
         expected_out: stdout.to_string(),
         expected_err: "".to_string(),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -428,6 +433,7 @@ fn test_ar_list() {
         expected_out: "lib1.o\nlib4.o\nlib5.o\n".to_string(),
         expected_err: "".to_string(),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -444,6 +450,7 @@ fn test_ar_list_some() {
         expected_out: "lib4.o\n".to_string(),
         expected_err: "".to_string(),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
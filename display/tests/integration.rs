@@ -19,6 +19,7 @@ fn echo_test(args: &[&str], expected_output: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -63,6 +63,7 @@ fn cmp_main(args: &Args) -> io::Result<u8> {
 
     let mut lines: u64 = 1;
     let mut bytes: u64 = 0;
+    let mut differ = false;
 
     loop {
         let c1 = getc(&mut reader1)?;
@@ -74,10 +75,12 @@ fn cmp_main(args: &Args) -> io::Result<u8> {
             (Some(c1), Some(c2)) => {
                 if c1 != c2 {
                     if args.silent {
-                        // Don't print anything
+                        return Ok(1);
                     } else if args.verbose {
+                        // `-l` lists every differing byte, so keep going;
                         // `{:o}` for the required octal representation output
                         println!("{} {:o} {:o}", &bytes, c1, c2);
+                        differ = true;
                     } else {
                         println!(
                             "{} {} differ: char {}, line {}",
@@ -86,24 +89,27 @@ fn cmp_main(args: &Args) -> io::Result<u8> {
                             bytes,
                             lines
                         );
+                        return Ok(1);
                     }
-                    return Ok(1);
                 }
             }
             (None, None) => break,
 
             // (Some, EOF) or (EOF, Some)
             (c1, _) => {
-                eprintln!(
-                    "cmp: EOF on {}",
-                    if c1.is_none() {
-                        &args.file1
-                    } else {
-                        &args.file2
-                    }
-                    .as_os_str()
-                    .to_string_lossy()
-                );
+                // -s writes nothing, not even the EOF diagnostic
+                if !args.silent {
+                    eprintln!(
+                        "cmp: EOF on {}",
+                        if c1.is_none() {
+                            &args.file1
+                        } else {
+                            &args.file2
+                        }
+                        .as_os_str()
+                        .to_string_lossy()
+                    );
+                }
                 return Ok(1);
             }
         }
@@ -113,7 +119,7 @@ fn cmp_main(args: &Args) -> io::Result<u8> {
         }
     }
 
-    Ok(0)
+    Ok(if differ { 1 } else { 0 })
 }
 
 fn main() -> ExitCode {
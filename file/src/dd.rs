@@ -7,12 +7,17 @@
 // SPDX-License-Identifier: MIT
 //
 
+extern crate libc;
 extern crate plib;
 
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
 use plib::PROJECT_NAME;
 use std::fs;
-use std::io::{self, Read, Write};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem::ManuallyDrop;
+use std::os::unix::io::FromRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
 const DEF_BLOCK_SIZE: usize = 512;
 
@@ -73,6 +78,13 @@ const CONV_ASCII_EBCDIC: [u8; 256] = [
     0xdd, 0xde, 0xdf, 0xea, 0xeb, 0xec, 0xed, 0xee, 0xef, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff,
 ];
 
+// set by the SIGUSR1 handler; the copy loop prints statistics when it sees it
+static INFO_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn request_info(_sig: libc::c_int) {
+    INFO_REQUESTED.store(true, Ordering::Relaxed);
+}
+
 #[derive(Debug)]
 enum AsciiConv {
     Ascii,
@@ -80,17 +92,26 @@ enum AsciiConv {
     IBM,
 }
 
+#[derive(Debug, PartialEq)]
+enum StatusLevel {
+    Default,
+    None,
+    NoXfer,
+    Progress,
+}
+
 #[derive(Debug)]
 struct Config {
     ifile: String,
     ofile: String,
-    bs: usize,
+    bs: Option<usize>,
     ibs: usize,
     obs: usize,
     cbs: usize,
-    seek: usize,
-    skip: usize,
-    count: usize,
+    seek: u64,
+    skip: u64,
+    count: Option<u64>,
+    status: StatusLevel,
 
     ascii: Option<AsciiConv>,
     block: Option<bool>,
@@ -107,13 +128,14 @@ impl Config {
         Config {
             ifile: String::from("-"),
             ofile: String::from("-"),
-            bs: DEF_BLOCK_SIZE,
+            bs: None,
             ibs: DEF_BLOCK_SIZE,
             obs: DEF_BLOCK_SIZE,
             cbs: 0,
             seek: 0,
             skip: 0,
-            count: 0,
+            count: None,
+            status: StatusLevel::Default,
             ascii: None,
             block: None,
             lcase: false,
@@ -124,6 +146,17 @@ impl Config {
             sync: false,
         }
     }
+
+    // Per POSIX, with bs= and no conversions other than sync, noerror
+    // and notrunc, each input block is written as one output block.
+    fn direct_blocks(&self) -> bool {
+        self.bs.is_some()
+            && self.ascii.is_none()
+            && self.block.is_none()
+            && !self.lcase
+            && !self.ucase
+            && !self.swab
+    }
 }
 
 fn parse_conv_list(config: &mut Config, s: &str) -> Result<(), Box<dyn std::error::Error>> {
@@ -146,13 +179,40 @@ fn parse_conv_list(config: &mut Config, s: &str) -> Result<(), Box<dyn std::erro
             }
         }
     }
+
+    if config.lcase && config.ucase {
+        eprintln!(
+            "{}",
+            gettext("conv=lcase and conv=ucase are mutually exclusive")
+        );
+        return Err("invalid conv option".into());
+    }
+
     Ok(())
 }
 
-fn parse_block_size(s: &str) -> Result<usize, Box<dyn std::error::Error>> {
+fn parse_status(config: &mut Config, s: &str) -> Result<(), Box<dyn std::error::Error>> {
+    config.status = match s {
+        "none" => StatusLevel::None,
+        "noxfer" => StatusLevel::NoXfer,
+        "progress" => StatusLevel::Progress,
+        _ => {
+            eprintln!("{}: {}", gettext("invalid status level"), s);
+            return Err("invalid status level".into());
+        }
+    };
+    Ok(())
+}
+
+// Parse one factor of a size operand: a decimal number with an optional
+// multiplier suffix.
+fn parse_factor(s: &str) -> Result<u64, Box<dyn std::error::Error>> {
     let mut s = s.to_string();
     let mut scale = 1;
-    let suffix = s.pop().unwrap();
+    let suffix = match s.pop() {
+        Some(c) => c,
+        None => return Err(gettext("missing number").into()),
+    };
     if suffix.is_alphabetic() {
         match suffix {
             'c' => scale = 1,
@@ -169,8 +229,30 @@ fn parse_block_size(s: &str) -> Result<usize, Box<dyn std::error::Error>> {
     } else {
         s.push(suffix);
     }
-    let size = s.parse::<usize>()?;
-    Ok(size * scale)
+    let size = s.parse::<u64>()?;
+    size.checked_mul(scale)
+        .ok_or_else(|| gettext("number too large").into())
+}
+
+// Sizes and counts may carry a multiplier suffix, and POSIX allows
+// several of them to be multiplied together with 'x', as in 2x512.
+fn parse_number(s: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    let mut total: u64 = 1;
+    for factor in s.split('x') {
+        total = total
+            .checked_mul(parse_factor(factor)?)
+            .ok_or_else(|| gettext("number too large"))?;
+    }
+    Ok(total)
+}
+
+fn parse_block_size(s: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    let size = usize::try_from(parse_number(s)?)?;
+    if size == 0 {
+        eprintln!("{}: {}", gettext("invalid block size"), s);
+        return Err("invalid block size".into());
+    }
+    Ok(size)
 }
 
 fn parse_cmdline(args: &[String]) -> Result<Config, Box<dyn std::error::Error>> {
@@ -195,109 +277,465 @@ fn parse_cmdline(args: &[String]) -> Result<Config, Box<dyn std::error::Error>>
             "of" => config.ofile = oparg,
             "ibs" => config.ibs = parse_block_size(&oparg)?,
             "obs" => config.obs = parse_block_size(&oparg)?,
-            "bs" => {
-                config.bs = parse_block_size(&oparg)?;
-                config.ibs = config.bs;
-                config.obs = config.bs;
-            }
+            "bs" => config.bs = Some(parse_block_size(&oparg)?),
             "cbs" => config.cbs = parse_block_size(&oparg)?,
-            "skip" => config.skip = oparg.parse::<usize>()?,
-            "seek" => config.seek = oparg.parse::<usize>()?,
-            "count" => config.count = oparg.parse::<usize>()?,
+            "skip" => config.skip = parse_number(&oparg)?,
+            "seek" => config.seek = parse_number(&oparg)?,
+            "count" => config.count = Some(parse_number(&oparg)?),
             "conv" => parse_conv_list(&mut config, &oparg)?,
+            "status" => parse_status(&mut config, &oparg)?,
 
             _ => {
-                eprintln!("{}: {}", gettext("invalid option"), op);
+                let msg = format!("{}: {}", gettext("invalid option"), op);
+                eprintln!("{}", msg);
+                return Err(msg.into());
             }
         }
     }
+
+    // bs= overrides ibs= and obs= regardless of operand order
+    if let Some(bs) = config.bs {
+        config.ibs = bs;
+        config.obs = bs;
+    }
+
+    // ascii implies unblock, ebcdic and ibm imply block, when cbs is given
+    if config.cbs > 0 && config.block.is_none() {
+        config.block = match config.ascii {
+            Some(AsciiConv::Ascii) => Some(false),
+            Some(_) => Some(true),
+            None => None,
+        };
+    }
+    if config.cbs == 0 {
+        config.block = None;
+    }
+
     Ok(config)
 }
 
-fn copy_convert_file(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    let mut ifile: Box<dyn Read>;
-    if config.ifile == "-" {
-        ifile = Box::new(io::stdin().lock());
-    } else {
-        ifile = Box::new(fs::File::open(&config.ifile)?);
+// Transfer statistics, reported at exit, on SIGUSR1 and after input
+// errors with conv=noerror.
+struct Stats {
+    in_full: u64,
+    in_partial: u64,
+    out_full: u64,
+    out_partial: u64,
+    truncated: u64,
+    bytes: u64,
+    start: Instant,
+}
+
+impl Stats {
+    fn new() -> Stats {
+        Stats {
+            in_full: 0,
+            in_partial: 0,
+            out_full: 0,
+            out_partial: 0,
+            truncated: 0,
+            bytes: 0,
+            start: Instant::now(),
+        }
     }
-    let mut ofile: Box<dyn Write>;
-    if config.ofile == "-" {
-        ofile = Box::new(io::stdout().lock())
-    } else {
-        ofile = Box::new(fs::File::create(&config.ofile)?)
+
+    fn transfer_line(&self) -> String {
+        let secs = self.start.elapsed().as_secs_f64();
+        let rate = if secs > 0.0 {
+            self.bytes as f64 / secs
+        } else {
+            0.0
+        };
+        format!(
+            "{} {}, {:.6} s, {:.0} {}",
+            self.bytes,
+            gettext("bytes copied"),
+            secs,
+            rate,
+            gettext("bytes/s")
+        )
     }
 
-    let mut ibuf = vec![0u8; config.ibs];
-    let mut obuf = vec![0u8; config.obs];
+    fn report(&self, status: &StatusLevel) {
+        if *status == StatusLevel::None {
+            return;
+        }
 
-    let mut count = 0;
-    let mut skip = config.skip;
-    let mut seek = config.seek;
+        eprintln!(
+            "{}+{} {}",
+            self.in_full,
+            self.in_partial,
+            gettext("records in")
+        );
+        eprintln!(
+            "{}+{} {}",
+            self.out_full,
+            self.out_partial,
+            gettext("records out")
+        );
+        if self.truncated > 0 {
+            let noun = if self.truncated == 1 {
+                gettext("truncated record")
+            } else {
+                gettext("truncated records")
+            };
+            eprintln!("{} {}", self.truncated, noun);
+        }
+        if *status != StatusLevel::NoXfer {
+            eprintln!("{}", self.transfer_line());
+        }
+    }
+}
 
-    loop {
-        if skip > 0 {
-            let n = ifile.read(&mut ibuf)?;
-            if n == 0 {
-                break;
+// Applies conv=block and conv=unblock, carrying partial records over
+// from one input block to the next.
+struct Blocker {
+    cbs: usize,
+    col: usize,
+    truncating: bool,
+    pending: Vec<u8>,
+}
+
+impl Blocker {
+    fn new(cbs: usize) -> Blocker {
+        Blocker {
+            cbs,
+            col: 0,
+            truncating: false,
+            pending: Vec::new(),
+        }
+    }
+
+    // newline-terminated records become fixed cbs-sized records,
+    // padded with spaces or truncated
+    fn block(&mut self, data: &[u8], out: &mut Vec<u8>, stats: &mut Stats) {
+        for &b in data {
+            if b == b'\n' {
+                out.resize(out.len() + self.cbs - self.col, b' ');
+                self.col = 0;
+                self.truncating = false;
+            } else if self.col < self.cbs {
+                out.push(b);
+                self.col += 1;
+            } else if !self.truncating {
+                stats.truncated += 1;
+                self.truncating = true;
             }
-            skip -= n;
-            continue;
         }
+    }
 
-        if seek > 0 {
-            let n = ifile.read(&mut ibuf)?;
-            if n == 0 {
-                break;
+    // fixed cbs-sized records lose trailing spaces and gain a newline
+    fn unblock(&mut self, data: &[u8], out: &mut Vec<u8>) {
+        self.pending.extend_from_slice(data);
+        let mut records = self.pending.chunks_exact(self.cbs);
+        for record in &mut records {
+            push_unblocked(record, out);
+        }
+        self.pending = records.remainder().to_vec();
+    }
+
+    fn finish(&mut self, block: bool, out: &mut Vec<u8>) {
+        if block {
+            if self.col > 0 || self.truncating {
+                out.resize(out.len() + self.cbs - self.col, b' ');
+            }
+        } else if !self.pending.is_empty() {
+            push_unblocked(&self.pending, out);
+        }
+    }
+}
+
+fn push_unblocked(record: &[u8], out: &mut Vec<u8>) {
+    let len = record
+        .iter()
+        .rposition(|&b| b != b' ')
+        .map_or(0, |pos| pos + 1);
+    out.extend_from_slice(&record[..len]);
+    out.push(b'\n');
+}
+
+fn convert(config: &Config, data: &mut [u8]) {
+    if config.swab {
+        for pair in data.chunks_exact_mut(2) {
+            pair.swap(0, 1);
+        }
+    }
+
+    if let Some(AsciiConv::Ascii) = config.ascii {
+        // convert EBCDIC to ASCII
+        for b in data.iter_mut() {
+            *b = CONV_EBCDIC_ASCII[*b as usize];
+        }
+    }
+
+    if config.lcase {
+        data.make_ascii_lowercase();
+    } else if config.ucase {
+        data.make_ascii_uppercase();
+    }
+}
+
+// ASCII to EBCDIC or IBM happens last, after blocking, so that the
+// blocking pads with ASCII spaces that are then converted too.
+fn convert_to_ebcdic(config: &Config, data: &mut [u8]) {
+    let table = match config.ascii {
+        Some(AsciiConv::EBCDIC) => &CONV_ASCII_EBCDIC,
+        Some(AsciiConv::IBM) => &CONV_ASCII_IBM,
+        _ => return,
+    };
+    for b in data.iter_mut() {
+        *b = table[*b as usize];
+    }
+}
+
+// Collects converted data and writes it out in obs-sized records.
+struct Output {
+    file: ManuallyDrop<fs::File>,
+    obs: usize,
+    direct: bool,
+    buf: Vec<u8>,
+}
+
+impl Output {
+    fn write_record(&mut self, data: &[u8], stats: &mut Stats) -> io::Result<()> {
+        self.file.write_all(data)?;
+        if data.len() == self.obs {
+            stats.out_full += 1;
+        } else {
+            stats.out_partial += 1;
+        }
+        stats.bytes += data.len() as u64;
+        Ok(())
+    }
+
+    fn write(&mut self, data: &[u8], stats: &mut Stats) -> io::Result<()> {
+        if self.direct {
+            return self.write_record(data, stats);
+        }
+
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= self.obs {
+            let buf = std::mem::take(&mut self.buf);
+            let mut records = buf.chunks_exact(self.obs);
+            for record in &mut records {
+                self.write_record(record, stats)?;
             }
-            seek -= n;
-            continue;
+            self.buf = records.remainder().to_vec();
         }
+        Ok(())
+    }
+
+    fn finish(&mut self, stats: &mut Stats) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            let buf = std::mem::take(&mut self.buf);
+            self.write_record(&buf, stats)?;
+        }
+        self.file.flush()
+    }
+}
+
+// Standard input and output are wrapped without taking ownership of
+// the descriptor, so that skip= and seek= can lseek() them when they
+// are redirected from or to a regular file.
+fn open_input(config: &Config) -> io::Result<ManuallyDrop<fs::File>> {
+    if config.ifile == "-" {
+        Ok(ManuallyDrop::new(unsafe {
+            fs::File::from_raw_fd(libc::STDIN_FILENO)
+        }))
+    } else {
+        Ok(ManuallyDrop::new(fs::File::open(&config.ifile)?))
+    }
+}
 
-        let n = ifile.read(&mut ibuf)?;
+fn open_output(config: &Config) -> io::Result<ManuallyDrop<fs::File>> {
+    if config.ofile == "-" {
+        return Ok(ManuallyDrop::new(unsafe {
+            fs::File::from_raw_fd(libc::STDOUT_FILENO)
+        }));
+    }
+
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&config.ofile)?;
+
+    // without notrunc, the file ends where the copy starts
+    if !config.notrunc && file.metadata()?.is_file() {
+        file.set_len(config.seek * config.obs as u64)?;
+    }
+
+    Ok(ManuallyDrop::new(file))
+}
+
+fn skip_input(
+    ifile: &mut fs::File,
+    config: &Config,
+    stats: &mut Stats,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if config.skip == 0 {
+        return Ok(());
+    }
+
+    let offset = config.skip * config.ibs as u64;
+    if ifile.seek(SeekFrom::Current(offset as i64)).is_ok() {
+        return Ok(());
+    }
+
+    // not seekable: read and discard the blocks instead
+    let mut buf = vec![0u8; config.ibs];
+    for _ in 0..config.skip {
+        let n = read_block(ifile, &mut buf, stats, config)?;
         if n == 0 {
             break;
         }
+    }
+    Ok(())
+}
 
-        if config.count > 0 {
-            if count >= config.count {
-                break;
+fn seek_output(output: &mut Output, config: &Config) -> io::Result<()> {
+    if config.seek == 0 {
+        return Ok(());
+    }
+
+    let offset = config.seek * config.obs as u64;
+    if output.file.seek(SeekFrom::Current(offset as i64)).is_ok() {
+        return Ok(());
+    }
+
+    // not seekable: fill the skipped blocks with NULs
+    let zeroes = vec![0u8; config.obs];
+    for _ in 0..config.seek {
+        output.file.write_all(&zeroes)?;
+    }
+    Ok(())
+}
+
+// Read one input block with a single read(), as dd does not combine
+// short reads.  SIGUSR1 interrupts a blocked read, which is retried
+// after the statistics are printed.
+fn read_block(
+    ifile: &mut fs::File,
+    buf: &mut [u8],
+    stats: &Stats,
+    config: &Config,
+) -> io::Result<usize> {
+    loop {
+        match ifile.read(buf) {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                if INFO_REQUESTED.swap(false, Ordering::Relaxed) {
+                    stats.report(&config.status);
+                }
             }
-            count += 1;
+            res => return res,
         }
+    }
+}
+
+fn copy_convert_file(config: &Config) -> Result<Stats, Box<dyn std::error::Error>> {
+    let mut stats = Stats::new();
 
-        let ibuf = &ibuf[..n];
-        let obuf = &mut obuf[..n];
+    let mut ifile = open_input(config)?;
+    let mut output = Output {
+        file: open_output(config)?,
+        obs: config.obs,
+        direct: config.direct_blocks(),
+        buf: Vec::new(),
+    };
 
-        if let Some(ascii) = &config.ascii {
-            match ascii {
-                AsciiConv::Ascii => {
-                    // convert EBCDIC to ASCII
-                    for i in 0..n {
-                        obuf[i] = CONV_EBCDIC_ASCII[ibuf[i] as usize];
-                    }
+    skip_input(&mut ifile, config, &mut stats)?;
+    seek_output(&mut output, config)?;
+
+    let pad = if config.block.is_some() { b' ' } else { 0u8 };
+    let mut ibuf = vec![0u8; config.ibs];
+    let mut blocked = Vec::new();
+    let mut blocker = Blocker::new(config.cbs);
+    let mut last_progress = Instant::now();
+
+    loop {
+        if let Some(count) = config.count {
+            if stats.in_full + stats.in_partial >= count {
+                break;
+            }
+        }
+
+        let n = match read_block(&mut ifile, &mut ibuf, &stats, config) {
+            Ok(0) => break,
+            Ok(n) => {
+                if n == config.ibs {
+                    stats.in_full += 1;
+                } else {
+                    stats.in_partial += 1;
+                }
+                n
+            }
+            Err(e) if config.noerror => {
+                eprintln!("dd: {}: {}", config.ifile, e);
+                if config.status != StatusLevel::None {
+                    stats.report(&StatusLevel::NoXfer);
                 }
-                AsciiConv::EBCDIC => {
-                    // convert ASCII to EBCDIC
-                    for i in 0..n {
-                        obuf[i] = CONV_ASCII_EBCDIC[ibuf[i] as usize];
-                    }
+                stats.in_partial += 1;
+
+                // step over the bad block where possible
+                let _ = ifile.seek(SeekFrom::Current(config.ibs as i64));
+                if !config.sync {
+                    continue;
                 }
-                AsciiConv::IBM => {
-                    // convert ASCII to IBM
-                    for i in 0..n {
-                        obuf[i] = CONV_ASCII_IBM[ibuf[i] as usize];
-                    }
+                0
+            }
+            Err(e) => return Err(Box::new(e)),
+        };
+
+        let mut len = n;
+        if config.sync && len < config.ibs {
+            ibuf[len..].fill(pad);
+            len = config.ibs;
+        }
+
+        let data = &mut ibuf[..len];
+        convert(config, data);
+
+        match config.block {
+            Some(block) => {
+                blocked.clear();
+                if block {
+                    blocker.block(data, &mut blocked, &mut stats);
+                } else {
+                    blocker.unblock(data, &mut blocked);
                 }
+                convert_to_ebcdic(config, &mut blocked);
+                output.write(&blocked, &mut stats)?;
+            }
+            None => {
+                convert_to_ebcdic(config, data);
+                output.write(data, &mut stats)?;
             }
-        } else {
-            obuf.copy_from_slice(ibuf);
         }
 
-        ofile.write(&obuf)?;
+        if INFO_REQUESTED.swap(false, Ordering::Relaxed) {
+            stats.report(&config.status);
+        }
+        if config.status == StatusLevel::Progress
+            && last_progress.elapsed() >= Duration::from_secs(1)
+        {
+            eprint!("\r{}", stats.transfer_line());
+            last_progress = Instant::now();
+        }
     }
 
-    Ok(())
+    if let Some(block) = config.block {
+        blocked.clear();
+        blocker.finish(block, &mut blocked);
+        convert_to_ebcdic(config, &mut blocked);
+        output.write(&blocked, &mut stats)?;
+    }
+    output.finish(&mut stats)?;
+
+    if config.status == StatusLevel::Progress && stats.start.elapsed() >= Duration::from_secs(1) {
+        eprintln!();
+    }
+
+    Ok(stats)
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -308,7 +746,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = std::env::args().skip(1).collect();
     let config = parse_cmdline(&args)?;
 
-    copy_convert_file(&config)?;
+    // Without SA_RESTART, so that a read() blocked on a pipe or terminal
+    // returns EINTR and the statistics are printed right away.
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = request_info as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = 0;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGUSR1, &action, std::ptr::null_mut());
+    }
+
+    let stats = copy_convert_file(&config)?;
+    stats.report(&config.status);
 
     Ok(())
 }
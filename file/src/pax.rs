@@ -0,0 +1,1147 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+// TODO:
+// - the pax interchange format and its -o options
+// - multi-volume archives
+//
+
+extern crate clap;
+extern crate libc;
+extern crate plib;
+
+mod pax_util;
+
+use self::pax_util::{
+    bytes_path, path_bytes, Entry, EntryType, Format, Reader, Substitution, Writer,
+};
+use chrono::{Local, TimeZone};
+use clap::Parser;
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::PROJECT_NAME;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
+
+/// pax - portable archive interchange
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about)]
+struct Args {
+    /// Read an archive file from standard input (or -f), and extract its members.
+    #[arg(short = 'r')]
+    read: bool,
+
+    /// Write files to an archive on standard output (or -f). With -r, copy files to a directory.
+    #[arg(short = 'w')]
+    write: bool,
+
+    /// Append files to the end of the archive.
+    #[arg(short = 'a', requires = "write")]
+    append: bool,
+
+    /// Block size for writing archives, in bytes (with a b or k suffix, in 512 or 1024 byte units).
+    #[arg(short = 'b')]
+    blocksize: Option<String>,
+
+    /// Match all members except those matching the patterns.
+    #[arg(short = 'c')]
+    complement: bool,
+
+    /// Do not descend into directories.
+    #[arg(short = 'd')]
+    no_recurse: bool,
+
+    /// Archive file to read or write, instead of standard input or output.
+    #[arg(short = 'f')]
+    archive: Option<PathBuf>,
+
+    /// Follow symbolic links named on the command line.
+    #[arg(short = 'H', overrides_with_all = ["follow_cli", "follow_all"])]
+    follow_cli: bool,
+
+    /// Follow all symbolic links.
+    #[arg(short = 'L', overrides_with_all = ["follow_cli", "follow_all"])]
+    follow_all: bool,
+
+    /// Interactively rename files.
+    #[arg(short = 'i')]
+    interactive: bool,
+
+    /// Do not overwrite existing files.
+    #[arg(short = 'k')]
+    keep: bool,
+
+    /// In copy mode, hard link files to the destination whenever possible.
+    #[arg(short = 'l')]
+    link: bool,
+
+    /// Select only the first member that matches each pattern.
+    #[arg(short = 'n')]
+    first_match: bool,
+
+    /// File characteristics to preserve: a (not access time), e (everything), m (not modification time), o (owner), p (mode).
+    #[arg(short = 'p')]
+    preserve: Vec<String>,
+
+    /// Rename files with a substitution of the form /old/new/[gp].
+    #[arg(short = 's')]
+    substitutions: Vec<String>,
+
+    /// Restore the access times of files read while writing an archive.
+    #[arg(short = 't')]
+    reset_atime: bool,
+
+    /// Do not replace files with older versions.
+    #[arg(short = 'u')]
+    update: bool,
+
+    /// List file names as they are processed, or a long listing in list mode.
+    #[arg(short = 'v')]
+    verbose: bool,
+
+    /// Output archive format: ustar (default) or cpio.
+    #[arg(short = 'x')]
+    format: Option<String>,
+
+    /// Do not cross file system boundaries when descending directories.
+    #[arg(short = 'X')]
+    one_file_system: bool,
+
+    /// Patterns selecting archive members (list and read modes), or files to archive or copy.
+    operands: Vec<PathBuf>,
+}
+
+/// Which characteristics of extracted or copied files are preserved.
+struct Preserve {
+    atime: bool,
+    mtime: bool,
+    owner: bool,
+    mode: bool,
+}
+
+impl Preserve {
+    fn parse(specs: &[String]) -> Result<Preserve, String> {
+        let mut preserve = Preserve {
+            atime: true,
+            mtime: true,
+            owner: false,
+            mode: false,
+        };
+
+        // later characters take precedence over earlier ones
+        for ch in specs.iter().flat_map(|s| s.chars()) {
+            match ch {
+                'a' => preserve.atime = false,
+                'e' => {
+                    preserve = Preserve {
+                        atime: true,
+                        mtime: true,
+                        owner: true,
+                        mode: true,
+                    }
+                }
+                'm' => preserve.mtime = false,
+                'o' => preserve.owner = true,
+                'p' => preserve.mode = true,
+                _ => return Err(gettext!("invalid -p option: {}", ch)),
+            }
+        }
+
+        Ok(preserve)
+    }
+}
+
+struct Config {
+    complement: bool,
+    no_recurse: bool,
+    follow_cli: bool,
+    follow_all: bool,
+    interactive: bool,
+    keep: bool,
+    link: bool,
+    first_match: bool,
+    preserve: Preserve,
+    substitutions: Vec<Substitution>,
+    reset_atime: bool,
+    update: bool,
+    verbose: bool,
+    one_file_system: bool,
+    umask: u32,
+}
+
+/// Selects archive members by shell pattern.
+struct Patterns {
+    patterns: Vec<CString>,
+    // per pattern: whether it matched, and with -n, the directory it matched
+    matched: Vec<bool>,
+    matched_dir: Vec<Option<Vec<u8>>>,
+    complement: bool,
+    first_match: bool,
+}
+
+extern "C" {
+    // not exported by the libc crate
+    #[link_name = "fnmatch"]
+    fn libc_fnmatch(
+        pattern: *const libc::c_char,
+        string: *const libc::c_char,
+        flags: libc::c_int,
+    ) -> libc::c_int;
+}
+
+fn fnmatch(pattern: &CStr, name: &[u8]) -> bool {
+    match CString::new(name) {
+        Ok(name) => unsafe { libc_fnmatch(pattern.as_ptr(), name.as_ptr(), 0) == 0 },
+        Err(_) => false,
+    }
+}
+
+impl Patterns {
+    fn new(cfg: &Config, operands: &[PathBuf]) -> Result<Patterns, String> {
+        let mut patterns = Vec::new();
+        for operand in operands {
+            let pattern = CString::new(path_bytes(operand))
+                .map_err(|_| gettext!("invalid pattern: {}", operand.display()))?;
+            patterns.push(pattern);
+        }
+
+        Ok(Patterns {
+            matched: vec![false; patterns.len()],
+            matched_dir: vec![None; patterns.len()],
+            patterns,
+            complement: cfg.complement,
+            first_match: cfg.first_match,
+        })
+    }
+
+    // A pattern matching a directory also selects everything below it.
+    fn matches(pattern: &CStr, name: &[u8]) -> bool {
+        let name = name.strip_suffix(b"/").unwrap_or(name);
+        if fnmatch(pattern, name) {
+            return true;
+        }
+        name.iter()
+            .enumerate()
+            .filter(|&(_, &b)| b == b'/')
+            .any(|(i, _)| i > 0 && fnmatch(pattern, &name[..i]))
+    }
+
+    fn select(&mut self, name: &[u8]) -> bool {
+        if self.patterns.is_empty() {
+            return true;
+        }
+
+        let mut selected = false;
+        for i in 0..self.patterns.len() {
+            if self.first_match && self.matched[i] {
+                // only the hierarchy of the first match is still selected
+                if let Some(dir) = &self.matched_dir[i] {
+                    if name.starts_with(dir) && name.get(dir.len()) == Some(&b'/') {
+                        selected = true;
+                    }
+                }
+                continue;
+            }
+
+            if Patterns::matches(&self.patterns[i], name) {
+                selected = true;
+                self.matched[i] = true;
+                if self.first_match {
+                    let dir = name.strip_suffix(b"/").unwrap_or(name);
+                    self.matched_dir[i] = Some(dir.to_vec());
+                }
+            }
+        }
+
+        selected != self.complement
+    }
+
+    fn report_unmatched(&self) -> bool {
+        if self.complement {
+            return true;
+        }
+
+        let mut all_matched = true;
+        for (pattern, matched) in self.patterns.iter().zip(&self.matched) {
+            if !matched {
+                eprintln!(
+                    "pax: {}: {}",
+                    pattern.to_string_lossy(),
+                    gettext("pattern not matched")
+                );
+                all_matched = false;
+            }
+        }
+        all_matched
+    }
+}
+
+/// Cache of user and group names, in both directions.
+#[derive(Default)]
+struct Names {
+    users: HashMap<u32, Option<String>>,
+    groups: HashMap<u32, Option<String>>,
+    uids: HashMap<String, Option<u32>>,
+    gids: HashMap<String, Option<u32>>,
+}
+
+impl Names {
+    fn user(&mut self, uid: u32) -> Option<String> {
+        self.users
+            .entry(uid)
+            .or_insert_with(|| unsafe {
+                let pw = libc::getpwuid(uid);
+                if pw.is_null() {
+                    None
+                } else {
+                    Some(CStr::from_ptr((*pw).pw_name).to_string_lossy().into_owned())
+                }
+            })
+            .clone()
+    }
+
+    fn group(&mut self, gid: u32) -> Option<String> {
+        self.groups
+            .entry(gid)
+            .or_insert_with(|| unsafe {
+                let gr = libc::getgrgid(gid);
+                if gr.is_null() {
+                    None
+                } else {
+                    Some(CStr::from_ptr((*gr).gr_name).to_string_lossy().into_owned())
+                }
+            })
+            .clone()
+    }
+
+    fn uid(&mut self, name: &str) -> Option<u32> {
+        *self.uids.entry(name.to_string()).or_insert_with(|| {
+            let cname = CString::new(name).ok()?;
+            let pw = unsafe { libc::getpwnam(cname.as_ptr()) };
+            if pw.is_null() {
+                None
+            } else {
+                Some(unsafe { (*pw).pw_uid })
+            }
+        })
+    }
+
+    fn gid(&mut self, name: &str) -> Option<u32> {
+        *self.gids.entry(name.to_string()).or_insert_with(|| {
+            let cname = CString::new(name).ok()?;
+            let gr = unsafe { libc::getgrnam(cname.as_ptr()) };
+            if gr.is_null() {
+                None
+            } else {
+                Some(unsafe { (*gr).gr_gid })
+            }
+        })
+    }
+}
+
+/// Apply the -s substitutions and -i prompt to `name`. Returns `None` if
+/// the file is to be skipped.
+fn rename(cfg: &Config, name: &Path) -> io::Result<Option<PathBuf>> {
+    let mut name = name.to_path_buf();
+
+    // the first substitution that matches is used
+    for subst in &cfg.substitutions {
+        if let Some(new_name) = subst.apply(path_bytes(&name)) {
+            let new_name = bytes_path(&new_name);
+            if subst.print() {
+                eprintln!("{} >> {}", name.display(), new_name.display());
+            }
+            name = new_name;
+            break;
+        }
+    }
+
+    if name.as_os_str().is_empty() {
+        return Ok(None);
+    }
+
+    if cfg.interactive {
+        let mut tty = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")?;
+        write!(tty, "{}: ", name.display())?;
+        tty.flush()?;
+
+        let mut response = String::new();
+        if io::BufReader::new(&tty).read_line(&mut response)? == 0 {
+            // end of input on the terminal ends the session
+            std::process::exit(1);
+        }
+        let response = response.trim_end_matches('\n');
+        match response {
+            "" => return Ok(None),
+            "." => {}
+            _ => name = PathBuf::from(response),
+        }
+    }
+
+    Ok(Some(name))
+}
+
+fn mode_string(kind: EntryType, mode: u32) -> String {
+    let mut s = String::with_capacity(10);
+    s.push(match kind {
+        EntryType::Regular | EntryType::HardLink => '-',
+        EntryType::Symlink => 'l',
+        EntryType::CharDevice => 'c',
+        EntryType::BlockDevice => 'b',
+        EntryType::Directory => 'd',
+        EntryType::Fifo => 'p',
+    });
+
+    let special = [(0o4000, 's'), (0o2000, 's'), (0o1000, 't')];
+    for (i, (bit, ch)) in special.iter().enumerate() {
+        let shift = 6 - i * 3;
+        s.push(if mode & (0o4 << shift) != 0 { 'r' } else { '-' });
+        s.push(if mode & (0o2 << shift) != 0 { 'w' } else { '-' });
+        let exec = mode & (0o1 << shift) != 0;
+        s.push(match (mode & bit != 0, exec) {
+            (true, true) => *ch,
+            (true, false) => ch.to_ascii_uppercase(),
+            (false, true) => 'x',
+            (false, false) => '-',
+        });
+    }
+    s
+}
+
+fn list_entry(entry: &Entry, verbose: bool, out: &mut impl Write) -> io::Result<()> {
+    if !verbose {
+        return writeln!(out, "{}", entry.path.display());
+    }
+
+    let user = entry.uname.clone().unwrap_or_else(|| entry.uid.to_string());
+    let group = entry.gname.clone().unwrap_or_else(|| entry.gid.to_string());
+    let size = match entry.kind {
+        EntryType::CharDevice | EntryType::BlockDevice => {
+            format!("{}, {}", entry.rdev_major, entry.rdev_minor)
+        }
+        _ => entry.size.to_string(),
+    };
+    let date = match Local.timestamp_opt(entry.mtime, 0) {
+        chrono::LocalResult::Single(dt) => dt.format("%b %e %H:%M %Y").to_string(),
+        _ => entry.mtime.to_string(),
+    };
+
+    write!(
+        out,
+        "{} {:>3} {:<8} {:<8} {:>8} {} {}",
+        mode_string(entry.kind, entry.mode),
+        entry.nlink,
+        user,
+        group,
+        size,
+        date,
+        entry.path.display()
+    )?;
+    match (&entry.kind, &entry.link_target) {
+        (EntryType::HardLink, Some(target)) => write!(out, " == {}", target.display())?,
+        (EntryType::Symlink, Some(target)) => write!(out, " -> {}", target.display())?,
+        _ => {}
+    }
+    writeln!(out)
+}
+
+fn set_times(path: &Path, atime: Option<i64>, mtime: Option<i64>) -> io::Result<()> {
+    let spec = |t: Option<i64>| match t {
+        Some(sec) => libc::timespec {
+            tv_sec: sec as libc::time_t,
+            tv_nsec: 0,
+        },
+        None => libc::timespec {
+            tv_sec: 0,
+            tv_nsec: libc::UTIME_OMIT,
+        },
+    };
+    let times = [spec(atime), spec(mtime)];
+
+    let path = CString::new(path_bytes(path))?;
+    let ret = unsafe {
+        libc::utimensat(
+            libc::AT_FDCWD,
+            path.as_ptr(),
+            times.as_ptr(),
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Creates files from archive members (read mode) or source files (copy mode).
+struct Extractor<'a> {
+    cfg: &'a Config,
+    names: Names,
+    // directories get their attributes once their contents are in place
+    dirs: Vec<(PathBuf, Entry)>,
+    // (dev, ino) of copied files with several links, and where they went
+    links: HashMap<(u64, u64), PathBuf>,
+}
+
+impl<'a> Extractor<'a> {
+    fn new(cfg: &'a Config) -> Extractor<'a> {
+        Extractor {
+            cfg,
+            names: Names::default(),
+            dirs: Vec::new(),
+            links: HashMap::new(),
+        }
+    }
+
+    fn set_attributes(&mut self, entry: &Entry, path: &Path) -> io::Result<()> {
+        let cfg = self.cfg;
+        let mut mode = entry.mode & 0o7777;
+        let mut result = Ok(());
+
+        let mut owner_preserved = false;
+        if cfg.preserve.owner {
+            // names in the archive take precedence over the numeric IDs
+            let uid = entry
+                .uname
+                .as_ref()
+                .and_then(|name| self.names.uid(name))
+                .unwrap_or(entry.uid);
+            let gid = entry
+                .gname
+                .as_ref()
+                .and_then(|name| self.names.gid(name))
+                .unwrap_or(entry.gid);
+            let cpath = CString::new(path_bytes(path))?;
+            if unsafe { libc::lchown(cpath.as_ptr(), uid, gid) } == 0 {
+                owner_preserved = true;
+            } else {
+                result = Err(io::Error::last_os_error());
+            }
+        }
+
+        // the set-ID bits only make sense for the original owner
+        if !owner_preserved {
+            mode &= !0o6000;
+        }
+        if !cfg.preserve.mode {
+            mode &= !cfg.umask;
+        }
+
+        if entry.kind != EntryType::Symlink {
+            if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(mode)) {
+                result = Err(e);
+            }
+        }
+
+        let atime = cfg.preserve.atime.then_some(entry.atime);
+        let mtime = cfg.preserve.mtime.then_some(entry.mtime);
+        if atime.is_some() || mtime.is_some() {
+            if let Err(e) = set_times(path, atime, mtime) {
+                result = Err(e);
+            }
+        }
+
+        result
+    }
+
+    /// Make room for `path`, honoring -k and -u. Returns false if the
+    /// existing file is to be kept.
+    fn prepare_target(&self, entry: &Entry, path: &Path) -> io::Result<bool> {
+        let md = match fs::symlink_metadata(path) {
+            Ok(md) => md,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                // intermediate directories are created as needed
+                if let Some(parent) = path.parent() {
+                    if !parent.as_os_str().is_empty() {
+                        fs::DirBuilder::new()
+                            .recursive(true)
+                            .mode(0o777)
+                            .create(parent)?;
+                    }
+                }
+                return Ok(true);
+            }
+            Err(e) => return Err(e),
+        };
+
+        if self.cfg.keep {
+            return Ok(false);
+        }
+        if self.cfg.update && md.mtime() >= entry.mtime {
+            return Ok(false);
+        }
+
+        if md.is_dir() {
+            if entry.kind != EntryType::Directory {
+                fs::remove_dir(path)?;
+            }
+        } else {
+            fs::remove_file(path)?;
+        }
+        Ok(true)
+    }
+
+    /// Create `path` from `entry`; `data` copies the file contents into
+    /// the new regular file.
+    fn extract(
+        &mut self,
+        entry: &Entry,
+        path: &Path,
+        data: &mut dyn FnMut(&mut dyn Write) -> io::Result<()>,
+    ) -> io::Result<()> {
+        if !self.prepare_target(entry, path)? {
+            return Ok(());
+        }
+
+        let cpath = CString::new(path_bytes(path))?;
+        match entry.kind {
+            EntryType::Regular => {
+                let mut file = fs::OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .mode(0o600)
+                    .open(path)?;
+                data(&mut file)?;
+            }
+            EntryType::HardLink => {
+                let target = entry.link_target.clone().unwrap_or_default();
+                fs::hard_link(target, path)?;
+                return Ok(());
+            }
+            EntryType::Symlink => {
+                let target = entry.link_target.clone().unwrap_or_default();
+                std::os::unix::fs::symlink(target, path)?;
+            }
+            EntryType::Directory => {
+                if !path.is_dir() {
+                    fs::DirBuilder::new().mode(0o700).create(path)?;
+                }
+                self.dirs.push((path.to_path_buf(), entry.clone()));
+                return Ok(());
+            }
+            EntryType::Fifo => {
+                if unsafe { libc::mkfifo(cpath.as_ptr(), 0o600) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            EntryType::CharDevice | EntryType::BlockDevice => {
+                let kind = if entry.kind == EntryType::CharDevice {
+                    libc::S_IFCHR
+                } else {
+                    libc::S_IFBLK
+                };
+                let dev = libc::makedev(entry.rdev_major as _, entry.rdev_minor as _);
+                if unsafe { libc::mknod(cpath.as_ptr(), kind | 0o600, dev) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+        }
+
+        self.set_attributes(entry, path)
+    }
+
+    /// Set the attributes of the extracted directories, deepest first.
+    fn finish(&mut self) -> bool {
+        let mut success = true;
+        let dirs = std::mem::take(&mut self.dirs);
+        for (path, entry) in dirs.iter().rev() {
+            if let Err(e) = self.set_attributes(entry, path) {
+                eprintln!("pax: {}: {}", path.display(), e);
+                success = false;
+            }
+        }
+        success
+    }
+}
+
+/// Build an archive entry describing the file at `path`.
+fn entry_from_metadata(
+    path: &Path,
+    md: &fs::Metadata,
+    names: &mut Names,
+) -> io::Result<Option<Entry>> {
+    let kind = match EntryType::from_mode(md.mode()) {
+        Some(kind) => kind,
+        // sockets cannot be archived
+        None => return Ok(None),
+    };
+
+    let link_target = if kind == EntryType::Symlink {
+        Some(fs::read_link(path)?)
+    } else {
+        None
+    };
+
+    let rdev = md.rdev() as libc::dev_t;
+    Ok(Some(Entry {
+        path: path.to_path_buf(),
+        kind,
+        mode: md.mode() & 0o7777,
+        uid: md.uid(),
+        gid: md.gid(),
+        uname: names.user(md.uid()),
+        gname: names.group(md.gid()),
+        atime: md.atime(),
+        mtime: md.mtime(),
+        size: if kind == EntryType::Regular {
+            md.len()
+        } else {
+            0
+        },
+        link_target,
+        nlink: md.nlink() as u32,
+        dev: md.dev(),
+        ino: md.ino(),
+        rdev_major: unsafe { libc::major(rdev) } as u32,
+        rdev_minor: unsafe { libc::minor(rdev) } as u32,
+    }))
+}
+
+/// Walks the file hierarchies named by the write and copy mode operands.
+struct Walker<'a> {
+    cfg: &'a Config,
+    // in copy mode, the destination directory is not copied into itself
+    skip: Option<(u64, u64)>,
+    success: bool,
+}
+
+impl<'a> Walker<'a> {
+    fn walk(
+        &mut self,
+        path: &Path,
+        top: bool,
+        root_dev: Option<u64>,
+        visit: &mut dyn FnMut(&Path, &fs::Metadata) -> io::Result<()>,
+    ) {
+        let follow = self.cfg.follow_all || (self.cfg.follow_cli && top);
+        let md = if follow {
+            // a dangling link is archived as a link
+            fs::metadata(path).or_else(|_| fs::symlink_metadata(path))
+        } else {
+            fs::symlink_metadata(path)
+        };
+        let md = match md {
+            Ok(md) => md,
+            Err(e) => {
+                eprintln!("pax: {}: {}", path.display(), e);
+                self.success = false;
+                return;
+            }
+        };
+
+        if self.cfg.one_file_system && root_dev.is_some_and(|dev| dev != md.dev()) {
+            return;
+        }
+        if self.skip == Some((md.dev(), md.ino())) {
+            return;
+        }
+
+        if let Err(e) = visit(path, &md) {
+            eprintln!("pax: {}: {}", path.display(), e);
+            self.success = false;
+        }
+
+        if !md.is_dir() || self.cfg.no_recurse {
+            return;
+        }
+
+        let mut children = match fs::read_dir(path) {
+            Ok(dir) => dir
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                eprintln!("pax: {}: {}", path.display(), e);
+                self.success = false;
+                return;
+            }
+        };
+        children.sort();
+
+        for child in children {
+            self.walk(&child, false, Some(md.dev()), visit);
+        }
+    }
+}
+
+/// File operands, or pathnames read from standard input if there are none.
+fn file_operands(operands: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    if !operands.is_empty() {
+        return Ok(operands.to_vec());
+    }
+
+    let mut files = Vec::new();
+    for line in io::stdin().lock().split(b'\n') {
+        let line = line?;
+        if !line.is_empty() {
+            files.push(bytes_path(&line));
+        }
+    }
+    Ok(files)
+}
+
+fn open_archive_input(archive: &Option<PathBuf>) -> io::Result<Box<dyn Read>> {
+    match archive {
+        Some(path) if path.as_os_str() != "-" => Ok(Box::new(fs::File::open(path)?)),
+        _ => Ok(Box::new(io::stdin().lock())),
+    }
+}
+
+fn list_mode(cfg: &Config, args: &Args) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut patterns = Patterns::new(cfg, &args.operands)?;
+    let mut reader = Reader::new(open_archive_input(&args.archive)?)?;
+    let mut out = io::BufWriter::new(io::stdout().lock());
+
+    while let Some(entry) = reader.next_entry()? {
+        if !patterns.select(path_bytes(&entry.path)) {
+            continue;
+        }
+        let mut entry = entry;
+        entry.path = match rename(cfg, &entry.path)? {
+            Some(name) => name,
+            None => continue,
+        };
+        list_entry(&entry, cfg.verbose, &mut out)?;
+    }
+    out.flush()?;
+
+    Ok(patterns.report_unmatched())
+}
+
+/// True if extracting to `name`, a name in the archive, could write
+/// outside the current directory.
+fn is_unsafe_name(name: &Path) -> bool {
+    name.is_absolute() || name.components().any(|c| c == Component::ParentDir)
+}
+
+fn read_mode(cfg: &Config, args: &Args) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut patterns = Patterns::new(cfg, &args.operands)?;
+    let mut reader = Reader::new(open_archive_input(&args.archive)?)?;
+    let mut extractor = Extractor::new(cfg);
+    let mut success = true;
+    // hard links refer to earlier members by their names in the archive
+    let mut renamed: HashMap<PathBuf, PathBuf> = HashMap::new();
+
+    while let Some(mut entry) = reader.next_entry()? {
+        if !patterns.select(path_bytes(&entry.path)) {
+            continue;
+        }
+
+        let unsafe_link = entry.kind == EntryType::HardLink
+            && entry.link_target.as_deref().is_some_and(is_unsafe_name);
+        if is_unsafe_name(&entry.path) || unsafe_link {
+            eprintln!(
+                "pax: {}: {}",
+                entry.path.display(),
+                gettext("unsafe file name, not extracted")
+            );
+            success = false;
+            continue;
+        }
+
+        let path = match rename(cfg, &entry.path)? {
+            Some(name) => name,
+            None => continue,
+        };
+        renamed.insert(entry.path.clone(), path.clone());
+
+        if entry.kind == EntryType::HardLink {
+            if let Some(target) = &entry.link_target {
+                if let Some(new_target) = renamed.get(target) {
+                    entry.link_target = Some(new_target.clone());
+                }
+            }
+        }
+
+        if cfg.verbose {
+            eprintln!("{}", path.display());
+        }
+
+        if let Err(e) = extractor.extract(&entry, &path, &mut |out| reader.copy_data(out)) {
+            eprintln!("pax: {}: {}", path.display(), e);
+            success = false;
+        }
+    }
+
+    success &= extractor.finish();
+    success &= patterns.report_unmatched();
+    Ok(success)
+}
+
+fn open_archive_output(
+    args: &Args,
+    format: &mut Option<Format>,
+) -> Result<Box<dyn Write>, Box<dyn std::error::Error>> {
+    let path = match &args.archive {
+        Some(path) if path.as_os_str() != "-" => path,
+        _ => {
+            if args.append {
+                return Err(gettext("-a requires an archive file given with -f").into());
+            }
+            return Ok(Box::new(io::stdout()));
+        }
+    };
+
+    if !args.append {
+        return Ok(Box::new(fs::File::create(path)?));
+    }
+
+    // find the trailer and write over it
+    let mut file = fs::OpenOptions::new().read(true).write(true).open(path)?;
+    let mut reader = Reader::new(Box::new(file.try_clone()?))?;
+    while reader.next_entry()?.is_some() {}
+
+    match format {
+        Some(f) if *f != reader.format() => {
+            return Err(gettext("cannot append in a different archive format").into())
+        }
+        _ => *format = Some(reader.format()),
+    }
+
+    file.seek(SeekFrom::Start(reader.header_offset))?;
+    Ok(Box::new(file))
+}
+
+fn parse_block_size(s: &str) -> Result<usize, String> {
+    let invalid = || gettext!("invalid block size: {}", s);
+
+    let (digits, unit) = match s.as_bytes().last() {
+        Some(b'b') => (&s[..s.len() - 1], 512),
+        Some(b'k') => (&s[..s.len() - 1], 1024),
+        _ => (s, 1),
+    };
+    let size = digits.parse::<usize>().map_err(|_| invalid())? * unit;
+    if size == 0 || size % 512 != 0 || size > 32256 {
+        return Err(invalid());
+    }
+    Ok(size)
+}
+
+fn write_mode(cfg: &Config, args: &Args) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut format = match &args.format {
+        Some(name) => Some(
+            Format::from_name(name)
+                .ok_or_else(|| gettext!("unsupported archive format: {}", name))?,
+        ),
+        None => None,
+    };
+    let out = open_archive_output(args, &mut format)?;
+    let format = format.unwrap_or(Format::Ustar);
+
+    let block_size = match &args.blocksize {
+        Some(s) => parse_block_size(s)?,
+        None => format.default_block_size(),
+    };
+
+    let files = file_operands(&args.operands)?;
+    let mut writer = Writer::new(out, format, block_size);
+    let mut names = Names::default();
+    let mut walker = Walker {
+        cfg,
+        skip: None,
+        success: true,
+    };
+
+    let mut archive_file = |path: &Path, md: &fs::Metadata| -> io::Result<()> {
+        let mut entry = match entry_from_metadata(path, md, &mut names)? {
+            Some(entry) => entry,
+            None => {
+                eprintln!(
+                    "pax: {}: {}",
+                    path.display(),
+                    gettext("sockets are not archived")
+                );
+                return Ok(());
+            }
+        };
+        entry.path = match rename(cfg, path)? {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+
+        if cfg.verbose {
+            eprintln!("{}", entry.path.display());
+        }
+
+        if entry.kind == EntryType::Regular && entry.size > 0 {
+            let mut file = fs::File::open(path)?;
+            writer.write_entry(&entry, Some(&mut file))?;
+            if cfg.reset_atime {
+                set_times(path, Some(md.atime()), Some(md.mtime()))?;
+            }
+        } else {
+            writer.write_entry(&entry, None)?;
+        }
+        Ok(())
+    };
+
+    for file in &files {
+        walker.walk(file, true, None, &mut archive_file);
+    }
+
+    writer.finish()?;
+    Ok(walker.success)
+}
+
+fn copy_mode(cfg: &Config, args: &Args) -> Result<bool, Box<dyn std::error::Error>> {
+    let (dest_dir, operands) = match args.operands.split_last() {
+        Some(split) => split,
+        None => return Err(gettext("missing destination directory").into()),
+    };
+    let dest_md = fs::metadata(dest_dir).map_err(|e| format!("{}: {}", dest_dir.display(), e))?;
+    if !dest_md.is_dir() {
+        return Err(gettext!("{}: not a directory", dest_dir.display()).into());
+    }
+
+    let files = file_operands(operands)?;
+    let mut extractor = Extractor::new(cfg);
+    let mut names = Names::default();
+    let mut walker = Walker {
+        cfg,
+        skip: Some((dest_md.dev(), dest_md.ino())),
+        success: true,
+    };
+
+    let mut copy_file = |path: &Path, md: &fs::Metadata| -> io::Result<()> {
+        let mut entry = match entry_from_metadata(path, md, &mut names)? {
+            Some(entry) => entry,
+            None => return Ok(()),
+        };
+        let name = match rename(cfg, path)? {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+
+        // the whole operand goes below the directory, even if absolute
+        let mut dest = path_bytes(dest_dir).to_vec();
+        dest.push(b'/');
+        dest.extend_from_slice(path_bytes(&name));
+        let dest = bytes_path(&dest);
+
+        if cfg.verbose {
+            eprintln!("{}", dest.display());
+        }
+
+        if entry.kind != EntryType::Directory {
+            if cfg.link
+                && extractor.prepare_target(&entry, &dest)?
+                && fs::hard_link(path, &dest).is_ok()
+            {
+                return Ok(());
+            }
+
+            // keep the links between the copied files
+            if entry.nlink > 1 {
+                let id = (entry.dev, entry.ino);
+                match extractor.links.get(&id) {
+                    Some(first) => {
+                        entry.kind = EntryType::HardLink;
+                        entry.link_target = Some(first.clone());
+                    }
+                    None => {
+                        extractor.links.insert(id, dest.clone());
+                    }
+                }
+            }
+        }
+
+        extractor.extract(&entry, &dest, &mut |out| {
+            let mut file = fs::File::open(path)?;
+            io::copy(&mut file, out)?;
+            Ok(())
+        })?;
+
+        if cfg.reset_atime && entry.kind == EntryType::Regular {
+            set_times(path, Some(md.atime()), Some(md.mtime()))?;
+        }
+        Ok(())
+    };
+
+    for file in &files {
+        walker.walk(file, true, None, &mut copy_file);
+    }
+
+    let success = walker.success;
+    Ok(extractor.finish() && success)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // parse command line arguments
+    let args = Args::parse();
+
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain(PROJECT_NAME)?;
+    bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
+
+    let mut substitutions = Vec::new();
+    for spec in &args.substitutions {
+        match Substitution::parse(spec) {
+            Ok(subst) => substitutions.push(subst),
+            Err(e) => {
+                eprintln!("pax: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let preserve = match Preserve::parse(&args.preserve) {
+        Ok(preserve) => preserve,
+        Err(e) => {
+            eprintln!("pax: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // There is no way to read the umask without setting it
+    let umask = unsafe {
+        let mask = libc::umask(0);
+        libc::umask(mask);
+        mask as u32
+    };
+
+    let cfg = Config {
+        complement: args.complement,
+        no_recurse: args.no_recurse,
+        follow_cli: args.follow_cli,
+        follow_all: args.follow_all,
+        interactive: args.interactive,
+        keep: args.keep,
+        link: args.link,
+        first_match: args.first_match,
+        preserve,
+        substitutions,
+        reset_atime: args.reset_atime,
+        update: args.update,
+        verbose: args.verbose,
+        one_file_system: args.one_file_system,
+        umask,
+    };
+
+    let result = match (args.read, args.write) {
+        (false, false) => list_mode(&cfg, &args),
+        (true, false) => read_mode(&cfg, &args),
+        (false, true) => write_mode(&cfg, &args),
+        (true, true) => copy_mode(&cfg, &args),
+    };
+
+    match result {
+        Ok(true) => Ok(()),
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("pax: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The POSIX cpio ("odc") header: fixed-width octal text fields,
+//! followed by the NUL-terminated name and the file data.
+
+use super::{path_bytes, Entry, EntryType};
+use std::io;
+
+pub const MAGIC: &[u8] = b"070707";
+pub const TRAILER: &[u8] = b"TRAILER!!!";
+pub const HEADER_LEN: usize = 76;
+
+// (offset, width) of each field
+const DEV: (usize, usize) = (6, 6);
+const INO: (usize, usize) = (12, 6);
+const MODE: (usize, usize) = (18, 6);
+const UID: (usize, usize) = (24, 6);
+const GID: (usize, usize) = (30, 6);
+const NLINK: (usize, usize) = (36, 6);
+const RDEV: (usize, usize) = (42, 6);
+const MTIME: (usize, usize) = (48, 11);
+const NAMESIZE: (usize, usize) = (59, 6);
+const FILESIZE: (usize, usize) = (65, 11);
+
+fn parse_octal(header: &[u8], (start, len): (usize, usize)) -> io::Result<u64> {
+    std::str::from_utf8(&header[start..start + len])
+        .ok()
+        .and_then(|text| u64::from_str_radix(text, 8).ok())
+        .ok_or_else(|| io::Error::other("invalid cpio header"))
+}
+
+/// Decode a header, returning the entry (without its name) and the
+/// length of the name that follows it, including the NUL.
+pub fn decode(header: &[u8]) -> io::Result<(Entry, usize)> {
+    if !header.starts_with(MAGIC) {
+        return Err(io::Error::other("invalid cpio header"));
+    }
+
+    let mode = parse_octal(header, MODE)? as u32;
+    // the trailer has no file type
+    let kind = EntryType::from_mode(mode).unwrap_or(EntryType::Regular);
+
+    // the 6-digit rdev field is split like an old 16-bit dev_t
+    let rdev = parse_octal(header, RDEV)? as u32;
+    let mtime = parse_octal(header, MTIME)? as i64;
+
+    let entry = Entry {
+        path: Default::default(),
+        kind,
+        mode: mode & 0o7777,
+        uid: parse_octal(header, UID)? as u32,
+        gid: parse_octal(header, GID)? as u32,
+        uname: None,
+        gname: None,
+        atime: mtime,
+        mtime,
+        size: parse_octal(header, FILESIZE)?,
+        link_target: None,
+        nlink: parse_octal(header, NLINK)? as u32,
+        dev: parse_octal(header, DEV)?,
+        ino: parse_octal(header, INO)?,
+        rdev_major: rdev >> 8,
+        rdev_minor: rdev & 0xff,
+    };
+
+    Ok((entry, parse_octal(header, NAMESIZE)? as usize))
+}
+
+fn put_octal(
+    header: &mut Vec<u8>,
+    (_, len): (usize, usize),
+    value: u64,
+    what: &str,
+) -> io::Result<()> {
+    let text = format!("{:0width$o}", value, width = len);
+    if text.len() > len {
+        return Err(io::Error::other(format!(
+            "{} too large for cpio format",
+            what
+        )));
+    }
+    header.extend_from_slice(text.as_bytes());
+    Ok(())
+}
+
+/// Encode the header and name of `entry`.
+pub fn encode(entry: &Entry) -> io::Result<Vec<u8>> {
+    let name = path_bytes(&entry.path);
+    let rdev = ((entry.rdev_major as u64) << 8) | entry.rdev_minor as u64;
+
+    let mut header = Vec::with_capacity(HEADER_LEN + name.len() + 1);
+    header.extend_from_slice(MAGIC);
+    put_octal(&mut header, DEV, entry.dev, "device")?;
+    put_octal(&mut header, INO, entry.ino, "inode")?;
+    put_octal(
+        &mut header,
+        MODE,
+        (entry.kind.mode_bits() | (entry.mode & 0o7777)) as u64,
+        "mode",
+    )?;
+    put_octal(&mut header, UID, entry.uid as u64, "user ID")?;
+    put_octal(&mut header, GID, entry.gid as u64, "group ID")?;
+    put_octal(&mut header, NLINK, entry.nlink as u64, "link count")?;
+    put_octal(&mut header, RDEV, rdev, "device number")?;
+    put_octal(
+        &mut header,
+        MTIME,
+        entry.mtime.max(0) as u64,
+        "modification time",
+    )?;
+    put_octal(
+        &mut header,
+        NAMESIZE,
+        name.len() as u64 + 1,
+        "file name length",
+    )?;
+    put_octal(&mut header, FILESIZE, entry.size, "file size")?;
+
+    header.extend_from_slice(name);
+    header.push(0);
+
+    Ok(header)
+}
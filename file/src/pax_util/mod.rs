@@ -0,0 +1,546 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+mod cpio;
+mod subst;
+mod ustar;
+
+pub use subst::Substitution;
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::io::{self, Read, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+/// Archive formats that pax can read and write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Ustar,
+    Cpio,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "ustar" => Some(Format::Ustar),
+            "cpio" => Some(Format::Cpio),
+            _ => None,
+        }
+    }
+
+    /// Default number of bytes per output block.
+    pub fn default_block_size(&self) -> usize {
+        match self {
+            Format::Ustar => 10240,
+            Format::Cpio => 5120,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryType {
+    Regular,
+    HardLink,
+    Symlink,
+    CharDevice,
+    BlockDevice,
+    Directory,
+    Fifo,
+}
+
+// mode_t is narrower than u32 on some systems
+#[allow(clippy::unnecessary_cast)]
+impl EntryType {
+    /// The S_IFMT bits for this type; a hard link reports those of a regular file.
+    pub fn mode_bits(&self) -> u32 {
+        (match self {
+            EntryType::Regular | EntryType::HardLink => libc::S_IFREG,
+            EntryType::Symlink => libc::S_IFLNK,
+            EntryType::CharDevice => libc::S_IFCHR,
+            EntryType::BlockDevice => libc::S_IFBLK,
+            EntryType::Directory => libc::S_IFDIR,
+            EntryType::Fifo => libc::S_IFIFO,
+        }) as u32
+    }
+
+    pub fn from_mode(mode: u32) -> Option<EntryType> {
+        match mode & libc::S_IFMT as u32 {
+            m if m == libc::S_IFREG as u32 => Some(EntryType::Regular),
+            m if m == libc::S_IFLNK as u32 => Some(EntryType::Symlink),
+            m if m == libc::S_IFCHR as u32 => Some(EntryType::CharDevice),
+            m if m == libc::S_IFBLK as u32 => Some(EntryType::BlockDevice),
+            m if m == libc::S_IFDIR as u32 => Some(EntryType::Directory),
+            m if m == libc::S_IFIFO as u32 => Some(EntryType::Fifo),
+            _ => None,
+        }
+    }
+}
+
+/// One archive member, independent of the archive format.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub path: PathBuf,
+    pub kind: EntryType,
+    /// Permission bits, including set-ID and sticky bits
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub uname: Option<String>,
+    pub gname: Option<String>,
+    /// Archives only record the modification time; readers use it for both
+    pub atime: i64,
+    pub mtime: i64,
+    pub size: u64,
+    /// Target of a hard link (an earlier member) or contents of a symlink
+    pub link_target: Option<PathBuf>,
+    pub nlink: u32,
+    pub dev: u64,
+    pub ino: u64,
+    pub rdev_major: u32,
+    pub rdev_minor: u32,
+}
+
+pub fn path_bytes(path: &Path) -> &[u8] {
+    path.as_os_str().as_bytes()
+}
+
+pub fn bytes_path(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(OsStr::from_bytes(bytes))
+}
+
+// largest extended header or long name read into memory
+const EXTENSION_MAX: u64 = 1 << 20;
+
+// largest cpio symlink target read into memory
+const LINK_TARGET_MAX: u64 = libc::PATH_MAX as u64;
+
+fn data_padding(format: Format, size: u64) -> u64 {
+    match format {
+        Format::Ustar => (512 - size % 512) % 512,
+        Format::Cpio => 0,
+    }
+}
+
+/// Sequential reader of archive members.
+pub struct Reader {
+    rdr: Box<dyn Read>,
+    format: Format,
+    // bytes read ahead while detecting the format
+    pending: Vec<u8>,
+    // data bytes (plus padding) of the current member not consumed yet
+    remaining: u64,
+    padding: u64,
+    /// Offset of the start of the last header read
+    pub header_offset: u64,
+    offset: u64,
+    // cpio marks hard links by repeating (dev, ino)
+    cpio_links: HashMap<(u64, u64), PathBuf>,
+}
+
+impl Reader {
+    pub fn new(mut rdr: Box<dyn Read>) -> io::Result<Reader> {
+        let mut pending = vec![0; 512];
+        let mut n = 0;
+        while n < pending.len() {
+            match rdr.read(&mut pending[n..]) {
+                Ok(0) => break,
+                Ok(len) => n += len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        pending.truncate(n);
+
+        let format = if pending.starts_with(cpio::MAGIC) {
+            Format::Cpio
+        } else if ustar::is_header(&pending) {
+            Format::Ustar
+        } else if pending.is_empty() {
+            return Err(io::Error::other("empty archive"));
+        } else {
+            return Err(io::Error::other("unknown archive format"));
+        };
+
+        Ok(Reader {
+            rdr,
+            format,
+            pending,
+            remaining: 0,
+            padding: 0,
+            header_offset: 0,
+            offset: 0,
+            cpio_links: HashMap::new(),
+        })
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    fn read_some(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.pending.is_empty() {
+            let n = buf.len().min(self.pending.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            self.offset += n as u64;
+            return Ok(n);
+        }
+
+        loop {
+            match self.rdr.read(buf) {
+                Ok(n) => {
+                    self.offset += n as u64;
+                    return Ok(n);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn read_full(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        let mut n = 0;
+        while n < buf.len() {
+            let len = self.read_some(&mut buf[n..])?;
+            if len == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "unexpected end of archive",
+                ));
+            }
+            n += len;
+        }
+        Ok(())
+    }
+
+    fn discard(&mut self, mut count: u64) -> io::Result<()> {
+        let mut buf = [0; 8192];
+        while count > 0 {
+            let len = count.min(buf.len() as u64) as usize;
+            self.read_full(&mut buf[..len])?;
+            count -= len as u64;
+        }
+        Ok(())
+    }
+
+    /// Read the header of the next member, skipping any data of the
+    /// current one. Returns `None` at the end of the archive.
+    pub fn next_entry(&mut self) -> io::Result<Option<Entry>> {
+        self.skip_data()?;
+
+        self.header_offset = self.offset;
+        let entry = match self.format {
+            Format::Ustar => {
+                // names too long for the header come before it
+                let mut long_name = None;
+                let mut long_link = None;
+
+                let entry = loop {
+                    let mut header = [0; 512];
+                    match self.read_full(&mut header) {
+                        Ok(()) => {}
+                        // some writers omit the trailer
+                        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+                        Err(e) => return Err(e),
+                    }
+
+                    let (typeflag, size) = match ustar::decode(&header)? {
+                        Some(ustar::Header::Entry(entry)) => break Some(entry),
+                        Some(ustar::Header::Extension(typeflag, size)) => (typeflag, size),
+                        None => break None,
+                    };
+                    if size > EXTENSION_MAX {
+                        return Err(io::Error::other("ustar extended header too large"));
+                    }
+                    let mut data = vec![0; size as usize];
+                    self.read_full(&mut data)?;
+                    self.discard(data_padding(self.format, size))?;
+
+                    let c_string = |data: &[u8]| {
+                        let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+                        data[..end].to_vec()
+                    };
+                    match typeflag {
+                        b'L' => long_name = Some(c_string(&data)),
+                        b'K' => long_link = Some(c_string(&data)),
+                        b'x' => {
+                            for (key, value) in ustar::pax_records(&data) {
+                                match key {
+                                    b"path" => long_name = Some(value.to_vec()),
+                                    b"linkpath" => long_link = Some(value.to_vec()),
+                                    _ => {}
+                                }
+                            }
+                        }
+                        // other extended data is not used
+                        _ => {}
+                    }
+                };
+
+                entry.map(|mut entry| {
+                    if let Some(name) = long_name {
+                        entry.path = bytes_path(&name);
+                    }
+                    if let (Some(link), Some(_)) = (long_link, &entry.link_target) {
+                        entry.link_target = Some(bytes_path(&link));
+                    }
+                    entry
+                })
+            }
+            Format::Cpio => {
+                let mut header = [0; cpio::HEADER_LEN];
+                self.read_full(&mut header)?;
+                let (mut entry, name_len) = cpio::decode(&header)?;
+                let mut name = vec![0; name_len];
+                self.read_full(&mut name)?;
+                match name.iter().position(|&b| b == 0) {
+                    Some(end) => name.truncate(end),
+                    None => return Err(io::Error::other("invalid cpio header")),
+                }
+                if name == cpio::TRAILER {
+                    None
+                } else {
+                    entry.path = bytes_path(&name);
+                    Some(entry)
+                }
+            }
+        };
+
+        let mut entry = match entry {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        self.remaining = entry.size;
+        self.padding = data_padding(self.format, entry.size);
+
+        if self.format == Format::Cpio {
+            // cpio stores symlink contents as file data
+            if entry.kind == EntryType::Symlink {
+                if entry.size > LINK_TARGET_MAX {
+                    return Err(io::Error::other("invalid cpio header"));
+                }
+                let mut target = vec![0; entry.size as usize];
+                self.read_full(&mut target)?;
+                self.remaining = 0;
+                entry.link_target = Some(bytes_path(&target));
+                entry.size = 0;
+            } else if entry.kind == EntryType::Regular && entry.nlink > 1 {
+                let id = (entry.dev, entry.ino);
+                match self.cpio_links.get(&id) {
+                    // each link may carry its own copy of the data; it is skipped
+                    Some(first) => {
+                        entry.kind = EntryType::HardLink;
+                        entry.link_target = Some(first.clone());
+                        entry.size = 0;
+                    }
+                    None => {
+                        self.cpio_links.insert(id, entry.path.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(Some(entry))
+    }
+
+    /// Copy the data of the current member to `out`.
+    pub fn copy_data(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        let mut buf = vec![0; 64 * 1024];
+        while self.remaining > 0 {
+            let len = self.remaining.min(buf.len() as u64) as usize;
+            self.read_full(&mut buf[..len])?;
+            out.write_all(&buf[..len])?;
+            self.remaining -= len as u64;
+        }
+        Ok(())
+    }
+
+    pub fn skip_data(&mut self) -> io::Result<()> {
+        let count = self.remaining + self.padding;
+        self.remaining = 0;
+        self.padding = 0;
+        self.discard(count)
+    }
+}
+
+/// Writes `out` in fixed-size blocks, as archives on tape require.
+struct BlockWriter {
+    out: Box<dyn Write>,
+    block: Vec<u8>,
+    block_size: usize,
+}
+
+impl BlockWriter {
+    fn write(&mut self, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            let len = data.len().min(self.block_size - self.block.len());
+            self.block.extend_from_slice(&data[..len]);
+            data = &data[len..];
+            if self.block.len() == self.block_size {
+                self.out.write_all(&self.block)?;
+                self.block.clear();
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if !self.block.is_empty() {
+            self.block.resize(self.block_size, 0);
+            self.out.write_all(&self.block)?;
+            self.block.clear();
+        }
+        self.out.flush()
+    }
+}
+
+/// Sequential writer of archive members.
+pub struct Writer {
+    out: BlockWriter,
+    format: Format,
+    // archived hard links, by (dev, ino), and their first name
+    links: HashMap<(u64, u64), PathBuf>,
+    // cpio inode numbers are only 6 octal digits, so they are renumbered
+    inodes: HashMap<(u64, u64), u64>,
+}
+
+impl Writer {
+    pub fn new(out: Box<dyn Write>, format: Format, block_size: usize) -> Writer {
+        Writer {
+            out: BlockWriter {
+                out,
+                block: Vec::with_capacity(block_size),
+                block_size,
+            },
+            format,
+            links: HashMap::new(),
+            inodes: HashMap::new(),
+        }
+    }
+
+    /// Write `entry` followed by `entry.size` bytes from `data`.
+    pub fn write_entry(&mut self, entry: &Entry, data: Option<&mut dyn Read>) -> io::Result<()> {
+        let mut entry = entry.clone();
+
+        match self.format {
+            Format::Ustar => {
+                // later names of a multiply linked file become hard links
+                if entry.kind == EntryType::Regular && entry.nlink > 1 {
+                    let id = (entry.dev, entry.ino);
+                    match self.links.get(&id) {
+                        Some(first) => {
+                            entry.kind = EntryType::HardLink;
+                            entry.link_target = Some(first.clone());
+                            entry.size = 0;
+                        }
+                        None => {
+                            self.links.insert(id, entry.path.clone());
+                        }
+                    }
+                }
+                self.out.write(&ustar::encode(&entry)?)?;
+            }
+            Format::Cpio => {
+                let next_ino = self.inodes.len() as u64 + 1;
+                entry.ino = *self
+                    .inodes
+                    .entry((entry.dev, entry.ino))
+                    .or_insert(next_ino);
+                entry.dev = 0;
+
+                if entry.kind == EntryType::Symlink {
+                    let target = entry.link_target.clone().unwrap_or_default();
+                    entry.size = path_bytes(&target).len() as u64;
+                    self.out.write(&cpio::encode(&entry)?)?;
+                    self.out.write(path_bytes(&target))?;
+                    return Ok(());
+                }
+                self.out.write(&cpio::encode(&entry)?)?;
+            }
+        }
+
+        if entry.kind != EntryType::Regular || entry.size == 0 {
+            return Ok(());
+        }
+
+        let data = match data {
+            Some(data) => data,
+            None => return Err(io::Error::other("missing file data")),
+        };
+
+        // the size is already in the header: pad or cut the data to match it,
+        // in case the file changed while it was being archived
+        let mut buf = vec![0; 64 * 1024];
+        let mut remaining = entry.size;
+        let mut short = false;
+        while remaining > 0 {
+            let len = remaining.min(buf.len() as u64) as usize;
+            let n = if short {
+                0
+            } else {
+                match data.read(&mut buf[..len]) {
+                    Ok(n) => n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                }
+            };
+            if n == 0 {
+                short = true;
+                buf[..len].fill(0);
+                self.out.write(&buf[..len])?;
+                remaining -= len as u64;
+            } else {
+                self.out.write(&buf[..n])?;
+                remaining -= n as u64;
+            }
+        }
+
+        let padding = data_padding(self.format, entry.size) as usize;
+        self.out.write(&vec![0; padding])?;
+
+        if short {
+            return Err(io::Error::other("file shrank while being archived"));
+        }
+        Ok(())
+    }
+
+    /// Write the trailer and pad the last block.
+    pub fn finish(&mut self) -> io::Result<()> {
+        match self.format {
+            Format::Ustar => self.out.write(&[0; 1024])?,
+            Format::Cpio => {
+                let trailer = Entry {
+                    path: bytes_path(cpio::TRAILER),
+                    kind: EntryType::Regular,
+                    mode: 0,
+                    uid: 0,
+                    gid: 0,
+                    uname: None,
+                    gname: None,
+                    atime: 0,
+                    mtime: 0,
+                    size: 0,
+                    link_target: None,
+                    nlink: 1,
+                    dev: 0,
+                    ino: 0,
+                    rdev_major: 0,
+                    rdev_minor: 0,
+                };
+                let mut header = cpio::encode(&trailer)?;
+                // a trailer has no file type
+                header[18..24].copy_from_slice(b"000000");
+                self.out.write(&header)?;
+            }
+        }
+        self.out.finish()
+    }
+}
@@ -0,0 +1,126 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The `-s /old/new/[gp]` name substitutions.
+
+use plib::regex::bre_to_regex;
+use regex::bytes::Regex;
+
+pub struct Substitution {
+    regex: Regex,
+    replacement: Vec<u8>,
+    global: bool,
+    print: bool,
+}
+
+impl Substitution {
+    /// Parse `/old/new/[gp]`, where any character may stand in for '/'.
+    pub fn parse(spec: &str) -> Result<Substitution, String> {
+        let invalid = || format!("invalid substitution: {}", spec);
+
+        let mut chars = spec.chars();
+        let delim = chars.next().ok_or_else(invalid)?;
+        if delim == '\\' {
+            return Err(invalid());
+        }
+
+        // split on unescaped delimiters; "\<delim>" stands for the delimiter
+        let mut parts = vec![String::new()];
+        let mut escaped = false;
+        for ch in chars {
+            if escaped {
+                if ch != delim {
+                    parts.last_mut().unwrap().push('\\');
+                }
+                parts.last_mut().unwrap().push(ch);
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == delim && parts.len() < 3 {
+                parts.push(String::new());
+            } else {
+                parts.last_mut().unwrap().push(ch);
+            }
+        }
+        if escaped {
+            parts.last_mut().unwrap().push('\\');
+        }
+        if parts.len() != 3 {
+            return Err(invalid());
+        }
+
+        let mut global = false;
+        let mut print = false;
+        for flag in parts[2].chars() {
+            match flag {
+                'g' => global = true,
+                'p' => print = true,
+                _ => return Err(invalid()),
+            }
+        }
+
+        let regex = Regex::new(&bre_to_regex(&parts[0]))
+            .map_err(|e| format!("invalid substitution: {}: {}", spec, e))?;
+
+        Ok(Substitution {
+            regex,
+            replacement: parts[1].as_bytes().to_vec(),
+            global,
+            print,
+        })
+    }
+
+    pub fn print(&self) -> bool {
+        self.print
+    }
+
+    fn expand(&self, caps: &regex::bytes::Captures, out: &mut Vec<u8>) {
+        let mut iter = self.replacement.iter();
+        while let Some(&b) = iter.next() {
+            match b {
+                b'&' => out.extend_from_slice(&caps[0]),
+                b'\\' => match iter.next() {
+                    Some(&d) if d.is_ascii_digit() => {
+                        if let Some(m) = caps.get((d - b'0') as usize) {
+                            out.extend_from_slice(m.as_bytes());
+                        }
+                    }
+                    Some(&c) => out.push(c),
+                    None => out.push(b'\\'),
+                },
+                _ => out.push(b),
+            }
+        }
+    }
+
+    /// Apply the substitution, returning `None` if the expression does
+    /// not match `name`.
+    pub fn apply(&self, name: &[u8]) -> Option<Vec<u8>> {
+        let mut out = Vec::with_capacity(name.len());
+        let mut last = 0;
+        let mut matched = false;
+
+        for caps in self.regex.captures_iter(name) {
+            let m = caps.get(0).unwrap();
+            out.extend_from_slice(&name[last..m.start()]);
+            self.expand(&caps, &mut out);
+            last = m.end();
+            matched = true;
+            if !self.global {
+                break;
+            }
+        }
+
+        if !matched {
+            return None;
+        }
+        out.extend_from_slice(&name[last..]);
+        Some(out)
+    }
+}
@@ -0,0 +1,286 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The POSIX ustar header: 512 bytes, numbers in NUL-terminated octal.
+
+use super::{bytes_path, path_bytes, Entry, EntryType};
+use std::io;
+
+const NAME: (usize, usize) = (0, 100);
+const MODE: (usize, usize) = (100, 8);
+const UID: (usize, usize) = (108, 8);
+const GID: (usize, usize) = (116, 8);
+const SIZE: (usize, usize) = (124, 12);
+const MTIME: (usize, usize) = (136, 12);
+const CHKSUM: (usize, usize) = (148, 8);
+const TYPEFLAG: usize = 156;
+const LINKNAME: (usize, usize) = (157, 100);
+const MAGIC: (usize, usize) = (257, 6);
+const VERSION: (usize, usize) = (263, 2);
+const UNAME: (usize, usize) = (265, 32);
+const GNAME: (usize, usize) = (297, 32);
+const DEVMAJOR: (usize, usize) = (329, 8);
+const DEVMINOR: (usize, usize) = (337, 8);
+const PREFIX: (usize, usize) = (345, 155);
+
+fn field(header: &[u8], (start, len): (usize, usize)) -> &[u8] {
+    let bytes = &header[start..start + len];
+    match bytes.iter().position(|&b| b == 0) {
+        Some(end) => &bytes[..end],
+        None => bytes,
+    }
+}
+
+fn parse_octal(header: &[u8], f: (usize, usize)) -> io::Result<u64> {
+    let text = std::str::from_utf8(field(header, f)).unwrap_or("");
+    let text = text.trim_matches(|c| c == ' ' || c == '\0');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| io::Error::other("invalid ustar header"))
+}
+
+fn checksum(header: &[u8]) -> u64 {
+    header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| {
+            if (CHKSUM.0..CHKSUM.0 + CHKSUM.1).contains(&i) {
+                b' ' as u64
+            } else {
+                b as u64
+            }
+        })
+        .sum()
+}
+
+/// True if `block` starts with a ustar header (POSIX or the older GNU magic).
+pub fn is_header(block: &[u8]) -> bool {
+    block.len() >= 512 && &block[MAGIC.0..MAGIC.0 + 5] == b"ustar"
+}
+
+/// A decoded header block.
+pub enum Header {
+    /// An archive member.
+    Entry(Entry),
+    /// Data about the member that follows, with its typeflag and size: a
+    /// GNU long name ('L') or link name ('K'), or a pax extended header
+    /// ('x', or 'g' for all following members).
+    Extension(u8, u64),
+}
+
+/// Decode a header block, or return `None` for the zero block that ends
+/// the archive.
+pub fn decode(header: &[u8]) -> io::Result<Option<Header>> {
+    if header.iter().all(|&b| b == 0) {
+        return Ok(None);
+    }
+
+    if parse_octal(header, CHKSUM)? != checksum(header) {
+        return Err(io::Error::other("ustar header checksum error"));
+    }
+
+    let kind = match header[TYPEFLAG] {
+        b'1' => EntryType::HardLink,
+        b'2' => EntryType::Symlink,
+        b'3' => EntryType::CharDevice,
+        b'4' => EntryType::BlockDevice,
+        b'5' => EntryType::Directory,
+        b'6' => EntryType::Fifo,
+        flag @ (b'L' | b'K' | b'x' | b'g') => {
+            return Ok(Some(Header::Extension(flag, parse_octal(header, SIZE)?)))
+        }
+        // '0', '7' (contiguous) and any unknown type are regular files
+        _ => EntryType::Regular,
+    };
+
+    let mut name = Vec::new();
+    let prefix = field(header, PREFIX);
+    // the old GNU format puts other data where the prefix belongs
+    if !prefix.is_empty() && &header[MAGIC.0..MAGIC.0 + 6] == b"ustar\0" {
+        name.extend_from_slice(prefix);
+        name.push(b'/');
+    }
+    name.extend_from_slice(field(header, NAME));
+
+    let text = |f| {
+        let bytes = field(header, f);
+        if bytes.is_empty() {
+            None
+        } else {
+            Some(String::from_utf8_lossy(bytes).into_owned())
+        }
+    };
+
+    let link_target = match kind {
+        EntryType::HardLink | EntryType::Symlink => Some(bytes_path(field(header, LINKNAME))),
+        _ => None,
+    };
+
+    let mtime = parse_octal(header, MTIME)? as i64;
+    let size = match kind {
+        EntryType::Regular => parse_octal(header, SIZE)?,
+        _ => 0,
+    };
+
+    Ok(Some(Header::Entry(Entry {
+        path: bytes_path(&name),
+        kind,
+        mode: parse_octal(header, MODE)? as u32 & 0o7777,
+        uid: parse_octal(header, UID)? as u32,
+        gid: parse_octal(header, GID)? as u32,
+        uname: text(UNAME),
+        gname: text(GNAME),
+        atime: mtime,
+        mtime,
+        size,
+        link_target,
+        nlink: 1,
+        dev: 0,
+        ino: 0,
+        rdev_major: parse_octal(header, DEVMAJOR)? as u32,
+        rdev_minor: parse_octal(header, DEVMINOR)? as u32,
+    })))
+}
+
+/// The `key=value` records of a pax extended header, each written as
+/// "length key=value\n", where the length counts the whole record.
+pub fn pax_records(data: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut records = Vec::new();
+    let mut rest = data;
+
+    while let Some(space) = rest.iter().position(|&b| b == b' ') {
+        let len = std::str::from_utf8(&rest[..space])
+            .ok()
+            .and_then(|text| text.parse::<usize>().ok());
+        let len = match len {
+            Some(len) if len > space && len <= rest.len() => len,
+            _ => break,
+        };
+
+        let record = &rest[space + 1..len];
+        let record = record.strip_suffix(b"\n").unwrap_or(record);
+        if let Some(eq) = record.iter().position(|&b| b == b'=') {
+            records.push((&record[..eq], &record[eq + 1..]));
+        }
+        rest = &rest[len..];
+    }
+
+    records
+}
+
+fn put_bytes(header: &mut [u8], (start, len): (usize, usize), bytes: &[u8]) {
+    header[start..start + bytes.len().min(len)].copy_from_slice(&bytes[..bytes.len().min(len)]);
+}
+
+fn put_octal(header: &mut [u8], f: (usize, usize), value: u64, what: &str) -> io::Result<()> {
+    let text = format!("{:0width$o}", value, width = f.1 - 1);
+    if text.len() > f.1 - 1 {
+        return Err(io::Error::other(format!(
+            "{} too large for ustar format",
+            what
+        )));
+    }
+    put_bytes(header, f, text.as_bytes());
+    Ok(())
+}
+
+/// Split a path into the prefix and name fields, at a '/'.
+fn split_name(path: &[u8]) -> Option<(&[u8], &[u8])> {
+    if path.len() <= NAME.1 {
+        return Some((&[], path));
+    }
+
+    // the name part must be non-empty and fit in 100 bytes,
+    // the prefix in 155
+    let start = path.len().saturating_sub(NAME.1 + 1);
+    let split =
+        (start..path.len().min(PREFIX.1 + 1)).find(|&i| path[i] == b'/' && i + 1 < path.len())?;
+    Some((&path[..split], &path[split + 1..]))
+}
+
+pub fn encode(entry: &Entry) -> io::Result<Vec<u8>> {
+    let mut header = vec![0; 512];
+
+    let mut path = path_bytes(&entry.path).to_vec();
+    if entry.kind == EntryType::Directory && !path.ends_with(b"/") {
+        path.push(b'/');
+    }
+    let (prefix, name) = match split_name(&path) {
+        Some(parts) => parts,
+        None => {
+            return Err(io::Error::other(format!(
+                "{}: file name too long for ustar format",
+                entry.path.display()
+            )))
+        }
+    };
+    put_bytes(&mut header, NAME, name);
+    put_bytes(&mut header, PREFIX, prefix);
+
+    put_octal(&mut header, MODE, (entry.mode & 0o7777) as u64, "mode")?;
+    put_octal(&mut header, UID, entry.uid as u64, "user ID")?;
+    put_octal(&mut header, GID, entry.gid as u64, "group ID")?;
+    put_octal(&mut header, SIZE, entry.size, "file size")?;
+    put_octal(
+        &mut header,
+        MTIME,
+        entry.mtime.max(0) as u64,
+        "modification time",
+    )?;
+
+    header[TYPEFLAG] = match entry.kind {
+        EntryType::Regular => b'0',
+        EntryType::HardLink => b'1',
+        EntryType::Symlink => b'2',
+        EntryType::CharDevice => b'3',
+        EntryType::BlockDevice => b'4',
+        EntryType::Directory => b'5',
+        EntryType::Fifo => b'6',
+    };
+
+    if let Some(target) = &entry.link_target {
+        let target = path_bytes(target);
+        if target.len() > LINKNAME.1 {
+            return Err(io::Error::other(format!(
+                "{}: link name too long for ustar format",
+                entry.path.display()
+            )));
+        }
+        put_bytes(&mut header, LINKNAME, target);
+    }
+
+    put_bytes(&mut header, MAGIC, b"ustar\0");
+    put_bytes(&mut header, VERSION, b"00");
+    if let Some(uname) = &entry.uname {
+        put_bytes(&mut header, UNAME, uname.as_bytes());
+    }
+    if let Some(gname) = &entry.gname {
+        put_bytes(&mut header, GNAME, gname.as_bytes());
+    }
+    if matches!(entry.kind, EntryType::CharDevice | EntryType::BlockDevice) {
+        put_octal(
+            &mut header,
+            DEVMAJOR,
+            entry.rdev_major as u64,
+            "device number",
+        )?;
+        put_octal(
+            &mut header,
+            DEVMINOR,
+            entry.rdev_minor as u64,
+            "device number",
+        )?;
+    }
+
+    let sum = checksum(&header);
+    put_bytes(&mut header, CHKSUM, format!("{:06o}\0 ", sum).as_bytes());
+
+    Ok(header)
+}
@@ -15,7 +15,7 @@ use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
 use plib::PROJECT_NAME;
 use std::cmp;
 use std::fs::{File, OpenOptions};
-use std::io::{self, BufRead, Error, ErrorKind, Read, Write};
+use std::io::{self, BufRead, ErrorKind, Read, Write};
 use std::path::PathBuf;
 
 /// split - split a file into pieces
@@ -26,32 +26,27 @@ struct Args {
     #[arg(short='a', long, default_value_t=2, value_parser = clap::value_parser!(u32).range(1..))]
     suffix_len: u32,
 
-    /// Use suffix_length letters to form the suffix portion of the filenames of the split file.
+    /// Split a file into pieces of line_count lines.
     #[arg(short, long, group = "mode", value_parser = clap::value_parser!(u64).range(1..))]
     lines: Option<u64>,
 
-    /// Split a file into pieces n bytes in size.
+    /// Split a file into pieces n bytes in size; n may be followed by k (1024) or m (1048576).
     #[arg(short, long, group = "mode")]
     bytes: Option<String>,
 
-    /// File to be split
-    #[arg(default_value = "")]
-    file: PathBuf,
+    /// File to be split; if omitted or '-', the standard input is used
+    file: Option<PathBuf>,
 
     /// Prefix of output files
     #[arg(default_value = "x")]
     prefix: String,
 }
 
-fn inc_char(ch: char) -> char {
-    ((ch as u8) + 1) as char
-}
-
 struct OutputState {
     prefix: String,
     boundary: u64,
 
-    suffix: String,
+    suffix: Vec<u8>,
     suffix_len: u32,
     count: u64,
     outf: Option<File>,
@@ -63,40 +58,30 @@ impl OutputState {
             prefix: String::from(prefix),
             boundary,
             suffix_len,
-            suffix: String::new(),
+            suffix: Vec::new(),
             count: 0,
             outf: None,
         }
     }
 
+    // Advance the suffix like an odometer: aa, ab, ..., az, ba, ..., zz.
+    // Running out of names is an error rather than a wraparound, so
+    // that no earlier output file is overwritten.
     fn incr_suffix(&mut self) -> Result<(), &'static str> {
-        assert!(self.suffix_len > 1);
-
         if self.suffix.is_empty() {
-            self.suffix = String::from("a".repeat(self.suffix_len as usize));
+            self.suffix = vec![b'a'; self.suffix_len as usize];
             return Ok(());
         }
 
-        assert!(self.suffix.len() > 1);
-        let mut i = self.suffix.len() - 1;
-        loop {
-            let ch = self.suffix.chars().nth(i).unwrap();
-            if ch != 'z' {
-                self.suffix
-                    .replace_range(i..i + 1, inc_char(ch).to_string().as_str());
+        for ch in self.suffix.iter_mut().rev() {
+            if *ch != b'z' {
+                *ch += 1;
                 return Ok(());
             }
-
-            self.suffix
-                .replace_range(i..i + 1, 'a'.to_string().as_str());
-
-            if i == 0 {
-                break;
-            }
-            i = i - 1;
+            *ch = b'a';
         }
 
-        Err("maximum suffix reached")
+        Err("output file suffixes exhausted")
     }
 
     fn open_output(&mut self) -> io::Result<()> {
@@ -104,12 +89,9 @@ impl OutputState {
             return Ok(());
         }
 
-        let inc_res = self.incr_suffix();
-        if let Err(e) = inc_res {
-            return Err(Error::new(ErrorKind::Other, e));
-        }
+        self.incr_suffix().map_err(io::Error::other)?;
 
-        let out_fn = format!("{}{}", self.prefix, self.suffix);
+        let out_fn = format!("{}{}", self.prefix, String::from_utf8_lossy(&self.suffix));
         let f = OpenOptions::new()
             .read(false)
             .write(true)
@@ -167,32 +149,30 @@ impl OutputState {
     }
 }
 
-fn split_by_bytes(args: &Args, bytesplit: String) -> io::Result<()> {
-    let mul: u64 = {
-        if bytesplit.ends_with("k") {
-            1024
-        } else if bytesplit.ends_with("m") {
-            1024 * 1024
-        } else if bytesplit.ends_with("g") {
-            1024 * 1024 * 1024
-        } else {
-            1
-        }
-    };
-    let bytestr = match mul {
-        1 => &bytesplit[..],
-        _ => &bytesplit[0..bytesplit.len() - 1],
-    };
-    let boundary: u64 = match bytestr.parse::<u64>() {
-        Ok(n) => n * mul,
-        Err(e) => {
-            eprintln!("{}", e);
-            return Err(Error::new(ErrorKind::Other, "invalid byte spec"));
-        }
+// Parse the -b operand: a decimal count, optionally followed by
+// k (kilobytes) or m (megabytes).
+fn parse_byte_count(bytesplit: &str) -> io::Result<u64> {
+    let (digits, mul) = match bytesplit.as_bytes().last() {
+        Some(b'k') => (&bytesplit[..bytesplit.len() - 1], 1024),
+        Some(b'm') => (&bytesplit[..bytesplit.len() - 1], 1024 * 1024),
+        Some(b'g') => (&bytesplit[..bytesplit.len() - 1], 1024 * 1024 * 1024),
+        _ => (bytesplit, 1),
     };
 
+    match digits.parse::<u64>().ok().and_then(|n| n.checked_mul(mul)) {
+        Some(n) if n > 0 => Ok(n),
+        _ => Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid number of bytes: {}", bytesplit),
+        )),
+    }
+}
+
+fn split_by_bytes(args: &Args, file: &PathBuf, bytesplit: String) -> io::Result<()> {
+    let boundary = parse_byte_count(&bytesplit)?;
+
     // open file, or stdin
-    let mut file = plib::io::input_stream(&args.file, false)?;
+    let mut file = plib::io::input_stream(file, false)?;
     let mut raw_buffer = [0; plib::BUFSZ];
     let mut state = OutputState::new(&args.prefix, boundary, args.suffix_len);
 
@@ -212,23 +192,25 @@ fn split_by_bytes(args: &Args, bytesplit: String) -> io::Result<()> {
     Ok(())
 }
 
-fn split_by_lines(args: &Args, linesplit: u64) -> io::Result<()> {
+fn split_by_lines(args: &Args, file: &PathBuf, linesplit: u64) -> io::Result<()> {
     assert!(linesplit > 0);
 
     // open file, or stdin
-    let mut reader = plib::io::input_reader(&args.file, false)?;
+    let mut reader = plib::io::input_reader(file, false)?;
     let mut state = OutputState::new(&args.prefix, linesplit, args.suffix_len);
 
+    // lines are handled as bytes, input need not be valid UTF-8
+    let mut buffer = Vec::new();
     loop {
-        let mut buffer = String::new();
-        let n_read = reader.read_line(&mut buffer)?;
+        buffer.clear();
+        let n_read = reader.read_until(b'\n', &mut buffer)?;
         if n_read == 0 {
             break;
         }
 
         state.open_output()?;
 
-        state.write(buffer.as_ref())?;
+        state.write(&buffer)?;
 
         state.incr_output(1);
     }
@@ -244,14 +226,25 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     textdomain(PROJECT_NAME)?;
     bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
 
+    // an empty pathname selects stdin in plib::io
+    let file = match &args.file {
+        Some(path) if path.as_os_str() != "-" => path.clone(),
+        _ => PathBuf::new(),
+    };
+
     if args.lines.is_none() && args.bytes.is_none() {
         args.lines = Some(1000);
     }
 
-    if args.lines.is_some() {
-        split_by_lines(&args, args.lines.unwrap())?;
+    let res = if let Some(lines) = args.lines {
+        split_by_lines(&args, &file, lines)
     } else {
-        split_by_bytes(&args, args.bytes.clone().unwrap())?;
+        split_by_bytes(&args, &file, args.bytes.clone().unwrap())
+    };
+
+    if let Err(e) = res {
+        eprintln!("split: {}", e);
+        std::process::exit(1);
     }
 
     Ok(())
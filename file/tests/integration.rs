@@ -26,6 +26,7 @@ fn run_test_helper(
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code,
+        ..Default::default()
     });
 }
 
@@ -39,6 +40,7 @@ fn file_test(args: &[&str], expected_output: &str, expected_error: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -361,3 +363,574 @@ fn test_file_magic_file_priority_with_M_and_m_option_as_they_appear_using_cpio_a
         "",
     );
 }
+
+fn dd_test(args: &[&str], stdin_data: &str, expected_output: &str, expected_error: &str) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("dd"),
+        args: str_args,
+        stdin_data: String::from(stdin_data),
+        expected_out: String::from(expected_output),
+        expected_err: String::from(expected_error),
+        expected_exit_code: 0,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_dd_case_conv() {
+    dd_test(
+        &["conv=ucase", "status=noxfer"],
+        "Hello, World\n",
+        "HELLO, WORLD\n",
+        "0+1 records in\n0+1 records out\n",
+    );
+    dd_test(&["conv=lcase,swab", "status=none"], "ABCD", "badc", "");
+}
+
+#[test]
+fn test_dd_block_unblock() {
+    dd_test(
+        &["conv=block", "cbs=6", "status=noxfer"],
+        "abc\nabcdefgh\n",
+        "abc   abcdef",
+        "0+1 records in\n0+1 records out\n1 truncated record\n",
+    );
+    dd_test(
+        &["conv=unblock", "cbs=4", "status=none"],
+        "ab  abcdx   ",
+        "ab\nabcd\nx\n",
+        "",
+    );
+}
+
+#[test]
+fn test_dd_count_skip_suffix() {
+    let input = "0123456789".repeat(300);
+    dd_test(
+        &["bs=1x2", "skip=1k", "count=5", "status=noxfer"],
+        &input,
+        &input[2048..2058],
+        "5+0 records in\n5+0 records out\n",
+    );
+}
+
+#[test]
+fn test_dd_sync() {
+    dd_test(
+        &["bs=4", "conv=sync,block", "cbs=4", "status=none"],
+        "ab\n",
+        "ab      ",
+        "",
+    );
+    dd_test(
+        &["ibs=4", "obs=2", "conv=sync", "status=noxfer"],
+        "abcdef",
+        "abcdef\0\0",
+        "1+1 records in\n4+0 records out\n",
+    );
+}
+
+#[test]
+fn test_dd_invalid_conv() {
+    run_test(TestPlan {
+        cmd: String::from("dd"),
+        args: vec![String::from("conv=upper")],
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::from("invalid conv option: upper\nError: \"invalid conv option\"\n"),
+        expected_exit_code: 1,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_dd_sigusr1_blocked_read() {
+    use std::io::{Read, Write};
+    use std::process::{Command, Stdio};
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dd"))
+        .args(["bs=512", "status=noxfer"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = child.stdout.take().unwrap();
+    let mut stderr = child.stderr.take().unwrap();
+
+    // once the first block is copied, dd goes on to wait in read()
+    stdin.write_all(b"abc").unwrap();
+    let mut block = [0; 3];
+    stdout.read_exact(&mut block).unwrap();
+    assert_eq!(&block, b"abc");
+    thread::sleep(Duration::from_millis(200));
+
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = [0; 256];
+        let mut report = Vec::new();
+        while report.iter().filter(|&&b| b == b'\n').count() < 2 {
+            match stderr.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => report.extend_from_slice(&buf[..n]),
+            }
+        }
+        tx.send(report).unwrap();
+    });
+
+    unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGUSR1) };
+    let report = rx.recv_timeout(Duration::from_secs(5));
+
+    drop(stdin);
+    child.wait().unwrap();
+    assert_eq!(
+        String::from_utf8(report.expect("no statistics while the read was blocked")).unwrap(),
+        "0+1 records in\n0+1 records out\n"
+    );
+}
+
+#[test]
+fn test_cmp_list_all_differences() {
+    let original = "tests/cmp/lorem_ipsum.txt";
+    let modified = "tests/cmp/lorem_ipsum_multi.txt";
+
+    run_test_helper(
+        &["-l", original, modified],
+        &format!(
+            "1 {:o} {:o}\n46 {:o} {:o}\n451 {:o} {:o}\n",
+            b'L', b'?', b's', b'?', b'.', b'?'
+        ),
+        "",
+        1,
+    );
+}
+
+#[test]
+fn test_cmp_list_eof() {
+    let original = "tests/cmp/lorem_ipsum.txt";
+    let truncated = "tests/cmp/lorem_ipsum_trunc.txt";
+
+    // identical prefix: nothing listed, but the EOF is still diagnosed
+    run_test_helper(
+        &["-l", truncated, original],
+        "",
+        &format!("cmp: EOF on {truncated}\n"),
+        1,
+    );
+    run_test_helper(&["-s", truncated, original], "", "", 1);
+}
+
+fn split_test(args: &[&str], stdin_data: &str, expected_error: &str, expected_exit_code: i32) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("split"),
+        args: str_args,
+        stdin_data: String::from(stdin_data),
+        expected_out: String::new(),
+        expected_err: String::from(expected_error),
+        expected_exit_code,
+        ..Default::default()
+    });
+}
+
+// Per-test output directory, so that concurrently running tests do not
+// see each other's pieces.
+fn split_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("split-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn split_pieces(dir: &PathBuf) -> Vec<(String, String)> {
+    let mut pieces: Vec<(String, String)> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            (
+                path.file_name().unwrap().to_string_lossy().into_owned(),
+                std::fs::read_to_string(&path).unwrap(),
+            )
+        })
+        .collect();
+    pieces.sort();
+    pieces
+}
+
+#[test]
+fn test_split_bytes_stdin() {
+    let dir = split_dir("bytes");
+    let prefix = dir.join("x");
+
+    split_test(
+        &["-b", "4", "-", prefix.to_str().unwrap()],
+        "abcdefghij",
+        "",
+        0,
+    );
+    assert_eq!(
+        split_pieces(&dir),
+        vec![
+            ("xaa".to_string(), "abcd".to_string()),
+            ("xab".to_string(), "efgh".to_string()),
+            ("xac".to_string(), "ij".to_string()),
+        ]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_split_bytes_suffix_multiplier() {
+    let dir = split_dir("kbytes");
+    let prefix = dir.join("part");
+    let input = "x".repeat(2500);
+
+    split_test(
+        &["-b", "1k", "-a", "3", "-", prefix.to_str().unwrap()],
+        &input,
+        "",
+        0,
+    );
+    let sizes: Vec<(String, usize)> = split_pieces(&dir)
+        .into_iter()
+        .map(|(name, data)| (name, data.len()))
+        .collect();
+    assert_eq!(
+        sizes,
+        vec![
+            ("partaaa".to_string(), 1024),
+            ("partaab".to_string(), 1024),
+            ("partaac".to_string(), 452),
+        ]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_split_lines_suffix_exhausted() {
+    let dir = split_dir("lines");
+    let prefix = dir.join("x");
+    let input: String = (1..=28).map(|i| format!("{}\n", i)).collect();
+
+    // 28 one-line pieces need more than the 26 one-letter suffixes
+    split_test(
+        &["-l", "1", "-a", "1", "-", prefix.to_str().unwrap()],
+        &input,
+        "split: output file suffixes exhausted\n",
+        1,
+    );
+    let pieces = split_pieces(&dir);
+    assert_eq!(pieces.len(), 26);
+    assert_eq!(pieces[0], ("xa".to_string(), "1\n".to_string()));
+    assert_eq!(pieces[25], ("xz".to_string(), "26\n".to_string()));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_split_invalid_byte_count() {
+    split_test(&["-b", "0"], "", "split: invalid number of bytes: 0\n", 1);
+}
+
+fn pax_test(args: &[&str], expected_output: &str, expected_error: &str, expected_exit_code: i32) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("pax"),
+        args: str_args,
+        stdin_data: String::new(),
+        expected_out: String::from(expected_output),
+        expected_err: String::from(expected_error),
+        expected_exit_code,
+        ..Default::default()
+    });
+}
+
+fn pax_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("pax-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+const PAX_TREE_LIST: &str =
+    "tests/pax/tree/\ntests/pax/tree/a.txt\ntests/pax/tree/sub/\ntests/pax/tree/sub/b.txt\n";
+
+fn pax_round_trip(format: &str) {
+    let dir = pax_dir(format);
+    let archive = dir.join("archive");
+    let archive = archive.to_str().unwrap();
+
+    pax_test(
+        &["-w", "-x", format, "-f", archive, "tests/pax/tree"],
+        "",
+        "",
+        0,
+    );
+    // only ustar marks directories with a trailing slash
+    let list = match format {
+        "ustar" => PAX_TREE_LIST.to_string(),
+        _ => PAX_TREE_LIST.replace("/\n", "\n"),
+    };
+    pax_test(&["-f", archive], &list, "", 0);
+
+    // extract below the temporary directory
+    let subst = format!(",^tests/pax,{},", dir.to_str().unwrap());
+    pax_test(&["-r", "-s", &subst, "-f", archive], "", "", 0);
+    assert_eq!(
+        std::fs::read_to_string(dir.join("tree/a.txt")).unwrap(),
+        "hello\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("tree/sub/b.txt")).unwrap(),
+        "world\n"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_pax_ustar_round_trip() {
+    pax_round_trip("ustar");
+}
+
+#[test]
+fn test_pax_cpio_round_trip() {
+    pax_round_trip("cpio");
+}
+
+#[test]
+fn test_pax_list_patterns() {
+    let dir = pax_dir("patterns");
+    let archive = dir.join("archive.tar");
+    let archive = archive.to_str().unwrap();
+
+    pax_test(&["-w", "-f", archive, "tests/pax/tree"], "", "", 0);
+    pax_test(
+        &["-f", archive, "tests/pax/tree/sub"],
+        "tests/pax/tree/sub/\ntests/pax/tree/sub/b.txt\n",
+        "",
+        0,
+    );
+    pax_test(
+        &["-c", "-f", archive, "*/sub*"],
+        "tests/pax/tree/\ntests/pax/tree/a.txt\n",
+        "",
+        0,
+    );
+    pax_test(
+        &["-f", archive, "-s", ",^tests/pax/,,", "*.txt"],
+        "tree/a.txt\ntree/sub/b.txt\n",
+        "",
+        0,
+    );
+    pax_test(
+        &["-f", archive, "nomatch"],
+        "",
+        "pax: nomatch: pattern not matched\n",
+        1,
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_pax_copy() {
+    let dir = pax_dir("copy");
+
+    pax_test(
+        &["-r", "-w", "tests/pax/tree", dir.to_str().unwrap()],
+        "",
+        "",
+        0,
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("tests/pax/tree/a.txt")).unwrap(),
+        "hello\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("tests/pax/tree/sub/b.txt")).unwrap(),
+        "world\n"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_pax_read_umask() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = pax_dir("umask");
+    let archive = dir.join("archive.tar");
+
+    pax_test(
+        &[
+            "-w",
+            "-f",
+            archive.to_str().unwrap(),
+            "tests/pax/tree/a.txt",
+        ],
+        "",
+        "",
+        0,
+    );
+
+    // without -p p, the mode of extracted files is limited by the umask
+    run_test(TestPlan {
+        cmd: String::from("pax"),
+        args: vec![
+            String::from("-r"),
+            String::from("-f"),
+            String::from("archive.tar"),
+        ],
+        cwd: Some(dir.clone()),
+        umask: Some(0o077),
+        ..Default::default()
+    });
+    let mode = std::fs::metadata(dir.join("tests/pax/tree/a.txt"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// A ustar member named `name`, of type `typeflag`, holding `data`
+fn ustar_member(archive: &mut Vec<u8>, name: &str, typeflag: u8, data: &[u8]) {
+    let mut header = vec![0; 512];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[100..107].copy_from_slice(b"0000644");
+    header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+    header[156] = typeflag;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[148..156].fill(b' ');
+    let sum: u32 = header.iter().map(|&b| b as u32).sum();
+    header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+
+    archive.extend_from_slice(&header);
+    archive.extend_from_slice(data);
+    archive.resize(archive.len().next_multiple_of(512), 0);
+}
+
+#[test]
+fn test_pax_ustar_extensions() {
+    let dir = pax_dir("extensions");
+    let archive = dir.join("archive.tar");
+
+    let long_name = format!("long/{}", "n".repeat(120));
+    let mut data = Vec::new();
+    // a GNU long name, then its member with the name cut short
+    ustar_member(
+        &mut data,
+        "././@LongLink",
+        b'L',
+        format!("{}\0", long_name).as_bytes(),
+    );
+    ustar_member(&mut data, &long_name[..100], b'0', b"one\n");
+    // a pax extended header renaming the next member
+    ustar_member(&mut data, "PaxHeaders/b", b'x', b"21 path=pax-name.txt\n");
+    ustar_member(&mut data, "b", b'0', b"two\n");
+    // a global header, and a member of an unknown type
+    ustar_member(&mut data, "PaxHeaders/g", b'g', b"14 comment=hi\n");
+    ustar_member(&mut data, "c", b'Z', b"three\n");
+    data.resize(data.len() + 1024, 0);
+    std::fs::write(&archive, &data).unwrap();
+
+    let archive = archive.to_str().unwrap();
+    pax_test(
+        &["-f", archive],
+        &format!("{}\npax-name.txt\nc\n", long_name),
+        "",
+        0,
+    );
+
+    let subst = format!(",^,{}/,", dir.to_str().unwrap());
+    pax_test(&["-r", "-s", &subst, "-f", archive], "", "", 0);
+    assert_eq!(
+        std::fs::read_to_string(dir.join(&long_name)).unwrap(),
+        "one\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("pax-name.txt")).unwrap(),
+        "two\n"
+    );
+    assert_eq!(std::fs::read_to_string(dir.join("c")).unwrap(), "three\n");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_pax_read_unsafe_names() {
+    let dir = pax_dir("unsafe");
+    let target = dir.join("target");
+    std::fs::create_dir(&target).unwrap();
+
+    let absolute = dir.join("absolute");
+    let mut data = Vec::new();
+    ustar_member(&mut data, "../escaped", b'0', b"bad\n");
+    ustar_member(&mut data, absolute.to_str().unwrap(), b'0', b"bad\n");
+    ustar_member(&mut data, "safe", b'0', b"good\n");
+    data.resize(data.len() + 1024, 0);
+    std::fs::write(dir.join("archive.tar"), &data).unwrap();
+
+    run_test(TestPlan {
+        cmd: String::from("pax"),
+        args: vec![
+            String::from("-r"),
+            String::from("-f"),
+            String::from("../archive.tar"),
+        ],
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: format!(
+            "pax: ../escaped: unsafe file name, not extracted\n\
+             pax: {}: unsafe file name, not extracted\n",
+            absolute.display()
+        ),
+        expected_exit_code: 1,
+        cwd: Some(target.clone()),
+        ..Default::default()
+    });
+    assert!(!dir.join("escaped").exists());
+    assert!(!absolute.exists());
+    assert_eq!(
+        std::fs::read_to_string(target.join("safe")).unwrap(),
+        "good\n"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_pax_cpio_symlink_size() {
+    let dir = pax_dir("cpio-symlink");
+    let archive = dir.join("archive.cpio");
+
+    // a symlink claiming an 8 GiB target
+    let mut data = Vec::new();
+    data.extend_from_slice(b"070707000000000001120777000000000000000001000000");
+    data.extend_from_slice(b"00000000000000002");
+    data.extend_from_slice(b"77777777777");
+    data.extend_from_slice(b"l\0");
+    std::fs::write(&archive, &data).unwrap();
+
+    pax_test(
+        &["-f", archive.to_str().unwrap()],
+        "",
+        "pax: invalid cpio header\n",
+        1,
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
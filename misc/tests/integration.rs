@@ -17,6 +17,7 @@ fn truefalse_test(cmd: &str, expected_exit_code: i32) {
         expected_out: String::new(),
         expected_err: String::new(),
         expected_exit_code,
+        ..Default::default()
     });
 }
 
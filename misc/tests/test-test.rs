@@ -20,6 +20,7 @@ fn test_test(args: &[&str], expected_code: i32) {
         expected_out: String::from(""),
         expected_err: String::from(""),
         expected_exit_code: expected_code,
+        ..Default::default()
     });
 }
 
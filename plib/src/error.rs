@@ -0,0 +1,66 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Diagnostics prefixed with the utility name, and conventional exit
+//! statuses.
+
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// General failure.
+pub const EXIT_FAILURE: i32 = 1;
+/// Invalid usage, or an error in a utility that reserves 1 for "false"
+/// (cmp, diff, grep, mesg).
+pub const EXIT_USAGE: i32 = 2;
+/// A utility that runs another command failed itself (env, nice, timeout).
+pub const EXIT_UTILITY_FAILED: i32 = 125;
+/// The command was found but could not be invoked.
+pub const EXIT_CANNOT_INVOKE: i32 = 126;
+/// The command was not found.
+pub const EXIT_NOT_FOUND: i32 = 127;
+
+/// Name of the running utility, taken from argv[0].
+pub fn util_name() -> &'static str {
+    static NAME: OnceLock<String> = OnceLock::new();
+    NAME.get_or_init(|| {
+        std::env::args_os()
+            .next()
+            .as_deref()
+            .map(Path::new)
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from(crate::PROJECT_NAME))
+    })
+}
+
+/// Exit status for a failure to execute a command.
+pub fn exec_exit_code(err: &io::Error) -> i32 {
+    match err.kind() {
+        io::ErrorKind::NotFound => EXIT_NOT_FOUND,
+        _ => EXIT_CANNOT_INVOKE,
+    }
+}
+
+/// Write a diagnostic to stderr, prefixed with the utility name.
+#[macro_export]
+macro_rules! util_error {
+    ($($arg:tt)*) => {
+        eprintln!("{}: {}", $crate::error::util_name(), format_args!($($arg)*))
+    };
+}
+
+/// Write a diagnostic to stderr, then exit with `code`.
+#[macro_export]
+macro_rules! util_exit {
+    ($code:expr, $($arg:tt)*) => {{
+        $crate::util_error!($($arg)*);
+        std::process::exit($code)
+    }};
+}
@@ -0,0 +1,32 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Message translation.
+//!
+//! Call [`init`] at the start of `main`, then mark user-facing strings
+//! with `gettext("...")`, or `gettext!("... {}", arg)` to format them.
+//! Strings without a translation in the catalog are used as written.
+
+use crate::PROJECT_NAME;
+use gettextrs::{bind_textdomain_codeset, textdomain};
+
+pub use gettextrs::{gettext, ngettext};
+
+/// Select the locale from the environment, as [`crate::locale::init`]
+/// does, and the project's message catalog.
+///
+/// A missing catalog or an unsupported locale is not an error: messages
+/// are then written untranslated.
+pub fn init() {
+    crate::locale::init();
+    // these only fail on an invalid domain name or when out of memory,
+    // and translation is not worth refusing to run over
+    let _ = textdomain(PROJECT_NAME);
+    let _ = bind_textdomain_codeset(PROJECT_NAME, "UTF-8");
+}
@@ -8,7 +8,7 @@
 //
 
 use std::fs;
-use std::io::{self, Read};
+use std::io::{self, BufWriter, Read, Write};
 use std::path::PathBuf;
 
 pub fn input_stream(pathname: &PathBuf, dashed_stdin: bool) -> io::Result<Box<dyn Read>> {
@@ -40,3 +40,65 @@ pub fn input_reader(
     let file = input_stream(pathname, dashed_stdin)?;
     Ok(io::BufReader::new(file))
 }
+
+/// End the process as if killed by SIGPIPE, as a C utility would be
+/// when its reader goes away. Rust ignores SIGPIPE, so the write that
+/// noticed fails with EPIPE instead.
+pub fn exit_broken_pipe() -> ! {
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+        libc::raise(libc::SIGPIPE);
+    }
+    // the signal is blocked
+    std::process::exit(128 + libc::SIGPIPE)
+}
+
+fn check_pipe<T>(res: io::Result<T>) -> io::Result<T> {
+    match res {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => exit_broken_pipe(),
+        res => res,
+    }
+}
+
+/// Buffered, locked standard output that exits quietly once the reader
+/// has gone away, as in "sort big | head".
+///
+/// Output is flushed when the writer is dropped; call `flush` first to
+/// see any other error.
+pub struct StdoutWriter {
+    inner: BufWriter<io::StdoutLock<'static>>,
+}
+
+impl StdoutWriter {
+    pub fn new() -> StdoutWriter {
+        StdoutWriter {
+            inner: BufWriter::with_capacity(crate::BUFSZ, io::stdout().lock()),
+        }
+    }
+}
+
+impl Default for StdoutWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for StdoutWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        check_pipe(self.inner.write(buf))
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        check_pipe(self.inner.write_all(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        check_pipe(self.inner.flush())
+    }
+}
+
+impl Drop for StdoutWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
@@ -7,12 +7,19 @@
 // SPDX-License-Identifier: MIT
 //
 
+pub mod error;
 pub mod group;
+pub mod i18n;
 pub mod io;
+pub mod locale;
 pub mod lzw;
 pub mod modestr;
+pub mod regex;
+pub mod signal;
+pub mod tabs;
 pub mod testing;
 pub mod utmpx;
+pub mod wait;
 
 pub const PROJECT_NAME: &'static str = "posixutils-rs";
 
@@ -0,0 +1,237 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! The locale categories that change what utilities do, as opposed to
+//! the messages they write.
+//!
+//! Call [`init`] (or [`crate::i18n::init`], which calls it) at the start
+//! of `main`. Afterwards [`Ctype`] classifies characters by `LC_CTYPE`,
+//! and [`strcoll`] and [`collation_key`] order strings by `LC_COLLATE`.
+
+use std::cmp::Ordering;
+use std::ffi::{CStr, CString};
+
+/// Set every locale category from the environment: `LC_ALL`, then the
+/// category's own variable, then `LANG`.
+///
+/// An unsupported locale is not an error: the categories then stay in
+/// the POSIX locale.
+pub fn init() {
+    unsafe {
+        libc::setlocale(libc::LC_ALL, c"".as_ptr());
+    }
+}
+
+/// Whether the character encoding of `LC_CTYPE` is UTF-8; otherwise
+/// every byte is a character.
+pub fn is_utf8() -> bool {
+    let codeset = unsafe { libc::nl_langinfo(libc::CODESET) };
+    if codeset.is_null() {
+        return false;
+    }
+    let name = unsafe { CStr::from_ptr(codeset) }.to_string_lossy();
+    name.eq_ignore_ascii_case("UTF-8") || name.eq_ignore_ascii_case("UTF8")
+}
+
+/// The character classes of `[:class:]` in bracket expressions and in
+/// `tr` operands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Class {
+    Alnum,
+    Alpha,
+    Blank,
+    Cntrl,
+    Digit,
+    Graph,
+    Lower,
+    Print,
+    Punct,
+    Space,
+    Upper,
+    Xdigit,
+}
+
+impl Class {
+    pub fn from_name(name: &str) -> Option<Class> {
+        let class = match name {
+            "alnum" => Class::Alnum,
+            "alpha" => Class::Alpha,
+            "blank" => Class::Blank,
+            "cntrl" => Class::Cntrl,
+            "digit" => Class::Digit,
+            "graph" => Class::Graph,
+            "lower" => Class::Lower,
+            "print" => Class::Print,
+            "punct" => Class::Punct,
+            "space" => Class::Space,
+            "upper" => Class::Upper,
+            "xdigit" => Class::Xdigit,
+            _ => return None,
+        };
+        Some(class)
+    }
+}
+
+/// The character-type rules of the current locale.
+///
+/// Only the difference between a UTF-8 locale and a single-byte one is
+/// modelled: in a UTF-8 locale the classes follow Unicode, otherwise
+/// they hold only ASCII characters, as in the POSIX locale.
+#[derive(Debug, Clone, Copy)]
+pub struct Ctype {
+    pub utf8: bool,
+}
+
+impl Ctype {
+    /// Reads the codeset of the `LC_CTYPE` category, which must already
+    /// have been set with [`init`].
+    pub fn current() -> Ctype {
+        Ctype { utf8: is_utf8() }
+    }
+
+    /// Checks whether `c` belongs to `class`.
+    pub fn is_class(&self, class: Class, c: char) -> bool {
+        if !self.utf8 && !c.is_ascii() {
+            return false;
+        }
+
+        match class {
+            Class::Alnum => c.is_alphanumeric(),
+            Class::Alpha => c.is_alphabetic(),
+            Class::Blank => {
+                c == ' '
+                    || c == '\t'
+                    || (c.is_whitespace()
+                        && !c.is_control()
+                        && !matches!(c, '\u{2028}' | '\u{2029}'))
+            }
+            Class::Cntrl => c.is_control(),
+            Class::Digit => c.is_ascii_digit(),
+            Class::Graph => !c.is_control() && !c.is_whitespace(),
+            Class::Lower => c.is_lowercase(),
+            Class::Print => !c.is_control() && (c == ' ' || !c.is_whitespace()),
+            Class::Punct => !c.is_control() && !c.is_whitespace() && !c.is_alphanumeric(),
+            // Rust's is_whitespace leaves out the vertical tab
+            Class::Space => c.is_whitespace() || c == '\x0b',
+            Class::Upper => c.is_uppercase(),
+            Class::Xdigit => c.is_ascii_hexdigit(),
+        }
+    }
+
+    /// Converts `c` to upper or lower case, when it has a single-character
+    /// counterpart.
+    pub fn to_case(&self, c: char, upper: bool) -> char {
+        if !self.utf8 && !c.is_ascii() {
+            return c;
+        }
+        let mut converted = if upper {
+            c.to_uppercase().collect::<Vec<char>>()
+        } else {
+            c.to_lowercase().collect::<Vec<char>>()
+        };
+        match converted.len() {
+            1 => converted.remove(0),
+            _ => c,
+        }
+    }
+
+    /// All characters the locale knows about, in code point order.
+    ///
+    /// Outside a UTF-8 locale these are the 256 bytes, each standing for
+    /// the character with the same code point.
+    pub fn universe(&self) -> impl Iterator<Item = char> {
+        let last = if self.utf8 { char::MAX } else { '\u{ff}' };
+        '\0'..=last
+    }
+}
+
+/// Whether `LC_COLLATE` orders strings by their bytes, as the POSIX
+/// locale does, so that collation can be skipped.
+pub fn collates_bytewise() -> bool {
+    let name = unsafe { libc::setlocale(libc::LC_COLLATE, std::ptr::null()) };
+    if name.is_null() {
+        return true;
+    }
+    let name = unsafe { CStr::from_ptr(name) }.to_bytes();
+    matches!(name, b"C" | b"POSIX" | b"C.UTF-8" | b"C.utf8")
+}
+
+/// Compare two strings in the collating sequence of `LC_COLLATE`.
+pub fn strcoll(a: &str, b: &str) -> Ordering {
+    match (CString::new(a), CString::new(b)) {
+        (Ok(a), Ok(b)) => unsafe { libc::strcoll(a.as_ptr(), b.as_ptr()) }.cmp(&0),
+        // the C library cannot see past a NUL
+        _ => collation_key(a).cmp(&collation_key(b)),
+    }
+}
+
+/// A key that orders `s` by `LC_COLLATE` when compared byte by byte,
+/// for strings that are compared many times.
+///
+/// The parts of a string separated by NUL characters are transformed
+/// one at a time and joined by NUL bytes, which no transformed part
+/// contains.
+pub fn collation_key(s: &str) -> Vec<u8> {
+    let mut key = Vec::with_capacity(s.len());
+    for (i, part) in s.split('\0').enumerate() {
+        if i > 0 {
+            key.push(0);
+        }
+        // split on NUL leaves none in the part
+        let part = CString::new(part).unwrap();
+        let mut buf = vec![0u8; part.as_bytes().len() * 2 + 1];
+        loop {
+            let len = unsafe {
+                libc::strxfrm(
+                    buf.as_mut_ptr() as *mut libc::c_char,
+                    part.as_ptr(),
+                    buf.len(),
+                )
+            };
+            if len < buf.len() {
+                key.extend_from_slice(&buf[..len]);
+                break;
+            }
+            buf.resize(len + 1, 0);
+        }
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_posix_ctype() {
+        let ctype = Ctype { utf8: false };
+        assert!(ctype.is_class(Class::Alpha, 'a'));
+        assert!(!ctype.is_class(Class::Alpha, 'é'));
+        assert!(ctype.is_class(Class::Space, '\x0b'));
+        assert_eq!(ctype.to_case('a', true), 'A');
+        assert_eq!(ctype.to_case('é', true), 'é');
+        assert_eq!(ctype.universe().count(), 256);
+
+        let ctype = Ctype { utf8: true };
+        assert!(ctype.is_class(Class::Alpha, 'é'));
+        assert_eq!(ctype.to_case('é', true), 'É');
+    }
+
+    #[test]
+    fn test_posix_collation() {
+        // the test process has not called init, so this is the C locale
+        assert!(collates_bytewise());
+        assert_eq!(strcoll("a", "b"), Ordering::Less);
+        assert_eq!(strcoll("B", "a"), Ordering::Less);
+        assert_eq!(strcoll("a\0b", "a\0c"), Ordering::Less);
+        assert_eq!(collation_key("abc"), b"abc");
+        assert!(collation_key("a\0b") < collation_key("a\0c"));
+        assert!(collation_key("a") < collation_key("a\0"));
+    }
+}
@@ -0,0 +1,103 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! POSIX basic regular expressions, for utilities that use the regex crate.
+
+/// Translate a basic regular expression into the syntax of the regex crate.
+pub fn bre_to_regex(bre: &str) -> String {
+    let mut out = String::with_capacity(bre.len() * 2);
+    let mut chars = bre.chars().peekable();
+    let mut in_bracket = false;
+
+    while let Some(ch) = chars.next() {
+        if in_bracket {
+            // backslash is literal inside a bracket expression
+            match ch {
+                ']' if !out.ends_with('[') && !out.ends_with("[^") => in_bracket = false,
+                // character classes such as [:alpha:] are copied whole
+                '[' if chars.peek() == Some(&':') => {
+                    out.push(ch);
+                    while let Some(c) = chars.next() {
+                        out.push(c);
+                        if c == ':' && chars.peek() == Some(&']') && !out.ends_with("[:") {
+                            out.push(chars.next().unwrap());
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                '\\' | '[' => out.push('\\'),
+                _ => {}
+            }
+            out.push(ch);
+            continue;
+        }
+
+        match ch {
+            '\\' => match chars.next() {
+                Some(c @ ('(' | ')' | '{' | '}')) => out.push(c),
+                Some(c @ '1'..='9') => {
+                    // back-references in the pattern are not supported by
+                    // the regex crate; keep the digit so the error is visible
+                    out.push('\\');
+                    out.push(c);
+                }
+                Some(c) => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                None => out.push_str("\\\\"),
+            },
+            '[' => {
+                in_bracket = true;
+                out.push('[');
+                if chars.peek() == Some(&'^') {
+                    out.push(chars.next().unwrap());
+                }
+                if chars.peek() == Some(&']') {
+                    chars.next();
+                    out.push_str("\\]");
+                }
+            }
+            '(' | ')' | '{' | '}' | '+' | '?' | '|' => {
+                out.push('\\');
+                out.push(ch);
+            }
+            // a leading '*' is literal
+            '*' if out.is_empty() || out.ends_with('(') || out == "^" => out.push_str("\\*"),
+            _ => out.push(ch),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bre_to_regex() {
+        assert_eq!(bre_to_regex(r"a\(b\)\{2\}"), "a(b){2}");
+        assert_eq!(bre_to_regex("a+b?(c)|{d}"), r"a\+b\?\(c\)\|\{d\}");
+        assert_eq!(bre_to_regex("*a"), r"\*a");
+        assert_eq!(bre_to_regex("^*a"), r"^\*a");
+        assert_eq!(bre_to_regex(r"\(*a\)"), r"(\*a)");
+        assert_eq!(bre_to_regex(".*"), ".*");
+    }
+
+    #[test]
+    fn test_bracket() {
+        assert_eq!(bre_to_regex(r"[\]"), r"[\\]");
+        assert_eq!(bre_to_regex("[]a]"), r"[\]a]");
+        assert_eq!(bre_to_regex("[^]a]"), r"[^\]a]");
+        assert_eq!(bre_to_regex("[[:alpha:]_]*"), "[[:alpha:]_]*");
+        assert_eq!(bre_to_regex("[a[]"), r"[a\[]");
+    }
+}
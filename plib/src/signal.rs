@@ -0,0 +1,179 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Signal names and numbers.
+//!
+//! Names are written without the SIG prefix, as kill -l prints them.
+//! Lookups by name ignore case and accept the prefix.
+
+extern crate libc;
+
+use libc::c_int;
+
+/// Signals by name. When several names share a number, the first one
+/// listed is the one printed.
+const SIGNALS: &[(&str, c_int)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("ILL", libc::SIGILL),
+    ("TRAP", libc::SIGTRAP),
+    ("ABRT", libc::SIGABRT),
+    ("IOT", libc::SIGIOT),
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    ("EMT", libc::SIGEMT),
+    ("BUS", libc::SIGBUS),
+    ("FPE", libc::SIGFPE),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("SEGV", libc::SIGSEGV),
+    ("USR2", libc::SIGUSR2),
+    ("PIPE", libc::SIGPIPE),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    #[cfg(target_os = "linux")]
+    ("STKFLT", libc::SIGSTKFLT),
+    ("CHLD", libc::SIGCHLD),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("TTIN", libc::SIGTTIN),
+    ("TTOU", libc::SIGTTOU),
+    ("URG", libc::SIGURG),
+    ("XCPU", libc::SIGXCPU),
+    ("XFSZ", libc::SIGXFSZ),
+    ("VTALRM", libc::SIGVTALRM),
+    ("PROF", libc::SIGPROF),
+    ("WINCH", libc::SIGWINCH),
+    ("IO", libc::SIGIO),
+    #[cfg(target_os = "linux")]
+    ("POLL", libc::SIGPOLL),
+    #[cfg(target_os = "linux")]
+    ("PWR", libc::SIGPWR),
+    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    ("INFO", libc::SIGINFO),
+    ("SYS", libc::SIGSYS),
+];
+
+/// The range of realtime signals, if the system has them.
+fn realtime_range() -> Option<(c_int, c_int)> {
+    #[cfg(target_os = "linux")]
+    {
+        Some((libc::SIGRTMIN(), libc::SIGRTMAX()))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// The number of the signal called `name`, such as "TERM", "sigterm",
+/// or "RTMIN+2".
+pub fn signal_number(name: &str) -> Option<c_int> {
+    let upper = name.to_uppercase();
+    let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+
+    if let Some(&(_, signo)) = SIGNALS.iter().find(|(n, _)| *n == name) {
+        return Some(signo);
+    }
+
+    let (min, max) = realtime_range()?;
+    let signo = if name == "RTMIN" {
+        min
+    } else if name == "RTMAX" {
+        max
+    } else if let Some(offset) = name.strip_prefix("RTMIN+") {
+        min.checked_add(offset.parse().ok()?)?
+    } else if let Some(offset) = name.strip_prefix("RTMAX-") {
+        max.checked_sub(offset.parse().ok()?)?
+    } else {
+        return None;
+    };
+    (min..=max).contains(&signo).then_some(signo)
+}
+
+/// The name of signal `signo`, without the SIG prefix.
+pub fn signal_name(signo: c_int) -> Option<String> {
+    if let Some(&(name, _)) = SIGNALS.iter().find(|(_, n)| *n == signo) {
+        return Some(name.to_string());
+    }
+
+    let (min, max) = realtime_range()?;
+    if !(min..=max).contains(&signo) {
+        return None;
+    }
+    // name each signal from the nearer end of the range, as bash does
+    let name = if signo == min {
+        String::from("RTMIN")
+    } else if signo == max {
+        String::from("RTMAX")
+    } else if signo - min <= max - signo {
+        format!("RTMIN+{}", signo - min)
+    } else {
+        format!("RTMAX-{}", max - signo)
+    };
+    Some(name)
+}
+
+/// All signals, in numeric order, each under its preferred name.
+pub fn signals() -> Vec<(String, c_int)> {
+    let mut numbers: Vec<c_int> = SIGNALS.iter().map(|&(_, signo)| signo).collect();
+    if let Some((min, max)) = realtime_range() {
+        numbers.extend(min..=max);
+    }
+    numbers.sort_unstable();
+    numbers.dedup();
+
+    numbers
+        .into_iter()
+        .filter_map(|signo| signal_name(signo).map(|name| (name, signo)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signal_number() {
+        assert_eq!(signal_number("TERM"), Some(libc::SIGTERM));
+        assert_eq!(signal_number("sigkill"), Some(libc::SIGKILL));
+        assert_eq!(signal_number("SigHup"), Some(libc::SIGHUP));
+        assert_eq!(signal_number("IOT"), Some(libc::SIGABRT));
+        assert_eq!(signal_number("NOSUCH"), None);
+        assert_eq!(signal_number("9"), None);
+    }
+
+    #[test]
+    fn test_signal_name() {
+        assert_eq!(signal_name(libc::SIGTERM).as_deref(), Some("TERM"));
+        assert_eq!(signal_name(libc::SIGABRT).as_deref(), Some("ABRT"));
+        assert_eq!(signal_name(0), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_realtime() {
+        let min = libc::SIGRTMIN();
+        let max = libc::SIGRTMAX();
+        assert_eq!(signal_number("RTMIN"), Some(min));
+        assert_eq!(signal_number("sigrtmin+1"), Some(min + 1));
+        assert_eq!(signal_number("RTMAX-1"), Some(max - 1));
+        assert_eq!(signal_number(&format!("RTMIN+{}", max - min + 1)), None);
+        assert_eq!(signal_name(min + 1).as_deref(), Some("RTMIN+1"));
+        assert_eq!(signal_name(max - 1).as_deref(), Some("RTMAX-1"));
+    }
+
+    #[test]
+    fn test_signals_round_trip() {
+        for (name, signo) in signals() {
+            assert_eq!(signal_number(&name), Some(signo));
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+//! Tab stops given by a `-t tablist` option, as used by expand and unexpand.
+
+/// Tab stops, as column positions counted from zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TabStops {
+    /// A tab stop every n columns.
+    Every(usize),
+    /// Explicit tab stops in ascending order.
+    List(Vec<usize>),
+}
+
+impl Default for TabStops {
+    fn default() -> TabStops {
+        TabStops::Every(8)
+    }
+}
+
+impl TabStops {
+    /// Parse a single positive interval, or a list of ascending positions
+    /// separated by commas or blanks.
+    pub fn parse(tablist: &str) -> Result<TabStops, String> {
+        let mut stops = Vec::new();
+        for token in tablist.split([',', ' ', '\t']).filter(|t| !t.is_empty()) {
+            let n = token
+                .parse::<usize>()
+                .map_err(|_| format!("tab size contains invalid character(s): '{}'", token))?;
+            if n == 0 {
+                return Err(String::from("tab size cannot be 0"));
+            }
+            if stops.last().is_some_and(|&last| n <= last) {
+                return Err(String::from("tab sizes must be ascending"));
+            }
+            stops.push(n);
+        }
+
+        match stops.len() {
+            0 => Err(String::from("tab list is empty")),
+            1 => Ok(TabStops::Every(stops[0])),
+            _ => Ok(TabStops::List(stops)),
+        }
+    }
+
+    /// The first tab stop after `column`, or None past the last stop of
+    /// a list.
+    pub fn next(&self, column: usize) -> Option<usize> {
+        match self {
+            TabStops::Every(n) => Some((column / n + 1) * n),
+            TabStops::List(stops) => stops.iter().copied().find(|&stop| stop > column),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(TabStops::parse("4"), Ok(TabStops::Every(4)));
+        assert_eq!(
+            TabStops::parse("4,12 20"),
+            Ok(TabStops::List(vec![4, 12, 20]))
+        );
+        assert!(TabStops::parse("0").is_err());
+        assert!(TabStops::parse("8,4").is_err());
+        assert!(TabStops::parse("4,x").is_err());
+        assert!(TabStops::parse("").is_err());
+    }
+
+    #[test]
+    fn test_next() {
+        assert_eq!(TabStops::Every(4).next(0), Some(4));
+        assert_eq!(TabStops::Every(4).next(4), Some(8));
+        let list = TabStops::List(vec![4, 12]);
+        assert_eq!(list.next(3), Some(4));
+        assert_eq!(list.next(4), Some(12));
+        assert_eq!(list.next(12), None);
+    }
+}
@@ -7,9 +7,17 @@
 // SPDX-License-Identifier: MIT
 //
 
+use std::fs;
 use std::io::Write;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Output, Stdio};
 
+/// Set to a non-empty value to rewrite snapshot files with the actual
+/// output instead of failing.
+pub const UPDATE_SNAPSHOTS_ENV: &str = "POSIXUTILS_UPDATE_SNAPSHOTS";
+
+#[derive(Default)]
 pub struct TestPlan {
     pub cmd: String,
     pub args: Vec<String>,
@@ -17,6 +25,12 @@ pub struct TestPlan {
     pub expected_out: String,
     pub expected_err: String,
     pub expected_exit_code: i32,
+    /// Variables added to the utility's environment
+    pub env: Vec<(String, String)>,
+    /// Working directory of the utility, instead of the package directory
+    pub cwd: Option<PathBuf>,
+    /// File mode creation mask of the utility
+    pub umask: Option<u32>,
 }
 
 fn run_test_base(plan: TestPlan) -> (TestPlan, Output) {
@@ -32,6 +46,20 @@ fn run_test_base(plan: TestPlan) -> (TestPlan, Output) {
         .join(relpath); // Adjust the path to the binary
 
     let mut command = Command::new(test_bin_path);
+    command.envs(plan.env.iter().map(|(k, v)| (k, v)));
+    if let Some(cwd) = &plan.cwd {
+        command.current_dir(cwd);
+    }
+    if let Some(mask) = plan.umask {
+        // only affects the child, between fork and exec
+        unsafe {
+            command.pre_exec(move || {
+                libc::umask(mask as libc::mode_t);
+                Ok(())
+            });
+        }
+    }
+
     let mut child = command
         .args(&plan.args)
         .stdin(Stdio::piped())
@@ -68,3 +96,140 @@ pub fn run_test_with_checker<F: FnMut(&TestPlan, &Output)>(plan: TestPlan, mut c
     let (plan, output) = run_test_base(plan);
     checker(&plan, &output);
 }
+
+/// A test whose expected output lives in fixture files: `<snapshot>.stdout`
+/// and, if the utility writes to it, `<snapshot>.stderr`.
+#[derive(Default)]
+pub struct SnapshotPlan {
+    pub cmd: String,
+    pub args: Vec<String>,
+    pub stdin_data: String,
+    /// Path of the fixture files without their extension, relative to
+    /// the package directory
+    pub snapshot: PathBuf,
+    pub expected_exit_code: i32,
+    /// Variables added to the utility's environment
+    pub env: Vec<(String, String)>,
+    /// Working directory of the utility, instead of the package directory
+    pub cwd: Option<PathBuf>,
+    /// File mode creation mask of the utility
+    pub umask: Option<u32>,
+}
+
+/// Line diff of `expected` and `actual`, with '-' for lines only
+/// expected, '+' for lines only produced, and ' ' for common lines.
+pub fn diff_lines(expected: &str, actual: &str) -> String {
+    let a: Vec<&str> = expected.lines().collect();
+    let b: Vec<&str> = actual.lines().collect();
+
+    // longest common subsequence lengths of the suffixes
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            out.push_str(&format!(" {}\n", a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push_str(&format!("-{}\n", a[i]));
+            i += 1;
+        } else {
+            out.push_str(&format!("+{}\n", b[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
+fn with_extension(path: &Path, ext: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(ext);
+    PathBuf::from(name)
+}
+
+/// Compare `actual` with the fixture `path` (missing means empty). On a
+/// mismatch, write `<path>.actual` and return a description with a diff.
+fn check_snapshot(path: &Path, actual: &str, update: bool) -> Option<String> {
+    let expected = fs::read_to_string(path).unwrap_or_default();
+    let actual_path = with_extension(path, "actual");
+
+    if expected == actual {
+        let _ = fs::remove_file(&actual_path);
+        return None;
+    }
+
+    if update {
+        if actual.is_empty() {
+            let _ = fs::remove_file(path);
+        } else {
+            fs::write(path, actual).expect("failed to update snapshot");
+        }
+        let _ = fs::remove_file(&actual_path);
+        return None;
+    }
+
+    fs::write(&actual_path, actual).expect("failed to write actual output");
+    Some(format!(
+        "{} differs (actual output in {}):\n{}",
+        path.display(),
+        actual_path.display(),
+        diff_lines(&expected, actual)
+    ))
+}
+
+/// Run `plan.cmd` and compare its output with the snapshot files. With
+/// POSIXUTILS_UPDATE_SNAPSHOTS set, the files are rewritten instead.
+pub fn run_snapshot_test(plan: SnapshotPlan) {
+    let snapshot = plan.snapshot.clone();
+    let expected_exit_code = plan.expected_exit_code;
+    let (_, output) = run_test_base(TestPlan {
+        cmd: plan.cmd,
+        args: plan.args,
+        stdin_data: plan.stdin_data,
+        expected_out: String::new(),
+        expected_err: String::new(),
+        expected_exit_code,
+        env: plan.env,
+        cwd: plan.cwd,
+        umask: plan.umask,
+    });
+
+    let update = std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some_and(|v| !v.is_empty());
+    let mut failures = Vec::new();
+    for (ext, data) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+        let actual = String::from_utf8_lossy(data);
+        if let Some(failure) = check_snapshot(&with_extension(&snapshot, ext), &actual, update) {
+            failures.push(failure);
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+    assert_eq!(output.status.code(), Some(expected_exit_code));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff_lines;
+
+    #[test]
+    fn test_diff_lines() {
+        assert_eq!(diff_lines("a\nb\nc\n", "a\nb\nc\n"), " a\n b\n c\n");
+        assert_eq!(
+            diff_lines("a\nb\nc\n", "a\nx\nc\nd\n"),
+            " a\n-b\n+x\n c\n+d\n"
+        );
+        assert_eq!(diff_lines("", "new\n"), "+new\n");
+    }
+}
@@ -9,78 +9,170 @@
 
 extern crate libc;
 use libc::{endutxent, getutxent, setutxent};
-use std::ffi::CStr;
+use std::ffi::CString;
+use std::io;
+use std::marker::PhantomData;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
 
-#[derive(Debug)]
+/// The kind of a utmpx record (`ut_type`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryType {
+    Empty,
+    RunLevel,
+    BootTime,
+    NewTime,
+    OldTime,
+    InitProcess,
+    LoginProcess,
+    UserProcess,
+    DeadProcess,
+    Unknown(libc::c_short),
+}
+
+impl EntryType {
+    pub fn from_raw(typ: libc::c_short) -> EntryType {
+        match typ {
+            libc::EMPTY => EntryType::Empty,
+            libc::RUN_LVL => EntryType::RunLevel,
+            libc::BOOT_TIME => EntryType::BootTime,
+            libc::NEW_TIME => EntryType::NewTime,
+            libc::OLD_TIME => EntryType::OldTime,
+            libc::INIT_PROCESS => EntryType::InitProcess,
+            libc::LOGIN_PROCESS => EntryType::LoginProcess,
+            libc::USER_PROCESS => EntryType::UserProcess,
+            libc::DEAD_PROCESS => EntryType::DeadProcess,
+            _ => EntryType::Unknown(typ),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryType::BootTime => "BOOT_TIME",
+            EntryType::DeadProcess => "DEAD_PROCESS",
+            EntryType::Empty => "EMPTY",
+            EntryType::InitProcess => "INIT_PROCESS",
+            EntryType::LoginProcess => "LOGIN_PROCESS",
+            EntryType::NewTime => "NEW_TIME",
+            EntryType::OldTime => "OLD_TIME",
+            EntryType::RunLevel => "RUN_LVL",
+            EntryType::UserProcess => "USER_PROCESS",
+            EntryType::Unknown(_) => "(unknown)",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Utmpx {
     pub user: String,
     pub id: String,
     pub line: String,
     pub pid: libc::pid_t,
-    pub typ: libc::c_short,
+    pub typ: EntryType,
     pub timestamp: libc::time_t,
     pub host: String,
 }
 
-pub fn ut_type_str(typ: libc::c_short) -> &'static str {
-    match typ {
-        libc::BOOT_TIME => "BOOT_TIME",
-        libc::DEAD_PROCESS => "DEAD_PROCESS",
-        libc::EMPTY => "EMPTY",
-        libc::INIT_PROCESS => "INIT_PROCESS",
-        libc::LOGIN_PROCESS => "LOGIN_PROCESS",
-        libc::NEW_TIME => "NEW_TIME",
-        libc::OLD_TIME => "OLD_TIME",
-        libc::RUN_LVL => "RUN_LVL",
-        libc::USER_PROCESS => "USER_PROCESS",
-
-        _ => "(unknown)",
+impl Utmpx {
+    /// Copy a record returned by getutxent.
+    fn from_raw(utx: &libc::utmpx) -> Utmpx {
+        let text = |field: &[libc::c_char]| {
+            // the fields are only NUL-terminated when shorter than the array
+            let bytes: Vec<u8> = field
+                .iter()
+                .take_while(|&&c| c != 0)
+                .map(|&c| c as u8)
+                .collect();
+            String::from_utf8_lossy(&bytes).into_owned()
+        };
+
+        Utmpx {
+            user: text(&utx.ut_user),
+            id: text(&utx.ut_id),
+            line: text(&utx.ut_line),
+            pid: utx.ut_pid,
+            typ: EntryType::from_raw(utx.ut_type),
+            timestamp: utx.ut_tv.tv_sec as libc::time_t,
+            host: text(&utx.ut_host),
+        }
+    }
+
+    /// True for the record of a logged-in user.
+    pub fn is_user_session(&self) -> bool {
+        self.typ == EntryType::UserProcess
     }
 }
 
-pub fn load() -> Vec<Utmpx> {
-    let mut entries = Vec::new();
-
-    unsafe {
-        setutxent(); // Initialize the utx entry stream
-        let mut utxent = getutxent(); // Get the first utx entry
-
-        // Loop through all utx entries
-        while !utxent.is_null() {
-            let utx = &*utxent;
-
-            let user = CStr::from_ptr(utx.ut_user.as_ptr())
-                .to_string_lossy()
-                .into_owned();
-            let id = CStr::from_ptr(utx.ut_id.as_ptr())
-                .to_string_lossy()
-                .into_owned();
-            let line = CStr::from_ptr(utx.ut_line.as_ptr())
-                .to_string_lossy()
-                .into_owned();
-            let pid = utx.ut_pid;
-            let typ = utx.ut_type;
-            let timestamp = utx.ut_tv.tv_sec;
-            let host = CStr::from_ptr(utx.ut_host.as_ptr())
-                .to_string_lossy()
-                .into_owned();
-
-            // Add the utx to the returned list of entries
-            entries.push(Utmpx {
-                user,
-                id,
-                line,
-                pid,
-                typ,
-                timestamp: timestamp as libc::time_t,
-                host,
-            });
-
-            utxent = getutxent(); // Move to the next utx entry
+pub fn ut_type_str(typ: libc::c_short) -> &'static str {
+    EntryType::from_raw(typ).as_str()
+}
+
+/// Iterator over the records of the utmpx database.
+///
+/// The C library keeps a single read position, so only one `Entries`
+/// should exist at a time. The database is closed when it is dropped.
+pub struct Entries {
+    // getutxent is neither thread-safe nor reentrant
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Iterator for Entries {
+    type Item = Utmpx;
+
+    fn next(&mut self) -> Option<Utmpx> {
+        let utxent = unsafe { getutxent() };
+        if utxent.is_null() {
+            return None;
         }
+        Some(Utmpx::from_raw(unsafe { &*utxent }))
+    }
+}
+
+impl Drop for Entries {
+    fn drop(&mut self) {
+        unsafe { endutxent() };
+    }
+}
+
+/// Iterate over the system utmpx database.
+pub fn entries() -> Entries {
+    unsafe { setutxent() };
+    Entries {
+        _not_send: PhantomData,
+    }
+}
+
+/// Iterate over an alternate accounting file, such as wtmp, instead of
+/// the system utmpx database.
+pub fn entries_from(path: &Path) -> io::Result<Entries> {
+    // utmpxname does not report a missing file, getutxent just finds no entries
+    std::fs::metadata(path)?;
 
-        endutxent(); // Close the utx entry stream
+    let cpath = CString::new(path.as_os_str().as_bytes())?;
+    let ret = unsafe { libc::utmpxname(cpath.as_ptr()) };
+    // macOS returns 1 on success, glibc returns 0
+    let failed = if cfg!(target_os = "macos") {
+        ret == 0
+    } else {
+        ret < 0
+    };
+    if failed {
+        return Err(io::Error::last_os_error());
     }
 
-    entries
+    Ok(entries())
+}
+
+/// The entries of users currently logged in.
+pub fn user_sessions(entries: &[Utmpx]) -> impl Iterator<Item = &Utmpx> {
+    entries.iter().filter(|entry| entry.is_user_session())
+}
+
+pub fn load() -> Vec<Utmpx> {
+    entries().collect()
+}
+
+/// Load the entries of an alternate accounting file.
+pub fn load_file(path: &Path) -> io::Result<Vec<Utmpx>> {
+    Ok(entries_from(path)?.collect())
 }
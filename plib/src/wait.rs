@@ -0,0 +1,176 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+extern crate libc;
+use std::io;
+use std::mem;
+
+/// How a child process changed state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitStatus {
+    /// The child called exit() with this status.
+    Exited(i32),
+    /// The child was terminated by this signal.
+    Signaled { signal: i32, core_dumped: bool },
+    /// The child was stopped by this signal (only reported with `WUNTRACED`).
+    Stopped(i32),
+    /// The stopped child was resumed (only reported with `WCONTINUED`).
+    Continued,
+}
+
+impl WaitStatus {
+    /// Decode the `si_code`/`si_status` pair filled in by waitid().
+    fn from_siginfo(info: &libc::siginfo_t) -> WaitStatus {
+        let status = unsafe { info.si_status() };
+        match info.si_code {
+            libc::CLD_EXITED => WaitStatus::Exited(status),
+            libc::CLD_KILLED => WaitStatus::Signaled {
+                signal: status,
+                core_dumped: false,
+            },
+            libc::CLD_DUMPED => WaitStatus::Signaled {
+                signal: status,
+                core_dumped: true,
+            },
+            libc::CLD_STOPPED | libc::CLD_TRAPPED => WaitStatus::Stopped(status),
+            _ => WaitStatus::Continued,
+        }
+    }
+
+    /// The exit status a shell would report for this child: the exit
+    /// value itself, or 128 plus the signal number for a child that was
+    /// killed or stopped.  A continued child has no status of its own
+    /// and reports 0.
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            WaitStatus::Exited(code) => code,
+            WaitStatus::Signaled { signal, .. } => 128 + signal,
+            WaitStatus::Stopped(signal) => 128 + signal,
+            WaitStatus::Continued => 0,
+        }
+    }
+
+    /// True if the child will not change state again.
+    pub fn is_terminated(&self) -> bool {
+        matches!(self, WaitStatus::Exited(_) | WaitStatus::Signaled { .. })
+    }
+}
+
+/// Wait for a state change in one child, or in any child if `pid` is
+/// `None`.  `options` is ORed with `WEXITED`, so callers only pass the
+/// extra flags they need (`WNOHANG`, `WUNTRACED`, `WCONTINUED`).
+///
+/// Returns `Ok(None)` if `WNOHANG` was given and no child was ready.
+/// Interrupted waits are retried.
+pub fn wait_child(
+    pid: Option<libc::pid_t>,
+    options: libc::c_int,
+) -> io::Result<Option<(libc::pid_t, WaitStatus)>> {
+    let (idtype, id) = match pid {
+        Some(pid) => (libc::P_PID, pid as libc::id_t),
+        None => (libc::P_ALL, 0),
+    };
+
+    // waitid() spells WUNTRACED as WSTOPPED
+    let mut flags = libc::WEXITED;
+    if options & libc::WUNTRACED != 0 {
+        flags |= libc::WSTOPPED;
+    }
+    flags |= options & (libc::WNOHANG | libc::WCONTINUED | libc::WNOWAIT);
+
+    loop {
+        let mut info: libc::siginfo_t = unsafe { mem::zeroed() };
+        let ret = unsafe { libc::waitid(idtype, id, &mut info, flags) };
+        if ret < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err);
+        }
+
+        // with WNOHANG, a zero pid means nothing was ready
+        let child = unsafe { info.si_pid() };
+        if child == 0 {
+            return Ok(None);
+        }
+
+        return Ok(Some((child, WaitStatus::from_siginfo(&info))));
+    }
+}
+
+/// Reap every child that has already terminated, without blocking.
+pub fn reap_zombies() -> Vec<(libc::pid_t, WaitStatus)> {
+    let mut reaped = Vec::new();
+
+    // stops on "no child ready" as well as on ECHILD
+    while let Ok(Some(res)) = wait_child(None, libc::WNOHANG) {
+        reaped.push(res);
+    }
+
+    reaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // fork a child that runs `body`, which must only make
+    // async-signal-safe calls and must not return
+    fn fork_child(body: fn() -> !) -> libc::pid_t {
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0);
+        if pid == 0 {
+            body();
+        }
+        pid
+    }
+
+    #[test]
+    fn test_wait_exited() {
+        let pid = fork_child(|| unsafe { libc::_exit(3) });
+
+        let (waited, status) = wait_child(Some(pid), 0).unwrap().unwrap();
+        assert_eq!(waited, pid);
+        assert_eq!(status, WaitStatus::Exited(3));
+        assert_eq!(status.exit_code(), 3);
+        assert!(status.is_terminated());
+    }
+
+    #[test]
+    fn test_wait_signaled() {
+        let pid = fork_child(|| unsafe {
+            libc::raise(libc::SIGKILL);
+            libc::_exit(0)
+        });
+
+        let (_, status) = wait_child(Some(pid), 0).unwrap().unwrap();
+        assert_eq!(
+            status,
+            WaitStatus::Signaled {
+                signal: libc::SIGKILL,
+                core_dumped: false
+            }
+        );
+        assert_eq!(status.exit_code(), 128 + libc::SIGKILL);
+    }
+
+    #[test]
+    fn test_wait_nohang() {
+        let pid = fork_child(|| loop {
+            unsafe { libc::pause() };
+        });
+
+        assert_eq!(wait_child(Some(pid), libc::WNOHANG).unwrap(), None);
+
+        unsafe { libc::kill(pid, libc::SIGKILL) };
+        let (_, status) = wait_child(Some(pid), 0).unwrap().unwrap();
+        assert_eq!(status.exit_code(), 128 + libc::SIGKILL);
+    }
+}
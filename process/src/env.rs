@@ -12,7 +12,8 @@ extern crate plib;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::PROJECT_NAME;
+use plib::error::exec_exit_code;
+use plib::{util_exit, PROJECT_NAME};
 use std::collections::HashMap;
 use std::env;
 use std::io;
@@ -105,7 +106,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         return print_env(new_env);
     }
 
-    exec_util(new_env, util_args)?;
-
-    Ok(())
+    // exec only returns on failure
+    let util = util_args[0].clone();
+    let e = exec_util(new_env, util_args).unwrap_err();
+    util_exit!(exec_exit_code(&e), "{}: {}", util, e);
 }
@@ -11,74 +11,78 @@ extern crate clap;
 extern crate libc;
 extern crate plib;
 
-mod osdata;
-
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::signal;
 use plib::PROJECT_NAME;
 use regex::Regex;
-use std::collections::HashMap;
 
-fn lookup_signum(sigmap: &HashMap<&str, u32>, signame: &str) -> Result<u32, &'static str> {
+fn lookup_signum(signame: &str) -> Result<u32, &'static str> {
     if signame == "0" {
-        Ok(0)
-    } else {
-        match sigmap.get(signame) {
-            Some(&sig_no) => Ok(sig_no),
-            None => Err("Unknown signal name"),
-        }
+        return Ok(0);
+    }
+
+    match signal::signal_number(signame) {
+        Some(sig_no) => Ok(sig_no as u32),
+        None => Err("Unknown signal name"),
     }
 }
 
 enum ConfigMode {
     Signal(u32),
-    List,
+    List(Vec<String>),
 }
 
 struct Config {
     mode: ConfigMode,
-    pids: Vec<u32>,
+    pids: Vec<libc::pid_t>,
 }
 
-fn parse_cmdline(sigmap: &HashMap<&str, u32>) -> Result<Config, &'static str> {
-    let signame_re = Regex::new(r"^-(\w+)$").unwrap();
+fn parse_cmdline() -> Result<Config, &'static str> {
+    let signame_re = Regex::new(r"^-([A-Za-z][\w+-]*)$").unwrap();
     let signum_re = Regex::new(r"^-(\d+)$").unwrap();
 
-    let mut pids = Vec::new();
+    let mut args = std::env::args().skip(1).peekable();
     let mut mode = ConfigMode::Signal(libc::SIGTERM as u32);
-    let mut in_args = true;
-    let mut in_s_arg = false;
-    for arg in std::env::args().skip(1) {
-        if in_args {
-            if in_s_arg {
-                let sig_no = lookup_signum(sigmap, &arg)?;
-                mode = ConfigMode::Signal(sig_no);
-                in_s_arg = false;
-            } else if arg == "-s" || arg == "--signal" {
-                in_s_arg = true;
-            } else if arg == "-l" || arg == "--list" {
-                mode = ConfigMode::List;
-            } else if arg == "--" {
-                in_args = false;
-            } else if let Some(caps) = signame_re.captures(&arg) {
-                let namestr = caps.get(1).unwrap().as_str();
-                let sig_no = lookup_signum(sigmap, namestr)?;
-                mode = ConfigMode::Signal(sig_no);
-            } else if let Some(caps) = signum_re.captures(&arg) {
-                let numstr = caps.get(1).unwrap().as_str();
-                let sig_no = numstr.parse::<u32>().unwrap();
-                mode = ConfigMode::Signal(sig_no);
-            } else {
-                in_args = false;
-            }
 
-            if in_args || arg == "--" {
-                continue;
+    // Only the first argument may select a signal or the list mode;
+    // anything after it (other than "--") is an operand, so that
+    // "kill -9 -123" signals process group 123.
+    if let Some(arg) = args.peek().cloned() {
+        if arg == "-l" || arg == "--list" {
+            args.next();
+            if args.peek().map(|s| s.as_str()) == Some("--") {
+                args.next();
             }
-
-            // fall through to process non-option arguments
+            return Ok(Config {
+                mode: ConfigMode::List(args.collect()),
+                pids: Vec::new(),
+            });
+        } else if arg == "-s" || arg == "--signal" {
+            args.next();
+            let signame = args.next().ok_or("Missing signal name")?;
+            mode = ConfigMode::Signal(lookup_signum(&signame)?);
+        } else if let Some(caps) = signum_re.captures(&arg) {
+            args.next();
+            let numstr = caps.get(1).unwrap().as_str();
+            let sig_no = numstr.parse::<u32>().map_err(|_| "Invalid signal number")?;
+            mode = ConfigMode::Signal(sig_no);
+        } else if let Some(caps) = signame_re.captures(&arg) {
+            args.next();
+            let namestr = caps.get(1).unwrap().as_str();
+            mode = ConfigMode::Signal(lookup_signum(namestr)?);
         }
+    }
 
-        match arg.parse::<u32>() {
+    if args.peek().map(|s| s.as_str()) == Some("--") {
+        args.next();
+    }
+
+    let mut pids = Vec::new();
+    for arg in args {
+        if arg.starts_with('%') {
+            return Err("Job control process IDs require the shell built-in kill");
+        }
+        match arg.parse::<libc::pid_t>() {
             Ok(pid) => pids.push(pid),
             Err(_) => {
                 return Err("Invalid PID");
@@ -86,26 +90,60 @@ fn parse_cmdline(sigmap: &HashMap<&str, u32>) -> Result<Config, &'static str> {
         }
     }
 
+    if pids.is_empty() {
+        return Err("No process ID specified");
+    }
+
     Ok(Config { mode, pids })
 }
 
-fn list_signals(sigmap: &HashMap<&str, u32>) -> u32 {
-    let mut output = String::new();
-    for name in sigmap.keys() {
-        output.push_str(name);
-        output.push(' ');
+fn list_signals(operands: &[String]) -> u32 {
+    if operands.is_empty() {
+        let names: Vec<String> = signal::signals()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        println!("{}", names.join(" "));
+
+        return 0;
     }
 
-    println!("{}", output);
+    let mut exit_code = 0;
+    for operand in operands {
+        match operand.parse::<u32>() {
+            // an exit status of a process terminated by a signal is 128+N
+            Ok(mut sig_no) => {
+                if sig_no > 128 {
+                    sig_no -= 128;
+                }
+                match signal::signal_name(sig_no as libc::c_int) {
+                    Some(name) => println!("{}", name),
+                    None => {
+                        eprintln!("kill: {}: Unknown signal number", operand);
+                        exit_code = 1;
+                    }
+                }
+            }
+
+            // also accept the reverse mapping, name to number
+            Err(_) => match lookup_signum(operand) {
+                Ok(sig_no) => println!("{}", sig_no),
+                Err(e) => {
+                    eprintln!("kill: {}: {}", operand, e);
+                    exit_code = 1;
+                }
+            },
+        }
+    }
 
-    0
+    exit_code
 }
 
 fn send_signal(prog_cfg: &Config, sig_no: u32) -> u32 {
     let mut exit_code = 0;
 
     for pid in &prog_cfg.pids {
-        let res = unsafe { libc::kill(*pid as libc::pid_t, sig_no as i32) };
+        let res = unsafe { libc::kill(*pid, sig_no as i32) };
         if res != 0 {
             let err = std::io::Error::last_os_error();
             eprintln!("kill pid {}: {}", pid, err);
@@ -121,11 +159,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     textdomain(PROJECT_NAME)?;
     bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
 
-    let sigmap = osdata::get_sigmap();
-    let prog_cfg = parse_cmdline(&sigmap)?;
+    let prog_cfg = parse_cmdline()?;
 
     let exit_code = match prog_cfg.mode {
-        ConfigMode::List => list_signals(&sigmap),
+        ConfigMode::List(ref operands) => list_signals(operands),
         ConfigMode::Signal(sig_no) => send_signal(&prog_cfg, sig_no),
     };
 
@@ -12,8 +12,10 @@ extern crate libc;
 extern crate plib;
 
 use clap::Parser;
+use errno::{errno, set_errno, Errno};
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::PROJECT_NAME;
+use plib::error::{exec_exit_code, EXIT_UTILITY_FAILED};
+use plib::{util_exit, PROJECT_NAME};
 use std::io;
 use std::os::unix::process::CommandExt;
 use std::process::{Command, Stdio};
@@ -23,7 +25,7 @@ use std::process::{Command, Stdio};
 #[command(author, version, about, long_about)]
 struct Args {
     /// A positive or negative decimal integer which shall have the same effect on the execution of the utility as if the utility had called the nice() function with the numeric value of the increment option-argument.
-    #[arg(short, long, default_value_t=10, value_parser = clap::value_parser!(i32).range(-30..30))]
+    #[arg(short, long, default_value_t = 10, allow_negative_numbers = true)]
     niceval: i32,
 
     /// utility to invoke
@@ -50,14 +52,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     textdomain(PROJECT_NAME)?;
     bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
 
+    // nice() clamps the result to the system's limits itself, and -1 is
+    // a valid return value, so only errno tells whether it failed
+    set_errno(Errno(0));
     let res = unsafe { libc::nice(args.niceval) };
-    if res < 0 {
-        let e = io::Error::last_os_error();
-        eprintln!("nice: {}", e);
-        return Err(Box::new(e));
+    if res == -1 && errno().0 != 0 {
+        util_exit!(EXIT_UTILITY_FAILED, "{}", io::Error::last_os_error());
     }
 
-    exec_util(&args.util, args.util_args)?;
-
-    Ok(())
+    // exec only returns on failure
+    let e = exec_util(&args.util, args.util_args).unwrap_err();
+    util_exit!(exec_exit_code(&e), "{}: {}", args.util, e);
 }
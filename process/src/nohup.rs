@@ -9,12 +9,16 @@
 
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
 use libc::signal;
-use libc::{dup, dup2, SIGHUP, SIG_IGN};
-use plib::PROJECT_NAME;
+use libc::{dup2, SIGHUP, SIG_IGN};
+use plib::error::{exec_exit_code, EXIT_NOT_FOUND};
+use plib::{util_error, util_exit, PROJECT_NAME};
 use std::env;
 use std::fs::{File, OpenOptions};
 use std::io;
+use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::io::AsRawFd;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
 use std::process::{self, Command};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -27,11 +31,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         signal(SIGHUP, SIG_IGN);
     }
 
-    // Save the original stderr
-    let original_stderr = unsafe { dup(libc::STDERR_FILENO) };
+    // Save the original stderr, closed on exec so that the command does
+    // not inherit it
+    let original_stderr = unsafe { libc::fcntl(libc::STDERR_FILENO, libc::F_DUPFD_CLOEXEC, 0) };
     if original_stderr == -1 {
-        eprintln!("Failed to duplicate stderr");
-        process::exit(127);
+        util_exit!(EXIT_NOT_FOUND, "cannot duplicate stderr");
     }
 
     // Getting the command and arguments
@@ -39,77 +43,59 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let command = match args.next() {
         Some(cmd) => cmd,
         None => {
-            eprintln!("Usage: nohup <command> [args...]");
-            process::exit(127);
+            util_exit!(EXIT_NOT_FOUND, "usage: nohup <command> [args...]");
         }
     };
 
-    // Redirecting stdout and stderr to the nohup.out file if they are connected to a terminal
-    if atty::is(atty::Stream::Stdout) || atty::is(atty::Stream::Stderr) {
-        let nohup_out_file =
-            get_nohup_out_file().expect("Failed to open nohup.out in current or home directory");
-
-        if atty::is(atty::Stream::Stdout) {
-            let fd = nohup_out_file.0.as_raw_fd();
-
-            if unsafe { dup2(fd, libc::STDOUT_FILENO) } == -1 {
-                eprintln!("Failed to redirect stdout");
-                process::exit(127);
+    // If stdout is a terminal, append it to nohup.out in the current
+    // directory, or failing that in $HOME
+    if atty::is(atty::Stream::Stdout) {
+        let (file, dir) = match get_nohup_out_file() {
+            Ok(res) => res,
+            Err(e) => {
+                util_exit!(EXIT_NOT_FOUND, "cannot open nohup.out: {}", e);
             }
+        };
 
-            match nohup_out_file.1 {
-                NohupDir::Current => {
-                    eprintln!(
-                        "Name of the file to which the output is being appended: `nohup.out`"
-                    );
-                }
-                NohupDir::Home => {
-                    eprintln!(
-                        "Name of the file to which the output is being appended: `$HOME/nohup.out`"
-                    );
-                }
+        // tell the user where output went, before stderr is redirected too
+        match dir {
+            NohupDir::Current => {
+                util_error!("appending output to 'nohup.out'");
+            }
+            NohupDir::Home => {
+                util_error!("appending output to '$HOME/nohup.out'");
             }
         }
 
-        if atty::is(atty::Stream::Stderr) {
-            let fd = nohup_out_file.0.as_raw_fd();
-
-            if unsafe { dup2(fd, libc::STDERR_FILENO) } == -1 {
-                eprintln!("Failed to redirect stderr");
-                process::exit(127);
-            }
+        if unsafe { dup2(file.as_raw_fd(), libc::STDOUT_FILENO) } == -1 {
+            util_exit!(EXIT_NOT_FOUND, "cannot redirect stdout");
         }
     }
 
-    match Command::new(command).args(args).spawn() {
-        Ok(mut process) => {
-            process::exit(process.wait()?.code().unwrap_or(127));
-        }
-        Err(error) => {
-            use std::io::ErrorKind;
+    // If stderr is a terminal, it shares the open file description of
+    // stdout, whether or not stdout was just redirected
+    if atty::is(atty::Stream::Stderr)
+        && unsafe { dup2(libc::STDOUT_FILENO, libc::STDERR_FILENO) } == -1
+    {
+        util_exit!(EXIT_NOT_FOUND, "cannot redirect stderr");
+    }
 
-            // Restore the original stderr
+    // exec only returns on failure
+    let error = Command::new(command).args(args).exec();
 
-            if unsafe { dup2(original_stderr, libc::STDERR_FILENO) } == -1 {
-                eprintln!("Failed to restore stderr");
-                process::exit(127);
-            }
+    // Restore the original stderr
+    if unsafe { dup2(original_stderr, libc::STDERR_FILENO) } == -1 {
+        process::exit(EXIT_NOT_FOUND);
+    }
 
-            // Close the duplicated descriptor as it's no longer needed
-            unsafe { libc::close(original_stderr) };
-
-            match error.kind() {
-                ErrorKind::NotFound => {
-                    eprintln!("Error: command not found");
-                    process::exit(127);
-                }
-                _ => {
-                    eprintln!("Error: command found but could not be invoked");
-                    process::exit(126);
-                }
-            }
-        }
+    // Close the duplicated descriptor as it's no longer needed
+    unsafe { libc::close(original_stderr) };
+
+    let code = exec_exit_code(&error);
+    if code == EXIT_NOT_FOUND {
+        util_exit!(code, "command not found");
     }
+    util_exit!(code, "command found but could not be invoked: {}", error);
 }
 
 enum NohupDir {
@@ -117,23 +103,24 @@ enum NohupDir {
     Home,
 }
 
-fn get_nohup_out_file() -> Result<(File, NohupDir), io::Error> {
-    // Attempting to open or create a nohup.out file in the current directory
-    match OpenOptions::new()
+fn open_nohup_out(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
         .create(true)
         .append(true)
-        .open("nohup.out")
-    {
+        .mode(0o600)
+        .open(path)
+}
+
+fn get_nohup_out_file() -> Result<(File, NohupDir), io::Error> {
+    // Attempting to open or create a nohup.out file in the current directory
+    match open_nohup_out(Path::new("nohup.out")) {
         Ok(file) => Ok((file, NohupDir::Current)),
         Err(_) => {
             // If unsuccessful, attempt to create a nohup.out file in the home directory
-            if let Some(home_dir) = dirs::home_dir() {
-                let mut home_nohup_path = home_dir;
+            if let Some(home_dir) = env::var_os("HOME") {
+                let mut home_nohup_path = PathBuf::from(home_dir);
                 home_nohup_path.push("nohup.out");
-                let file = OpenOptions::new()
-                    .create(true)
-                    .append(true)
-                    .open(home_nohup_path)?;
+                let file = open_nohup_out(&home_nohup_path)?;
                 Ok((file, NohupDir::Home))
             } else {
                 Err(io::Error::new(
@@ -0,0 +1,745 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+extern crate clap;
+extern crate libc;
+extern crate plib;
+
+#[cfg(target_os = "linux")]
+mod pslinux;
+#[cfg(target_os = "macos")]
+mod psmacos;
+
+#[cfg(target_os = "linux")]
+use pslinux as platform;
+#[cfg(target_os = "macos")]
+use psmacos as platform;
+
+use chrono::{Local, TimeZone};
+use clap::{ArgAction, Parser};
+use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::PROJECT_NAME;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+
+/// ps - report process status
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about)]
+struct Args {
+    /// Write information for all processes.
+    #[arg(short = 'A')]
+    all: bool,
+
+    /// Write information for all processes (equivalent to -A).
+    #[arg(short = 'e')]
+    every: bool,
+
+    /// Write information for all processes associated with terminals, except session leaders.
+    #[arg(short = 'a')]
+    terminals: bool,
+
+    /// Write information for all processes, except session leaders.
+    #[arg(short = 'd')]
+    no_leaders: bool,
+
+    /// Generate a full listing.
+    #[arg(short = 'f')]
+    full: bool,
+
+    /// Write information in the user-defined format given by a list of field names, optionally followed by =header.
+    #[arg(short = 'o', action = ArgAction::Append)]
+    format: Vec<String>,
+
+    /// Write information for processes whose process ID numbers are given in proclist.
+    #[arg(short = 'p', action = ArgAction::Append)]
+    proclist: Vec<String>,
+
+    /// Write information for processes whose session leaders are given in grouplist.
+    #[arg(short = 'g', action = ArgAction::Append)]
+    grouplist: Vec<String>,
+
+    /// Write information for processes whose real group ID numbers or names are given in grouplist.
+    #[arg(short = 'G', action = ArgAction::Append)]
+    rgrouplist: Vec<String>,
+
+    /// Write information for processes associated with terminals given in termlist.
+    #[arg(short = 't', action = ArgAction::Append)]
+    termlist: Vec<String>,
+
+    /// Write information for processes whose user ID numbers or login names are given in userlist.
+    #[arg(short = 'u', action = ArgAction::Append)]
+    userlist: Vec<String>,
+
+    /// Write information for processes whose real user ID numbers or login names are given in userlist.
+    #[arg(short = 'U', action = ArgAction::Append)]
+    ruserlist: Vec<String>,
+
+    /// Sort by a list of field names (e.g. cpu, mem, pid); prefix a name with - for descending order.
+    #[arg(long, allow_hyphen_values = true)]
+    sort: Option<String>,
+}
+
+/// Which parts of the process table a run of ps needs.  Reading them
+/// lazily keeps listing large process tables cheap.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Needs {
+    /// scheduling, memory, tty and session data
+    pub stat: bool,
+    /// real and effective user and group IDs
+    pub ids: bool,
+    /// the full argument vector
+    pub args: bool,
+}
+
+/// One process, as read from the platform's process table.
+#[derive(Debug, Default, Clone)]
+pub struct ProcessInfo {
+    pub pid: libc::pid_t,
+    pub ppid: libc::pid_t,
+    pub pgid: libc::pid_t,
+    pub sid: libc::pid_t,
+    pub uid: u32,
+    pub euid: u32,
+    pub gid: u32,
+    pub egid: u32,
+    pub tty: u64,
+    pub state: char,
+    pub priority: i32,
+    pub nice: i32,
+    /// virtual memory size, in kilobytes
+    pub vsz: u64,
+    /// resident set size, in kilobytes
+    pub rss: u64,
+    /// cumulative CPU time, in seconds
+    pub cpu_time: u64,
+    /// start time, in seconds since the epoch
+    pub start_time: i64,
+    pub comm: String,
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Ruser,
+    User,
+    Rgroup,
+    Group,
+    Pid,
+    Ppid,
+    Pgid,
+    Pcpu,
+    Vsz,
+    Nice,
+    Etime,
+    Time,
+    Tty,
+    Comm,
+    Args,
+    // extensions, used by -f or common in scripts
+    Uid,
+    Rss,
+    State,
+    Stime,
+    Cpu,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Field> {
+        match name {
+            "ruser" => Some(Field::Ruser),
+            "user" => Some(Field::User),
+            "rgroup" => Some(Field::Rgroup),
+            "group" => Some(Field::Group),
+            "pid" => Some(Field::Pid),
+            "ppid" => Some(Field::Ppid),
+            "pgid" => Some(Field::Pgid),
+            "pcpu" => Some(Field::Pcpu),
+            "vsz" => Some(Field::Vsz),
+            "nice" => Some(Field::Nice),
+            "etime" => Some(Field::Etime),
+            "time" => Some(Field::Time),
+            "tty" => Some(Field::Tty),
+            "comm" => Some(Field::Comm),
+            "args" => Some(Field::Args),
+            "uid" => Some(Field::Uid),
+            "rss" => Some(Field::Rss),
+            "s" | "state" => Some(Field::State),
+            "stime" => Some(Field::Stime),
+            "c" => Some(Field::Cpu),
+            _ => None,
+        }
+    }
+
+    fn default_header(&self) -> &'static str {
+        match self {
+            Field::Ruser => "RUSER",
+            Field::User => "USER",
+            Field::Rgroup => "RGROUP",
+            Field::Group => "GROUP",
+            Field::Pid => "PID",
+            Field::Ppid => "PPID",
+            Field::Pgid => "PGID",
+            Field::Pcpu => "%CPU",
+            Field::Vsz => "VSZ",
+            Field::Nice => "NI",
+            Field::Etime => "ELAPSED",
+            Field::Time => "TIME",
+            Field::Tty => "TT",
+            Field::Comm => "COMMAND",
+            Field::Args => "COMMAND",
+            Field::Uid => "UID",
+            Field::Rss => "RSS",
+            Field::State => "S",
+            Field::Stime => "STIME",
+            Field::Cpu => "C",
+        }
+    }
+
+    fn add_needs(&self, needs: &mut Needs) {
+        match self {
+            Field::Pid => {}
+            Field::Ruser | Field::User | Field::Rgroup | Field::Group | Field::Uid => {
+                needs.ids = true
+            }
+            Field::Args => {
+                // kernel threads have no arguments, and show their name instead
+                needs.args = true;
+                needs.stat = true;
+            }
+            _ => needs.stat = true,
+        }
+    }
+
+    fn right_aligned(&self) -> bool {
+        matches!(
+            self,
+            Field::Pid
+                | Field::Ppid
+                | Field::Pgid
+                | Field::Pcpu
+                | Field::Vsz
+                | Field::Nice
+                | Field::Etime
+                | Field::Time
+                | Field::Rss
+                | Field::Cpu
+        )
+    }
+}
+
+struct Column {
+    field: Field,
+    header: String,
+}
+
+// Parse the -o option-arguments.  Field names are separated by commas
+// or blanks; a "name=header" item takes the rest of the option-argument
+// as its header, so headers may contain commas and blanks.
+fn parse_format(specs: &[String]) -> Result<Vec<Column>, String> {
+    let mut columns = Vec::new();
+
+    for spec in specs {
+        let mut rest = spec.as_str();
+        loop {
+            rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+            if rest.is_empty() {
+                break;
+            }
+
+            let end = rest
+                .find(|c: char| c == ',' || c == '=' || c.is_whitespace())
+                .unwrap_or(rest.len());
+            let name = &rest[..end];
+            let field =
+                Field::from_name(name).ok_or_else(|| format!("unknown output format: {}", name))?;
+
+            if rest[end..].starts_with('=') {
+                columns.push(Column {
+                    field,
+                    header: rest[end + 1..].to_string(),
+                });
+                break;
+            }
+
+            columns.push(Column {
+                field,
+                header: field.default_header().to_string(),
+            });
+            rest = &rest[end..];
+        }
+    }
+
+    Ok(columns)
+}
+
+struct SortKey {
+    field: Field,
+    descending: bool,
+}
+
+// Parse a --sort list such as "-cpu,pid".  "cpu" and "mem" are
+// accepted as shorthand for pcpu and rss.
+fn parse_sort(spec: &str) -> Result<Vec<SortKey>, String> {
+    spec.split(',')
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let (descending, name) = match item.strip_prefix('-') {
+                Some(name) => (true, name),
+                None => (false, item.strip_prefix('+').unwrap_or(item)),
+            };
+            let field = match name {
+                "cpu" => Some(Field::Pcpu),
+                "mem" => Some(Field::Rss),
+                _ => Field::from_name(name),
+            }
+            .ok_or_else(|| format!("unknown sort key: {}", name))?;
+
+            Ok(SortKey { field, descending })
+        })
+        .collect()
+}
+
+fn default_columns(full: bool) -> Vec<Column> {
+    let fields: &[(Field, &str)] = if full {
+        &[
+            (Field::Uid, "UID"),
+            (Field::Pid, "PID"),
+            (Field::Ppid, "PPID"),
+            (Field::Cpu, "C"),
+            (Field::Stime, "STIME"),
+            (Field::Tty, "TTY"),
+            (Field::Time, "TIME"),
+            (Field::Args, "CMD"),
+        ]
+    } else {
+        &[
+            (Field::Pid, "PID"),
+            (Field::Tty, "TTY"),
+            (Field::Time, "TIME"),
+            (Field::Comm, "CMD"),
+        ]
+    };
+
+    fields
+        .iter()
+        .map(|(field, header)| Column {
+            field: *field,
+            header: header.to_string(),
+        })
+        .collect()
+}
+
+// split a POSIX list option-argument: items separated by commas or blanks
+fn split_list(lists: &[String]) -> Vec<String> {
+    lists
+        .iter()
+        .flat_map(|list| list.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn lookup_uid(name: &str) -> Option<u32> {
+    if let Ok(uid) = name.parse::<u32>() {
+        return Some(uid);
+    }
+
+    let c_name = CString::new(name).ok()?;
+    let pw = unsafe { libc::getpwnam(c_name.as_ptr()) };
+    if pw.is_null() {
+        None
+    } else {
+        Some(unsafe { (*pw).pw_uid })
+    }
+}
+
+fn lookup_gid(name: &str) -> Option<u32> {
+    if let Ok(gid) = name.parse::<u32>() {
+        return Some(gid);
+    }
+
+    let c_name = CString::new(name).ok()?;
+    let gr = unsafe { libc::getgrnam(c_name.as_ptr()) };
+    if gr.is_null() {
+        None
+    } else {
+        Some(unsafe { (*gr).gr_gid })
+    }
+}
+
+fn parse_ids<F: Fn(&str) -> Option<u32>>(lists: &[String], lookup: F) -> Result<Vec<u32>, String> {
+    split_list(lists)
+        .iter()
+        .map(|name| lookup(name).ok_or_else(|| format!("invalid user or group: {}", name)))
+        .collect()
+}
+
+fn parse_pids(lists: &[String]) -> Result<Vec<libc::pid_t>, String> {
+    split_list(lists)
+        .iter()
+        .map(|pid| {
+            pid.parse::<libc::pid_t>()
+                .map_err(|_| format!("invalid process ID: {}", pid))
+        })
+        .collect()
+}
+
+struct Selection {
+    all: bool,
+    terminals: bool,
+    no_leaders: bool,
+    pids: Vec<libc::pid_t>,
+    sessions: Vec<libc::pid_t>,
+    rgids: Vec<u32>,
+    ttys: Vec<String>,
+    euids: Vec<u32>,
+    ruids: Vec<u32>,
+}
+
+impl Selection {
+    fn from_args(args: &Args) -> Result<Selection, String> {
+        Ok(Selection {
+            all: args.all || args.every,
+            terminals: args.terminals,
+            no_leaders: args.no_leaders,
+            pids: parse_pids(&args.proclist)?,
+            sessions: parse_pids(&args.grouplist)?,
+            rgids: parse_ids(&args.rgrouplist, lookup_gid)?,
+            ttys: split_list(&args.termlist)
+                .iter()
+                .map(|tty| tty.trim_start_matches("/dev/").to_string())
+                .collect(),
+            euids: parse_ids(&args.userlist, lookup_uid)?,
+            ruids: parse_ids(&args.ruserlist, lookup_uid)?,
+        })
+    }
+
+    fn is_default(&self) -> bool {
+        !self.all
+            && !self.terminals
+            && !self.no_leaders
+            && self.pids.is_empty()
+            && self.sessions.is_empty()
+            && self.rgids.is_empty()
+            && self.ttys.is_empty()
+            && self.euids.is_empty()
+            && self.ruids.is_empty()
+    }
+
+    fn add_needs(&self, needs: &mut Needs) {
+        if self.is_default() {
+            needs.stat = true;
+            needs.ids = true;
+        }
+        if self.terminals || self.no_leaders || !self.sessions.is_empty() || !self.ttys.is_empty() {
+            needs.stat = true;
+        }
+        if !self.rgids.is_empty() || !self.euids.is_empty() || !self.ruids.is_empty() {
+            needs.ids = true;
+        }
+    }
+
+    fn tty_matches(&self, pinfo: &ProcessInfo) -> bool {
+        match platform::tty_name(pinfo.tty) {
+            Some(name) => self
+                .ttys
+                .iter()
+                .any(|tty| *tty == name || tty.strip_prefix("tty").is_some_and(|t| t == name)),
+            None => false,
+        }
+    }
+
+    // Selection options are cumulative: a process is listed if any of
+    // them selects it.
+    fn selects(&self, pinfo: &ProcessInfo, me: &ProcessInfo) -> bool {
+        if self.is_default() {
+            return pinfo.euid == me.euid && pinfo.tty == me.tty;
+        }
+
+        let leader = pinfo.pid == pinfo.sid;
+        self.all
+            || (self.terminals && !leader && pinfo.tty != 0)
+            || (self.no_leaders && !leader)
+            || self.pids.contains(&pinfo.pid)
+            || self.sessions.contains(&pinfo.sid)
+            || self.rgids.contains(&pinfo.gid)
+            || self.euids.contains(&pinfo.euid)
+            || self.ruids.contains(&pinfo.uid)
+            || (!self.ttys.is_empty() && self.tty_matches(pinfo))
+    }
+}
+
+// format seconds of CPU time as [dd-]hh:mm:ss
+fn fmt_time(secs: u64) -> String {
+    let days = secs / 86400;
+    let hours = (secs / 3600) % 24;
+    let mins = (secs / 60) % 60;
+    let secs = secs % 60;
+    if days > 0 {
+        format!("{}-{:02}:{:02}:{:02}", days, hours, mins, secs)
+    } else {
+        format!("{:02}:{:02}:{:02}", hours, mins, secs)
+    }
+}
+
+// format elapsed seconds as [[dd-]hh:]mm:ss
+fn fmt_etime(secs: u64) -> String {
+    let days = secs / 86400;
+    let hours = (secs / 3600) % 24;
+    let mins = (secs / 60) % 60;
+    let secs = secs % 60;
+    if days > 0 {
+        format!("{}-{:02}:{:02}:{:02}", days, hours, mins, secs)
+    } else if hours > 0 {
+        format!("{:02}:{:02}:{:02}", hours, mins, secs)
+    } else {
+        format!("{:02}:{:02}", mins, secs)
+    }
+}
+
+// format a start time as HH:MM if today, otherwise as MonDD
+fn fmt_stime(start: i64, now: i64) -> String {
+    let start_dt = match Local.timestamp_opt(start, 0).single() {
+        Some(dt) => dt,
+        None => return String::from("?"),
+    };
+    let now_dt = Local.timestamp_opt(now, 0).single().unwrap_or(start_dt);
+
+    if start_dt.date_naive() == now_dt.date_naive() {
+        start_dt.format("%H:%M").to_string()
+    } else {
+        start_dt.format("%b%d").to_string()
+    }
+}
+
+struct Formatter {
+    now: i64,
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+}
+
+impl Formatter {
+    fn new() -> Formatter {
+        Formatter {
+            now: chrono::Utc::now().timestamp(),
+            users: HashMap::new(),
+            groups: HashMap::new(),
+        }
+    }
+
+    fn user_name(&mut self, uid: u32) -> String {
+        self.users
+            .entry(uid)
+            .or_insert_with(|| {
+                let pw = unsafe { libc::getpwuid(uid) };
+                if pw.is_null() {
+                    uid.to_string()
+                } else {
+                    let name = unsafe { CStr::from_ptr((*pw).pw_name) };
+                    name.to_string_lossy().into_owned()
+                }
+            })
+            .clone()
+    }
+
+    fn group_name(&mut self, gid: u32) -> String {
+        self.groups
+            .entry(gid)
+            .or_insert_with(|| {
+                let gr = unsafe { libc::getgrgid(gid) };
+                if gr.is_null() {
+                    gid.to_string()
+                } else {
+                    let name = unsafe { CStr::from_ptr((*gr).gr_name) };
+                    name.to_string_lossy().into_owned()
+                }
+            })
+            .clone()
+    }
+
+    fn elapsed(&self, pinfo: &ProcessInfo) -> u64 {
+        (self.now - pinfo.start_time).max(0) as u64
+    }
+
+    fn pcpu(&self, pinfo: &ProcessInfo) -> f64 {
+        let elapsed = self.elapsed(pinfo);
+        if elapsed == 0 {
+            0.0
+        } else {
+            (pinfo.cpu_time as f64 * 100.0) / elapsed as f64
+        }
+    }
+
+    // order two processes by the raw value behind a field
+    fn compare(&self, field: Field, a: &ProcessInfo, b: &ProcessInfo) -> Ordering {
+        match field {
+            Field::Ruser => a.uid.cmp(&b.uid),
+            Field::User | Field::Uid => a.euid.cmp(&b.euid),
+            Field::Rgroup => a.gid.cmp(&b.gid),
+            Field::Group => a.egid.cmp(&b.egid),
+            Field::Pid => a.pid.cmp(&b.pid),
+            Field::Ppid => a.ppid.cmp(&b.ppid),
+            Field::Pgid => a.pgid.cmp(&b.pgid),
+            Field::Pcpu | Field::Cpu => self.pcpu(a).total_cmp(&self.pcpu(b)),
+            Field::Vsz => a.vsz.cmp(&b.vsz),
+            Field::Nice => a.nice.cmp(&b.nice),
+            Field::Etime => b.start_time.cmp(&a.start_time),
+            Field::Stime => a.start_time.cmp(&b.start_time),
+            Field::Time => a.cpu_time.cmp(&b.cpu_time),
+            Field::Tty => a.tty.cmp(&b.tty),
+            Field::Comm => a.comm.cmp(&b.comm),
+            Field::Args => a.args.cmp(&b.args),
+            Field::Rss => a.rss.cmp(&b.rss),
+            Field::State => a.state.cmp(&b.state),
+        }
+    }
+
+    fn value(&mut self, field: Field, pinfo: &ProcessInfo) -> String {
+        match field {
+            Field::Ruser => self.user_name(pinfo.uid),
+            Field::User => self.user_name(pinfo.euid),
+            Field::Rgroup => self.group_name(pinfo.gid),
+            Field::Group => self.group_name(pinfo.egid),
+            Field::Pid => pinfo.pid.to_string(),
+            Field::Ppid => pinfo.ppid.to_string(),
+            Field::Pgid => pinfo.pgid.to_string(),
+            Field::Pcpu => format!("{:.1}", self.pcpu(pinfo)),
+            Field::Vsz => pinfo.vsz.to_string(),
+            Field::Nice => pinfo.nice.to_string(),
+            Field::Etime => fmt_etime(self.elapsed(pinfo)),
+            Field::Time => fmt_time(pinfo.cpu_time),
+            Field::Tty => platform::tty_name(pinfo.tty).unwrap_or_else(|| String::from("?")),
+            Field::Comm => pinfo.comm.clone(),
+            Field::Args => {
+                if pinfo.args.is_empty() {
+                    format!("[{}]", pinfo.comm)
+                } else {
+                    pinfo.args.join(" ")
+                }
+            }
+            Field::Uid => self.user_name(pinfo.euid),
+            Field::Rss => pinfo.rss.to_string(),
+            Field::State => pinfo.state.to_string(),
+            Field::Stime => fmt_stime(pinfo.start_time, self.now),
+            Field::Cpu => (self.pcpu(pinfo) as u64).to_string(),
+        }
+    }
+}
+
+fn print_table(columns: &[Column], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = columns.iter().map(|col| col.header.len()).collect();
+    for row in rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.len());
+        }
+    }
+
+    let print_row = |values: Vec<&str>| {
+        let mut line = String::new();
+        for (i, (col, value)) in columns.iter().zip(values).enumerate() {
+            if i > 0 {
+                line.push(' ');
+            }
+            if i == columns.len() - 1 && !col.field.right_aligned() {
+                // never pad the last column
+                line.push_str(value);
+            } else if col.field.right_aligned() {
+                line.push_str(&format!("{:>1$}", value, widths[i]));
+            } else {
+                line.push_str(&format!("{:<1$}", value, widths[i]));
+            }
+        }
+        println!("{}", line);
+    };
+
+    // POSIX: if all headers are null, no header line is written
+    if columns.iter().any(|col| !col.header.is_empty()) {
+        print_row(columns.iter().map(|col| col.header.as_str()).collect());
+    }
+
+    for row in rows {
+        print_row(row.iter().map(|s| s.as_str()).collect());
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // parse command line arguments
+    let args = Args::parse();
+
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain(PROJECT_NAME)?;
+    bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
+
+    let columns = if args.format.is_empty() {
+        default_columns(args.full)
+    } else {
+        parse_format(&args.format)?
+    };
+    let selection = Selection::from_args(&args)?;
+    let sort_keys = match &args.sort {
+        Some(spec) => parse_sort(spec)?,
+        None => Vec::new(),
+    };
+
+    // work out which parts of the process table must be read
+    let mut needs = Needs::default();
+    for col in &columns {
+        col.field.add_needs(&mut needs);
+    }
+    for key in &sort_keys {
+        key.field.add_needs(&mut needs);
+    }
+    selection.add_needs(&mut needs);
+
+    let mut processes = platform::list_processes(needs)?;
+
+    let my_pid = std::process::id() as libc::pid_t;
+    let me = match platform::get_process(my_pid, needs) {
+        Some(me) => me,
+        None => {
+            eprintln!("ps: cannot find own process");
+            std::process::exit(1);
+        }
+    };
+
+    processes.retain(|pinfo| selection.selects(pinfo, &me));
+
+    let mut formatter = Formatter::new();
+
+    // stable sort, so processes with equal keys stay in PID order
+    processes.sort_by(|a, b| {
+        sort_keys
+            .iter()
+            .map(|key| {
+                let ord = formatter.compare(key.field, a, b);
+                if key.descending {
+                    ord.reverse()
+                } else {
+                    ord
+                }
+            })
+            .find(|ord| *ord != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let rows: Vec<Vec<String>> = processes
+        .iter()
+        .map(|pinfo| {
+            columns
+                .iter()
+                .map(|col| formatter.value(col.field, pinfo))
+                .collect()
+        })
+        .collect();
+
+    print_table(&columns, &rows);
+
+    Ok(())
+}
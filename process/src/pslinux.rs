@@ -0,0 +1,307 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use crate::{Needs, ProcessInfo};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// system-wide values needed to turn clock ticks into wall-clock times
+struct SysInfo {
+    ticks_per_sec: u64,
+    page_kb: u64,
+    boot_time: i64,
+}
+
+fn sys_info(root: &Path) -> io::Result<SysInfo> {
+    let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as u64;
+    let page_kb = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64 / 1024;
+
+    let stat = fs::read_to_string(root.join("stat"))?;
+    let boot_time = stat
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .and_then(|val| val.trim().parse::<i64>().ok())
+        .unwrap_or(0);
+
+    Ok(SysInfo {
+        ticks_per_sec,
+        page_kb,
+        boot_time,
+    })
+}
+
+// Parse /proc/<pid>/stat.  The command name is wrapped in parentheses
+// and may itself contain spaces or parentheses, so split on the last ')'.
+fn parse_stat(pinfo: &mut ProcessInfo, stat: &str, sys: &SysInfo) -> Option<()> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    pinfo.comm = stat[open + 1..close].to_string();
+
+    let fields: Vec<&str> = stat[close + 1..].split_whitespace().collect();
+    // fields[0] is the state, i.e. field 3 in proc(5) numbering
+    let field = |n: usize| fields.get(n - 3).copied();
+
+    pinfo.state = field(3)?.chars().next()?;
+    pinfo.ppid = field(4)?.parse().ok()?;
+    pinfo.pgid = field(5)?.parse().ok()?;
+    pinfo.sid = field(6)?.parse().ok()?;
+    pinfo.tty = field(7)?.parse().ok()?;
+
+    let utime: u64 = field(14)?.parse().ok()?;
+    let stime: u64 = field(15)?.parse().ok()?;
+    pinfo.cpu_time = (utime + stime) / sys.ticks_per_sec;
+
+    pinfo.priority = field(18)?.parse().ok()?;
+    pinfo.nice = field(19)?.parse().ok()?;
+
+    let start_ticks: u64 = field(22)?.parse().ok()?;
+    pinfo.start_time = sys.boot_time + (start_ticks / sys.ticks_per_sec) as i64;
+
+    let vsize: u64 = field(23)?.parse().ok()?;
+    pinfo.vsz = vsize / 1024;
+    let rss: u64 = field(24)?.parse().ok()?;
+    pinfo.rss = rss * sys.page_kb;
+
+    Some(())
+}
+
+// Parse the real and effective IDs from /proc/<pid>/status.
+fn parse_status(pinfo: &mut ProcessInfo, status: &str) {
+    for line in status.lines() {
+        let (key, ids) = match line.split_once(':') {
+            Some(kv) => kv,
+            None => continue,
+        };
+        let ids: Vec<u32> = ids
+            .split_whitespace()
+            .filter_map(|id| id.parse().ok())
+            .collect();
+        if ids.len() < 2 {
+            continue;
+        }
+
+        match key {
+            "Uid" => {
+                pinfo.uid = ids[0];
+                pinfo.euid = ids[1];
+            }
+            "Gid" => {
+                pinfo.gid = ids[0];
+                pinfo.egid = ids[1];
+            }
+            _ => {}
+        }
+    }
+}
+
+// Read only the /proc files that the requested columns, selections
+// and sort keys need; on large process tables most of the cost of ps
+// is in opening and parsing these files.
+fn read_process(root: &Path, pid: libc::pid_t, sys: &SysInfo, needs: Needs) -> Option<ProcessInfo> {
+    let dir = root.join(pid.to_string());
+
+    let mut pinfo = ProcessInfo {
+        pid,
+        ..Default::default()
+    };
+
+    // a process may exit while we are looking at it; skip it quietly
+    if needs.stat {
+        let stat = fs::read_to_string(dir.join("stat")).ok()?;
+        parse_stat(&mut pinfo, &stat, sys)?;
+    }
+
+    if needs.ids {
+        let status = fs::read_to_string(dir.join("status")).ok()?;
+        parse_status(&mut pinfo, &status);
+    }
+
+    if needs.args {
+        if let Ok(cmdline) = fs::read(dir.join("cmdline")) {
+            pinfo.args = cmdline
+                .split(|&b| b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect();
+        }
+    }
+
+    Some(pinfo)
+}
+
+fn list_processes_in(root: &Path, needs: Needs) -> io::Result<Vec<ProcessInfo>> {
+    let sys = sys_info(root)?;
+
+    let mut processes = Vec::new();
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        let pid = match entry.file_name().to_string_lossy().parse::<libc::pid_t>() {
+            Ok(pid) => pid,
+            Err(_) => continue,
+        };
+
+        if let Some(pinfo) = read_process(root, pid, &sys, needs) {
+            processes.push(pinfo);
+        }
+    }
+
+    processes.sort_by_key(|pinfo| pinfo.pid);
+
+    Ok(processes)
+}
+
+pub fn list_processes(needs: Needs) -> io::Result<Vec<ProcessInfo>> {
+    list_processes_in(Path::new("/proc"), needs)
+}
+
+pub fn get_process(pid: libc::pid_t, needs: Needs) -> Option<ProcessInfo> {
+    let root = Path::new("/proc");
+    let sys = sys_info(root).ok()?;
+    read_process(root, pid, &sys, needs)
+}
+
+// Map a Linux tty device number onto its /dev name.
+pub fn tty_name(tty: u64) -> Option<String> {
+    if tty == 0 {
+        return None;
+    }
+
+    let major = (tty >> 8) & 0xfff;
+    let minor = (tty & 0xff) | ((tty >> 12) & 0xfff00);
+    match major {
+        4 if minor < 64 => Some(format!("tty{}", minor)),
+        4 => Some(format!("ttyS{}", minor - 64)),
+        136..=143 => Some(format!("pts/{}", minor + (major - 136) * 256)),
+        _ => Some(format!("{}:{}", major, minor)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // A fake /proc under the temp directory, removed when dropped.
+    struct FakeProc {
+        root: PathBuf,
+    }
+
+    impl FakeProc {
+        // build a table of n_procs processes
+        fn new(name: &str, n_procs: usize) -> FakeProc {
+            let root = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            fs::write(root.join("stat"), "cpu 1 2 3 4\nbtime 1700000000\n").unwrap();
+
+            for i in 0..n_procs {
+                let pid = i + 1;
+                let dir = root.join(pid.to_string());
+                fs::create_dir(&dir).unwrap();
+                fs::write(
+                    dir.join("stat"),
+                    format!(
+                        "{} (proc {}) S 1 {} {} 34816 {} 0 0 0 0 0 {} {} 0 0 20 0 1 0 {} {} {} 0\n",
+                        pid,
+                        pid,
+                        pid,
+                        pid,
+                        pid,
+                        i * 3,
+                        i,
+                        i * 10,
+                        4096 * (i + 1),
+                        i % 97
+                    ),
+                )
+                .unwrap();
+                fs::write(
+                    dir.join("status"),
+                    format!(
+                        "Name:\tproc\nUid:\t{} {} {} {}\nGid:\t{} {} {} {}\n",
+                        i % 7,
+                        i % 7,
+                        i % 7,
+                        i % 7,
+                        i % 5,
+                        i % 5,
+                        i % 5,
+                        i % 5
+                    ),
+                )
+                .unwrap();
+                fs::write(dir.join("cmdline"), format!("proc\0--id\0{}\0", pid)).unwrap();
+            }
+
+            FakeProc { root }
+        }
+
+        // remove one file from every process directory
+        fn remove_all(&self, file: &str) {
+            for entry in fs::read_dir(&self.root).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    fs::remove_file(path.join(file)).unwrap();
+                }
+            }
+        }
+    }
+
+    impl Drop for FakeProc {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    #[test]
+    fn test_parse_simulated_table() {
+        let fake = FakeProc::new("ps-parse", 100);
+
+        let needs = Needs {
+            stat: true,
+            ids: true,
+            args: true,
+        };
+        let procs = list_processes_in(&fake.root, needs).unwrap();
+        assert_eq!(procs.len(), 100);
+
+        let p = &procs[41];
+        assert_eq!(p.pid, 42);
+        assert_eq!(p.ppid, 1);
+        assert_eq!(p.comm, "proc 42");
+        assert_eq!(p.euid, 41 % 7);
+        assert_eq!(p.egid, 41 % 5);
+        assert_eq!(p.args, vec!["proc", "--id", "42"]);
+    }
+
+    // Listing must not read the files that the columns do not need: a
+    // process whose needed file is missing is skipped as having exited,
+    // so every process is still listed only if the files are left alone.
+    #[test]
+    fn test_lazy_read() {
+        let fake = FakeProc::new("ps-lazy", 100);
+
+        fake.remove_all("cmdline");
+        fake.remove_all("status");
+        let needs = Needs {
+            stat: true,
+            ..Default::default()
+        };
+        let procs = list_processes_in(&fake.root, needs).unwrap();
+        assert_eq!(procs.len(), 100);
+        assert_eq!(procs[41].comm, "proc 42");
+        assert!(procs.iter().all(|p| p.args.is_empty() && p.euid == 0));
+
+        fake.remove_all("stat");
+        let procs = list_processes_in(&fake.root, Needs::default()).unwrap();
+        assert_eq!(procs.len(), 100);
+        assert!(procs.iter().all(|p| p.comm.is_empty() && p.args.is_empty()));
+    }
+}
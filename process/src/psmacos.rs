@@ -0,0 +1,161 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use crate::{Needs, ProcessInfo};
+use std::ffi::CStr;
+use std::io;
+use std::mem;
+
+fn list_pids() -> io::Result<Vec<libc::pid_t>> {
+    // ask for the count first, then leave headroom for new processes
+    let count = unsafe { libc::proc_listallpids(std::ptr::null_mut(), 0) };
+    if count < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut pids: Vec<libc::pid_t> = vec![0; count as usize + 64];
+    let bufsize = (pids.len() * mem::size_of::<libc::pid_t>()) as libc::c_int;
+    let count = unsafe { libc::proc_listallpids(pids.as_mut_ptr() as *mut libc::c_void, bufsize) };
+    if count < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    pids.truncate(count as usize);
+    Ok(pids)
+}
+
+// Read the argument vector via sysctl(KERN_PROCARGS2): an argc word,
+// the executable path, padding NULs, then argc NUL-terminated strings.
+fn read_args(pid: libc::pid_t) -> Vec<String> {
+    let mut mib = [libc::CTL_KERN, libc::KERN_PROCARGS2, pid];
+    let mut buf: Vec<u8> = vec![0; 64 * 1024];
+    let mut size = buf.len();
+
+    let ret = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as libc::c_uint,
+            buf.as_mut_ptr() as *mut libc::c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ret != 0 || size < mem::size_of::<libc::c_int>() {
+        return Vec::new();
+    }
+    buf.truncate(size);
+
+    let argc = libc::c_int::from_ne_bytes(buf[..4].try_into().unwrap()) as usize;
+    let mut rest = &buf[4..];
+
+    // skip the executable path and the NUL padding after it
+    let path_end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+    rest = &rest[path_end..];
+    let args_start = rest.iter().position(|&b| b != 0).unwrap_or(rest.len());
+    rest = &rest[args_start..];
+
+    rest.split(|&b| b == 0)
+        .take(argc)
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+// proc_pidinfo() returns everything but the arguments in one call;
+// the arguments need a separate, more expensive sysctl.
+fn read_process(pid: libc::pid_t, needs: Needs) -> Option<ProcessInfo> {
+    let mut info: libc::proc_taskallinfo = unsafe { mem::zeroed() };
+    let size = mem::size_of::<libc::proc_taskallinfo>() as libc::c_int;
+    let ret = unsafe {
+        libc::proc_pidinfo(
+            pid,
+            libc::PROC_PIDTASKALLINFO,
+            0,
+            &mut info as *mut _ as *mut libc::c_void,
+            size,
+        )
+    };
+    if ret != size {
+        return None;
+    }
+
+    let bsd = &info.pbsd;
+    let task = &info.ptinfo;
+
+    let comm = unsafe { CStr::from_ptr(bsd.pbi_comm.as_ptr()) }
+        .to_string_lossy()
+        .into_owned();
+
+    // pbi_status uses the SIDL..SZOMB numbering from <sys/proc.h>
+    let state = match bsd.pbi_status {
+        1 => 'I',
+        2 => 'R',
+        3 => 'S',
+        4 => 'T',
+        5 => 'Z',
+        _ => '?',
+    };
+
+    Some(ProcessInfo {
+        pid,
+        ppid: bsd.pbi_ppid as libc::pid_t,
+        pgid: bsd.pbi_pgid as libc::pid_t,
+        sid: unsafe { libc::getsid(pid) },
+        uid: bsd.pbi_ruid,
+        euid: bsd.pbi_uid,
+        gid: bsd.pbi_rgid,
+        egid: bsd.pbi_gid,
+        tty: bsd.e_tdev as u64,
+        state,
+        priority: task.pti_priority,
+        nice: bsd.pbi_nice,
+        vsz: task.pti_virtual_size / 1024,
+        rss: task.pti_resident_size / 1024,
+        cpu_time: (task.pti_total_user + task.pti_total_system) / 1_000_000_000,
+        start_time: bsd.pbi_start_tvsec as i64,
+        comm,
+        args: if needs.args {
+            read_args(pid)
+        } else {
+            Vec::new()
+        },
+    })
+}
+
+pub fn list_processes(needs: Needs) -> io::Result<Vec<ProcessInfo>> {
+    let mut processes: Vec<ProcessInfo> = list_pids()?
+        .into_iter()
+        .filter_map(|pid| read_process(pid, needs))
+        .collect();
+
+    processes.sort_by_key(|pinfo| pinfo.pid);
+
+    Ok(processes)
+}
+
+pub fn get_process(pid: libc::pid_t, needs: Needs) -> Option<ProcessInfo> {
+    read_process(pid, needs)
+}
+
+pub fn tty_name(tty: u64) -> Option<String> {
+    // NODEV is all-ones
+    if tty == 0 || tty as u32 == u32::MAX {
+        return None;
+    }
+
+    let name = unsafe { libc::devname(tty as libc::dev_t, libc::S_IFCHR) };
+    if name.is_null() {
+        return None;
+    }
+
+    let name = unsafe { CStr::from_ptr(name) }
+        .to_string_lossy()
+        .into_owned();
+    Some(name)
+}
@@ -27,7 +27,7 @@ const PRIO_MAX: i32 = 20;
 #[command(author, version, about, long_about)]
 struct Args {
     /// A positive or negative decimal integer which shall have the same effect on the execution of the utility as if the utility had called the nice() function with the numeric value of the increment option-argument.
-    #[arg(short, long, required=true, value_parser = clap::value_parser!(i32).range(-20..20))]
+    #[arg(short, long, required = true, allow_negative_numbers = true)]
     niceval: i32,
 
     /// Interpret the following operands as unsigned decimal integer process group IDs.
@@ -42,8 +42,13 @@ struct Args {
     #[arg(short, long, group = "mode")]
     user: bool,
 
-    /// process id to adjust priority
-    id: String,
+    /// Report the old and new nice value of each adjusted process, process group or user.
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// process IDs, process group IDs or users whose priority is adjusted
+    #[arg(required = true)]
+    ids: Vec<String>,
 }
 
 fn lookup_uid(username: &str) -> Result<u32, &'static str> {
@@ -62,23 +67,57 @@ fn parse_id(which: u32, input: &str) -> Result<u32, &'static str> {
     match input.parse::<u32>() {
         Ok(0) => Err("Invalid ID"),
         Ok(n) => Ok(n),
-        Err(e) => {
+        Err(_) => {
             if which != libc::PRIO_USER as u32 {
-                eprintln!("{}", e);
                 Err("Invalid ID")
             } else {
-                match lookup_uid(input) {
-                    Ok(uid) => Ok(uid),
-                    Err(e) => {
-                        eprintln!("{}", e);
-                        Err("Invalid ID or username")
-                    }
-                }
+                lookup_uid(input)
             }
         }
     }
 }
 
+// Find the processes in a process group or owned by a user, so the
+// increment can be applied to each process's own nice value.
+#[cfg(target_os = "linux")]
+fn matching_pids(which: u32, id: u32) -> io::Result<Vec<u32>> {
+    let mut pids = Vec::new();
+
+    for entry in std::fs::read_dir("/proc")? {
+        let entry = entry?;
+        let pid = match entry.file_name().to_string_lossy().parse::<u32>() {
+            Ok(pid) => pid,
+            Err(_) => continue,
+        };
+
+        let matched = if which == libc::PRIO_PGRP as u32 {
+            // the process group is the third field after the parenthesized name
+            std::fs::read_to_string(format!("/proc/{}/stat", pid))
+                .ok()
+                .and_then(|stat| {
+                    let fields = &stat[stat.rfind(')')? + 1..];
+                    fields.split_whitespace().nth(2)?.parse::<u32>().ok()
+                })
+                == Some(id)
+        } else {
+            // match on the real user ID, the first one on the Uid: line
+            std::fs::read_to_string(format!("/proc/{}/status", pid))
+                .ok()
+                .and_then(|status| {
+                    let uids = status.lines().find_map(|l| l.strip_prefix("Uid:"))?;
+                    uids.split_whitespace().next()?.parse::<u32>().ok()
+                })
+                == Some(id)
+        };
+
+        if matched {
+            pids.push(pid);
+        }
+    }
+
+    Ok(pids)
+}
+
 fn xgetpriority(which: u32, id: u32) -> io::Result<i32> {
     set_errno(errno::Errno(0));
 
@@ -92,9 +131,7 @@ fn xgetpriority(which: u32, id: u32) -> io::Result<i32> {
     if errno_res == 0 {
         Ok(res)
     } else {
-        let e = io::Error::from_raw_os_error(errno_res);
-        eprintln!("getpriority: {}", e);
-        Err(e)
+        Err(io::Error::from_raw_os_error(errno_res))
     }
 }
 
@@ -106,14 +143,85 @@ fn xsetpriority(which: u32, id: u32, prio: i32) -> io::Result<()> {
     let res = unsafe { libc::setpriority(which as i32, id, prio) };
 
     if res < 0 {
-        let e = io::Error::last_os_error();
-        eprintln!("setpriority: {}", e);
-        Err(e)
+        Err(io::Error::last_os_error())
     } else {
         Ok(())
     }
 }
 
+fn which_str(which: u32) -> &'static str {
+    if which == libc::PRIO_PGRP as u32 {
+        "process group ID"
+    } else if which == libc::PRIO_USER as u32 {
+        "user ID"
+    } else {
+        "process ID"
+    }
+}
+
+fn renice_one(which: u32, id: u32, niceval: i32) -> io::Result<()> {
+    // get current priority
+    let prio = xgetpriority(which, id)?;
+
+    // adjust priority based on user input, keeping it within bounds
+    let newprio = prio.saturating_add(niceval).clamp(PRIO_MIN, PRIO_MAX);
+
+    // attempt to set new priority
+    xsetpriority(which, id, newprio)?;
+
+    Ok(())
+}
+
+// Apply the increment to one operand.  Process groups and users may
+// cover many processes with different nice values; each one is
+// adjusted relative to its own value.
+fn adjust_target(which: u32, id: u32, niceval: i32) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    if which != libc::PRIO_PROCESS as u32 {
+        let pids = matching_pids(which, id)?;
+        if pids.is_empty() {
+            return Err(io::Error::from_raw_os_error(libc::ESRCH));
+        }
+
+        // a process that exited since the scan is no longer a member
+        let mut result = Ok(());
+        for pid in pids {
+            match renice_one(libc::PRIO_PROCESS as u32, pid, niceval) {
+                Err(e) if e.raw_os_error() == Some(libc::ESRCH) => {}
+                Err(e) => result = Err(e),
+                Ok(()) => {}
+            }
+        }
+        return result;
+    }
+
+    renice_one(which, id, niceval)
+}
+
+// With -v, one line is reported per operand.  For a process group or
+// user, the priorities are those getpriority() reports for the whole
+// group: the highest priority (lowest nice value) among its processes.
+fn renice_target(which: u32, id: u32, niceval: i32, verbose: bool) -> io::Result<()> {
+    if !verbose {
+        return adjust_target(which, id, niceval);
+    }
+
+    let prio = xgetpriority(which, id)?;
+    adjust_target(which, id, niceval)?;
+
+    // read it back: the system may clamp to a narrower range
+    let newprio = xgetpriority(which, id)?;
+    println!(
+        "{} ({}) old priority {}, new priority {}",
+        id,
+        which_str(which),
+        prio,
+        newprio
+    );
+
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // parse command line arguments
     let args = Args::parse();
@@ -133,17 +241,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    // who: obtain pgrp/pid/uid
-    let id = parse_id(which, &args.id)?;
-
-    // get current priority
-    let prio = xgetpriority(which, id)?;
+    let mut exit_code = 0;
 
-    // adjust priority based on user input
-    let newprio = (prio + args.niceval).clamp(PRIO_MIN, PRIO_MAX);
+    for id_str in &args.ids {
+        // who: obtain pgrp/pid/uid
+        let id = match parse_id(which, id_str) {
+            Ok(id) => id,
+            Err(e) => {
+                eprintln!("renice: {}: {}", id_str, e);
+                exit_code = 1;
+                continue;
+            }
+        };
 
-    // attempt to set new priority
-    xsetpriority(which, id, newprio)?;
+        if let Err(e) = renice_target(which, id, args.niceval, args.verbose) {
+            eprintln!("renice: {}: {}", id_str, e);
+            exit_code = 1;
+        }
+    }
 
-    Ok(())
+    std::process::exit(exit_code)
 }
@@ -7,7 +7,6 @@
 // SPDX-License-Identifier: MIT
 //
 // TODO:
-// - the -0 argument used in conjunction with find -print0
 // - trace mode (-t)
 // - prompt mode (-p)
 // - insert mode (-I)
@@ -21,9 +20,14 @@ extern crate plib;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::PROJECT_NAME;
+use plib::error::{EXIT_CANNOT_INVOKE, EXIT_FAILURE, EXIT_NOT_FOUND};
+use plib::wait::{wait_child, WaitStatus};
+use plib::{util_error, PROJECT_NAME};
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
 use std::io::{self, Read};
-use std::process::{Command, Stdio};
+use std::os::unix::ffi::OsStringExt;
+use std::process::{Child, Command, Stdio};
 
 const ARG_MAX: i32 = 131072; // arbitrary.  todo: discover actual value
 const MAX_ARGS_BYTES: usize = ARG_MAX as usize - 2048;
@@ -64,6 +68,14 @@ struct Args {
     #[arg(short = 'x', long)]
     exit: bool,
 
+    /// Run up to maxprocs invocations of utility at the same time.
+    #[arg(short = 'P', long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    maxprocs: u32,
+
+    /// Input items are terminated by a null character instead of by whitespace, and quotes and backslashes are not special.
+    #[arg(short = '0', long)]
+    null: bool,
+
     /// utility to invoke
     util: String,
 
@@ -71,21 +83,92 @@ struct Args {
     util_args: Vec<String>,
 }
 
-// find a string in a vector of strings
-fn find_str(needle: &str, haystack: &[String]) -> Option<usize> {
-    haystack.iter().position(|s| s == needle)
+// find a string in a vector of arguments
+fn find_str(needle: &str, haystack: &[OsString]) -> Option<usize> {
+    haystack.iter().position(|s| s == OsStr::new(needle))
 }
 
-// execute the utility
-fn exec_util(util: &str, util_args: Vec<String>) -> io::Result<()> {
-    let _output = Command::new(util)
-        .args(util_args)
-        .stdin(Stdio::null())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .output()?;
+// Runs utility invocations, at most maxprocs at a time, and folds
+// their exit statuses into the final exit status of xargs.
+struct Spawner {
+    maxprocs: usize,
+    running: HashMap<libc::pid_t, Child>,
+    exit_code: i32,
+}
 
-    Ok(())
+impl Spawner {
+    fn new(maxprocs: usize) -> Spawner {
+        Spawner {
+            maxprocs,
+            running: HashMap::new(),
+            exit_code: 0,
+        }
+    }
+
+    // record the wait status of a finished invocation
+    fn reap(&mut self, status: WaitStatus) -> io::Result<()> {
+        match status {
+            WaitStatus::Exited(0) => {}
+            WaitStatus::Exited(255) => {
+                return Err(io::Error::other("utility exited with status 255; aborting"))
+            }
+            WaitStatus::Exited(_) => self.exit_code = 1,
+            WaitStatus::Signaled { signal, .. } => {
+                return Err(io::Error::other(format!(
+                    "utility terminated by signal {}; aborting",
+                    signal
+                )));
+            }
+            WaitStatus::Stopped(_) | WaitStatus::Continued => {}
+        }
+
+        Ok(())
+    }
+
+    // wait for any one running invocation to finish
+    fn wait_one(&mut self) -> io::Result<()> {
+        if let Some((pid, status)) = wait_child(None, 0)? {
+            // the child is already reaped; dropping the handle does not wait
+            if self.running.remove(&pid).is_some() {
+                self.reap(status)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // execute the utility, once a slot is free
+    fn spawn(&mut self, util: &str, util_args: Vec<OsString>) -> io::Result<()> {
+        while self.running.len() >= self.maxprocs {
+            self.wait_one()?;
+        }
+
+        let child = Command::new(util)
+            .args(util_args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        self.running.insert(child.id() as libc::pid_t, child);
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<i32> {
+        while !self.running.is_empty() {
+            self.wait_one()?;
+        }
+
+        Ok(self.exit_code)
+    }
+
+    // after an error, wait for the invocations still running, whose
+    // statuses no longer matter
+    fn abandon(&mut self) {
+        for (_, mut child) in self.running.drain() {
+            let _ = child.wait();
+        }
+    }
 }
 
 struct ParseState {
@@ -94,19 +177,20 @@ struct ParseState {
     util_n_args: usize,
 
     // input state
-    tmp_arg: String,
+    tmp_arg: Vec<u8>,
     in_arg: bool,
     in_quote: bool,
     in_escape: bool,
-    quote_char: char,
+    quote_char: u8,
     skip_remainder: bool,
+    null_sep: bool,
 
     // output state
     max_bytes: usize,
     max_args: Option<usize>,
 
     // parsed args, ready for exec
-    args: Vec<String>,
+    args: Vec<OsString>,
 }
 
 impl ParseState {
@@ -119,12 +203,13 @@ impl ParseState {
         ParseState {
             util_size: total,
             util_n_args: args.util_args.len(),
-            tmp_arg: String::new(),
+            tmp_arg: Vec::new(),
             in_arg: false,
             in_quote: false,
             in_escape: false,
-            quote_char: '"',
+            quote_char: b'"',
             skip_remainder: false,
+            null_sep: args.null,
             max_bytes: args.maxsize.unwrap_or(MAX_ARGS_BYTES),
             max_args: args.maxnum,
             args: Vec::new(),
@@ -146,7 +231,7 @@ impl ParseState {
         }
     }
 
-    fn remove_args(&mut self) -> Vec<String> {
+    fn remove_args(&mut self) -> Vec<OsString> {
         let mut total = self.util_size;
         let mut ret = Vec::new();
         while !self.args.is_empty() {
@@ -176,32 +261,37 @@ impl ParseState {
             return Ok(());
         }
 
-        for c8 in buf {
-            let ch = *c8 as char;
+        if self.null_sep {
+            self.parse_buf_null(buf);
+            return Ok(());
+        }
+
+        // bytes, not characters: only ASCII bytes are special, so the
+        // bytes of a multibyte character are kept as they are
+        for &ch in buf {
+            let is_blank = matches!(ch, b' ' | b'\t'..=b'\r');
 
             if self.in_quote {
                 if ch == self.quote_char {
                     self.in_quote = false;
                     self.in_arg = false;
-                    self.args.push(self.tmp_arg.clone());
-                    self.tmp_arg = String::new();
+                    self.push_arg();
                 } else {
                     self.tmp_arg.push(ch);
                 }
             } else if self.in_escape {
                 self.in_escape = false;
                 self.tmp_arg.push(ch);
-            } else if self.in_arg && ch.is_whitespace() {
+            } else if self.in_arg && is_blank {
                 self.in_arg = false;
-                self.args.push(self.tmp_arg.clone());
-                self.tmp_arg = String::new();
-            } else if ch == '\'' || ch == '"' {
+                self.push_arg();
+            } else if ch == b'\'' || ch == b'"' {
                 self.in_arg = true;
                 self.in_quote = true;
                 self.quote_char = ch;
-            } else if ch == '\\' {
+            } else if ch == b'\\' {
                 self.in_escape = true;
-            } else if ch.is_whitespace() {
+            } else if is_blank {
                 // ignore whitespace
             } else {
                 self.in_arg = true;
@@ -212,11 +302,36 @@ impl ParseState {
         Ok(())
     }
 
+    // -0 mode: items end at NUL bytes, everything else is literal
+    fn parse_buf_null(&mut self, buf: &[u8]) {
+        for chunk in buf.split_inclusive(|&b| b == 0) {
+            let (data, terminated) = match chunk.split_last() {
+                Some((0, data)) => (data, true),
+                _ => (chunk, false),
+            };
+
+            // an item may be split across reads, so it is only turned
+            // into an argument once it is complete
+            self.tmp_arg.extend_from_slice(data);
+            self.in_arg = true;
+
+            if terminated {
+                self.in_arg = false;
+                self.push_arg();
+            }
+        }
+    }
+
+    // the bytes collected so far become the next argument
+    fn push_arg(&mut self) {
+        let arg = std::mem::take(&mut self.tmp_arg);
+        self.args.push(OsString::from_vec(arg));
+    }
+
     fn parse_finalize(&mut self) {
         if self.in_arg {
             self.in_arg = false;
-            self.args.push(self.tmp_arg.clone());
-            self.tmp_arg = String::new();
+            self.push_arg();
         }
     }
 
@@ -232,7 +347,31 @@ impl ParseState {
     }
 }
 
-fn read_and_spawn(args: &Args) -> io::Result<()> {
+// invoke the utility with as many pending args as fit
+fn spawn_batch(args: &Args, state: &mut ParseState, spawner: &mut Spawner) -> io::Result<()> {
+    let mut batch = state.remove_args();
+    if batch.is_empty() {
+        return Err(io::Error::other("argument list too long"));
+    }
+
+    let mut util_args: Vec<OsString> = args.util_args.iter().map(OsString::from).collect();
+    util_args.append(&mut batch);
+    spawner.spawn(&args.util, util_args)
+}
+
+fn read_and_spawn(args: &Args) -> io::Result<i32> {
+    let mut spawner = Spawner::new(args.maxprocs as usize);
+
+    let result = spawn_all(args, &mut spawner).and_then(|()| spawner.finish());
+    if result.is_err() {
+        spawner.abandon();
+    }
+
+    result
+}
+
+// read all of standard input, invoking the utility as arguments pile up
+fn spawn_all(args: &Args, spawner: &mut Spawner) -> io::Result<()> {
     let mut state = ParseState::new(args);
 
     let mut buffer = [0; plib::BUFSZ];
@@ -252,10 +391,8 @@ fn read_and_spawn(args: &Args) -> io::Result<()> {
         state.postprocess(args)?;
 
         // if enough args, spawn the utility
-        if state.full() {
-            let mut util_args = args.util_args.clone();
-            util_args.append(&mut state.remove_args());
-            exec_util(&args.util, util_args)?;
+        while state.full() {
+            spawn_batch(args, &mut state, spawner)?;
         }
     }
 
@@ -263,10 +400,8 @@ fn read_and_spawn(args: &Args) -> io::Result<()> {
     state.parse_finalize();
 
     // if there are any remaining args, spawn the utility
-    if !state.args.is_empty() {
-        let mut util_args = args.util_args.clone();
-        util_args.append(&mut state.remove_args());
-        exec_util(&args.util, util_args)?;
+    while !state.args.is_empty() {
+        spawn_batch(args, &mut state, spawner)?;
     }
 
     Ok(())
@@ -280,7 +415,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     textdomain(PROJECT_NAME)?;
     bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
 
-    read_and_spawn(&args)?;
+    let exit_code = match read_and_spawn(&args) {
+        Ok(code) => code,
+        Err(e) => {
+            util_error!("{}: {}", args.util, e);
+            match e.kind() {
+                io::ErrorKind::NotFound => EXIT_NOT_FOUND,
+                io::ErrorKind::PermissionDenied => EXIT_CANNOT_INVOKE,
+                _ => EXIT_FAILURE,
+            }
+        }
+    };
 
-    Ok(())
+    std::process::exit(exit_code)
 }
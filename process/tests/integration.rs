@@ -0,0 +1,409 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::{run_test, TestPlan};
+
+fn kill_test(args: &[&str], expected_output: &str, expected_error: &str, expected_exit_code: i32) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("kill"),
+        args: str_args,
+        stdin_data: String::new(),
+        expected_out: String::from(expected_output),
+        expected_err: String::from(expected_error),
+        expected_exit_code,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_kill_list_exit_status() {
+    kill_test(&["-l", "143"], "TERM\n", "", 0);
+    kill_test(&["-l", "9"], "KILL\n", "", 0);
+}
+
+#[test]
+fn test_kill_list_name() {
+    kill_test(&["-l", "TERM", "SIGHUP"], "15\n1\n", "", 0);
+}
+
+#[test]
+fn test_kill_list_alias() {
+    // IOT is another name for ABRT; the number lists under the first name
+    kill_test(&["-l", "SIGIOT"], &format!("{}\n", libc::SIGABRT), "", 0);
+    kill_test(&["-l", &libc::SIGABRT.to_string()], "ABRT\n", "", 0);
+}
+
+#[test]
+fn test_kill_signal_zero() {
+    let pid = std::process::id().to_string();
+    kill_test(&["-s", "0", &pid], "", "", 0);
+    kill_test(&["-0", "--", &pid], "", "", 0);
+}
+
+fn ps_test(args: &[&str], expected_output: &str, expected_error: &str, expected_exit_code: i32) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("ps"),
+        args: str_args,
+        stdin_data: String::new(),
+        expected_out: String::from(expected_output),
+        expected_err: String::from(expected_error),
+        expected_exit_code,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_ps_pid_no_header() {
+    let pid = std::process::id().to_string();
+    ps_test(&["-p", &pid, "-o", "pid="], &format!("{}\n", pid), "", 0);
+}
+
+#[test]
+fn test_ps_custom_header() {
+    let pid = std::process::id().to_string();
+    let expected = format!("{:>6}\n{:>6}\n", "My PID", pid);
+    ps_test(&["-p", &pid, "-o", "pid=My PID"], &expected, "", 0);
+}
+
+#[test]
+fn test_ps_sort() {
+    let pid = std::process::id().to_string();
+    let pids = format!("1,{}", pid);
+    let width = pid.len();
+
+    ps_test(
+        &["-p", &pids, "--sort", "-pid", "-o", "pid="],
+        &format!("{}\n{:>2$}\n", pid, 1, width),
+        "",
+        0,
+    );
+    ps_test(
+        &["-p", &pids, "--sort=pid", "-o", "pid="],
+        &format!("{:>2$}\n{}\n", 1, pid, width),
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_ps_unknown_field() {
+    ps_test(
+        &["-o", "bogus"],
+        "",
+        "Error: \"unknown output format: bogus\"\n",
+        1,
+    );
+}
+
+fn renice_test(
+    args: &[&str],
+    expected_output: &str,
+    expected_error: &str,
+    expected_exit_code: i32,
+) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("renice"),
+        args: str_args,
+        stdin_data: String::new(),
+        expected_out: String::from(expected_output),
+        expected_err: String::from(expected_error),
+        expected_exit_code,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_renice_clamped() {
+    let mut child = std::process::Command::new("sleep")
+        .arg("10")
+        .spawn()
+        .unwrap();
+    let pid = child.id().to_string();
+
+    // an increment far beyond the limit is clamped rather than rejected
+    renice_test(&["-n", "100", &pid], "", "", 0);
+    let prio = unsafe { libc::getpriority(libc::PRIO_PROCESS, child.id()) };
+    assert_eq!(prio, 19);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_renice_verbose() {
+    let mut child = std::process::Command::new("sleep")
+        .arg("10")
+        .spawn()
+        .unwrap();
+    let pid = child.id().to_string();
+    let old = unsafe { libc::getpriority(libc::PRIO_PROCESS, child.id()) };
+    let new = (old + 4).min(19);
+
+    renice_test(
+        &["-v", "-n", "4", &pid],
+        &format!(
+            "{} (process ID) old priority {}, new priority {}\n",
+            pid, old, new
+        ),
+        "",
+        0,
+    );
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_renice_verbose_group() {
+    use std::os::unix::process::CommandExt;
+
+    // a process group of its own, with the same ID as the process
+    let mut child = std::process::Command::new("sleep")
+        .arg("10")
+        .process_group(0)
+        .spawn()
+        .unwrap();
+    let pgid = child.id().to_string();
+    let old = unsafe { libc::getpriority(libc::PRIO_PGRP, child.id()) };
+    let new = (old + 4).min(19);
+
+    renice_test(
+        &["-v", "-n", "4", "-g", &pgid],
+        &format!(
+            "{} (process group ID) old priority {}, new priority {}\n",
+            pgid, old, new
+        ),
+        "",
+        0,
+    );
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_renice_bad_target_continues() {
+    let mut child = std::process::Command::new("sleep")
+        .arg("10")
+        .spawn()
+        .unwrap();
+    let pid = child.id().to_string();
+
+    renice_test(
+        &["-n", "1", "abc", &pid],
+        "",
+        "renice: abc: Invalid ID\n",
+        1,
+    );
+    let prio = unsafe { libc::getpriority(libc::PRIO_PROCESS, child.id()) };
+    assert_eq!(prio, 1);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+fn nohup_test(args: &[&str], expected_output: &str, expected_error: &str, expected_exit_code: i32) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("nohup"),
+        args: str_args,
+        stdin_data: String::new(),
+        expected_out: String::from(expected_output),
+        expected_err: String::from(expected_error),
+        expected_exit_code,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_nohup_not_a_tty() {
+    // neither stdout nor stderr is a terminal, so nothing is redirected
+    nohup_test(&["echo", "hello"], "hello\n", "", 0);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_nohup_no_extra_descriptors() {
+    // the saved copy of stderr must not leak into the command
+    nohup_test(&["sh", "-c", "ls /proc/$$/fd"], "0\n1\n2\n", "", 0);
+}
+
+#[test]
+fn test_nohup_not_found() {
+    nohup_test(
+        &["posixutils-no-such-command"],
+        "",
+        "nohup: command not found\n",
+        127,
+    );
+}
+
+fn xargs_test(
+    args: &[&str],
+    stdin_data: &str,
+    expected_output: &str,
+    expected_error: &str,
+    expected_exit_code: i32,
+) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("xargs"),
+        args: str_args,
+        stdin_data: String::from(stdin_data),
+        expected_out: String::from(expected_output),
+        expected_err: String::from(expected_error),
+        expected_exit_code,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_xargs_null_input() {
+    xargs_test(
+        &["-0", "-n", "1", "echo"],
+        "a b\0'c'\0d",
+        "a b\n'c'\nd\n",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_xargs_parallel() {
+    // invocations finish in any order under -P, so use a silent utility
+    xargs_test(
+        &["-P", "4", "-n", "2", "true"],
+        "1 2 3 4 5 6 7 8",
+        "",
+        "",
+        0,
+    );
+}
+
+#[test]
+fn test_xargs_failed_invocation() {
+    xargs_test(&["-n", "1", "false"], "1 2", "", "", 1);
+}
+
+#[test]
+fn test_xargs_not_found() {
+    xargs_test(
+        &["posixutils-no-such-command"],
+        "x",
+        "",
+        "xargs: posixutils-no-such-command: No such file or directory (os error 2)\n",
+        127,
+    );
+}
+
+#[test]
+fn test_xargs_null_raw_bytes() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    // a multibyte character split across reads, and a name that is not UTF-8
+    let mut input = vec![b'a'; plib::BUFSZ - 1];
+    input.extend_from_slice("\u{e9}\0".as_bytes());
+    input.extend_from_slice(b"caf\xe9\0");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_xargs"))
+        .args(["-0", "printf", "%s\\n"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(&input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let expected: Vec<u8> = input
+        .iter()
+        .map(|&b| if b == 0 { b'\n' } else { b })
+        .collect();
+    assert_eq!(output.stdout, expected);
+}
+
+#[test]
+fn test_xargs_abort_waits_for_children() {
+    use std::io::Write;
+    use std::os::unix::fs::PermissionsExt;
+    use std::process::{Command, Stdio};
+
+    let dir = std::env::temp_dir().join(format!("xargs-abort-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let done = dir.join("done");
+
+    // the second invocation exits with 255 while the first still runs
+    let script = dir.join("script");
+    std::fs::write(
+        &script,
+        format!(
+            "#!/bin/sh\nexec >/dev/null 2>&1\nif [ \"$1\" = slow ]; then sleep 1; : > {}; else exit 255; fi\n",
+            done.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_xargs"))
+        .args(["-P", "2", "-n", "1", script.to_str().unwrap()])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"slow fast").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(!output.status.success());
+    assert!(done.exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_env_not_found() {
+    run_test(TestPlan {
+        cmd: String::from("env"),
+        args: vec![String::from("posixutils-no-such-command")],
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::from(
+            "env: posixutils-no-such-command: No such file or directory (os error 2)\n",
+        ),
+        expected_exit_code: 127,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_nice_not_found() {
+    run_test(TestPlan {
+        cmd: String::from("nice"),
+        args: vec![
+            String::from("-n"),
+            String::from("0"),
+            String::from("posixutils-no-such-command"),
+        ],
+        stdin_data: String::new(),
+        expected_out: String::new(),
+        expected_err: String::from(
+            "nice: posixutils-no-such-command: No such file or directory (os error 2)\n",
+        ),
+        expected_exit_code: 127,
+        ..Default::default()
+    });
+}
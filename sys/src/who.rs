@@ -11,9 +11,14 @@ extern crate chrono;
 extern crate clap;
 extern crate plib;
 
+use chrono::{Local, TimeZone};
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::utmpx::{EntryType, Utmpx};
 use plib::PROJECT_NAME;
+use std::ffi::CStr;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
 use std::path::PathBuf;
 
 /// who - display who is on the system
@@ -57,15 +62,15 @@ struct Args {
     runlevel: bool,
 
     /// List only the name, line, and time fields (default).
-    #[arg(short, long = "short", default_value_t = true, group = "output")]
+    #[arg(short, long = "short", overrides_with = "terminals")]
     short_format: bool,
 
     /// Indicate the last change to the system clock.
     #[arg(short = 't', long = "time")]
     last_change: bool,
 
-    /// Show the state of each terminal
-    #[arg(short = 'T', long, group = "output")]
+    /// Show the state of each terminal: + (writable by others), - (not writable), or ? (bad line).
+    #[arg(short = 'T', long, overrides_with = "short_format")]
     terminals: bool,
 
     /// Normal selection of information
@@ -76,61 +81,130 @@ struct Args {
     #[arg(short = 'u', long = "users")]
     idle_time: bool,
 
-    /// Gather information from FILE, instead of default utmp file.
+    /// Read the entries from FILE, such as /var/log/wtmp, instead of the utmpx database.
     file: Option<PathBuf>,
 }
 
 // convert timestamp into POSIX-specified strftime format
 fn fmt_timestamp(ts: libc::time_t) -> String {
-    let dt = chrono::DateTime::from_timestamp(ts, 0).unwrap();
-    dt.format("%b %e %H:%M").to_string()
+    match Local.timestamp_opt(ts, 0) {
+        chrono::LocalResult::Single(dt) => dt.format("%b %e %H:%M").to_string(),
+        _ => ts.to_string(),
+    }
 }
 
-fn print_fmt_short(entry: &plib::utmpx::Utmpx, line: &str) {
-    println!(
-        "{:<16} {:<12} {}",
-        entry.user,
-        line,
-        fmt_timestamp(entry.timestamp)
-    );
+/// Terminal state for -T: '+' if other users may write to it, '-' if
+/// not, '?' if the line is not a terminal.
+fn term_state(line: &str) -> char {
+    match fs::metadata(format!("/dev/{}", line)) {
+        // mesg y sets the group write bit
+        Ok(md) if md.mode() & 0o020 != 0 => '+',
+        Ok(_) => '-',
+        Err(_) => '?',
+    }
 }
 
-fn print_fmt_term(entry: &plib::utmpx::Utmpx, line: &str) {
-    let term_state = '?';
-    println!(
-        "{:<16} {} {:<12} {}",
-        entry.user,
-        term_state,
-        line,
-        fmt_timestamp(entry.timestamp)
-    );
-}
+/// Idle time for -u: '.' for activity within the last minute, "old"
+/// beyond a day, otherwise hours and minutes.
+fn idle_time(line: &str) -> String {
+    let atime = match fs::metadata(format!("/dev/{}", line)) {
+        Ok(md) => md.atime(),
+        Err(_) => return String::from("?"),
+    };
 
-fn print_entry(args: &Args, entry: &plib::utmpx::Utmpx) {
-    let mut selected = false;
-    if (args.boot && entry.typ == libc::BOOT_TIME)
-        || (args.userproc && entry.typ == libc::USER_PROCESS)
-        || (args.dead && entry.typ == libc::DEAD_PROCESS)
-        || (args.login && entry.typ == libc::LOGIN_PROCESS)
-        || (args.runlevel && entry.typ == libc::RUN_LVL)
-    {
-        selected = true;
+    let idle = Local::now().timestamp() - atime;
+    if idle < 60 {
+        String::from(".")
+    } else if idle >= 24 * 60 * 60 {
+        String::from("old")
+    } else {
+        format!("{:02}:{:02}", idle / 3600, (idle % 3600) / 60)
     }
+}
 
-    if !selected {
-        return;
+fn print_heading(args: &Args) {
+    let mut heading = String::from("NAME    ");
+    if args.terminals {
+        heading.push_str("  ");
     }
+    heading.push_str(" LINE         TIME        ");
+    if args.idle_time {
+        heading.push_str(" IDLE   PID");
+    }
+    println!("{}", heading.trim_end());
+}
 
-    let line = match entry.typ {
-        libc::BOOT_TIME => "system boot",
-        _ => entry.line.as_str(),
+fn print_entry(args: &Args, entry: &Utmpx) {
+    let user_entry = entry.typ == EntryType::UserProcess;
+    let (name, line) = match entry.typ {
+        EntryType::BootTime => ("", String::from("system boot")),
+        // the run level is kept in the low byte of the pid
+        EntryType::RunLevel => (
+            "",
+            format!("run-level {}", ((entry.pid % 256) as u8) as char),
+        ),
+        EntryType::NewTime => ("", String::from("new time")),
+        EntryType::OldTime => ("", String::from("old time")),
+        _ => (entry.user.as_str(), entry.line.clone()),
     };
 
-    if args.short_format {
-        print_fmt_short(entry, line);
-    } else {
-        print_fmt_term(entry, line);
+    let mut out = format!("{:<8}", name);
+    if args.terminals {
+        let state = if user_entry {
+            term_state(&entry.line)
+        } else {
+            ' '
+        };
+        out.push(' ');
+        out.push(state);
+    }
+    out.push_str(&format!(" {:<12} {}", line, fmt_timestamp(entry.timestamp)));
+
+    if args.idle_time {
+        let idle = match entry.typ {
+            EntryType::UserProcess | EntryType::LoginProcess => idle_time(&entry.line),
+            _ => String::new(),
+        };
+        out.push_str(&format!(" {:>5}", idle));
+        if entry.typ != EntryType::BootTime && entry.typ != EntryType::RunLevel {
+            out.push_str(&format!(" {:>5}", entry.pid));
+        }
+        if entry.typ == EntryType::UserProcess && !entry.host.is_empty() {
+            out.push_str(&format!(" ({})", entry.host));
+        }
+    }
+
+    println!("{}", out.trim_end());
+}
+
+fn is_selected(args: &Args, entry: &Utmpx) -> bool {
+    (args.boot && entry.typ == EntryType::BootTime)
+        || (args.userproc && entry.typ == EntryType::UserProcess)
+        || (args.dead && entry.typ == EntryType::DeadProcess)
+        || (args.login && entry.typ == EntryType::LoginProcess)
+        || (args.process && entry.typ == EntryType::InitProcess)
+        || (args.runlevel && entry.typ == EntryType::RunLevel)
+        || (args.last_change
+            && (entry.typ == EntryType::NewTime || entry.typ == EntryType::OldTime))
+}
+
+/// The terminal of standard input, relative to /dev.
+fn current_terminal() -> Option<String> {
+    let name = unsafe { libc::ttyname(libc::STDIN_FILENO) };
+    if name.is_null() {
+        return None;
     }
+    let name = unsafe { CStr::from_ptr(name) }.to_string_lossy();
+    Some(name.strip_prefix("/dev/").unwrap_or(&name).to_string())
+}
+
+fn print_summary(entries: &[Utmpx]) {
+    let users: Vec<&str> = plib::utmpx::user_sessions(entries)
+        .map(|entry| entry.user.as_str())
+        .collect();
+
+    println!("{}", users.join(" "));
+    println!("# users={}", users.len());
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -141,7 +215,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // parse command line arguments; if "who am i", use special args
     let mut args = {
         if am_i {
-            Args::parse_from(&["-m"])
+            Args::parse_from(["who", "-m"])
         } else {
             Args::parse()
         }
@@ -156,7 +230,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         args.last_change = true;
         args.terminals = true;
         args.idle_time = true;
-    } else if !args.boot && !args.dead && !args.login && !args.process && !args.runlevel {
+    } else if !args.boot
+        && !args.dead
+        && !args.login
+        && !args.process
+        && !args.runlevel
+        && !args.last_change
+    {
         args.userproc = true;
     }
 
@@ -164,17 +244,48 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     textdomain(PROJECT_NAME)?;
     bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
 
-    let mut exit_code = 0;
+    let entries = match &args.file {
+        Some(file) => match plib::utmpx::load_file(file) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("who: {}: {}", file.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => plib::utmpx::load(),
+    };
 
-    if args.file.is_some() {
-        eprintln!("who: -f option not yet implemented");
-        exit_code = 1;
+    // -q overrides all other options
+    if args.summary {
+        print_summary(&entries);
+        return Ok(());
+    }
+
+    let terminal = if args.current_terminal {
+        match current_terminal() {
+            Some(terminal) => Some(terminal),
+            None => {
+                eprintln!("who: {}", gettext("standard input is not a terminal"));
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
+    if args.heading {
+        print_heading(&args);
     }
 
-    let entries = plib::utmpx::load();
     for entry in &entries {
+        if !is_selected(&args, entry) {
+            continue;
+        }
+        if terminal.as_ref().is_some_and(|t| *t != entry.line) {
+            continue;
+        }
         print_entry(&args, entry);
     }
 
-    std::process::exit(exit_code)
+    Ok(())
 }
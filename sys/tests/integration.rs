@@ -0,0 +1,114 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::{run_test, TestPlan};
+
+fn who_test(args: &[&str], expected_output: &str, expected_error: &str, expected_exit_code: i32) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("who"),
+        args: str_args,
+        stdin_data: String::new(),
+        expected_out: String::from(expected_output),
+        expected_err: String::from(expected_error),
+        expected_exit_code,
+        // who prints local time
+        env: vec![(String::from("TZ"), String::from("UTC0"))],
+        ..Default::default()
+    });
+}
+
+// The fixture is in the glibc utmpx record layout.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+mod who_file {
+    use super::who_test;
+    use plib::{run_snapshot_test, run_test, SnapshotPlan, TestPlan};
+    use std::path::PathBuf;
+
+    const UTMP: &str = "tests/who/utmp";
+
+    #[test]
+    fn test_who_users() {
+        who_test(
+            &[UTMP],
+            "alice    pts/0        Nov 14 22:15\nbob      pts/1        Nov 14 23:15\n",
+            "",
+            0,
+        );
+    }
+
+    #[test]
+    fn test_who_summary() {
+        run_snapshot_test(SnapshotPlan {
+            cmd: String::from("who"),
+            args: vec![String::from("-q"), String::from(UTMP)],
+            stdin_data: String::new(),
+            snapshot: PathBuf::from("tests/who/summary"),
+            expected_exit_code: 0,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_who_dead_and_login() {
+        run_snapshot_test(SnapshotPlan {
+            cmd: String::from("who"),
+            args: vec![String::from("-d"), String::from("-l"), String::from(UTMP)],
+            stdin_data: String::new(),
+            snapshot: PathBuf::from("tests/who/dead_and_login"),
+            expected_exit_code: 0,
+            // who prints local time
+            env: vec![(String::from("TZ"), String::from("UTC0"))],
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_who_boot_and_run_level() {
+        who_test(
+            &["-b", "-r", UTMP],
+            "         system boot  Nov 14 22:13\n         run-level 5  Nov 14 22:13\n",
+            "",
+            0,
+        );
+    }
+
+    #[test]
+    fn test_who_heading() {
+        who_test(
+            &["-H", UTMP],
+            "NAME     LINE         TIME\nalice    pts/0        Nov 14 22:15\nbob      pts/1        Nov 14 23:15\n",
+            "",
+            0,
+        );
+    }
+
+    #[test]
+    fn test_who_cwd() {
+        // the file operand is relative to the working directory
+        run_test(TestPlan {
+            cmd: String::from("who"),
+            args: vec![String::from("-q"), String::from("utmp")],
+            expected_out: String::from("alice bob\n# users=2\n"),
+            cwd: Some(PathBuf::from("tests/who")),
+            ..Default::default()
+        });
+    }
+}
+
+#[test]
+fn test_who_missing_file() {
+    who_test(
+        &["tests/who/missing"],
+        "",
+        "who: tests/who/missing: No such file or directory (os error 2)\n",
+        1,
+    );
+}
@@ -11,10 +11,10 @@ extern crate clap;
 extern crate plib;
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::io::{input_reader, StdoutWriter};
 use plib::PROJECT_NAME;
-use std::fs;
-use std::io::{self, BufRead, Write};
+use std::io::{self, BufRead, Read, Write};
 use std::path::PathBuf;
 
 const NO1: u32 = 1 << 0;
@@ -37,100 +37,127 @@ struct Args {
     #[arg(short = '3', long)]
     no_dup: bool,
 
-    /// Comparison file1
+    /// Comparison file1, or '-' for standard input
     file1: PathBuf,
 
-    /// Comparison file2
+    /// Comparison file2, or '-' for standard input
     file2: PathBuf,
 }
 
-fn line_out(lead_dup: &'static str, outmask: u32, curtype: u32, s: &str) -> io::Result<()> {
+fn line_out(
+    out: &mut StdoutWriter,
+    lead_dup: &'static str,
+    outmask: u32,
+    curtype: u32,
+    s: &[u8],
+) -> io::Result<()> {
     if (outmask & curtype) != 0 {
         return Ok(());
     }
 
     match curtype {
-        NO1 => {
-            io::stdout().write_all(s.as_bytes())?;
-        }
+        NO1 => {}
         NO2 => {
-            let lead_f2 = {
-                if (outmask & NO1) != 0 {
-                    ""
-                } else {
-                    "\t"
-                }
-            };
-            let newstr = format!("{}{}", lead_f2, s);
-            io::stdout().write_all(newstr.as_bytes())?;
+            if (outmask & NO1) == 0 {
+                out.write_all(b"\t")?;
+            }
         }
         NODUP => {
-            let newstr = format!("{}{}", lead_dup, s);
-            io::stdout().write_all(newstr.as_bytes())?;
+            out.write_all(lead_dup.as_bytes())?;
         }
         _ => {
             panic!("should never occur");
         }
     }
 
-    Ok(())
+    out.write_all(s)?;
+    out.write_all(b"\n")
 }
 
-fn open_file(pathname: &PathBuf) -> io::Result<io::BufReader<fs::File>> {
-    Ok(io::BufReader::new(fs::File::open(pathname)?))
+/// One of the two input files, with its current line.
+struct Input {
+    rdr: io::BufReader<Box<dyn Read>>,
+    number: u32,
+    line: Vec<u8>,
+    prev: Vec<u8>,
+    eof: bool,
+    started: bool,
+    disordered: bool,
 }
 
-fn comm_file(
-    mask: u32,
-    lead_dup: &'static str,
-    file1name: &PathBuf,
-    file2name: &PathBuf,
-) -> io::Result<()> {
-    // open files, or stdin
-    let mut rdr1 = open_file(file1name)?;
-    let mut rdr2 = open_file(file2name)?;
-
-    let mut buf1 = String::new();
-    let mut buf2 = String::new();
-    let mut want1 = true;
-    let mut want2 = true;
-
-    loop {
-        if want1 && buf1.is_empty() {
-            if rdr1.read_line(&mut buf1)? == 0 {
-                want1 = false;
-            }
+impl Input {
+    fn open(pathname: &PathBuf, number: u32) -> io::Result<Input> {
+        let rdr = input_reader(pathname, true)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", pathname.display(), e)))?;
+
+        Ok(Input {
+            rdr,
+            number,
+            line: Vec::new(),
+            prev: Vec::new(),
+            eof: false,
+            started: false,
+            disordered: false,
+        })
+    }
+
+    /// Read the next line, without its newline, noting the first line
+    /// that sorts before the one preceding it.
+    fn advance(&mut self) -> io::Result<()> {
+        std::mem::swap(&mut self.line, &mut self.prev);
+        self.line.clear();
+
+        if self.rdr.read_until(b'\n', &mut self.line)? == 0 {
+            self.eof = true;
+            return Ok(());
         }
-        if want2 && buf2.is_empty() {
-            if rdr2.read_line(&mut buf2)? == 0 {
-                want2 = false;
-            }
+        if self.line.last() == Some(&b'\n') {
+            self.line.pop();
         }
 
-        if buf1.is_empty() && buf2.is_empty() {
-            break;
+        if self.started && !self.disordered && self.line < self.prev {
+            self.disordered = true;
+            eprintln!(
+                "comm: {}",
+                gettext!("file {} is not in sorted order", self.number)
+            );
         }
+        self.started = true;
 
-        if buf1.is_empty() {
-            line_out(lead_dup, mask, NO2, &buf2)?;
-            buf2.clear();
-        } else if buf2.is_empty() {
-            line_out(lead_dup, mask, NO1, &buf1)?;
-            buf1.clear();
-        } else if buf1 < buf2 {
-            line_out(lead_dup, mask, NO1, &buf1)?;
-            buf1.clear();
-        } else if buf2 < buf1 {
-            line_out(lead_dup, mask, NO2, &buf2)?;
-            buf2.clear();
+        Ok(())
+    }
+}
+
+fn comm_file(
+    mask: u32,
+    lead_dup: &'static str,
+    file1name: &PathBuf,
+    file2name: &PathBuf,
+) -> io::Result<bool> {
+    let mut in1 = Input::open(file1name, 1)?;
+    let mut in2 = Input::open(file2name, 2)?;
+    let mut out = StdoutWriter::new();
+
+    in1.advance()?;
+    in2.advance()?;
+
+    while !in1.eof || !in2.eof {
+        if in2.eof || (!in1.eof && in1.line < in2.line) {
+            line_out(&mut out, lead_dup, mask, NO1, &in1.line)?;
+            in1.advance()?;
+        } else if in1.eof || in2.line < in1.line {
+            line_out(&mut out, lead_dup, mask, NO2, &in2.line)?;
+            in2.advance()?;
         } else {
-            line_out(lead_dup, mask, NODUP, &buf1)?;
-            buf1.clear();
-            buf2.clear();
+            line_out(&mut out, lead_dup, mask, NODUP, &in1.line)?;
+            in1.advance()?;
+            in2.advance()?;
         }
     }
 
-    Ok(())
+    out.flush()?;
+
+    Ok(!in1.disordered && !in2.disordered)
 }
 
 fn args_mask(args: &Args) -> u32 {
@@ -171,9 +198,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut exit_code = 0;
 
-    if let Err(e) = comm_file(mask, lead_dup, &args.file1, &args.file2) {
-        exit_code = 1;
-        eprintln!("{}", e);
+    match comm_file(mask, lead_dup, &args.file1, &args.file2) {
+        Ok(true) => {}
+        Ok(false) => {
+            exit_code = 1;
+            eprintln!("comm: {}", gettext("input is not in sorted order"));
+        }
+        Err(e) => {
+            exit_code = 1;
+            eprintln!("comm: {}", e);
+        }
     }
 
     std::process::exit(exit_code)
@@ -6,19 +6,17 @@
 // file in the root directory of this project.
 // SPDX-License-Identifier: MIT
 //
-// TODO:
-// - err on line num == 0
-//
 
 extern crate clap;
 extern crate plib;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::regex::bre_to_regex;
 use plib::PROJECT_NAME;
-use regex::Regex;
+use regex::bytes::Regex;
 use std::fs::{self, File, OpenOptions};
-use std::io::{self, BufRead, Error, ErrorKind, Read, Write};
+use std::io::{self, Error, ErrorKind, Read, Write};
 use std::path::PathBuf;
 
 /// csplit - split files based on context
@@ -45,6 +43,7 @@ struct Args {
     filename: PathBuf,
 
     /// Operands defining context on which to split.
+    #[arg(required = true)]
     operands: Vec<String>,
 }
 
@@ -64,24 +63,18 @@ struct SplitOps {
 struct OutputState {
     /// A String representing the prefix to be added to file name.
     prefix: String,
-    /// An usize representing the current line number in the file reader.
-    in_line_no: usize,
     ///  A String representing the suffix to be added to file name.
     suffix: String,
     /// A u32 representing the length of the suffix.
     suffix_len: u8,
-    /// representing an output file if set to None, the output stream is not redirected to any file.
-    outf: Option<File>,
 }
 
 impl OutputState {
     fn new(prefix: &str, suffix_len: u8) -> OutputState {
         OutputState {
             prefix: String::from(prefix),
-            in_line_no: 1,
             suffix: String::new(),
             suffix_len,
-            outf: None,
         }
     }
 
@@ -99,13 +92,7 @@ impl OutputState {
     ///
     /// * `Result<(), &'static str>` - `Ok(())` if the suffix is successfully incremented, otherwise an error message.
     ///
-    /// # Panics
-    ///
-    /// This function will panic if `suffix_len` is less than 2, as the minimum suffix length is 2.
-    ///
     fn incr_suffix(&mut self) -> Result<(), &'static str> {
-        assert!(self.suffix_len >= 2); // Minimum suffix length is 2
-
         if self.suffix.is_empty() {
             self.suffix = format!("{:01$}", 0, self.suffix_len as usize);
             return Ok(());
@@ -122,58 +109,115 @@ impl OutputState {
         Ok(())
     }
 
-    /// Opens the output file for writing.
-    ///
-    /// This function opens the output file for writing. If the output file is already open, it does nothing.
-    /// Otherwise, it increments the suffix of the output filename and creates a new file with the updated filename.
-    ///
-    /// # Arguments
-    ///
-    /// * `self` - A mutable reference to the `OutputState` struct.
+    /// Creates the next output file.
     ///
     /// # Returns
     ///
-    /// * `io::Result<()>` - `Ok(())` if the output file is successfully opened or already open, otherwise an error indicating the failure to open the file.
-    ///
-    /// # Errors
+    /// * `io::Result<(String, File)>` - The name of the new file and the file itself, otherwise an
+    ///   error indicating that the suffixes are used up or the file could not be created.
     ///
-    /// Returns an error if there is a problem creating or opening the output file.
-    ///
-    fn open_output(&mut self) -> io::Result<String> {
-        if self.outf.is_some() {
-            return Ok(String::new());
-        }
-
-        let inc_res = self.incr_suffix();
-        if let Err(e) = inc_res {
-            return Err(Error::new(ErrorKind::Other, e));
-        }
+    fn open_output(&mut self) -> io::Result<(String, File)> {
+        self.incr_suffix().map_err(io::Error::other)?;
 
         let out_fn = format!("{}{}", self.prefix, self.suffix);
         let f = OpenOptions::new()
-            .read(false)
             .write(true)
             .create(true)
             .truncate(true)
             .open(&out_fn)?;
-        self.outf = Some(f);
 
-        Ok(out_fn)
+        Ok((out_fn, f))
     }
+}
 
-    fn close_output(&mut self) {
-        if self.outf.is_some() {
-            self.outf = None;
+/// The outcome of applying one operand.
+enum Split {
+    Done,
+    NotFound,
+    OutOfRange,
+}
+
+/// The input and the position reached in it.
+struct Splitter<'a> {
+    /// The input lines, each with its newline.
+    lines: Vec<&'a [u8]>,
+    /// The first line of the file being collected.
+    cur: usize,
+    /// Where the next regular expression search starts.
+    search_from: usize,
+    state: OutputState,
+    new_files: &'a mut Vec<String>,
+    suppress: bool,
+}
+
+impl Splitter<'_> {
+    /// Write the lines from the current line up to, but not including,
+    /// `end` to the next output file, and report its size.
+    fn write_piece(&mut self, end: usize) -> io::Result<()> {
+        let (file_name, mut file) = self.state.open_output()?;
+        self.new_files.push(file_name);
+
+        let mut size = 0;
+        for line in &self.lines[self.cur..end] {
+            file.write_all(line)?;
+            size += line.len();
+        }
+        if !self.suppress {
+            println!("{}", size);
+        }
+
+        self.cur = end;
+        Ok(())
+    }
+
+    /// Split before line number `line_no`, counting from one.
+    fn split_at_line(&mut self, line_no: usize) -> io::Result<Split> {
+        let end = line_no - 1;
+        if end < self.cur || end >= self.lines.len() {
+            return Ok(Split::OutOfRange);
+        }
+
+        self.write_piece(end)?;
+        self.search_from = end;
+        Ok(Split::Done)
+    }
+
+    /// Split at the next line matching `re`, moved by `offset` lines. With
+    /// `skip`, the lines before the split are discarded.
+    fn split_at_match(&mut self, re: &Regex, offset: isize, skip: bool) -> io::Result<Split> {
+        let found = (self.search_from..self.lines.len()).find(|&i| {
+            let line = self.lines[i];
+            re.is_match(line.strip_suffix(b"\n").unwrap_or(line))
+        });
+        let Some(matched) = found else {
+            return Ok(Split::NotFound);
+        };
+
+        let end = matched as isize + offset;
+        if end < self.cur as isize || end > self.lines.len() as isize {
+            return Ok(Split::OutOfRange);
         }
+        let end = end as usize;
+
+        if skip {
+            self.cur = end;
+        } else {
+            self.write_piece(end)?;
+        }
+
+        // the line starting the next file is only searched again when the
+        // offset did not move past the match
+        self.search_from = if offset > 0 { end + 1 } else { matched + 1 };
+        Ok(Split::Done)
     }
 }
 
 /// Splits a file based on specified conditions.
 ///
 /// This function splits a file based on the provided splitting options and writes
-/// the resulting parts to separate output files. It reads the input file line by
-/// line, applies the splitting options to determine where to split the file, and
-/// writes the parts to output files accordingly.
+/// the resulting parts to separate output files, reporting the size of each one.
+/// Each operand is applied in turn, as many times as the repeat operand that follows
+/// it says, and the lines left after the last operand go to a final file.
 ///
 /// # Arguments
 ///
@@ -187,214 +231,67 @@ impl OutputState {
 ///   or an `io` error.
 ///
 fn csplit_file(args: &Args, ctx: SplitOps, new_files: &mut Vec<String>) -> io::Result<()> {
-    let mut split_options = ctx.ops;
     // open file, or stdin
-    let file: Box<dyn Read> = {
+    let mut file: Box<dyn Read> = {
         if args.filename == PathBuf::from("-") {
             Box::new(io::stdin().lock())
         } else {
-            Box::new(fs::File::open(&args.filename)?)
+            let f = fs::File::open(&args.filename)
+                .map_err(|e| Error::new(e.kind(), format!("{}: {}", args.filename.display(), e)))?;
+            Box::new(f)
         }
     };
-    let mut state = OutputState::new(&args.prefix, args.num);
-    let mut reader = io::BufReader::new(file);
-
-    let mut lines = String::new();
-
-    loop {
-        let mut line = String::new();
-        let n_read = reader.read_line(&mut line)?;
-        if n_read == 0 {
-            process_lines(&mut lines, &mut state, new_files, args.suppress)?;
-            break;
-        }
-
-        if split_options.is_empty() {
-            lines.push_str(&line);
-            continue;
-        }
-        match split_options.first().unwrap() {
-            Operand::LineNum(num) => {
-                if *num == state.in_line_no {
-                    if lines.ends_with('\n') && lines != "\n" {
-                        lines.pop();
-                    }
-                    process_lines(&mut lines, &mut state, new_files, args.suppress)?;
-                    state.in_line_no = 1;
-                    lines = String::new();
-                    lines.push_str(&line);
-
-                    if split_options.len() > 1 {
-                        if let Operand::Repeat(repeat) = &mut split_options[1] {
-                            *repeat -= 1;
-                            if *repeat == 0 {
-                                split_options.remove(0);
-                                split_options.remove(0);
-                            }
-                        }
-                    }
-                } else {
-                    lines.push_str(&line);
-                }
-            }
-            Operand::Rx(regex, offset, skip) => {
-                if line == "\n" {
-                    line = String::new();
-                }
-                if regex.is_match(&line) {
-                    match offset.cmp(&0) {
-                        std::cmp::Ordering::Less => {
-                            let mut lines_vec: Vec<&str> = lines.lines().collect();
-
-                            let mut removed_lines_string = String::new();
-                            let length = lines_vec.len();
-                            if length >= offset.unsigned_abs() {
-                                let removed_lines =
-                                    lines_vec.split_off(length - offset.unsigned_abs());
-                                removed_lines_string = removed_lines.join("\n");
-
-                                removed_lines_string.push('\n');
-                            }
-
-                            lines = lines_vec.join("\n");
-                            if !lines.is_empty() {
-                                if *skip {
-                                    lines.clear();
-                                } else {
-                                    if lines.ends_with('\n') {
-                                        lines.pop();
-                                    }
-                                    process_lines(
-                                        &mut lines,
-                                        &mut state,
-                                        new_files,
-                                        args.suppress,
-                                    )?;
-                                }
-                            }
-
-                            lines = removed_lines_string;
-
-                            if line.is_empty() {
-                                lines.push('\n');
-                            } else {
-                                lines.push_str(&line);
-                            }
-                        }
-                        std::cmp::Ordering::Equal => {
-                            if *skip {
-                                lines.clear();
-                                if line.is_empty() {
-                                    line = "\n".to_string();
-                                }
-                                lines.push_str(&line);
-                            } else {
-                                if lines.ends_with('\n') {
-                                    lines.pop();
-                                }
-                                process_lines(&mut lines, &mut state, new_files, args.suppress)?;
-
-                                if line.is_empty() {
-                                    lines = "\n".to_string();
-                                } else {
-                                    lines = line;
-                                }
-                            }
-                        }
-                        std::cmp::Ordering::Greater => {
-                            if line.is_empty() {
-                                lines.push('\n');
-                            } else {
-                                lines.push_str(&line);
-                            }
-                            for _ in 0..*offset - 1 {
-                                let mut new_line = String::new();
-                                let n_read = reader.read_line(&mut new_line)?;
-                                if n_read == 0 {
-                                    process_lines(
-                                        &mut lines,
-                                        &mut state,
-                                        new_files,
-                                        args.suppress,
-                                    )?;
-                                    break;
-                                }
-                                lines.push_str(&new_line);
-                            }
-
-                            if *skip {
-                                lines.clear();
-                            } else {
-                                if lines.ends_with('\n') {
-                                    lines.pop();
-                                }
-                                process_lines(&mut lines, &mut state, new_files, args.suppress)?;
-                                lines = String::new();
-                            }
-                        }
-                    }
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+
+    let mut splitter = Splitter {
+        lines: data.split_inclusive(|&b| b == b'\n').collect(),
+        cur: 0,
+        search_from: 0,
+        state: OutputState::new(&args.prefix, args.num),
+        new_files,
+        suppress: args.suppress,
+    };
 
-                    if split_options.len() == 1 {
-                        split_options.remove(0);
-                    } else if split_options.len() > 1 {
-                        if let Operand::Repeat(repeat) = &mut split_options[1] {
-                            *repeat -= 1;
-                            if *repeat == 0 {
-                                split_options.remove(0);
-                                split_options.remove(0);
-                            }
-                        } else {
-                            split_options.remove(0);
-                        }
-                    }
-                } else {
-                    if line.is_empty() {
-                        line = "\n".to_string();
-                    }
-                    lines.push_str(&line);
-                }
+    let mut ops = ctx.ops.iter().zip(&args.operands).peekable();
+    while let Some((op, opstr)) = ops.next() {
+        let repeat = match ops.peek() {
+            Some((Operand::Repeat(n), _)) => {
+                let n = *n;
+                ops.next();
+                n
             }
-            _ => {}
+            _ => 0,
+        };
+        // {*} repeats until the input runs out
+        let forever = repeat == usize::MAX;
+
+        for rep in 0..=repeat {
+            let res = match op {
+                Operand::LineNum(n) => match n.checked_mul(rep + 1) {
+                    Some(line_no) => splitter.split_at_line(line_no)?,
+                    None => Split::OutOfRange,
+                },
+                Operand::Rx(re, offset, skip) => splitter.split_at_match(re, *offset, *skip)?,
+                Operand::Repeat(_) => unreachable!(),
+            };
+
+            let msg = match res {
+                Split::Done => continue,
+                _ if forever => break,
+                Split::NotFound => "match not found",
+                Split::OutOfRange => "line number out of range",
+            };
+            return Err(if rep > 0 {
+                Error::other(format!("{}: {} on repetition {}", opstr, msg, rep))
+            } else {
+                Error::other(format!("{}: {}", opstr, msg))
+            });
         }
-
-        state.in_line_no += 1;
     }
 
-    Ok(())
-}
-
-/// Processes lines and writes them to output.
-///
-/// This function is responsible for processing lines and writing them to the output
-/// according to the specified conditions. It opens the output stream, writes the lines
-/// to a file, adds the file name to the list of new files, and closes the output stream.
-///
-/// # Arguments
-///
-/// * `lines` - The lines to be processed and written.
-/// * `state` - The current output state.
-/// * `new_files` - The list of new files.
-///
-/// # Returns
-///
-/// * `io::Result<()>` - `Ok(())` if the lines are successfully processed and written to output,
-///   or an `io` error.
-///
-fn process_lines(
-    lines: &mut String,
-    state: &mut OutputState,
-    new_files: &mut Vec<String>,
-    suppress: bool,
-) -> io::Result<()> {
-    let file_name = state.open_output()?;
-    state.outf.as_mut().unwrap().write_all(lines.as_bytes())?;
-    new_files.push(file_name);
-    if !suppress {
-        println!("{}\n", lines.len());
-    }
-
-    state.close_output();
-    Ok(())
+    let end = splitter.lines.len();
+    splitter.write_piece(end)
 }
 
 /// Finds the position of the delimiter in the input string, or None if the delimiter is not found.
@@ -412,7 +309,7 @@ fn process_lines(
 fn escaped_end_pos(s: &str, delim: char) -> Option<usize> {
     let mut first = true;
     let mut escaped = false;
-    for (i, ch) in s.chars().enumerate() {
+    for (i, ch) in s.char_indices() {
         if first {
             if ch != delim {
                 return None;
@@ -453,7 +350,7 @@ fn parse_op_rx(opstr: &str, delim: char) -> io::Result<Operand> {
     // parse string sandwiched between two delimiter chars
     let end_pos = res.unwrap();
     let re_str = &opstr[1..end_pos];
-    let res = Regex::new(re_str);
+    let res = Regex::new(&bre_to_regex(re_str));
     if res.is_err() {
         return Err(Error::new(ErrorKind::Other, "invalid regex"));
     }
@@ -500,7 +397,7 @@ fn parse_op_rx(opstr: &str, delim: char) -> io::Result<Operand> {
 ///
 fn parse_op_repeat(opstr: &str) -> io::Result<Operand> {
     // a regex fully describes what must be parsed
-    let re = Regex::new(r"^\{(\d*|[*])}$").unwrap();
+    let re = regex::Regex::new(r"^\{(\d*|[*])}$").unwrap();
 
     // grab and parse capture #1, if matched
     match re.captures(opstr) {
@@ -575,18 +472,23 @@ fn parse_operands(args: &Args) -> io::Result<SplitOps> {
     let mut ops = Vec::new();
 
     for opstr in &args.operands {
-        let first_ch = opstr.chars().next().unwrap();
-
         let op = {
-            match first_ch {
-                '/' => parse_op_rx(opstr, '/')?,
-                '%' => parse_op_rx(opstr, '%')?,
-                '{' => parse_op_repeat(opstr)?,
-                '1'..='9' => parse_op_linenum(opstr)?,
+            match opstr.chars().next() {
+                Some('/') => parse_op_rx(opstr, '/')?,
+                Some('%') => parse_op_rx(opstr, '%')?,
+                Some('{') => parse_op_repeat(opstr)?,
+                Some('1'..='9') => parse_op_linenum(opstr)?,
                 _ => return Err(Error::new(ErrorKind::Other, "invalid operand")),
             }
         };
 
+        // a repeat applies to the operand before it
+        if let Operand::Repeat(_) = op {
+            if matches!(ops.last(), None | Some(Operand::Repeat(_))) {
+                return Err(Error::new(ErrorKind::Other, "invalid repeating operand"));
+            }
+        }
+
         ops.push(op);
     }
 
@@ -601,16 +503,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     textdomain(PROJECT_NAME)?;
     bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
 
-    let ctx = parse_operands(&args)?;
+    let ctx = match parse_operands(&args) {
+        Ok(ctx) => ctx,
+        Err(e) => {
+            eprintln!("csplit: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     let mut exit_code = 0;
     let mut new_files = vec![];
     if let Err(err) = csplit_file(&args, ctx, &mut new_files) {
         exit_code = 1;
-        eprintln!("{}: {}", args.filename.display(), err);
+        eprintln!("csplit: {}", err);
         if !args.keep {
             for file_name in new_files.iter() {
-                fs::remove_file(file_name).unwrap();
+                let _ = fs::remove_file(file_name);
             }
         }
     }
@@ -853,7 +761,7 @@ mod tests {
             num: 2,
             suppress: false,
             filename: PathBuf::from("tests/assets/test_file.txt"),
-            operands: vec![String::from("5"), String::from("{3}")],
+            operands: vec![String::from("5"), String::from("{2}")],
         };
 
         let ctx = parse_operands(&args).unwrap();
@@ -875,7 +783,7 @@ mod tests {
         let mut contents = String::new();
         file.read_to_string(&mut contents).unwrap();
 
-        let expected = String::from("1sdfghnm\n2sadsgdhjmf\n3zcxbncvm vbm\n4asdbncv");
+        let expected = String::from("1sdfghnm\n2sadsgdhjmf\n3zcxbncvm vbm\n4asdbncv\n");
 
         assert_eq!(contents, expected);
 
@@ -884,7 +792,7 @@ mod tests {
         let mut contents = String::new();
         file.read_to_string(&mut contents).unwrap();
 
-        let expected = String::from("13\n14\n15\n16\n17");
+        let expected = String::from("15\n16\n17");
 
         assert_eq!(contents, expected);
 
@@ -904,7 +812,7 @@ mod tests {
             suppress: false,
             filename: PathBuf::from("tests/assets/test_file_c"),
             operands: vec![
-                String::from(r"%main\(%"),
+                String::from("%main(%"),
                 String::from("/^}/+1"),
                 String::from("{3}"),
             ],
@@ -931,7 +839,7 @@ mod tests {
         file.read_to_string(&mut contents).unwrap();
 
         let expected =
-            String::from("int main() {\n    printf(\"Hello, world!\\n\");\n    return 0;\n}");
+            String::from("int main() {\n    printf(\"Hello, world!\\n\");\n    return 0;\n}\n");
 
         assert_eq!(contents, expected);
 
@@ -949,6 +857,7 @@ mod tests {
         fs::remove_file("c_file01").unwrap();
         fs::remove_file("c_file02").unwrap();
         fs::remove_file("c_file03").unwrap();
+        fs::remove_file("c_file04").unwrap();
     }
 
     #[test]
@@ -961,7 +870,7 @@ mod tests {
             suppress: false,
             filename: PathBuf::from("tests/assets/test_file_c"),
             operands: vec![
-                String::from(r"%main\(%+1"),
+                String::from("%main(%+1"),
                 String::from("/^}/+1"),
                 String::from("{3}"),
             ],
@@ -987,7 +896,7 @@ mod tests {
         let mut contents = String::new();
         file.read_to_string(&mut contents).unwrap();
 
-        let expected = String::from("    printf(\"Hello, world!\\n\");\n    return 0;\n}");
+        let expected = String::from("    printf(\"Hello, world!\\n\");\n    return 0;\n}\n");
 
         assert_eq!(contents, expected);
 
@@ -1005,6 +914,7 @@ mod tests {
         fs::remove_file("c_file_2_01").unwrap();
         fs::remove_file("c_file_2_02").unwrap();
         fs::remove_file("c_file_2_03").unwrap();
+        fs::remove_file("c_file_2_04").unwrap();
     }
 
     #[test]
@@ -1017,7 +927,7 @@ mod tests {
             suppress: false,
             filename: PathBuf::from("tests/assets/test_file_c"),
             operands: vec![
-                String::from(r"%main\(%-1"),
+                String::from("%main(%-1"),
                 String::from("/^}/+1"),
                 String::from("{3}"),
             ],
@@ -1044,7 +954,7 @@ mod tests {
         file.read_to_string(&mut contents).unwrap();
 
         let expected =
-            String::from("\nint main() {\n    printf(\"Hello, world!\\n\");\n    return 0;\n}");
+            String::from("\nint main() {\n    printf(\"Hello, world!\\n\");\n    return 0;\n}\n");
 
         assert_eq!(contents, expected);
 
@@ -1062,6 +972,7 @@ mod tests {
         fs::remove_file("c_file_3_01").unwrap();
         fs::remove_file("c_file_3_02").unwrap();
         fs::remove_file("c_file_3_03").unwrap();
+        fs::remove_file("c_file_3_04").unwrap();
     }
 
     #[test]
@@ -1074,7 +985,7 @@ mod tests {
             suppress: false,
             filename: PathBuf::from("tests/assets/test_file_c"),
             operands: vec![
-                String::from(r"%main\(%"),
+                String::from("%main(%"),
                 String::from("/^}/"),
                 String::from("{3}"),
             ],
@@ -1101,7 +1012,7 @@ mod tests {
         file.read_to_string(&mut contents).unwrap();
 
         let expected =
-            String::from("int main() {\n    printf(\"Hello, world!\\n\");\n    return 0;");
+            String::from("int main() {\n    printf(\"Hello, world!\\n\");\n    return 0;\n");
 
         assert_eq!(contents, expected);
 
@@ -1111,7 +1022,7 @@ mod tests {
         file.read_to_string(&mut contents).unwrap();
 
         let expected =
-            String::from("}\n\nvoid func3() {\n    printf(\"This is function 3\\n\");\n}\n");
+            String::from("}\n\nvoid func3() {\n    printf(\"This is function 3\\n\");\n");
 
         assert_eq!(contents, expected);
 
@@ -1119,6 +1030,7 @@ mod tests {
         fs::remove_file("c_file_4_01").unwrap();
         fs::remove_file("c_file_4_02").unwrap();
         fs::remove_file("c_file_4_03").unwrap();
+        fs::remove_file("c_file_4_04").unwrap();
     }
 
     #[test]
@@ -1131,7 +1043,7 @@ mod tests {
             suppress: false,
             filename: PathBuf::from("tests/assets/test_file_c"),
             operands: vec![
-                String::from(r"%main\(%"),
+                String::from("%main(%"),
                 String::from("/^}/-1"),
                 String::from("{3}"),
             ],
@@ -1157,7 +1069,7 @@ mod tests {
         let mut contents = String::new();
         file.read_to_string(&mut contents).unwrap();
 
-        let expected = String::from("int main() {\n    printf(\"Hello, world!\\n\");");
+        let expected = String::from("int main() {\n    printf(\"Hello, world!\\n\");\n");
 
         assert_eq!(contents, expected);
 
@@ -1167,7 +1079,7 @@ mod tests {
         file.read_to_string(&mut contents).unwrap();
 
         let expected =
-            String::from("    printf(\"This is function 2\\n\");\n}\n\nvoid func3() {\n    printf(\"This is function 3\\n\");\n}\n");
+            String::from("    printf(\"This is function 2\\n\");\n}\n\nvoid func3() {\n");
 
         assert_eq!(contents, expected);
 
@@ -1175,5 +1087,6 @@ mod tests {
         fs::remove_file("c_file_5_01").unwrap();
         fs::remove_file("c_file_5_02").unwrap();
         fs::remove_file("c_file_5_03").unwrap();
+        fs::remove_file("c_file_5_04").unwrap();
     }
 }
@@ -9,7 +9,7 @@
 
 extern crate clap;
 extern crate plib;
-use std::io::{self, BufRead, Error, ErrorKind, Read};
+use std::io::{self, BufRead, Error, ErrorKind, Read, Write};
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
@@ -21,15 +21,15 @@ use std::path::PathBuf;
 #[command(author, version, about, long_about)]
 struct Args {
     /// Cut based on a list of bytes
-    #[arg(short = 'b', long)]
+    #[arg(short = 'b', long, allow_hyphen_values = true)]
     bytes: Option<String>,
 
     /// Cut based on a list of characters
-    #[arg(short = 'c', long)]
+    #[arg(short = 'c', long, allow_hyphen_values = true)]
     characters: Option<String>,
 
     /// Cut based on a list of fields
-    #[arg(short = 'f', long)]
+    #[arg(short = 'f', long, allow_hyphen_values = true)]
     fields: Option<String>,
 
     /// Set the field delimiter
@@ -88,26 +88,21 @@ enum ParseVariat {
     Fields(Vec<(i32, i32)>),
 }
 
-/// Helper function to determine if the given bytes form a valid UTF-8 character boundary.
-///
-/// This function checks if the first byte of the provided byte slice `bytes` represents
-/// the start of a valid UTF-8 character. If the bytes are valid UTF-8, it returns true;
-/// otherwise, it returns false.
-///
-/// # Arguments
-///
-/// * `bytes` - A slice of bytes to be checked.
-///
-/// # Returns
-///
-/// A boolean value indicating whether the provided bytes form a valid UTF-8 character boundary.
-///
-fn is_character_boundary(bytes: &[u8]) -> bool {
-    // Check if the first byte of `bytes` is a valid UTF-8 character boundary
-    match std::str::from_utf8(bytes) {
-        Ok(s) => s.chars().next().is_some(),
-        Err(_) => false,
+/// Returns the index of the first byte of the UTF-8 character containing byte `i`.
+fn char_start(line: &[u8], mut i: usize) -> usize {
+    while i > 0 && (line[i] & 0xC0) == 0x80 {
+        i -= 1;
     }
+    i
+}
+
+/// Returns the index of the last byte of the last character that ends at or
+/// before byte `i`, or `None` if there is no such character.
+fn char_end(line: &[u8], i: usize) -> Option<usize> {
+    if i + 1 == line.len() || (line[i + 1] & 0xC0) != 0x80 {
+        return Some(i);
+    }
+    char_start(line, i).checked_sub(1)
 }
 
 /// Cuts out selected bytes from the given line based on the specified ranges.
@@ -115,7 +110,9 @@ fn is_character_boundary(bytes: &[u8]) -> bool {
 /// This function takes a slice of bytes representing the input `line`, along with optional
 /// delimiter character `delim` and a vector of tuples `ranges` representing the start and
 /// end indices of the byte ranges to cut. The boolean `n` flag indicates whether character
-/// boundaries should be respected when selecting byte ranges.
+/// boundaries should be respected when selecting byte ranges: the start of a range moves back
+/// to the first byte of its character, and the end moves back to the last byte of the last
+/// character that fits, dropping the range if nothing is left.
 ///
 /// # Arguments
 ///
@@ -130,42 +127,31 @@ fn is_character_boundary(bytes: &[u8]) -> bool {
 ///
 fn cut_bytes(line: &[u8], delim: Option<char>, ranges: &Vec<(i32, i32)>, n: bool) -> Vec<u8> {
     let mut result = Vec::new();
+    let mut first = true;
 
     for (start, end) in ranges {
         let mut start = *start as usize;
-        let mut end = *end as usize;
+        if start >= line.len() {
+            continue;
+        }
+        let mut end = std::cmp::min(*end as usize, line.len() - 1);
 
         if n {
-            if start != 0 && !is_character_boundary(&line[start..]) {
-                start -= 1;
-            }
-            if end != 0 && !is_character_boundary(&line[end..]) {
-                end -= 1;
-            }
+            start = char_start(line, start);
+            end = match char_end(line, end) {
+                Some(end) if end >= start => end,
+                _ => continue,
+            };
         }
 
-        if line.get(start).is_some() {
-            if start == end {
-                result.push(line[start]);
-                if let Some(delim) = delim {
-                    for byte in delim.to_string().as_bytes() {
-                        result.push(*byte);
-                    }
-                }
-            } else {
-                for byte in line.iter().take(end + 1).skip(start) {
-                    result.push(*byte);
-                }
-                if let Some(delim) = delim {
-                    for byte in delim.to_string().as_bytes() {
-                        result.push(*byte);
-                    }
-                }
+        if !first {
+            if let Some(delim) = delim {
+                result.extend_from_slice(delim.to_string().as_bytes());
             }
         }
-    }
-    if delim.is_some() {
-        result.pop();
+        first = false;
+
+        result.extend_from_slice(&line[start..=end]);
     }
 
     result
@@ -292,20 +278,24 @@ fn cut_files(args: Args) -> Result<(), Box<dyn std::error::Error>> {
 
     // open files, or stdin
 
-    let readers: Vec<Box<dyn Read>> =
-        if args.filenames.len() == 1 && args.filenames[0] == PathBuf::from("-") {
-            vec![Box::new(io::stdin().lock())]
-        } else {
-            let mut bufs: Vec<Box<dyn Read>> = vec![];
-            for file in &args.filenames {
+    let readers: Vec<Box<dyn Read>> = if args.filenames.is_empty() {
+        vec![Box::new(io::stdin().lock())]
+    } else {
+        let mut bufs: Vec<Box<dyn Read>> = vec![];
+        for file in &args.filenames {
+            if *file == PathBuf::from("-") {
+                bufs.push(Box::new(io::stdin().lock()))
+            } else {
                 bufs.push(Box::new(std::fs::File::open(file)?))
             }
-            bufs
-        };
+        }
+        bufs
+    };
+    let mut stdout = io::stdout().lock();
 
     // Process each file
     for file in readers {
-        let reader = io::BufReader::new(file);
+        let mut reader = io::BufReader::new(file);
 
         let parse_option;
 
@@ -327,27 +317,35 @@ fn cut_files(args: Args) -> Result<(), Box<dyn std::error::Error>> {
             return Err(Box::new(Error::new(ErrorKind::Other, "Invalid arguments")));
         }
 
-        for line in reader.lines() {
-            let line = line?;
+        // lines are read as bytes, so that -b works on any input
+        let mut raw_line = Vec::new();
+        loop {
+            raw_line.clear();
+            if reader.read_until(b'\n', &mut raw_line)? == 0 {
+                break;
+            }
+            if raw_line.last() == Some(&b'\n') {
+                raw_line.pop();
+            }
+            let line = String::from_utf8_lossy(&raw_line);
+
             match parse_option.clone() {
                 ParseVariat::Bytes(ranges) => {
-                    let bytes = cut_bytes(line.as_bytes(), args.delimiter, &ranges, args.no_split);
-                    match String::from_utf8(bytes) {
-                        Ok(string) => println!("{}", string),
-                        Err(e) => eprintln!("Conversion error to string: {}", e),
-                    }
+                    let bytes = cut_bytes(&raw_line, args.delimiter, &ranges, args.no_split);
+                    stdout.write_all(&bytes)?;
+                    stdout.write_all(b"\n")?;
                 }
                 ParseVariat::Characters(ranges) => {
-                    println!("{}", cut_characters(&line, args.delimiter, &ranges))
+                    writeln!(stdout, "{}", cut_characters(&line, args.delimiter, &ranges))?
                 }
                 ParseVariat::Fields(ranges) => {
                     if let Some(delim) = args.delimiter {
                         let result = cut_fields(&line, delim, &ranges, args.suppress);
                         if !result.1 {
-                            println!("{}", result.0)
+                            writeln!(stdout, "{}", result.0)?
                         }
                     } else {
-                        println!("{}", line);
+                        writeln!(stdout, "{}", line)?;
                     }
                 }
             }
@@ -367,6 +365,9 @@ fn read_range(line: &str) -> Result<Vec<(i32, i32)>, String> {
         .iter()
         .map(|range| {
             let nums: Vec<&str> = range.split('-').collect();
+            if nums.len() > 2 {
+                return Err(format!("Invalid range: {}", range));
+            }
 
             let start = if nums[0].is_empty() {
                 0
@@ -377,7 +378,7 @@ fn read_range(line: &str) -> Result<Vec<(i32, i32)>, String> {
                 }
             };
 
-            let end = if range.len() == 1 {
+            let end = if nums.len() == 1 {
                 start
             } else if nums[1].is_empty() {
                 std::i32::MAX - 1
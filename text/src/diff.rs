@@ -7,8 +7,6 @@
 // SPDX-License-Identifier: MIT
 //
 // TODO:
-// - Just a start with the core algorithm; -C and -U both need context output
-// - Implement -r (recurse)
 // - Research and implement -f alternate output format properly
 //
 
@@ -44,7 +42,7 @@ struct Args {
     context3: bool,
 
     /// Output <N> lines of copied context
-    #[arg(short='C', value_parser = clap::value_parser!(u32).range(1..))]
+    #[arg(short='C', value_parser = clap::value_parser!(u32).range(0..))]
     context: Option<u32>,
 
     /// Produce output in a form suitable as input for the ed utility
@@ -97,7 +95,6 @@ impl From<&Args> for OutputFormat {
         } else if args.fed {
             OutputFormat::ForwardEditScript
         } else if let Some(n) = args.context {
-            let n = if n == 0 { 1 } else { n };
             OutputFormat::Context(n as usize)
         } else if let Some(n) = args.unified {
             OutputFormat::Unified(n as usize)
@@ -124,8 +121,9 @@ fn check_difference(args: Args) -> io::Result<DiffExitStatus> {
         return Ok(DiffExitStatus::Trouble);
     }
 
+    // a file does not differ from itself
     if path1 == path2 {
-        return Ok(DiffExitStatus::Trouble);
+        return Ok(DiffExitStatus::NotDifferent);
     }
 
     let path1_is_file = fs::metadata(&path1)?.is_file();
@@ -164,5 +162,5 @@ fn main() -> Result<DiffExitStatus, Box<dyn std::error::Error>> {
         eprintln!("diff: {}", error);
     }
 
-    return Ok(DiffExitStatus::NotDifferent);
+    return Ok(DiffExitStatus::Trouble);
 }
@@ -0,0 +1,190 @@
+use super::file_data::FileData;
+
+/// A run of lines in a context or unified diff. Line indexes count from 0.
+#[derive(Clone, Debug)]
+pub enum Block {
+    /// A line present in both files.
+    Common(usize, usize),
+    /// Lines of the first file replaced by lines of the second.
+    Change { old: Vec<usize>, new: Vec<usize> },
+}
+
+/// The changes close enough together to share their context lines.
+#[derive(Debug)]
+pub struct ContextHunk {
+    pub blocks: Vec<Block>,
+    /// Where the hunk starts in each file, and how many lines it covers.
+    pub old_start: usize,
+    pub old_count: usize,
+    pub new_start: usize,
+    pub new_count: usize,
+}
+
+impl ContextHunk {
+    fn new(blocks: Vec<Block>, old_start: usize, new_start: usize) -> Self {
+        let (mut old_count, mut new_count) = (0, 0);
+        for block in &blocks {
+            match block {
+                Block::Common(_, _) => {
+                    old_count += 1;
+                    new_count += 1;
+                }
+                Block::Change { old, new } => {
+                    old_count += old.len();
+                    new_count += new.len();
+                }
+            }
+        }
+
+        Self {
+            blocks,
+            old_start,
+            old_count,
+            new_start,
+            new_count,
+        }
+    }
+
+    pub fn has_old_changes(&self) -> bool {
+        self.blocks
+            .iter()
+            .any(|block| matches!(block, Block::Change { old, .. } if !old.is_empty()))
+    }
+
+    pub fn has_new_changes(&self) -> bool {
+        self.blocks
+            .iter()
+            .any(|block| matches!(block, Block::Change { new, .. } if !new.is_empty()))
+    }
+}
+
+/// The range of a hunk in one file, as a unified diff header writes it:
+/// the first line and the count, or for no lines the line before them.
+pub fn unified_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+/// The range of a hunk in one file, as a context diff header writes it:
+/// the first and last lines, or for no lines the line before them.
+pub fn context_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{}", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, start + count),
+    }
+}
+
+/// The lines of a file as they are compared. A last line without a
+/// newline differs from the same text with one.
+fn line_keys(file: &FileData, ignore_trailing_white_spaces: bool) -> Vec<(&str, bool)> {
+    let count = file.line_count();
+
+    (0..count)
+        .map(|i| {
+            let line = file.line(i).as_str();
+            let line = if ignore_trailing_white_spaces {
+                line.trim_end()
+            } else {
+                line
+            };
+            (line, i + 1 == count && !file.ends_with_newline())
+        })
+        .collect()
+}
+
+/// Compare two files line by line and group the differences into hunks
+/// with up to `context` common lines around each change.
+pub fn context_hunks(
+    file1: &FileData,
+    file2: &FileData,
+    context: usize,
+    ignore_trailing_white_spaces: bool,
+) -> Vec<ContextHunk> {
+    let keys1 = line_keys(file1, ignore_trailing_white_spaces);
+    let keys2 = line_keys(file2, ignore_trailing_white_spaces);
+
+    let mut blocks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for result in diff::slice(&keys1, &keys2) {
+        match result {
+            diff::Result::Both(_, _) => {
+                blocks.push(Block::Common(i, j));
+                i += 1;
+                j += 1;
+                continue;
+            }
+            diff::Result::Left(_) => i += 1,
+            diff::Result::Right(_) => j += 1,
+        }
+
+        if !matches!(blocks.last(), Some(Block::Change { .. })) {
+            blocks.push(Block::Change {
+                old: Vec::new(),
+                new: Vec::new(),
+            });
+        }
+        if let Some(Block::Change { old, new }) = blocks.last_mut() {
+            match result {
+                diff::Result::Left(_) => old.push(i - 1),
+                _ => new.push(j - 1),
+            }
+        }
+    }
+
+    // the position of each block in the two files
+    let mut positions = Vec::with_capacity(blocks.len());
+    let (mut i, mut j) = (0, 0);
+    for block in &blocks {
+        positions.push((i, j));
+        match block {
+            Block::Common(_, _) => {
+                i += 1;
+                j += 1;
+            }
+            Block::Change { old, new } => {
+                i += old.len();
+                j += new.len();
+            }
+        }
+    }
+
+    let mut hunks = Vec::new();
+    let mut index = 0;
+    while index < blocks.len() {
+        if let Block::Common(_, _) = blocks[index] {
+            index += 1;
+            continue;
+        }
+
+        let start = index.saturating_sub(context);
+        let mut end = index + 1;
+        // take in the following changes while the common lines between
+        // them are no more than the context on both sides
+        loop {
+            let common = blocks[end..]
+                .iter()
+                .take_while(|block| matches!(block, Block::Common(_, _)))
+                .count();
+            if end + common < blocks.len() && common <= 2 * context {
+                end += common + 1;
+            } else {
+                end = (end + context).min(blocks.len());
+                break;
+            }
+        }
+
+        let (old_start, new_start) = positions[start];
+        hunks.push(ContextHunk::new(
+            blocks[start..end].to_vec(),
+            old_start,
+            new_start,
+        ));
+        index = end;
+    }
+
+    hunks
+}
@@ -3,7 +3,7 @@ use std::process::Termination;
 use super::constants::{EXIT_STATUS_DIFFERENCE, EXIT_STATUS_NO_DIFFERENCE, EXIT_STATUS_TROUBLE};
 
 #[allow(dead_code)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum DiffExitStatus {
     NotDifferent,
     Different,
@@ -22,10 +22,6 @@ impl DiffExitStatus {
 
 impl Termination for DiffExitStatus {
     fn report(self) -> std::process::ExitCode {
-        if self.status_code() == EXIT_STATUS_TROUBLE {
-            return std::process::ExitCode::FAILURE;
-        }
-
-        std::process::ExitCode::SUCCESS
+        std::process::ExitCode::from(self.status_code() as u8)
     }
 }
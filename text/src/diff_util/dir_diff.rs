@@ -134,11 +134,10 @@ impl<'a> DirDiff<'a> {
                         }
 
                         if let Some(label2) = &self.format_options.label2 {
-                            show_if_different.push_str(format!("{} ", label2).as_str())
+                            show_if_different.push_str(label2)
                         } else {
                             show_if_different
                                 .push_str(path2.to_str().unwrap_or(COULD_NOT_UNWRAP_FILENAME));
-                            show_if_different.push(' ');
                         }
 
                         let inner_exit_status = FileDiff::file_diff(
@@ -153,15 +152,19 @@ impl<'a> DirDiff<'a> {
                         }
                     } else if !in_dir1_is_file && !in_dir2_is_file {
                         if self.recursive {
-                            Self::dir_diff(
+                            let inner_exit_status = Self::dir_diff(
                                 self.dir1.path().join(file_name),
                                 self.dir2.path().join(file_name),
                                 self.format_options,
                                 self.recursive,
                             )?;
+
+                            if exit_status.status_code() < inner_exit_status.status_code() {
+                                exit_status = inner_exit_status;
+                            }
                         } else {
                             println!(
-                                "Common subdirectories: \"{}\" and \"{}\"",
+                                "Common subdirectories: {} and {}",
                                 self.dir1
                                     .path()
                                     .join(file_name)
@@ -187,10 +190,19 @@ impl<'a> DirDiff<'a> {
                             )
                         };
 
-                        println!(
-                            "File \"{}\" is a directory while file \"{}\" is a regular file",
-                            dir, file
-                        );
+                        if in_dir1_is_file {
+                            println!(
+                                "File {} is a regular file while file {} is a directory",
+                                file, dir
+                            );
+                        } else {
+                            println!(
+                                "File {} is a directory while file {} is a regular file",
+                                dir, file
+                            );
+                        }
+
+                        exit_status = exit_status.max(DiffExitStatus::Different);
                     }
                 }
                 (true, false) => {
@@ -198,14 +210,18 @@ impl<'a> DirDiff<'a> {
                         "Only in {}: {}",
                         self.dir1.path_str(),
                         file_name.to_str().unwrap_or(COULD_NOT_UNWRAP_FILENAME)
-                    )
+                    );
+
+                    exit_status = exit_status.max(DiffExitStatus::Different);
                 }
                 (false, true) => {
                     println!(
                         "Only in {}: {}",
                         self.dir2.path_str(),
                         file_name.to_str().unwrap_or(COULD_NOT_UNWRAP_FILENAME)
-                    )
+                    );
+
+                    exit_status = exit_status.max(DiffExitStatus::Different);
                 }
                 (false, false) => {
                     eprintln!(
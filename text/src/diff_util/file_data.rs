@@ -28,17 +28,19 @@ impl FileData {
         let mut content = String::new();
         buf_reader.read_to_string(&mut content)?;
 
-        let mut lines = content
-            .lines()
-            .map(|line| line.to_string())
-            .collect::<Vec<String>>();
+        // split on newlines alone, so that a carriage return stays part of
+        // its line; a final newline leaves an empty last line
+        let lines = if content.is_empty() {
+            Vec::new()
+        } else {
+            content
+                .split('\n')
+                .map(|line| line.to_string())
+                .collect::<Vec<String>>()
+        };
 
         let ends_with_newline = content.ends_with("\n");
 
-        if ends_with_newline {
-            lines.push(String::from(""));
-        }
-
         let changes = vec![Change::None; lines.len()];
 
         let result = Self {
@@ -52,20 +54,20 @@ impl FileData {
         Ok(result)
     }
 
-    pub fn get_context_identifier(&self, change_index: usize) -> &str {
-        match self.changes[change_index] {
-            Change::None => " ",
-            Change::Unchanged(_) => " ",
-            Change::Insert(_) => "+",
-            Change::Delete(_) => "-",
-            Change::Substitute(_) => "!",
-        }
-    }
-
     pub fn lines(&self) -> &Vec<String> {
         &self.lines
     }
 
+    /// The number of lines, not counting the empty one after a final
+    /// newline.
+    pub fn line_count(&self) -> usize {
+        if self.ends_with_newline {
+            self.lines.len() - 1
+        } else {
+            self.lines.len()
+        }
+    }
+
     pub fn line(&self, index: usize) -> &String {
         &self.lines[index]
     }
@@ -88,27 +90,6 @@ impl FileData {
         self.changes[index] = change;
     }
 
-    pub fn expected_changed_in_range(
-        &self,
-        start: usize,
-        end: usize,
-        expected_changes: &Vec<fn(&Change) -> bool>,
-    ) -> bool {
-        for i in start..=end {
-            for expected_change in expected_changes {
-                if expected_change(&self.changes[i]) {
-                    return true;
-                }
-            }
-        }
-
-        return false;
-    }
-
-    pub fn change(&self, index: usize) -> &Change {
-        &self.changes[index]
-    }
-
     pub fn path(&self) -> &str {
         self.path.to_str().unwrap_or(&COULD_NOT_UNWRAP_FILENAME)
     }
@@ -1,21 +1,17 @@
 use super::{
     change::ChangeData,
     common::{FormatOptions, OutputFormat},
-    constants::COULD_NOT_UNWRAP_FILENAME,
+    constants::{CONTEXT_TIME_FORMAT, COULD_NOT_UNWRAP_FILENAME, UNIFIED_TIME_FORMAT},
+    context::{context_hunks, context_range, unified_range, Block},
     diff_exit_status::DiffExitStatus,
     file_data::FileData,
-    functions::{check_existance, is_binary, system_time_to_rfc2822, vec_min},
+    functions::{check_existance, format_system_time, is_binary, vec_min},
     hunks::Hunks,
 };
 
-use crate::diff_util::{
-    change::Change,
-    constants::NO_NEW_LINE_AT_END_OF_FILE,
-    functions::{calculate_hash, increase_by_one_if},
-};
+use crate::diff_util::{change::Change, constants::NO_NEW_LINE_AT_END_OF_FILE};
 
 use std::{
-    collections::HashMap,
     fs::File,
     io::{self, BufReader, Read},
     os::unix::fs::MetadataExt,
@@ -143,9 +139,13 @@ impl<'a> FileDiff<'a> {
         self.order_hunks_by_output_format();
 
         if let OutputFormat::Context(context) = self.format_options.output_format {
-            self.print_context(context);
+            if self.are_different() {
+                self.print_context(context);
+            }
         } else if let OutputFormat::Unified(unified) = self.format_options.output_format {
-            self.print_unified(unified);
+            if self.are_different() {
+                self.print_unified(unified);
+            }
         } else {
             let hunks_count = self.hunks.hunks().len();
 
@@ -280,64 +280,6 @@ impl<'a> FileDiff<'a> {
         self.hunks.add_change(change);
     }
 
-    fn get_context_ranges(&self, context: usize) -> Vec<(usize, usize, usize, usize)> {
-        let f1_lines = self.file1.lines().len();
-        let f2_lines = self.file2.lines().len();
-
-        let mut change_ranges = self
-            .hunks
-            .hunks()
-            .iter()
-            .filter(|hunk| !Change::is_none(&hunk.kind()) && !Change::is_unchanged(&hunk.kind()))
-            .map(|hunk| {
-                (
-                    hunk.ln1_start() as i64,
-                    hunk.ln1_end() as i64,
-                    hunk.ln2_start() as i64,
-                    hunk.ln2_end() as i64,
-                )
-            })
-            .collect::<Vec<(i64, i64, i64, i64)>>();
-
-        change_ranges.sort_by_key(|change| (change.1, change.3));
-
-        let mut context_ranges = vec![(usize::MIN, usize::MIN, usize::MIN, usize::MIN); 0];
-
-        let f1_max = if self.file1.ends_with_newline() && f1_lines - 1 >= 1 {
-            f1_lines - 1
-        } else {
-            f1_lines
-        };
-
-        let f2_max = if self.file2.ends_with_newline() && f2_lines - 1 >= 1 {
-            f2_lines - 1
-        } else {
-            f2_lines
-        };
-
-        for cr in change_ranges {
-            let ln1s = i64::clamp(cr.0 - context as i64, 1, f1_max as i64);
-            let ln1e = i64::clamp(cr.1 + context as i64, 1, f1_max as i64);
-            let ln2s = i64::clamp(cr.2 - context as i64, 1, f2_max as i64);
-            let ln2e = i64::clamp(cr.3 + context as i64, 1, f2_max as i64);
-
-            if context_ranges.len() > 0 {
-                // Overlap check
-                if let Some((_, ln1_end, _, ln2_end)) = context_ranges.last_mut() {
-                    if *ln1_end >= ln1s as usize || *ln2_end >= ln2s as usize {
-                        *ln1_end = ln1e as usize;
-                        *ln2_end = ln2e as usize;
-                        continue;
-                    }
-                }
-            }
-
-            context_ranges.push((ln1s as usize, ln1e as usize, ln2s as usize, ln2e as usize));
-        }
-
-        return context_ranges;
-    }
-
     fn order_hunks_by_output_format(&mut self) {
         match self.format_options.output_format {
             OutputFormat::Debug => self.order_hunks_ascending(),
@@ -363,59 +305,50 @@ impl<'a> FileDiff<'a> {
     fn print_context(&mut self, context: usize) {
         println!(
             "*** {}",
-            Self::get_header(self.file1, &self.format_options.label1)
+            Self::get_header(self.file1, &self.format_options.label1, CONTEXT_TIME_FORMAT)
         );
         println!(
             "--- {}",
-            Self::get_header(self.file2, &self.format_options.label2)
+            Self::get_header(self.file2, &self.format_options.label2, CONTEXT_TIME_FORMAT)
         );
 
-        let change_ranges = self.get_context_ranges(context);
-
-        for cr_index in 0..change_ranges.len() {
-            let cr = change_ranges[cr_index];
+        let hunks = context_hunks(
+            self.file1,
+            self.file2,
+            context,
+            self.format_options.ignore_trailing_white_spaces,
+        );
 
+        for hunk in hunks {
             println!("***************");
-            println!("*** {} ***", format!("{},{}", cr.0, cr.1));
-            if self.file1.expected_changed_in_range(
-                cr.0 - 1,
-                cr.1 - 1,
-                &vec![Change::is_delete, Change::is_substitute],
-            ) {
-                for i in cr.0..=cr.1 {
-                    println!(
-                        "{} {}",
-                        self.file1.get_context_identifier(i - 1),
-                        self.file1.line(i - 1)
-                    );
-                }
-            }
 
-            if cr_index == change_ranges.len() - 1 {
-                if self.file1.ends_with_newline() == false {
-                    println!("{}", NO_NEW_LINE_AT_END_OF_FILE);
-                }
-            }
-
-            println!("--- {} ---", format!("{},{}", cr.2, cr.3));
-
-            if self.file2.expected_changed_in_range(
-                cr.2 - 1,
-                cr.3 - 1,
-                &vec![Change::is_insert, Change::is_substitute],
-            ) {
-                for i in cr.2..=cr.3 {
-                    println!(
-                        "{} {}",
-                        self.file2.get_context_identifier(i - 1),
-                        self.file2.line(i - 1)
-                    );
+            println!("*** {} ****", context_range(hunk.old_start, hunk.old_count));
+            if hunk.has_old_changes() {
+                for block in &hunk.blocks {
+                    match block {
+                        Block::Common(i, _) => self.print_line(self.file1, "  ", *i),
+                        Block::Change { old, new } => {
+                            let mark = if new.is_empty() { "- " } else { "! " };
+                            for i in old {
+                                self.print_line(self.file1, mark, *i);
+                            }
+                        }
+                    }
                 }
             }
 
-            if cr_index == change_ranges.len() - 1 {
-                if self.file2.ends_with_newline() == false {
-                    println!("{}", NO_NEW_LINE_AT_END_OF_FILE);
+            println!("--- {} ----", context_range(hunk.new_start, hunk.new_count));
+            if hunk.has_new_changes() {
+                for block in &hunk.blocks {
+                    match block {
+                        Block::Common(_, j) => self.print_line(self.file2, "  ", *j),
+                        Block::Change { old, new } => {
+                            let mark = if old.is_empty() { "+ " } else { "! " };
+                            for j in new {
+                                self.print_line(self.file2, mark, *j);
+                            }
+                        }
+                    }
                 }
             }
         }
@@ -424,151 +357,61 @@ impl<'a> FileDiff<'a> {
     fn print_unified(&mut self, unified: usize) {
         println!(
             "--- {}",
-            Self::get_header(self.file1, &self.format_options.label1)
+            Self::get_header(self.file1, &self.format_options.label1, UNIFIED_TIME_FORMAT)
         );
         println!(
             "+++ {}",
-            Self::get_header(self.file2, &self.format_options.label2)
+            Self::get_header(self.file2, &self.format_options.label2, UNIFIED_TIME_FORMAT)
         );
 
-        let context_ranges = self.get_context_ranges(unified);
-
-        for cr_index in 0..context_ranges.len() {
-            let cr = context_ranges[cr_index];
-            let mut changes = HashMap::<u64, Change>::new();
-
-            for f1_line in cr.0..=cr.1 {
-                let change = self.file1.change(f1_line - 1);
-                changes.insert(calculate_hash(change), change.clone());
-            }
-
-            for f2_line in cr.2..=cr.3 {
-                let change = self.file2.change(f2_line - 1);
-                let hash = calculate_hash(change);
-                if changes.contains_key(&hash) == false {
-                    changes.insert(calculate_hash(change), change.clone());
-                }
-            }
-
-            if changes.values().any(|change| {
-                Change::is_delete(change)
-                    || Change::is_insert(change)
-                    || Change::is_substitute(change)
-            }) {
-                let mut values = changes.values().cloned().collect::<Vec<Change>>();
-                values.sort_by_key(|change| change.get_lns());
-
-                let f1_range = if values.iter().all(|change| change.is_insert()) {
-                    format!("{},{}", cr.0, 0)
-                } else {
-                    let start = cr.0;
-                    let count = cr.1 - cr.0 + 1;
-                    if count == 1 {
-                        format!("{}", start)
-                    } else {
-                        format!("{},{}", start, count)
-                    }
-                };
+        let hunks = context_hunks(
+            self.file1,
+            self.file2,
+            unified,
+            self.format_options.ignore_trailing_white_spaces,
+        );
 
-                let f2_range = if values.iter().all(|change| change.is_delete()) {
-                    format!("{},{}", cr.2, 0)
-                } else {
-                    let start = cr.2;
-                    let count = cr.3 - cr.2 + 1;
-                    if count == 1 {
-                        format!("{}", start)
-                    } else {
-                        format!("{},{}", start, count)
-                    }
-                };
+        for hunk in hunks {
+            println!(
+                "@@ -{} +{} @@",
+                unified_range(hunk.old_start, hunk.old_count),
+                unified_range(hunk.new_start, hunk.new_count)
+            );
 
-                let unchanged_count = values.iter().filter(|change| change.is_unchanged()).count();
-                let insert_count = values.iter().filter(|change| change.is_insert()).count();
-                let delete_count = values.iter().filter(|change| change.is_delete()).count();
-                let substitute_count = values
-                    .iter()
-                    .filter(|change| change.is_substitute())
-                    .count();
-
-                let mut printed_unchanged = 0usize;
-                let mut printed_insert = 0usize;
-                let mut printed_delete = 0usize;
-                let mut printed_substitute = 0usize;
-
-                let mut f1_no_eof_printable = true;
-                let mut f2_no_eof_printable = true;
-
-                println!("@@ -{} +{} @@", f1_range, f2_range);
-
-                for change in values {
-                    increase_by_one_if(change.is_unchanged(), &mut printed_unchanged);
-                    increase_by_one_if(change.is_insert(), &mut printed_insert);
-                    increase_by_one_if(change.is_delete(), &mut printed_delete);
-                    increase_by_one_if(change.is_substitute(), &mut printed_substitute);
-
-                    let printables = match change {
-                        Change::None => vec![String::new(); 0],
-                        Change::Unchanged(data) => {
-                            vec![format!(" {}", self.file1.line(data.ln1() - 1))]
-                        }
-                        Change::Insert(data) => {
-                            vec![format!("+{}", self.file2.line(data.ln2() - 1))]
-                        }
-                        Change::Delete(data) => {
-                            vec![format!("-{}", self.file1.line(data.ln1() - 1))]
+            for block in &hunk.blocks {
+                match block {
+                    Block::Common(i, _) => self.print_line(self.file1, " ", *i),
+                    Block::Change { old, new } => {
+                        for i in old {
+                            self.print_line(self.file1, "-", *i);
                         }
-                        Change::Substitute(data) => {
-                            vec![
-                                format!("-{}", self.file1.line(data.ln1() - 1)),
-                                format!("+{}", self.file2.line(data.ln2() - 1)),
-                            ]
+                        for j in new {
+                            self.print_line(self.file2, "+", *j);
                         }
-                    };
-
-                    if change.is_none() {
-                        continue;
-                    }
-
-                    println!("{}", printables[0]);
-
-                    if f1_no_eof_printable
-                        && cr_index == context_ranges.len() - 1
-                        && printed_unchanged == unchanged_count
-                        && printed_delete == delete_count
-                        && printed_substitute == substitute_count
-                        && self.file1.ends_with_newline() == false
-                    {
-                        println!("{}", NO_NEW_LINE_AT_END_OF_FILE);
-                        f1_no_eof_printable = false;
-                    }
-
-                    if change.is_substitute() {
-                        println!("{}", printables[1]);
-                    }
-
-                    if f2_no_eof_printable
-                        && unified != 0
-                        && cr_index == context_ranges.len() - 1
-                        && insert_count == printed_insert
-                        && printed_substitute == substitute_count
-                        && self.file2.ends_with_newline() == false
-                    {
-                        println!("{}", NO_NEW_LINE_AT_END_OF_FILE);
-                        f2_no_eof_printable = false;
                     }
                 }
             }
         }
     }
 
-    pub fn get_header(file: &FileData, label: &Option<String>) -> String {
+    /// Print a line of a context or unified diff, noting a missing
+    /// newline after the last line.
+    fn print_line(&self, file: &FileData, prefix: &str, index: usize) {
+        println!("{}{}", prefix, file.line(index));
+
+        if index + 1 == file.line_count() && !file.ends_with_newline() {
+            println!("{}", NO_NEW_LINE_AT_END_OF_FILE);
+        }
+    }
+
+    pub fn get_header(file: &FileData, label: &Option<String>, time_format: &str) -> String {
         if let Some(label) = label {
             return format!("{}", label);
         } else {
             return format!(
                 "{}\t{}",
                 file.path(),
-                system_time_to_rfc2822(file.modified())
+                format_system_time(file.modified(), time_format)
             );
         }
     }
@@ -1,7 +1,6 @@
 use chrono::{DateTime, Local};
 use std::{
     fs::File,
-    hash::{DefaultHasher, Hash, Hasher},
     io::{self, Read},
     path::PathBuf,
     time::SystemTime,
@@ -10,20 +9,10 @@ use std::{
 use super::constants::UTF8_NOT_ALLOWED_BYTES;
 use crate::diff_util::constants::COULD_NOT_UNWRAP_FILENAME;
 
-pub fn system_time_to_rfc2822(system_time: SystemTime) -> String {
-    Into::<DateTime<Local>>::into(system_time).to_rfc2822()
-}
-
-pub fn calculate_hash<T: Hash>(obj: &T) -> u64 {
-    let mut hasher = DefaultHasher::new();
-    obj.hash(&mut hasher);
-    hasher.finish()
-}
-
-pub fn increase_by_one_if(condition: bool, value: &mut usize) {
-    if condition {
-        *value += 1;
-    }
+pub fn format_system_time(system_time: SystemTime, format: &str) -> String {
+    Into::<DateTime<Local>>::into(system_time)
+        .format(format)
+        .to_string()
 }
 
 pub fn vec_min(nums: &[usize]) -> usize {
@@ -90,14 +90,6 @@ impl Hunk {
         self.changes.push(change);
     }
 
-    pub fn ln1_start(&self) -> usize {
-        self.ln1_start
-    }
-
-    pub fn ln2_start(&self) -> usize {
-        self.ln2_start
-    }
-
     pub fn ln1_end(&self) -> usize {
         self.ln1_end
     }
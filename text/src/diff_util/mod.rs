@@ -1,6 +1,7 @@
 pub(crate) mod change;
 pub(crate) mod common;
 pub(crate) mod constants;
+pub(crate) mod context;
 pub(crate) mod diff_exit_status;
 pub(crate) mod dir_data;
 pub(crate) mod dir_diff;
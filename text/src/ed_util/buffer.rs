@@ -0,0 +1,101 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use std::rc::Rc;
+
+/// A line of text, without its newline.
+///
+/// Lines are shared between the buffer and its undo copy, and a line is
+/// identified by its allocation, so that marks and the lines selected by
+/// a global command follow the line as others are added or deleted.
+pub type Line = Rc<Vec<u8>>;
+
+/// The lines being edited. Line numbers count from 1, and 0 is the
+/// position before the first line.
+#[derive(Clone, Default)]
+pub struct Buffer {
+    lines: Vec<Line>,
+    /// The current line, 0 only when the buffer is empty.
+    pub cur: usize,
+    marks: [Option<Line>; 26],
+}
+
+impl Buffer {
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn line(&self, n: usize) -> &[u8] {
+        &self.lines[n - 1]
+    }
+
+    pub fn handle(&self, n: usize) -> Line {
+        Rc::clone(&self.lines[n - 1])
+    }
+
+    /// The current number of a line, if it is still in the buffer.
+    pub fn find(&self, line: &Line) -> Option<usize> {
+        self.find_near(line, 1)
+    }
+
+    /// Like `find`, looking from line `hint` onwards first.
+    pub fn find_near(&self, line: &Line, hint: usize) -> Option<usize> {
+        let start = hint.clamp(1, self.lines.len().max(1)) - 1;
+        let (before, after) = self.lines.split_at(start.min(self.lines.len()));
+        after
+            .iter()
+            .position(|l| Rc::ptr_eq(l, line))
+            .map(|i| start + i + 1)
+            .or_else(|| {
+                before
+                    .iter()
+                    .position(|l| Rc::ptr_eq(l, line))
+                    .map(|i| i + 1)
+            })
+    }
+
+    /// Insert lines after line `after`, returning the number of the last
+    /// one inserted.
+    pub fn insert(&mut self, after: usize, lines: Vec<Line>) -> usize {
+        let count = lines.len();
+        self.lines.splice(after..after, lines);
+        after + count
+    }
+
+    /// Remove lines `from` through `to`.
+    pub fn delete(&mut self, from: usize, to: usize) -> Vec<Line> {
+        self.lines.drain(from - 1..to).collect()
+    }
+
+    /// Copies of lines `from` through `to`, as new lines.
+    pub fn copy(&self, from: usize, to: usize) -> Vec<Line> {
+        self.lines[from - 1..to]
+            .iter()
+            .map(|l| Rc::new(l.to_vec()))
+            .collect()
+    }
+
+    pub fn replace(&mut self, n: usize, text: Vec<u8>) {
+        self.lines[n - 1] = Rc::new(text);
+    }
+
+    pub fn set_mark(&mut self, name: u8, n: usize) {
+        self.marks[(name - b'a') as usize] = Some(self.handle(n));
+    }
+
+    pub fn mark(&self, name: u8) -> Option<usize> {
+        self.marks[(name - b'a') as usize]
+            .as_ref()
+            .and_then(|line| self.find(line))
+    }
+}
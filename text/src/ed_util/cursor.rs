@@ -0,0 +1,210 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+/// How a delimited piece of a command ended.
+#[derive(Debug, PartialEq)]
+pub enum Delimited {
+    /// At the closing delimiter.
+    Closed,
+    /// At the end of the line.
+    Open,
+    /// At a backslash ending the line, so the text continues on the next.
+    Continued,
+}
+
+/// A command line being parsed.
+pub struct Cursor {
+    chars: Vec<char>,
+    pub pos: usize,
+}
+
+impl Cursor {
+    pub fn new(line: &str) -> Cursor {
+        Cursor {
+            chars: line.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    pub fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.pos += 1;
+        Some(ch)
+    }
+
+    pub fn eat(&mut self, ch: char) -> bool {
+        if self.peek() == Some(ch) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    pub fn skip_blanks(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    /// The unparsed rest of the line.
+    pub fn rest(&mut self) -> String {
+        let rest = self.chars[self.pos..].iter().collect();
+        self.pos = self.chars.len();
+        rest
+    }
+
+    /// Add the next input line, for a command continued with a backslash.
+    pub fn push_line(&mut self, line: &str) {
+        self.chars.push('\n');
+        self.chars.extend(line.chars());
+    }
+
+    /// A decimal number, if one starts here.
+    pub fn number(&mut self) -> Option<Result<usize, &'static str>> {
+        let start = self.pos;
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return None;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        Some(digits.parse().map_err(|_| "number out of range"))
+    }
+
+    /// A regular expression ending at `delim`, which does not end it
+    /// inside a bracket expression, and which stands for itself when
+    /// escaped.
+    pub fn regex(&mut self, delim: char) -> (String, Delimited) {
+        let mut re = String::new();
+
+        while let Some(ch) = self.bump() {
+            match ch {
+                _ if ch == delim => return (re, Delimited::Closed),
+                '\\' => match self.bump() {
+                    // keep the backslash where the delimiter would otherwise
+                    // be special
+                    Some(c) if c == delim && !".*[]^$".contains(c) => re.push(c),
+                    Some(c) => {
+                        re.push('\\');
+                        re.push(c);
+                    }
+                    None => re.push('\\'),
+                },
+                '[' => {
+                    re.push(ch);
+                    self.bracket(&mut re);
+                }
+                _ => re.push(ch),
+            }
+        }
+
+        (re, Delimited::Open)
+    }
+
+    /// Copy the rest of a bracket expression whose '[' was just read.
+    fn bracket(&mut self, re: &mut String) {
+        if self.eat('^') {
+            re.push('^');
+        }
+        if self.eat(']') {
+            re.push(']');
+        }
+        while let Some(ch) = self.bump() {
+            re.push(ch);
+            match ch {
+                ']' => return,
+                '[' if matches!(self.peek(), Some(':' | '.' | '=')) => {
+                    // [:class:], [.coll.] and [=equiv=] may contain ']'
+                    let kind = self.bump().unwrap();
+                    re.push(kind);
+                    while let Some(c) = self.bump() {
+                        re.push(c);
+                        if c == kind && self.eat(']') {
+                            re.push(']');
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The replacement of a substitute command, ending at `delim`.
+    pub fn replacement(&mut self, delim: char) -> (String, Delimited) {
+        let mut repl = String::new();
+
+        while let Some(ch) = self.bump() {
+            if ch == delim {
+                return (repl, Delimited::Closed);
+            }
+            if ch == '\\' {
+                match self.bump() {
+                    Some(c) if c == delim => repl.push(c),
+                    Some(c) => {
+                        repl.push('\\');
+                        repl.push(c);
+                    }
+                    None => return (repl, Delimited::Continued),
+                }
+            } else {
+                repl.push(ch);
+            }
+        }
+
+        (repl, Delimited::Open)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regex() {
+        let mut c = Cursor::new(r"a\/b[/]/p");
+        assert_eq!(c.regex('/'), (String::from("a/b[/]"), Delimited::Closed));
+        assert_eq!(c.rest(), "p");
+
+        let mut c = Cursor::new(r"a\.b.p");
+        assert_eq!(c.regex('.'), (String::from(r"a\.b"), Delimited::Closed));
+        assert_eq!(c.rest(), "p");
+
+        let mut c = Cursor::new("[[:alpha:]]x/");
+        assert_eq!(
+            c.regex('/'),
+            (String::from("[[:alpha:]]x"), Delimited::Closed)
+        );
+    }
+
+    #[test]
+    fn test_replacement() {
+        let mut c = Cursor::new(r"a\/b\&/g");
+        assert_eq!(
+            c.replacement('/'),
+            (String::from(r"a/b\&"), Delimited::Closed)
+        );
+
+        let mut c = Cursor::new(r"a\");
+        assert_eq!(
+            c.replacement('/'),
+            (String::from("a"), Delimited::Continued)
+        );
+    }
+}
@@ -0,0 +1,1253 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use super::buffer::{Buffer, Line};
+use super::cursor::{Cursor, Delimited};
+use gettextrs::gettext;
+use plib::io::StdoutWriter;
+use plib::regex::bre_to_regex;
+use regex::bytes::Regex;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by SIGINT, to abandon the command being run.
+pub static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+/// Set by SIGHUP, to save the buffer and exit.
+pub static HANGUP: AtomicBool = AtomicBool::new(false);
+
+/// Lines written by `l` are folded to fit this width.
+const LIST_WIDTH: usize = 72;
+
+type EdResult<T> = Result<T, String>;
+
+fn err<T>(msg: &str) -> EdResult<T> {
+    Err(gettext(msg))
+}
+
+/// What to do after a command.
+#[derive(PartialEq)]
+enum Flow {
+    Continue,
+    Quit,
+}
+
+/// The addresses given to a command.
+struct Range {
+    count: usize,
+    first: usize,
+    second: usize,
+}
+
+/// A piece of the replacement in a substitute command.
+#[derive(Clone)]
+enum Piece {
+    Text(Vec<u8>),
+    /// '&', the whole match.
+    Match,
+    /// "\n", a subexpression.
+    Group(usize),
+}
+
+pub struct Options {
+    pub prompt: Option<String>,
+    pub silent: bool,
+    pub restricted: bool,
+    /// Set when commands are read from a regular file.
+    pub script: bool,
+}
+
+pub struct Editor {
+    opts: Options,
+    buf: Buffer,
+    /// The buffer before the last command that changed it.
+    undo: Option<(Buffer, bool)>,
+    /// Set when a command changes the buffer.
+    changed: bool,
+    modified: bool,
+    /// Set when the last command was refused because of unsaved changes.
+    warned: bool,
+    filename: Option<String>,
+    last_regex: Option<Regex>,
+    last_replacement: Option<Vec<Piece>>,
+    last_shell: Option<String>,
+    last_error: Option<String>,
+    verbose: bool,
+    /// Set once a command has failed.
+    failed: bool,
+    prompting: bool,
+    /// The rest of the command list of a global command.
+    pending: VecDeque<Vec<u8>>,
+    in_global: bool,
+    input: Box<dyn BufRead>,
+    out: StdoutWriter,
+}
+
+impl Editor {
+    pub fn new(opts: Options, input: Box<dyn BufRead>) -> Editor {
+        let prompting = opts.prompt.is_some();
+        Editor {
+            opts,
+            buf: Buffer::default(),
+            undo: None,
+            changed: false,
+            modified: false,
+            warned: false,
+            filename: None,
+            last_regex: None,
+            last_replacement: None,
+            last_shell: None,
+            last_error: None,
+            verbose: false,
+            failed: false,
+            prompting,
+            pending: VecDeque::new(),
+            in_global: false,
+            input,
+            out: StdoutWriter::new(),
+        }
+    }
+
+    /// Read and run commands until q or the end of input, returning the
+    /// exit status.
+    pub fn run(&mut self, file: Option<&str>) -> i32 {
+        if let Some(name) = file {
+            if let Err(e) = self.check_filename(name) {
+                self.report(e);
+            } else {
+                self.filename = Some(name.to_string());
+                if let Err(e) = self.edit(name) {
+                    // a missing file starts an empty buffer with that name
+                    let _ = self.out.flush();
+                    eprintln!("{}", e);
+                }
+            }
+        }
+
+        loop {
+            if self.prompting {
+                let prompt = self.opts.prompt.as_deref().unwrap_or("*");
+                let _ = self.out.write_all(prompt.as_bytes());
+            }
+
+            let res = match self.read_input() {
+                Some(line) => self.exec_line(&String::from_utf8_lossy(&line)),
+                // the end of input is a q command
+                None if HANGUP.load(Ordering::SeqCst) => Ok(Flow::Quit),
+                None => self.exec_line("q"),
+            };
+            INTERRUPTED.store(false, Ordering::SeqCst);
+
+            if HANGUP.load(Ordering::SeqCst) {
+                self.save_on_hangup();
+                return 1;
+            }
+
+            match res {
+                Ok(Flow::Quit) => break,
+                Ok(Flow::Continue) => {}
+                Err(e) => {
+                    self.report(e);
+                    self.failed = true;
+                    // a script read from a file ends at the first error
+                    if self.opts.script {
+                        let _ = self.out.flush();
+                        return 1;
+                    }
+                }
+            }
+        }
+
+        match self.out.flush() {
+            Ok(()) if !self.failed => 0,
+            _ => 1,
+        }
+    }
+
+    fn report(&mut self, e: String) {
+        let _ = self.out.write_all(b"?\n");
+        if self.verbose {
+            let _ = writeln!(self.out, "{}", e);
+        }
+        self.last_error = Some(e);
+    }
+
+    fn save_on_hangup(&mut self) {
+        if !self.modified {
+            return;
+        }
+        let data = self.text_of(1, self.buf.len());
+        if fs::write("ed.hup", &data).is_err() {
+            if let Some(home) = std::env::var_os("HOME") {
+                let _ = fs::write(std::path::Path::new(&home).join("ed.hup"), &data);
+            }
+        }
+    }
+
+    /// The next input line, without its newline: from the command list
+    /// of a global command while one runs, otherwise from standard input.
+    fn read_line(&mut self) -> Option<Vec<u8>> {
+        if self.in_global {
+            self.pending.pop_front()
+        } else {
+            self.read_input()
+        }
+    }
+
+    fn read_input(&mut self) -> Option<Vec<u8>> {
+        let _ = self.out.flush();
+        let mut line = Vec::new();
+        match self.input.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => {
+                if line.ends_with(b"\n") {
+                    line.pop();
+                }
+                Some(line)
+            }
+        }
+    }
+
+    /// Run one command line, as typed at the top level.
+    fn exec_line(&mut self, line: &str) -> EdResult<Flow> {
+        let before = (self.buf.clone(), self.modified);
+        self.changed = false;
+
+        let res = self.exec_command(line);
+
+        // a global command is undone as a whole
+        if self.changed {
+            self.undo = Some(before);
+        }
+        res
+    }
+
+    fn exec_command(&mut self, line: &str) -> EdResult<Flow> {
+        // a q or e after a refused one goes ahead
+        let confirmed = std::mem::replace(&mut self.warned, false);
+
+        let mut c = Cursor::new(line);
+        let range = self.addresses(&mut c)?;
+        c.skip_blanks();
+        let cmd = c.bump();
+
+        match cmd {
+            None => {
+                // print the addressed line, or the next one
+                let n = if range.count > 0 {
+                    range.second
+                } else {
+                    self.buf.cur + 1
+                };
+                if n == 0 || n > self.buf.len() {
+                    return err("invalid address");
+                }
+                self.print(n, n, 'p')?;
+            }
+            Some('a') => {
+                let n = self.default1(&range, self.buf.cur);
+                self.suffix_end(&mut c)?;
+                let lines = self.read_text();
+                self.append(n, lines);
+            }
+            Some('i') => {
+                let n = self.default1(&range, self.buf.cur);
+                self.suffix_end(&mut c)?;
+                let lines = self.read_text();
+                self.append(n.saturating_sub(1), lines);
+            }
+            Some('c') => {
+                let (first, second) = self.default2(&range, self.buf.cur, self.buf.cur)?;
+                let print = self.suffix(&mut c)?;
+                let lines = self.read_text();
+                self.delete(first, second);
+                if !lines.is_empty() {
+                    self.append(first - 1, lines);
+                }
+                self.print_suffix(print)?;
+            }
+            Some('d') => {
+                let (first, second) = self.default2(&range, self.buf.cur, self.buf.cur)?;
+                let print = self.suffix(&mut c)?;
+                self.delete(first, second);
+                self.print_suffix(print)?;
+            }
+            Some(ch @ ('e' | 'E')) => {
+                self.no_address(&range)?;
+                let name = self.file_operand(&mut c)?;
+                if ch == 'e' && self.modified && !confirmed {
+                    self.warned = true;
+                    return err("warning: buffer modified");
+                }
+                let name = self.filename_or_default(name)?;
+                if !name.starts_with('!') {
+                    self.filename = Some(name.clone());
+                }
+                self.edit(&name)?;
+                self.undo = None;
+                self.changed = false;
+            }
+            Some('f') => {
+                self.no_address(&range)?;
+                if let Some(name) = self.file_operand(&mut c)? {
+                    if name.starts_with('!') {
+                        return err("invalid filename");
+                    }
+                    self.filename = Some(name);
+                }
+                match &self.filename {
+                    Some(name) => {
+                        let name = name.clone();
+                        self.write_out(name.as_bytes())?;
+                    }
+                    None => return err("no current filename"),
+                }
+            }
+            Some(ch @ ('g' | 'v' | 'G' | 'V')) => {
+                let (first, second) = self.default2(&range, 1, self.buf.len())?;
+                let invert = ch == 'v' || ch == 'V';
+                let interactive = ch == 'G' || ch == 'V';
+                return self.global(&mut c, first, second, invert, interactive);
+            }
+            Some('h') => {
+                self.no_address(&range)?;
+                self.suffix_end(&mut c)?;
+                if let Some(e) = self.last_error.clone() {
+                    self.write_out(e.as_bytes())?;
+                }
+            }
+            Some('H') => {
+                self.no_address(&range)?;
+                self.suffix_end(&mut c)?;
+                self.verbose = !self.verbose;
+                if self.verbose {
+                    if let Some(e) = self.last_error.clone() {
+                        self.write_out(e.as_bytes())?;
+                    }
+                }
+            }
+            Some('j') => {
+                let (first, second) = self.default2(&range, self.buf.cur, self.buf.cur + 1)?;
+                let print = self.suffix(&mut c)?;
+                if first < second {
+                    let mut joined = Vec::new();
+                    for n in first..=second {
+                        joined.extend_from_slice(self.buf.line(n));
+                    }
+                    self.buf.delete(first + 1, second);
+                    self.buf.replace(first, joined);
+                    self.touch();
+                }
+                self.buf.cur = first;
+                self.print_suffix(print)?;
+            }
+            Some('k') => {
+                let n = self.default1(&range, self.buf.cur);
+                if n == 0 {
+                    return err("invalid address");
+                }
+                match c.bump() {
+                    Some(m @ 'a'..='z') => self.buf.set_mark(m as u8, n),
+                    _ => return err("invalid mark character"),
+                }
+                self.suffix_end(&mut c)?;
+            }
+            Some(ch @ ('l' | 'n' | 'p')) => {
+                let (first, second) = self.default2(&range, self.buf.cur, self.buf.cur)?;
+                let print = self.suffix(&mut c)?;
+                self.print(first, second, ch)?;
+                self.print_suffix(print)?;
+            }
+            Some(ch @ ('m' | 't')) => {
+                let (first, second) = self.default2(&range, self.buf.cur, self.buf.cur)?;
+                let dest = self.address(&mut c)?.unwrap_or(self.buf.cur);
+                let print = self.suffix(&mut c)?;
+                if ch == 'm' {
+                    self.move_lines(first, second, dest)?;
+                } else {
+                    let lines = self.buf.copy(first, second);
+                    self.append(dest, lines);
+                }
+                self.print_suffix(print)?;
+            }
+            Some('P') => {
+                self.no_address(&range)?;
+                self.suffix_end(&mut c)?;
+                self.prompting = !self.prompting;
+            }
+            Some(ch @ ('q' | 'Q')) => {
+                self.no_address(&range)?;
+                self.suffix_end(&mut c)?;
+                if ch == 'q' && self.modified && !confirmed {
+                    self.warned = true;
+                    return err("warning: buffer modified");
+                }
+                return Ok(Flow::Quit);
+            }
+            Some('r') => {
+                let n = self.default1(&range, self.buf.len());
+                let name = self.file_operand(&mut c)?;
+                let name = self.filename_or_default(name)?;
+                if self.filename.is_none() && !name.starts_with('!') {
+                    self.filename = Some(name.clone());
+                }
+                let (lines, size) = self.read_file(&name)?;
+                if !lines.is_empty() {
+                    self.append(n, lines);
+                }
+                self.print_size(size)?;
+            }
+            Some('s') => {
+                let (first, second) = self.default2(&range, self.buf.cur, self.buf.cur)?;
+                self.substitute(&mut c, first, second)?;
+            }
+            Some('u') => {
+                self.no_address(&range)?;
+                self.suffix_end(&mut c)?;
+                match self.undo.take() {
+                    Some((buf, modified)) => {
+                        let current = std::mem::replace(&mut self.buf, buf);
+                        self.undo = Some((current, self.modified));
+                        self.modified = modified;
+                        // undoing is itself undone by u
+                        self.changed = false;
+                    }
+                    None => return err("nothing to undo"),
+                }
+            }
+            Some('w') => {
+                // an empty buffer may be written
+                let (first, second) = if range.count == 0 && self.buf.is_empty() {
+                    (1, 0)
+                } else {
+                    self.default2(&range, 1, self.buf.len())?
+                };
+                let quit = c.eat('q');
+                let name = self.file_operand(&mut c)?;
+                let name = self.filename_or_default(name)?;
+                if self.filename.is_none() && !name.starts_with('!') {
+                    self.filename = Some(name.clone());
+                }
+                let size = self.write_file(&name, first, second)?;
+                if first <= 1 && second == self.buf.len() && !name.starts_with('!') {
+                    self.modified = false;
+                }
+                self.print_size(size)?;
+                if quit {
+                    return self.exec_command("q");
+                }
+            }
+            Some('=') => {
+                let n = if range.count > 0 {
+                    range.second
+                } else {
+                    self.buf.len()
+                };
+                self.suffix_end(&mut c)?;
+                self.write_out(n.to_string().as_bytes())?;
+            }
+            Some('!') => {
+                self.no_address(&range)?;
+                let command = self.shell_command(&c.rest())?;
+                let _ = self.out.flush();
+                Command::new("sh")
+                    .arg("-c")
+                    .arg(&command)
+                    .status()
+                    .map_err(|e| e.to_string())?;
+                if !self.opts.silent {
+                    self.write_out(b"!")?;
+                }
+            }
+            Some(_) => return err("unknown command"),
+        }
+
+        Ok(Flow::Continue)
+    }
+
+    fn touch(&mut self) {
+        self.modified = true;
+        self.changed = true;
+    }
+
+    fn write_out(&mut self, text: &[u8]) -> EdResult<()> {
+        self.out.write_all(text).map_err(|e| e.to_string())?;
+        self.out.write_all(b"\n").map_err(|e| e.to_string())
+    }
+
+    fn print_size(&mut self, size: usize) -> EdResult<()> {
+        if self.opts.silent {
+            return Ok(());
+        }
+        self.write_out(size.to_string().as_bytes())
+    }
+
+    //
+    // addresses
+    //
+
+    /// Parse the addresses before a command. Of several, the last two
+    /// count.
+    fn addresses(&mut self, c: &mut Cursor) -> EdResult<Range> {
+        let mut addrs = Vec::new();
+        let mut addr = self.address(c)?;
+
+        while let Some(sep @ (',' | ';')) = c.peek() {
+            c.bump();
+            // a separator with no address before it starts at the first or
+            // current line, and with none after it ends at the last line,
+            // or at the address before it
+            let explicit = addr.is_some();
+            let n = match addr {
+                Some(n) => n,
+                None if sep == ',' => 1,
+                None => self.buf.cur,
+            };
+            if sep == ';' {
+                self.buf.cur = n;
+            }
+            addrs.push(n);
+            addr = match self.address(c)? {
+                None if explicit => Some(n),
+                None => Some(self.buf.len()),
+                addr => addr,
+            };
+        }
+        if let Some(n) = addr {
+            addrs.push(n);
+        }
+
+        let count = addrs.len().min(2);
+        let second = addrs.last().copied().unwrap_or(self.buf.cur);
+        let first = if addrs.len() >= 2 {
+            addrs[addrs.len() - 2]
+        } else {
+            second
+        };
+        Ok(Range {
+            count,
+            first,
+            second,
+        })
+    }
+
+    /// Parse one address, if there is one.
+    fn address(&mut self, c: &mut Cursor) -> EdResult<Option<usize>> {
+        c.skip_blanks();
+        let start = c.pos;
+        let cur = self.buf.cur as isize;
+
+        let mut addr: Option<isize> = match c.peek() {
+            Some('.') => {
+                c.bump();
+                Some(cur)
+            }
+            Some('$') => {
+                c.bump();
+                Some(self.buf.len() as isize)
+            }
+            Some('0'..='9') => Some(c.number().unwrap()? as isize),
+            Some('\'') => {
+                c.bump();
+                match c.bump() {
+                    Some(m @ 'a'..='z') => match self.buf.mark(m as u8) {
+                        Some(n) => Some(n as isize),
+                        None => return err("invalid address"),
+                    },
+                    _ => return err("invalid mark character"),
+                }
+            }
+            Some(delim @ ('/' | '?')) => {
+                c.bump();
+                let (src, _) = c.regex(delim);
+                let re = self.regex(&src)?;
+                Some(self.search(&re, delim == '/')? as isize)
+            }
+            _ => None,
+        };
+
+        loop {
+            let save = c.pos;
+            c.skip_blanks();
+            match c.peek() {
+                Some(sign @ ('+' | '-' | '^')) => {
+                    c.bump();
+                    let n = match c.number() {
+                        Some(n) => n? as isize,
+                        None => 1,
+                    };
+                    let base = addr.unwrap_or(cur);
+                    addr = Some(if sign == '+' { base + n } else { base - n });
+                }
+                // an address followed by a number adds it
+                Some('0'..='9') if addr.is_some() => {
+                    let n = c.number().unwrap()? as isize;
+                    addr = Some(addr.unwrap() + n);
+                }
+                _ => {
+                    c.pos = save;
+                    break;
+                }
+            }
+        }
+
+        match addr {
+            Some(n) if n < 0 || n > self.buf.len() as isize => err("invalid address"),
+            Some(n) => Ok(Some(n as usize)),
+            None => {
+                c.pos = start;
+                Ok(None)
+            }
+        }
+    }
+
+    /// The next line matching `re`, wrapping around the buffer.
+    fn search(&mut self, re: &Regex, forward: bool) -> EdResult<usize> {
+        let len = self.buf.len();
+        let mut n = self.buf.cur;
+        for _ in 0..len {
+            n = if forward {
+                n % len + 1
+            } else if n <= 1 {
+                len
+            } else {
+                n - 1
+            };
+            if re.is_match(self.buf.line(n)) {
+                return Ok(n);
+            }
+        }
+        err("no match")
+    }
+
+    fn regex(&mut self, src: &str) -> EdResult<Regex> {
+        if src.is_empty() {
+            return match &self.last_regex {
+                Some(re) => Ok(re.clone()),
+                None => err("no previous regular expression"),
+            };
+        }
+        let re = Regex::new(&bre_to_regex(src)).or(err("invalid regular expression"))?;
+        self.last_regex = Some(re.clone());
+        Ok(re)
+    }
+
+    /// The address of a command taking one, which may be 0.
+    fn default1(&self, range: &Range, default: usize) -> usize {
+        if range.count > 0 {
+            range.second
+        } else {
+            default
+        }
+    }
+
+    /// The addresses of a command taking two, which may not be 0.
+    fn default2(&self, range: &Range, first: usize, second: usize) -> EdResult<(usize, usize)> {
+        let (first, second) = if range.count > 0 {
+            (range.first, range.second)
+        } else {
+            (first, second)
+        };
+        if first == 0 || first > second || second > self.buf.len() {
+            return err("invalid address");
+        }
+        Ok((first, second))
+    }
+
+    fn no_address(&self, range: &Range) -> EdResult<()> {
+        if range.count > 0 {
+            return err("unexpected address");
+        }
+        Ok(())
+    }
+
+    //
+    // command suffixes and operands
+    //
+
+    /// The print commands that may follow a command.
+    fn suffix(&self, c: &mut Cursor) -> EdResult<Option<char>> {
+        let mut print = None;
+        while let Some(ch @ ('l' | 'n' | 'p')) = c.peek() {
+            c.bump();
+            print = Some(ch);
+        }
+        self.suffix_end(c)?;
+        Ok(print)
+    }
+
+    fn suffix_end(&self, c: &mut Cursor) -> EdResult<()> {
+        if !c.at_end() {
+            return err("invalid command suffix");
+        }
+        Ok(())
+    }
+
+    fn print_suffix(&mut self, print: Option<char>) -> EdResult<()> {
+        match print {
+            Some(ch) if self.buf.cur > 0 => self.print(self.buf.cur, self.buf.cur, ch),
+            _ => Ok(()),
+        }
+    }
+
+    /// The file name after e, f, r or w, if any.
+    fn file_operand(&self, c: &mut Cursor) -> EdResult<Option<String>> {
+        if !c.at_end() && !matches!(c.peek(), Some(' ' | '\t')) {
+            return err("invalid command suffix");
+        }
+        c.skip_blanks();
+        let name = c.rest();
+        if name.is_empty() {
+            return Ok(None);
+        }
+        self.check_filename(&name)?;
+        Ok(Some(name))
+    }
+
+    /// red may not run commands or reach outside the current directory.
+    fn check_filename(&self, name: &str) -> EdResult<()> {
+        if self.opts.restricted && (name.starts_with('!') || name.contains('/') || name == "..") {
+            return err("restricted file name");
+        }
+        Ok(())
+    }
+
+    fn filename_or_default(&self, name: Option<String>) -> EdResult<String> {
+        match name.or_else(|| self.filename.clone()) {
+            Some(name) => Ok(name),
+            None => err("no current filename"),
+        }
+    }
+
+    /// Expand the command of a `!` command: `%` is the file name and a
+    /// leading `!` the previous command.
+    fn shell_command(&mut self, text: &str) -> EdResult<String> {
+        if self.opts.restricted {
+            return err("shell access restricted");
+        }
+
+        let mut command = String::new();
+        let mut expanded = false;
+        let mut chars = text.chars().peekable();
+        if chars.peek() == Some(&'!') {
+            chars.next();
+            match &self.last_shell {
+                Some(prev) => command.push_str(prev),
+                None => return err("no previous command"),
+            }
+            expanded = true;
+        }
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' if chars.peek() == Some(&'%') => command.push(chars.next().unwrap()),
+                '%' => match &self.filename {
+                    Some(name) => {
+                        command.push_str(name);
+                        expanded = true;
+                    }
+                    None => return err("no current filename"),
+                },
+                _ => command.push(ch),
+            }
+        }
+
+        if expanded {
+            self.write_out(command.as_bytes())?;
+        }
+        self.last_shell = Some(command.clone());
+        Ok(command)
+    }
+
+    //
+    // changing the buffer
+    //
+
+    /// Lines of text for a, c or i, up to a line containing only '.'.
+    fn read_text(&mut self) -> Vec<Line> {
+        let mut lines = Vec::new();
+        while let Some(line) = self.read_line() {
+            if line == b"." {
+                break;
+            }
+            lines.push(Rc::new(line));
+        }
+        lines
+    }
+
+    fn append(&mut self, after: usize, lines: Vec<Line>) {
+        if lines.is_empty() {
+            self.buf.cur = after;
+            return;
+        }
+        self.buf.cur = self.buf.insert(after, lines);
+        self.touch();
+    }
+
+    fn delete(&mut self, first: usize, second: usize) {
+        self.buf.delete(first, second);
+        self.buf.cur = if first <= self.buf.len() {
+            first
+        } else {
+            self.buf.len()
+        };
+        self.touch();
+    }
+
+    fn move_lines(&mut self, first: usize, second: usize, dest: usize) -> EdResult<()> {
+        if dest >= first && dest < second {
+            return err("invalid destination");
+        }
+        let count = second - first + 1;
+        let lines = self.buf.delete(first, second);
+        let dest = if dest > second { dest - count } else { dest };
+        self.buf.cur = self.buf.insert(dest, lines);
+        self.touch();
+        Ok(())
+    }
+
+    fn global(
+        &mut self,
+        c: &mut Cursor,
+        first: usize,
+        second: usize,
+        invert: bool,
+        interactive: bool,
+    ) -> EdResult<Flow> {
+        if self.in_global {
+            return err("cannot nest global commands");
+        }
+        let delim = match c.bump() {
+            Some(ch) if ch != ' ' && ch != '\n' => ch,
+            _ => return err("invalid pattern delimiter"),
+        };
+        let (src, _) = c.regex(delim);
+        let re = self.regex(&src)?;
+
+        // the command list continues over lines ending in a backslash
+        let mut list = c.rest().into_bytes();
+        while list.ends_with(b"\\") {
+            list.pop();
+            list.push(b'\n');
+            match self.read_input() {
+                Some(line) => list.extend_from_slice(&line),
+                None => break,
+            }
+        }
+        let mut commands: Vec<Vec<u8>> = list.split(|&b| b == b'\n').map(|l| l.to_vec()).collect();
+        if interactive && !commands.iter().all(|l| l.is_empty()) {
+            return err("invalid command suffix");
+        }
+        if commands == [Vec::<u8>::new()] {
+            commands = vec![b"p".to_vec()];
+        }
+
+        let selected: Vec<Line> = (first..=second)
+            .filter(|&n| re.is_match(self.buf.line(n)) != invert)
+            .map(|n| self.buf.handle(n))
+            .collect();
+
+        self.in_global = true;
+        let res = self.run_global(&selected, first, commands, interactive);
+        self.in_global = false;
+        self.pending.clear();
+        res
+    }
+
+    fn run_global(
+        &mut self,
+        selected: &[Line],
+        mut hint: usize,
+        commands: Vec<Vec<u8>>,
+        interactive: bool,
+    ) -> EdResult<Flow> {
+        let mut previous: Option<Vec<Vec<u8>>> = None;
+
+        for line in selected {
+            if INTERRUPTED.load(Ordering::SeqCst) {
+                return err("interrupt");
+            }
+            // lines deleted by an earlier command are skipped
+            let Some(n) = self.buf.find_near(line, hint) else {
+                continue;
+            };
+            hint = n;
+            self.buf.cur = n;
+
+            let list = if interactive {
+                self.print(n, n, 'p')?;
+                let Some(input) = self.read_input() else {
+                    break;
+                };
+                match input.as_slice() {
+                    b"" => continue,
+                    b"&" => match &previous {
+                        Some(list) => list.clone(),
+                        None => return err("no previous command"),
+                    },
+                    _ => {
+                        previous = Some(vec![input.clone()]);
+                        vec![input]
+                    }
+                }
+            } else {
+                commands.clone()
+            };
+
+            self.pending = list.into();
+            while let Some(cmd) = self.pending.pop_front() {
+                if self.exec_command(&String::from_utf8_lossy(&cmd))? == Flow::Quit {
+                    return Ok(Flow::Quit);
+                }
+            }
+        }
+
+        Ok(Flow::Continue)
+    }
+
+    fn substitute(&mut self, c: &mut Cursor, first: usize, second: usize) -> EdResult<()> {
+        let delim = match c.bump() {
+            Some(ch) if ch != ' ' && ch != '\n' => ch,
+            _ => return err("invalid pattern delimiter"),
+        };
+        let (src, end) = c.regex(delim);
+        if end != Delimited::Closed {
+            return err("missing pattern delimiter");
+        }
+        let re = self.regex(&src)?;
+
+        // a replacement may continue over lines ending in a backslash
+        let start = c.pos;
+        let (repl, end) = loop {
+            match c.replacement(delim) {
+                (_, Delimited::Continued) => match self.read_line() {
+                    Some(line) => {
+                        c.push_line(&String::from_utf8_lossy(&line));
+                        c.pos = start;
+                    }
+                    None => return err("unexpected end of file"),
+                },
+                res => break res,
+            }
+        };
+        let template = if repl == "%" {
+            match &self.last_replacement {
+                Some(t) => t.clone(),
+                None => return err("no previous substitution"),
+            }
+        } else {
+            parse_replacement(&repl)
+        };
+        self.last_replacement = Some(template.clone());
+
+        // with the last delimiter left out, the line is printed
+        let mut global = false;
+        let mut nth = 1;
+        let mut print = if end == Delimited::Open {
+            Some('p')
+        } else {
+            None
+        };
+        loop {
+            match c.peek() {
+                Some('g') => global = true,
+                Some(ch @ ('l' | 'n' | 'p')) => print = Some(ch),
+                Some('0'..='9') => {
+                    nth = c.number().unwrap()?;
+                    if nth == 0 {
+                        return err("invalid command suffix");
+                    }
+                    continue;
+                }
+                Some(_) => return err("invalid command suffix"),
+                None => break,
+            }
+            c.bump();
+        }
+
+        let mut last = None;
+        let mut n = first;
+        let mut second = second;
+        while n <= second {
+            if INTERRUPTED.load(Ordering::SeqCst) {
+                return err("interrupt");
+            }
+            if let Some(text) = replace(&re, self.buf.line(n), &template, global, nth) {
+                // a replaced newline splits the line
+                let mut pieces = text.split(|&b| b == b'\n');
+                self.buf.replace(n, pieces.next().unwrap().to_vec());
+                let rest: Vec<Line> = pieces.map(|p| Rc::new(p.to_vec())).collect();
+                let extra = rest.len();
+                self.buf.insert(n, rest);
+                n += extra;
+                second += extra;
+                last = Some(n);
+            }
+            n += 1;
+        }
+
+        match last {
+            Some(n) => {
+                self.buf.cur = n;
+                self.touch();
+                self.print_suffix(print)
+            }
+            // in a global command, lines without a match are passed over
+            None if self.in_global => Ok(()),
+            None => err("no match"),
+        }
+    }
+
+    //
+    // files
+    //
+
+    /// Replace the buffer with a file.
+    fn edit(&mut self, name: &str) -> EdResult<()> {
+        self.buf = Buffer::default();
+        self.modified = false;
+        let (lines, size) = self.read_file(name)?;
+        self.buf.insert(0, lines);
+        self.buf.cur = self.buf.len();
+        self.print_size(size)
+    }
+
+    /// The lines of a file, or of the output of a command, and their size.
+    fn read_file(&mut self, name: &str) -> EdResult<(Vec<Line>, usize)> {
+        let data = match name.strip_prefix('!') {
+            Some(command) => {
+                let command = self.shell_command(command)?;
+                let _ = self.out.flush();
+                let output = Command::new("sh")
+                    .arg("-c")
+                    .arg(&command)
+                    .stderr(Stdio::inherit())
+                    .output()
+                    .map_err(|e| e.to_string())?;
+                output.stdout
+            }
+            None => fs::read(name).map_err(|e| format!("{}: {}", name, e))?,
+        };
+
+        let mut lines: Vec<Line> = data
+            .split(|&b| b == b'\n')
+            .map(|l| Rc::new(l.to_vec()))
+            .collect();
+        // the empty piece after the last newline, or a missing newline
+        // that is supplied
+        if data.is_empty() || data.ends_with(b"\n") {
+            lines.pop();
+        }
+        Ok((lines, data.len()))
+    }
+
+    fn text_of(&self, first: usize, second: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        for n in first..=second {
+            data.extend_from_slice(self.buf.line(n));
+            data.push(b'\n');
+        }
+        data
+    }
+
+    /// Write lines to a file, or to the input of a command.
+    fn write_file(&mut self, name: &str, first: usize, second: usize) -> EdResult<usize> {
+        let data = self.text_of(first, second);
+
+        match name.strip_prefix('!') {
+            Some(command) => {
+                let command = self.shell_command(command)?;
+                let _ = self.out.flush();
+                let mut child = Command::new("sh")
+                    .arg("-c")
+                    .arg(&command)
+                    .stdin(Stdio::piped())
+                    .spawn()
+                    .map_err(|e| e.to_string())?;
+                // the command need not read all of it
+                let _ = child.stdin.take().unwrap().write_all(&data);
+                child.wait().map_err(|e| e.to_string())?;
+            }
+            None => fs::write(name, &data).map_err(|e| format!("{}: {}", name, e))?,
+        }
+        Ok(data.len())
+    }
+
+    //
+    // output
+    //
+
+    fn print(&mut self, first: usize, second: usize, how: char) -> EdResult<()> {
+        for n in first..=second {
+            let text = match how {
+                'l' => list_line(self.buf.line(n)),
+                'n' => {
+                    let mut text = format!("{}\t", n).into_bytes();
+                    text.extend_from_slice(self.buf.line(n));
+                    text
+                }
+                _ => self.buf.line(n).to_vec(),
+            };
+            self.write_out(&text)?;
+        }
+        self.buf.cur = second;
+        Ok(())
+    }
+}
+
+fn parse_replacement(repl: &str) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut text = Vec::new();
+    let mut chars = repl.chars();
+
+    while let Some(ch) = chars.next() {
+        let piece = match ch {
+            '&' => Piece::Match,
+            '\\' => match chars.next() {
+                Some(d @ '1'..='9') => Piece::Group(d as usize - '0' as usize),
+                Some(c) => {
+                    let mut buf = [0; 4];
+                    text.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                    continue;
+                }
+                None => {
+                    text.push(b'\\');
+                    continue;
+                }
+            },
+            _ => {
+                let mut buf = [0; 4];
+                text.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                continue;
+            }
+        };
+        if !text.is_empty() {
+            pieces.push(Piece::Text(std::mem::take(&mut text)));
+        }
+        pieces.push(piece);
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    pieces
+}
+
+/// Replace the `nth` match of `re` in `line`, or with `global` that and
+/// all later ones. None if nothing was replaced.
+fn replace(
+    re: &Regex,
+    line: &[u8],
+    template: &[Piece],
+    global: bool,
+    nth: usize,
+) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut last = 0;
+    let mut replaced = false;
+
+    for (i, caps) in re.captures_iter(line).enumerate() {
+        if i + 1 < nth {
+            continue;
+        }
+        let m = caps.get(0).unwrap();
+        out.extend_from_slice(&line[last..m.start()]);
+        for piece in template {
+            match piece {
+                Piece::Text(text) => out.extend_from_slice(text),
+                Piece::Match => out.extend_from_slice(m.as_bytes()),
+                Piece::Group(g) => {
+                    if let Some(group) = caps.get(*g) {
+                        out.extend_from_slice(group.as_bytes());
+                    }
+                }
+            }
+        }
+        last = m.end();
+        replaced = true;
+        if !global {
+            break;
+        }
+    }
+
+    if !replaced {
+        return None;
+    }
+    out.extend_from_slice(&line[last..]);
+    Some(out)
+}
+
+/// A line written unambiguously, as by the l command.
+fn list_line(line: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut width = 0;
+
+    for &byte in line {
+        let escaped = match byte {
+            b'\\' => b"\\\\".to_vec(),
+            b'$' => b"\\$".to_vec(),
+            0x07 => b"\\a".to_vec(),
+            0x08 => b"\\b".to_vec(),
+            0x0c => b"\\f".to_vec(),
+            b'\r' => b"\\r".to_vec(),
+            b'\t' => b"\\t".to_vec(),
+            0x0b => b"\\v".to_vec(),
+            0x20..=0x7e => vec![byte],
+            _ => format!("\\{:03o}", byte).into_bytes(),
+        };
+        // long lines are folded, with a backslash marking each fold
+        if width + escaped.len() > LIST_WIDTH - 1 {
+            out.extend_from_slice(b"\\\n");
+            width = 0;
+        }
+        width += escaped.len();
+        out.extend_from_slice(&escaped);
+    }
+
+    out.push(b'$');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace() {
+        let re = Regex::new("b").unwrap();
+        let template = parse_replacement(r"<&>");
+        assert_eq!(
+            replace(&re, b"abcb", &template, false, 1).unwrap(),
+            b"a<b>cb"
+        );
+        assert_eq!(
+            replace(&re, b"abcb", &template, true, 1).unwrap(),
+            b"a<b>c<b>"
+        );
+        assert_eq!(
+            replace(&re, b"abcb", &template, false, 2).unwrap(),
+            b"abc<b>"
+        );
+        assert!(replace(&re, b"abcb", &template, false, 3).is_none());
+
+        let re = Regex::new(&bre_to_regex(r"\(a\)\(b\)")).unwrap();
+        let template = parse_replacement(r"\2\1\&");
+        assert_eq!(replace(&re, b"xab", &template, false, 1).unwrap(), b"xba&");
+    }
+
+    #[test]
+    fn test_list_line() {
+        assert_eq!(list_line(b"a\tb$\\"), b"a\\tb\\$\\\\$");
+        assert_eq!(list_line(b"\x01\xff"), b"\\001\\377$");
+        let long = vec![b'x'; 100];
+        let listed = list_line(&long);
+        assert_eq!(&listed[71..73], b"\\\n");
+    }
+}
@@ -0,0 +1,93 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+pub(crate) mod buffer;
+pub(crate) mod cursor;
+pub(crate) mod editor;
+
+use clap::Parser;
+use editor::{Editor, Options, HANGUP, INTERRUPTED};
+use std::io::{self, BufReader};
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
+
+/// ed - edit text
+#[derive(Parser)]
+#[command(author, version, about, long_about)]
+struct Args {
+    /// Use this string as the prompt in command mode
+    #[arg(short, long)]
+    prompt: Option<String>,
+
+    /// Suppress byte counts, diagnostics and the '!' prompt
+    #[arg(short, long)]
+    silent: bool,
+
+    /// The file to edit
+    file: Option<String>,
+}
+
+extern "C" fn interrupt_handler(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+    let msg = b"\n?\n";
+    unsafe {
+        libc::write(
+            libc::STDOUT_FILENO,
+            msg.as_ptr() as *const libc::c_void,
+            msg.len(),
+        );
+    }
+}
+
+extern "C" fn hangup_handler(_: libc::c_int) {
+    HANGUP.store(true, Ordering::SeqCst);
+    // end the read of the next command, so the buffer is saved
+    unsafe {
+        libc::close(libc::STDIN_FILENO);
+    }
+}
+
+fn stdin_is_file() -> bool {
+    let mut st: libc::stat = unsafe { std::mem::zeroed() };
+    unsafe {
+        libc::fstat(libc::STDIN_FILENO, &mut st) == 0
+            && (st.st_mode & libc::S_IFMT) == libc::S_IFREG
+    }
+}
+
+/// Run ed, or red when `restricted` is set.
+pub(crate) fn ed_main(restricted: bool) -> ExitCode {
+    let args = Args::parse();
+
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            interrupt_handler as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+        libc::signal(
+            libc::SIGHUP,
+            hangup_handler as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+        libc::signal(libc::SIGQUIT, libc::SIG_IGN);
+    }
+
+    let opts = Options {
+        prompt: args.prompt,
+        silent: args.silent,
+        restricted,
+        script: stdin_is_file(),
+    };
+    let input = Box::new(BufReader::new(io::stdin()));
+    let mut editor = Editor::new(opts, input);
+
+    match editor.run(args.file.as_deref()) {
+        0 => ExitCode::SUCCESS,
+        _ => ExitCode::FAILURE,
+    }
+}
@@ -12,8 +12,10 @@ extern crate plib;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::io::StdoutWriter;
+use plib::tabs::TabStops;
 use plib::PROJECT_NAME;
-use std::io::{self, BufWriter, Read, Write};
+use std::io::{self, Read, Write};
 use std::path::PathBuf;
 
 /// expand - convert tabs to spaces
@@ -28,53 +30,13 @@ struct Args {
     files: Vec<PathBuf>,
 }
 
-enum TabList {
-    UniStop(usize),
-    Stops(Vec<usize>),
-}
-
-fn parse_tablist(tablist: &str) -> Result<TabList, &'static str> {
-    let res = tablist.parse::<usize>();
-    if let Ok(tab) = res {
-        return Ok(TabList::UniStop(tab));
-    }
-
-    let mut v = Vec::new();
-    for token in tablist.split(&[' ', ','][..]) {
-        let n = match token.parse::<usize>() {
-            Ok(val) => val,
-            Err(_e) => return Err("Invalid tab stop in list"),
-        };
-
-        if !v.is_empty() {
-            let last = *v.iter().last().unwrap();
-            if n <= last {
-                return Err("Invalid tab stop order in list");
-            }
-        }
-
-        v.push(n);
-    }
-
-    Ok(TabList::Stops(v))
-}
-
-fn space_out(column: &mut usize, writer: &mut BufWriter<dyn Write>) -> io::Result<()> {
-    *column = *column + 1;
-
-    writer.write_all(b" ")?;
-
-    Ok(())
-}
-
-fn expand_file(tablist: &TabList, pathname: &PathBuf) -> io::Result<()> {
+fn expand_file(tabstops: &TabStops, out: &mut StdoutWriter, pathname: &PathBuf) -> io::Result<()> {
     // open file, or stdin
-    let mut file = plib::io::input_stream(pathname, false)?;
+    let dashed_stdin = pathname.as_os_str() == "-";
+    let mut file = plib::io::input_stream(pathname, dashed_stdin)?;
 
     let mut raw_buffer = [0; plib::BUFSZ];
-    let mut writer = BufWriter::new(io::stdout());
-    let mut column: usize = 1;
-    let mut cur_stop = 0;
+    let mut column: usize = 0;
 
     loop {
         // read a chunk of file data
@@ -86,49 +48,33 @@ fn expand_file(tablist: &TabList, pathname: &PathBuf) -> io::Result<()> {
         // slice of buffer containing file data
         let buf = &raw_buffer[0..n_read];
 
-        for byte_ref in buf {
-            let byte = *byte_ref;
-            if byte == 0x8 {
+        for &byte in buf {
+            match byte {
+                b'\t' => {
+                    // past the last stop of a list, a tab becomes one space
+                    let stop = tabstops.next(column).unwrap_or(column + 1);
+                    for _ in column..stop {
+                        out.write_all(b" ")?;
+                    }
+                    column = stop;
+                }
                 // backspace
-                writer.write_all(&[byte])?;
-                if column > 1 {
-                    column = column - 1;
+                0x8 => {
+                    out.write_all(&[byte])?;
+                    column = column.saturating_sub(1);
                 }
-            } else if byte == '\r' as u8 || byte == '\n' as u8 {
-                writer.write_all(&[byte])?;
-                column = 1;
-            } else if byte != '\t' as u8 {
-                writer.write_all(&[byte])?;
-                column = column + 1;
-            } else {
-                match tablist {
-                    TabList::UniStop(n) => {
-                        while (column % n) != 0 {
-                            space_out(&mut column, &mut writer)?;
-                        }
-                        space_out(&mut column, &mut writer)?;
-                    }
-                    TabList::Stops(tabvec) => {
-                        let last_tab: usize = tabvec[tabvec.len() - 1];
-                        let next_tab = tabvec[cur_stop];
-
-                        if column >= last_tab {
-                            space_out(&mut column, &mut writer)?;
-                        } else {
-                            while column < next_tab {
-                                space_out(&mut column, &mut writer)?;
-                            }
-                            cur_stop = cur_stop + 1;
-                            space_out(&mut column, &mut writer)?;
-                        }
-                    }
+                b'\r' | b'\n' => {
+                    out.write_all(&[byte])?;
+                    column = 0;
+                }
+                _ => {
+                    out.write_all(&[byte])?;
+                    column += 1;
                 }
             }
         }
     }
 
-    writer.flush()?;
-
     Ok(())
 }
 
@@ -140,18 +86,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     textdomain(PROJECT_NAME)?;
     bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
 
-    let tablist = {
-        if let Some(ref tablist) = args.tablist {
-            match parse_tablist(&tablist) {
-                Ok(tl) => tl,
-                Err(e) => {
-                    eprintln!("{}", e);
-                    std::process::exit(1);
-                }
-            }
-        } else {
-            TabList::UniStop(8)
+    let tabstops = match args.tablist.as_deref().map(TabStops::parse) {
+        Some(Ok(tabstops)) => tabstops,
+        Some(Err(e)) => {
+            eprintln!("expand: {}", e);
+            std::process::exit(1);
         }
+        None => TabStops::default(),
     };
 
     // if no files, read from stdin
@@ -160,13 +101,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let mut exit_code = 0;
+    let mut out = StdoutWriter::new();
 
     for filename in &args.files {
-        if let Err(e) = expand_file(&tablist, filename) {
+        if let Err(e) = expand_file(&tabstops, &mut out, filename) {
             exit_code = 1;
-            eprintln!("{}: {}", filename.display(), e);
+            eprintln!("expand: {}: {}", filename.display(), e);
         }
     }
 
+    if let Err(e) = out.flush() {
+        eprintln!("expand: {}", e);
+        exit_code = 1;
+    }
+
     std::process::exit(exit_code)
 }
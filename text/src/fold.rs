@@ -6,16 +6,13 @@
 // file in the root directory of this project.
 // SPDX-License-Identifier: MIT
 //
-// TODO:
-// - integration tests
-// - verify break-with-spaces correctness
-//
 
 extern crate clap;
 extern crate plib;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::io::StdoutWriter;
 use plib::PROJECT_NAME;
 use std::io::{self, Read, Write};
 use std::path::PathBuf;
@@ -61,56 +58,68 @@ impl OutputState {
         self.data.push(byte);
     }
 
-    fn incr_column(&mut self, ch: char) {
+    /// The column after writing `byte` at `column`.
+    fn next_column(&self, column: usize, byte: u8) -> usize {
         if self.args.bytes {
-            self.column += 1;
-        } else {
-            match ch {
-                '\x08' => {
-                    if self.column > 0 {
-                        self.column -= 1;
-                    }
-                }
-                '\t' => {
-                    self.column += TABSTOP - (self.column % TABSTOP);
-                }
-                '\r' => {
-                    self.column = 0;
-                }
-                _ => {
-                    self.column += 1;
-                }
-            }
+            return column + 1;
+        }
+
+        match byte {
+            b'\x08' => column.saturating_sub(1),
+            b'\t' => column + TABSTOP - (column % TABSTOP),
+            b'\r' => 0,
+            // the rest of a UTF-8 sequence takes no further column
+            0x80..=0xbf => column,
+            _ => column + 1,
         }
     }
 
-    fn write_line(&mut self) -> io::Result<()> {
-        io::stdout().write_all(&self.data)?;
+    fn write_line(&mut self, out: &mut StdoutWriter) -> io::Result<()> {
+        out.write_all(&self.data)?;
 
         self.column = 0;
         self.data.clear();
 
         Ok(())
     }
-}
 
-fn find_last_blank(v: &Vec<u8>) -> Option<usize> {
-    for (pos, chv) in v.iter().rev().enumerate() {
-        let ch = *chv as char;
-        if ch.is_whitespace() {
-            return Some(pos as usize);
+    /// Ends the current output line, because `byte` would not fit on it.
+    ///
+    /// With -s, the line is broken after its last blank, and what follows
+    /// the blank starts the next line.
+    fn break_line(&mut self, out: &mut StdoutWriter) -> io::Result<()> {
+        let blank = match self.args.spaces {
+            true => self.data.iter().rposition(|&b| b == b' ' || b == b'\t'),
+            false => None,
+        };
+
+        match blank {
+            Some(pos) => {
+                let spill = self.data.split_off(pos + 1);
+                self.push(b'\n');
+                self.write_line(out)?;
+                for &byte in &spill {
+                    self.column = self.next_column(self.column, byte);
+                }
+                self.data = spill;
+            }
+            None => {
+                self.push(b'\n');
+                self.write_line(out)?;
+            }
         }
-    }
 
-    return None;
+        Ok(())
+    }
 }
 
-fn fold_file(args: &Args, pathname: &PathBuf) -> io::Result<()> {
+fn fold_file(args: &Args, out: &mut StdoutWriter, pathname: &PathBuf) -> io::Result<()> {
     // open file, or stdin
     let mut file = plib::io::input_stream(pathname, false)?;
 
     let mut raw_buffer = [0; plib::BUFSZ];
     let mut state = OutputState::new(args);
+    let width = args.width as usize;
 
     loop {
         // read a chunk of file data
@@ -122,53 +131,31 @@ fn fold_file(args: &Args, pathname: &PathBuf) -> io::Result<()> {
         // slice of buffer containing file data
         let buf = &raw_buffer[0..n_read];
 
-        // loop for each character in buffer, which may include partial lines
-        for chv in buf {
-            let ch = *chv as char;
-
-            if ch == '\n' {
-                state.push(*chv);
-                state.write_line()?;
+        // loop for each byte in buffer, which may include partial lines
+        for &byte in buf {
+            if byte == b'\n' {
+                state.push(byte);
+                state.write_line(out)?;
                 continue;
             }
 
-            loop {
-                state.incr_column(ch);
-
-                if state.column <= args.width as usize {
-                    state.push(*chv);
-                    break;
-                }
-
-                if args.spaces {
-                    if let Some(blankpos) = find_last_blank(&state.data) {
-                        let mut spill: Vec<u8> = Vec::new();
-                        let rhs = &state.data[blankpos + 1..];
-                        spill.extend_from_slice(rhs);
-                        state.data.truncate(blankpos + 1);
-                        state.push('\n' as u8);
-                        state.write_line()?;
-                        for dchv in &spill {
-                            let dch = *dchv as char;
-                            state.incr_column(dch);
-                        }
-                        state.data = spill;
-                        continue;
-                    }
+            // a line that is too long is broken before this byte, unless
+            // the byte alone is wider than the line
+            if !state.data.is_empty() && state.next_column(state.column, byte) > width {
+                state.break_line(out)?;
+                if !state.data.is_empty() && state.next_column(state.column, byte) > width {
+                    state.push(b'\n');
+                    state.write_line(out)?;
                 }
-
-                if state.data.is_empty() {
-                    state.push(*chv);
-                    break;
-                }
-
-                state.push('\n' as u8);
-                state.write_line()?;
             }
+
+            state.column = state.next_column(state.column, byte);
+            state.push(byte);
         }
     }
 
-    Ok(())
+    // the last line need not end with a newline
+    state.write_line(out)
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -185,13 +172,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let mut exit_code = 0;
+    let mut out = StdoutWriter::new();
 
     for filename in &args.files {
-        if let Err(e) = fold_file(&args, filename) {
+        if let Err(e) = fold_file(&args, &mut out, filename) {
             exit_code = 1;
             eprintln!("{}: {}", filename.display(), e);
         }
     }
 
+    if let Err(e) = out.flush() {
+        exit_code = 1;
+        eprintln!("fold: {}", e);
+    }
+
     std::process::exit(exit_code)
 }
@@ -12,6 +12,7 @@ extern crate plib;
 
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::io::StdoutWriter;
 use plib::PROJECT_NAME;
 use std::io::{self, Read, Write};
 use std::path::PathBuf;
@@ -24,27 +25,25 @@ struct Args {
     #[arg(short, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
     n: u64,
 
+    /// The first <N> bytes of each input file shall be copied to standard output.
+    #[arg(short = 'c', conflicts_with = "n", value_parser = clap::value_parser!(u64).range(1..))]
+    bytes: Option<u64>,
+
+    /// Never print headers giving file names.
+    #[arg(short, long)]
+    quiet: bool,
+
     /// Files to read as input.
     files: Vec<PathBuf>,
 }
 
-fn head_file(args: &Args, pathname: &PathBuf, first: bool, want_header: bool) -> io::Result<()> {
-    // print file header
-    if want_header {
-        if first {
-            println!("==> {} <==\n", pathname.display());
-        } else {
-            println!("\n==> {} <==\n", pathname.display());
-        }
-    }
-
-    // open file, or stdin
-    let mut file = plib::io::input_stream(pathname, false)?;
-
+// Copy from `file` until `limit` units have been written, where a unit
+// is a byte, or a line when `lines` is set.
+fn copy_head(file: &mut dyn Read, out: &mut dyn Write, limit: u64, lines: bool) -> io::Result<()> {
     let mut raw_buffer = [0; plib::BUFSZ];
-    let mut nl = 0;
+    let mut seen: u64 = 0;
 
-    loop {
+    while seen < limit {
         // read a chunk of file data
         let n_read = file.read(&mut raw_buffer[..])?;
         if n_read == 0 {
@@ -53,34 +52,61 @@ fn head_file(args: &Args, pathname: &PathBuf, first: bool, want_header: bool) ->
 
         // slice of buffer containing file data
         let buf = &raw_buffer[0..n_read];
-        let mut pos = 0;
-
-        // count newlines
-        for chv in buf {
-            // LF character encountered
-            if *chv == 10 {
-                nl = nl + 1;
-            }
-
-            pos = pos + 1;
 
-            // if user-specified limit reached, stop
-            if nl >= args.n {
-                break;
+        let pos = if lines {
+            // count newlines, stopping just after the last one wanted
+            let mut pos = buf.len();
+            for (i, chv) in buf.iter().enumerate() {
+                if *chv == b'\n' {
+                    seen += 1;
+                    if seen >= limit {
+                        pos = i + 1;
+                        break;
+                    }
+                }
             }
-        }
+            pos
+        } else {
+            let pos = std::cmp::min(buf.len() as u64, limit - seen) as usize;
+            seen += pos as u64;
+            pos
+        };
 
         // output full or partial buffer
-        let final_buf = &raw_buffer[0..pos];
-        io::stdout().write_all(final_buf)?;
+        out.write_all(&buf[..pos])?;
+    }
 
-        // if user-specified limit reached, stop
-        if nl >= args.n {
-            break;
+    Ok(())
+}
+
+fn head_file(
+    args: &Args,
+    out: &mut StdoutWriter,
+    pathname: &PathBuf,
+    first: bool,
+    want_header: bool,
+) -> io::Result<()> {
+    // print file header
+    if want_header {
+        let name = if pathname.as_os_str() == "-" {
+            "standard input".into()
+        } else {
+            pathname.display().to_string()
+        };
+        if first {
+            writeln!(out, "==> {} <==", name)?;
+        } else {
+            writeln!(out, "\n==> {} <==", name)?;
         }
     }
 
-    Ok(())
+    // open file, or stdin
+    let mut file = plib::io::input_stream(pathname, true)?;
+
+    match args.bytes {
+        Some(bytes) => copy_head(&mut file, out, bytes, false),
+        None => copy_head(&mut file, out, args.n, true),
+    }
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -93,15 +119,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // if no files, read from stdin
     if args.files.is_empty() {
-        args.files.push(PathBuf::new());
+        args.files.push(PathBuf::from("-"));
     }
 
     let mut exit_code = 0;
-    let want_header = args.files.len() > 1;
+    let want_header = args.files.len() > 1 && !args.quiet;
     let mut first = true;
+    let mut out = StdoutWriter::new();
 
     for filename in &args.files {
-        if let Err(e) = head_file(&args, filename, first, want_header) {
+        if let Err(e) = head_file(&args, &mut out, filename, first, want_header) {
             exit_code = 1;
             eprintln!("{}: {}", filename.display(), e);
         }
@@ -109,5 +136,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         first = false;
     }
 
+    if let Err(e) = out.flush() {
+        exit_code = 1;
+        eprintln!("head: {}", e);
+    }
+
     std::process::exit(exit_code)
 }
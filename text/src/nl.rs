@@ -8,11 +8,13 @@
 //
 
 use clap::{Parser, ValueEnum};
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
+use plib::io::StdoutWriter;
+use plib::regex::bre_to_regex;
 use plib::PROJECT_NAME;
-use regex::Regex;
+use regex::bytes::Regex;
 use std::fs;
-use std::io::{self, BufRead, Read};
+use std::io::{self, BufRead, Read, Write};
 use std::path::PathBuf;
 use std::process::ExitCode;
 use std::str::FromStr;
@@ -53,7 +55,7 @@ struct Args {
     header_numbering: LineNumberingStyle,
 
     /// Specify the increment value used to number logical page lines.
-    #[arg(short = 'i', long, default_value_t = 1)]
+    #[arg(short = 'i', long, default_value_t = 1, allow_negative_numbers = true)]
     line_increment: i64,
 
     /// Specify the number of blank lines to be considered as one. For example,
@@ -79,7 +81,7 @@ struct Args {
     number_separator: String,
 
     /// Specify the initial value used to number logical page lines.
-    #[arg(short = 'v', long, default_value_t = 1)]
+    #[arg(short = 'v', long, default_value_t = 1, allow_negative_numbers = true)]
     starting_line_number: i64,
 
     /// Specify the number of characters to be used for the line number.
@@ -111,7 +113,7 @@ impl FromStr for LineNumberingStyle {
             "n" => Ok(LineNumberingStyle::None),
             s => {
                 if let Some(re) = s.strip_prefix('p') {
-                    if let Ok(regexp) = Regex::new(re) {
+                    if let Ok(regexp) = Regex::new(&bre_to_regex(re)) {
                         return Ok(LineNumberingStyle::Regex(regexp));
                     } else {
                         return Err(format!("invalid regular expression: {re}"));
@@ -154,182 +156,208 @@ impl std::fmt::Display for NumberFormat {
     }
 }
 
-fn nl_main(args: &Args) -> io::Result<()> {
-    let readable: Box<dyn Read> = if let Some(path) = &args.file {
-        if path.as_os_str() == "-" {
-            Box::new(io::stdin().lock())
-        } else {
-            Box::new(fs::File::open(path)?)
-        }
-    } else {
-        Box::new(io::stdin().lock())
-    };
-    let mut reader = io::BufReader::new(readable);
+/// The sections of a logical page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    Header,
+    Body,
+    Footer,
+}
 
-    let delimiter_header: String = (0..3).map(|_| args.section_delimiter.as_str()).collect();
-    let delimiter_body: String = (0..2).map(|_| args.section_delimiter.as_str()).collect();
-    let delimiter_footer: String = args.section_delimiter.clone();
-
-    let mut line_buffer = String::new();
-    let mut line_number = args.starting_line_number;
-    let mut line_number_overflowed = false;
-    let mut current_numbering_style = &args.body_numbering;
-    let mut consecutive_blank_lines = 0;
-
-    // Need to pass `line_number` and `line_number_overflowed` as arguments since
-    // they are also being mutated outside the closure.
-    let print_line_number =
-        |line_number: &mut i64, line_number_overflowed: &mut bool| -> io::Result<()> {
-            if *line_number_overflowed {
-                return Err(io::Error::other("line number overflowed"));
-            }
+/// The delimiter lines that start each section of a logical page.
+struct Delimiters {
+    header: Vec<u8>,
+    body: Vec<u8>,
+    footer: Vec<u8>,
+}
 
-            match args.number_format {
-                NumberFormat::Ln => print!(
-                    "{:<width$}{}",
-                    line_number,
-                    args.number_separator,
-                    width = args.number_width as usize
-                ),
-                NumberFormat::Rn => print!(
-                    "{:>width$}{}",
-                    line_number,
-                    args.number_separator,
-                    width = args.number_width as usize
-                ),
-                NumberFormat::Rz => print!(
-                    "{:0>width$}{}",
-                    line_number,
-                    args.number_separator,
-                    width = args.number_width as usize
-                ),
-            }
-            if let Some(sum) = line_number.checked_add(args.line_increment) {
-                *line_number = sum;
-            } else {
-                *line_number_overflowed = true;
-            }
+impl Delimiters {
+    fn new(delimiter: &str) -> Delimiters {
+        Delimiters {
+            header: delimiter.repeat(3).into_bytes(),
+            body: delimiter.repeat(2).into_bytes(),
+            footer: delimiter.as_bytes().to_vec(),
+        }
+    }
 
-            Ok(())
-        };
+    fn section(&self, line: &[u8]) -> Option<Section> {
+        if line == self.header {
+            Some(Section::Header)
+        } else if line == self.body {
+            Some(Section::Body)
+        } else if line == self.footer {
+            Some(Section::Footer)
+        } else {
+            None
+        }
+    }
+}
 
-    let print_spacer = || {
-        print!(
-            "{:width$}",
-            "",
-            width = args.number_separator.len() + args.number_width as usize
-        );
-    };
+struct Numberer<'a> {
+    args: &'a Args,
+    line_number: i64,
+    overflowed: bool,
+    consecutive_blank_lines: i64,
+}
 
-    loop {
-        line_buffer.clear();
+impl Numberer<'_> {
+    fn write_number(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if self.overflowed {
+            return Err(io::Error::other("line number overflow"));
+        }
 
-        if let Ok(read_bytes) = reader.read_line(&mut line_buffer) {
-            // EOF
-            if read_bytes == 0 {
-                break;
-            }
+        let width = self.args.number_width as usize;
+        let n = self.line_number;
+        match self.args.number_format {
+            NumberFormat::Ln => write!(out, "{:<width$}", n)?,
+            NumberFormat::Rn => write!(out, "{:>width$}", n)?,
+            NumberFormat::Rz => write!(out, "{:0width$}", n)?,
+        }
+        out.write_all(self.args.number_separator.as_bytes())?;
 
-            // Removing the newline makes for easier checks but it has to be
-            // added back later
-            if line_buffer.ends_with('\n') {
-                line_buffer.pop();
-            }
+        match n.checked_add(self.args.line_increment) {
+            Some(sum) => self.line_number = sum,
+            None => self.overflowed = true,
+        }
+        Ok(())
+    }
 
-            // If we are in a header, body or footer
-            let mut non_text = false;
-
-            if line_buffer == delimiter_header {
-                non_text = true;
-                current_numbering_style = &args.header_numbering;
-            } else if line_buffer == delimiter_body {
-                non_text = true;
-                current_numbering_style = &args.body_numbering;
-            } else if line_buffer == delimiter_footer {
-                non_text = true;
-                current_numbering_style = &args.footer_numbering;
-            } else {
-                match current_numbering_style {
-                    LineNumberingStyle::All => {
-                        if args.join_blank_lines > 1 {
-                            if !line_buffer.is_empty() {
-                                print_line_number(&mut line_number, &mut line_number_overflowed)?;
-                                consecutive_blank_lines = 0;
-                            } else {
-                                consecutive_blank_lines += 1;
-                                if consecutive_blank_lines == args.join_blank_lines {
-                                    print_line_number(
-                                        &mut line_number,
-                                        &mut line_number_overflowed,
-                                    )?;
-                                    consecutive_blank_lines = 0;
-                                } else {
-                                    print_spacer();
-                                }
-                            }
-                        } else {
-                            print_line_number(&mut line_number, &mut line_number_overflowed)?;
-                        }
-                    }
-                    LineNumberingStyle::NonEmpty => {
-                        if !line_buffer.is_empty() {
-                            print_line_number(&mut line_number, &mut line_number_overflowed)?;
-                        } else {
-                            print_spacer();
-                        }
-                    }
-                    LineNumberingStyle::None => print_spacer(),
-                    LineNumberingStyle::Regex(regexp) => {
-                        if regexp.is_match(&line_buffer) {
-                            print_line_number(&mut line_number, &mut line_number_overflowed)?;
-                        } else {
-                            print_spacer();
-                        }
-                    }
-                }
+    /// Unnumbered lines are indented to line up with the numbered ones.
+    fn write_spacer(&self, out: &mut impl Write) -> io::Result<()> {
+        let width = self.args.number_width as usize + self.args.number_separator.chars().count();
+        write!(out, "{:width$}", "")
+    }
 
-                // Reference `nl` unconditionally adds a newline even on files
-                // not ending on a newline
-                line_buffer.push('\n');
-                print!("{}", &line_buffer);
-            }
+    fn start_section(&mut self) {
+        if !self.args.no_renumber {
+            self.line_number = self.args.starting_line_number;
+            self.overflowed = false;
+        }
+        self.consecutive_blank_lines = 0;
+    }
 
-            if non_text {
-                if !args.no_renumber {
-                    line_number = args.starting_line_number;
-                    line_number_overflowed = false;
+    fn number_line(
+        &mut self,
+        out: &mut impl Write,
+        style: &LineNumberingStyle,
+        line: &[u8],
+    ) -> io::Result<()> {
+        let numbered = match style {
+            LineNumberingStyle::All if line.is_empty() => {
+                // with -l, only every join_blank_lines'th adjacent blank
+                // line is numbered
+                self.consecutive_blank_lines += 1;
+                if self.consecutive_blank_lines == self.args.join_blank_lines {
+                    self.consecutive_blank_lines = 0;
+                    true
+                } else {
+                    false
                 }
-                println!("");
             }
+            LineNumberingStyle::All => true,
+            LineNumberingStyle::NonEmpty => !line.is_empty(),
+            LineNumberingStyle::None => false,
+            LineNumberingStyle::Regex(regexp) => regexp.is_match(line),
+        };
+        if !line.is_empty() {
+            self.consecutive_blank_lines = 0;
+        }
+
+        if numbered {
+            self.write_number(out)?;
         } else {
+            self.write_spacer(out)?;
+        }
+        out.write_all(line)?;
+        out.write_all(b"\n")
+    }
+}
+
+fn nl_main(args: &Args, out: &mut StdoutWriter) -> io::Result<()> {
+    let readable: Box<dyn Read> = match &args.file {
+        Some(path) if path.as_os_str() != "-" => {
+            let file = fs::File::open(path)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+            Box::new(file)
+        }
+        _ => Box::new(io::stdin().lock()),
+    };
+    let mut reader = io::BufReader::new(readable);
+
+    // an empty delimiter disables the section delimiter lines
+    let delimiters =
+        (!args.section_delimiter.is_empty()).then(|| Delimiters::new(&args.section_delimiter));
+
+    let mut numberer = Numberer {
+        args,
+        line_number: args.starting_line_number,
+        overflowed: false,
+        consecutive_blank_lines: 0,
+    };
+    let mut section = Section::Body;
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
             break;
         }
+
+        // Reference `nl` unconditionally adds a newline even on files not
+        // ending on a newline
+        if line.ends_with(b"\n") {
+            line.pop();
+        }
+
+        // a delimiter line is replaced by an empty line
+        if let Some(next) = delimiters.as_ref().and_then(|d| d.section(&line)) {
+            section = next;
+            numberer.start_section();
+            out.write_all(b"\n")?;
+            continue;
+        }
+
+        let style = match section {
+            Section::Header => &args.header_numbering,
+            Section::Body => &args.body_numbering,
+            Section::Footer => &args.footer_numbering,
+        };
+        numberer.number_line(out, style, &line)?;
     }
 
-    Ok(())
+    out.flush()
 }
 
 fn main() -> ExitCode {
     let mut args = Args::parse();
 
-    match args.section_delimiter.len() {
+    // Initialize translation system
+    setlocale(LocaleCategory::LcAll, "");
+    textdomain(PROJECT_NAME).unwrap();
+    bind_textdomain_codeset(PROJECT_NAME, "UTF-8").unwrap();
+
+    match args.section_delimiter.chars().count() {
+        0 | 2 => (),
         1 => {
             args.section_delimiter.push(':');
         }
-        2 => (),
         _ => {
             // Delimiter should be at most 2 characters.
+            eprintln!(
+                "nl: {}: {}",
+                gettext("invalid section delimiter"),
+                args.section_delimiter
+            );
             return ExitCode::from(1);
         }
     }
 
-    // Initialize translation system
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain(PROJECT_NAME).unwrap();
-    bind_textdomain_codeset(PROJECT_NAME, "UTF-8").unwrap();
-
-    match nl_main(&args) {
+    let mut out = StdoutWriter::new();
+    match nl_main(&args, &mut out) {
         Ok(_) => ExitCode::from(0),
-        Err(_) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("nl: {}", e);
+            ExitCode::from(1)
+        }
     }
 }
@@ -7,8 +7,6 @@
 // SPDX-License-Identifier: MIT
 //
 // TODO:
-// - stdin ("-")
-// - fix:  empty-string delimiters \0
 // - improve:  don't open all files at once in --serial mode
 //
 
@@ -19,7 +17,7 @@ use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
 use plib::PROJECT_NAME;
 use std::fs::{self, File};
-use std::io::{self, BufRead, BufReader, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
 
 /// paste - merge corresponding or subsequent lines of files
 #[derive(Parser, Debug)]
@@ -33,17 +31,49 @@ struct Args {
     #[arg(short, long)]
     delims: Option<String>,
 
-    /// One or more input files.
+    /// One or more input files ("-" is standard input).
+    #[arg(default_value = "-")]
     files: Vec<String>,
 }
 
+enum Source {
+    File(BufReader<File>),
+    // every "-" operand shares the one standard input
+    Stdin,
+}
+
 struct PasteFile {
     filename: String,
-    rdr: BufReader<File>,
+    rdr: Source,
     eof: bool,
     last: bool,
 }
 
+impl PasteFile {
+    /// Read the next line into `buffer`, without its newline.
+    /// Returns false at end of file.
+    fn read_line(&mut self, buffer: &mut Vec<u8>) -> io::Result<bool> {
+        buffer.clear();
+        let n_read = match &mut self.rdr {
+            Source::File(rdr) => rdr.read_until(b'\n', buffer),
+            Source::Stdin => io::stdin().lock().read_until(b'\n', buffer),
+        };
+        let n_read = match n_read {
+            Ok(n) => n,
+            Err(e) => {
+                eprintln!("{}: {}", self.filename, e);
+                return Err(e);
+            }
+        };
+
+        if buffer.last() == Some(&b'\n') {
+            buffer.pop();
+        }
+
+        Ok(n_read > 0)
+    }
+}
+
 struct PasteInfo {
     inputs: Vec<PasteFile>,
 }
@@ -56,63 +86,83 @@ impl PasteInfo {
 
 struct DelimInfo {
     cur_delim: usize,
-    delims: String,
+    // an empty entry comes from "\0" and means no delimiter
+    delims: Vec<String>,
 }
 
 impl DelimInfo {
     fn new() -> DelimInfo {
         DelimInfo {
             cur_delim: 0,
-            delims: String::from("\t"),
+            delims: vec![String::from("\t")],
         }
     }
 
-    fn delim(&mut self) -> char {
-        let ch = self.delims.chars().nth(self.cur_delim).unwrap();
+    fn delim(&mut self) -> &str {
+        let idx = self.cur_delim;
 
-        self.advance();
+        self.cur_delim += 1;
+        if self.cur_delim >= self.delims.len() {
+            self.cur_delim = 0;
+        }
 
-        ch
+        &self.delims[idx]
     }
 
-    fn advance(&mut self) {
-        if self.delims.len() > 1 {
-            self.cur_delim = self.cur_delim + 1;
-            if self.cur_delim >= self.delims.len() {
-                self.cur_delim = 0;
-            }
-        }
+    // the list starts over with each output line (or each file, in serial mode)
+    fn reset(&mut self) {
+        self.cur_delim = 0;
     }
 }
 
-fn xlat_delim_str(s: &str) -> String {
-    let mut output = String::with_capacity(s.len() + 10);
+fn xlat_delim_str(s: &str) -> Vec<String> {
+    let mut output = Vec::with_capacity(s.len());
 
     let mut in_escape = false;
     for ch in s.chars() {
         if in_escape {
-            let out_ch = match ch {
-                'n' => '\n',
-                't' => '\t',
-                '0' => '\0',
-                _ => ch,
+            let out = match ch {
+                'n' => String::from("\n"),
+                't' => String::from("\t"),
+                '0' => String::new(),
+                _ => ch.to_string(),
             };
 
-            output.push(out_ch);
+            output.push(out);
             in_escape = false;
         } else if ch == '\\' {
             in_escape = true;
         } else {
-            output.push(ch);
+            output.push(ch.to_string());
         }
     }
 
+    // a trailing backslash stands for itself
+    if in_escape {
+        output.push(String::from("\\"));
+    }
+
+    // an empty list pastes lines together with nothing between them
+    if output.is_empty() {
+        output.push(String::new());
+    }
+
     output
 }
 
 fn open_inputs(args: &Args, info: &mut PasteInfo) -> io::Result<()> {
     // open each input
     for filename in &args.files {
+        if filename == "-" {
+            info.inputs.push(PasteFile {
+                filename: String::from("stdin"),
+                rdr: Source::Stdin,
+                eof: false,
+                last: false,
+            });
+            continue;
+        }
+
         let f_res = fs::File::open(filename);
 
         match f_res {
@@ -123,7 +173,7 @@ fn open_inputs(args: &Args, info: &mut PasteInfo) -> io::Result<()> {
             Ok(f) => {
                 info.inputs.push(PasteFile {
                     filename: filename.to_string(),
-                    rdr: BufReader::new(f),
+                    rdr: Source::File(BufReader::new(f)),
                     eof: false,
                     last: false,
                 });
@@ -132,90 +182,78 @@ fn open_inputs(args: &Args, info: &mut PasteInfo) -> io::Result<()> {
     }
 
     // mark final input
-    let idx = info.inputs.len() - 1;
-    info.inputs[idx].last = true;
+    if let Some(input) = info.inputs.last_mut() {
+        input.last = true;
+    }
+
+    Ok(())
+}
 
+fn write_stdout(out: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    if let Err(e) = out.write_all(data) {
+        eprintln!("stdout: {}", e);
+        return Err(e);
+    }
     Ok(())
 }
 
 fn paste_files_serial(mut info: PasteInfo, mut dinfo: DelimInfo) -> io::Result<()> {
+    let mut stdout = BufWriter::new(io::stdout().lock());
+    let mut buffer = Vec::new();
+
     // loop serially for each input file
     for input in &mut info.inputs {
         let mut first_line = true;
+        dinfo.reset();
 
         // for each input line
-        loop {
-            // read line
-            let mut buffer = String::new();
-            let n_read_res = input.rdr.read_line(&mut buffer);
-            if let Err(e) = n_read_res {
-                eprintln!("{}: {}", input.filename, e);
-                return Err(e);
-            }
-            let n_read = n_read_res.unwrap();
-
-            // if EOF, output line terminator and end inner loop
-            if n_read == 0 {
-                println!("");
-                break;
-
+        while input.read_line(&mut buffer)? {
             // output line segment
-            } else {
-                let slice = &buffer[0..buffer.len() - 1];
-
-                if first_line {
-                    print!("{}", slice);
-                } else {
-                    print!("{}{}", dinfo.delim(), slice);
-                }
+            if !first_line {
+                write_stdout(&mut stdout, dinfo.delim().as_bytes())?;
             }
+            write_stdout(&mut stdout, &buffer)?;
 
-            if first_line {
-                first_line = false;
-            }
+            first_line = false;
         }
+
+        // at EOF, output line terminator
+        write_stdout(&mut stdout, b"\n")?;
     }
 
-    Ok(())
+    stdout.flush()
 }
 
 fn paste_files(mut info: PasteInfo, mut dinfo: DelimInfo) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    let mut buffer = Vec::new();
+
     // for each input line, across N files
     loop {
-        let mut output = String::new();
+        let mut output = Vec::new();
         let mut have_data = false;
+        dinfo.reset();
 
         // for each input line
         for input in &mut info.inputs {
-            // if not already at EOF, read and process a line
+            // if not already at EOF, read and process a line;
+            // a file that ran out contributes an empty field
             if !input.eof {
-                // read input line
-                let mut buffer = String::new();
-                let n_read_res = input.rdr.read_line(&mut buffer);
-                if let Err(e) = n_read_res {
-                    eprintln!("{}: {}", input.filename, e);
-                    return Err(e);
-                }
-                let n_read = n_read_res.unwrap();
-
-                // if at EOF, note and continue
-                if n_read == 0 {
-                    input.eof = true;
-
-                // otherwise add to output line, sans trailing NL
-                } else {
+                if input.read_line(&mut buffer)? {
                     have_data = true;
-                    output.push_str(&buffer[0..buffer.len() - 1]);
+                    output.extend_from_slice(&buffer);
+                } else {
+                    input.eof = true;
                 }
             }
 
             // final record, output line end
             if input.last {
-                output.push('\n');
+                output.push(b'\n');
 
             // next delimiter
             } else {
-                output.push(dinfo.delim());
+                output.extend_from_slice(dinfo.delim().as_bytes());
             }
         }
 
@@ -224,13 +262,7 @@ fn paste_files(mut info: PasteInfo, mut dinfo: DelimInfo) -> io::Result<()> {
         }
 
         // output all segments to stdout at once (one write per line)
-        match io::stdout().write_all(output.as_bytes()) {
-            Ok(()) => {}
-            Err(e) => {
-                eprintln!("stdout: {}", e);
-                return Err(e);
-            }
-        }
+        write_stdout(&mut stdout, &output)?;
     }
 
     Ok(())
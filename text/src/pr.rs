@@ -162,10 +162,20 @@ fn write_line_content(
     if !params.merge {
         write_line_number(&mut tmp, params, line_number)?;
     }
+    let start = tmp.len();
     write!(&mut tmp, "{}", &line.line).into_io_result()?;
 
     // -e
-    line_transform::expand_tabs(&mut tmp, params.expand_tabs);
+    let tabs_expanded = params.expand_tabs.is_none() && params.num_columns > 1 && !params.merge;
+    if params.expand_tabs.is_some() {
+        line_transform::expand_tabs(&mut tmp, params.expand_tabs);
+    } else if tabs_expanded {
+        // -e is assumed for multiple text-column output, so that the columns
+        // line up
+        let mut content = tmp.split_off(start);
+        line_transform::expand_tabs(&mut content, Some((TAB, DEFAULT_TAB_WIDTH)));
+        tmp.push_str(&content);
+    }
     // -i
     line_transform::replace_spaces(&mut tmp, params.output_tabs);
 
@@ -179,7 +189,9 @@ fn write_line_content(
         //
         // https://github.com/coreutils/coreutils/blob/f56ae60585cff021e92c11c1d4917fe7a7dce38b/src/pr.c#L2310-L2317
         for c in line.line.chars() {
-            if c == BACKSPACE {
+            if c == TAB && tabs_expanded {
+                continue;
+            } else if c == BACKSPACE {
                 width += 2;
             } else if is_non_printable(c) {
                 width += 1;
@@ -195,8 +207,7 @@ fn write_line_content(
 
 /// Get the current date as a string formatted according to pr's spec.
 fn datetime_now() -> String {
-    let dt = Local::now().to_utc();
-    dt.format(DATE_TIME_FORMAT).to_string()
+    Local::now().format(DATE_TIME_FORMAT).to_string()
 }
 
 /// Calculate the column width.
@@ -212,6 +223,13 @@ fn column_width(num_columns: usize, page_width: usize) -> usize {
 }
 
 fn pr_serial(path: &PathBuf, params: &Parameters) -> io::Result<()> {
+    // standard input has no name to show in the header
+    let name = if path.as_os_str() == "-" {
+        String::new()
+    } else {
+        path.to_string_lossy().into_owned()
+    };
+
     let dt = if path.as_os_str() == "-" {
         datetime_now()
     } else {
@@ -254,7 +272,7 @@ fn pr_serial(path: &PathBuf, params: &Parameters) -> io::Result<()> {
             if !params.omit_header {
                 print_header(
                     &dt,
-                    &*path.to_string_lossy(),
+                    &name,
                     page_number,
                     params.header.as_deref(),
                     params.page_width,
@@ -343,7 +361,7 @@ fn pr_serial(path: &PathBuf, params: &Parameters) -> io::Result<()> {
             if !params.omit_header {
                 print_header(
                     &dt,
-                    &*path.to_string_lossy(),
+                    &name,
                     page_number,
                     params.header.as_deref(),
                     params.page_width,
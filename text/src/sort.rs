@@ -9,18 +9,19 @@
 
 extern crate clap;
 extern crate plib;
+use std::borrow::Cow;
 use std::cmp::Ordering;
 
-use std::io::{ErrorKind, Read};
 use std::{
-    fs::File,
-    io::{self, BufRead, BufWriter, Error, Write},
+    fs::{self, File},
+    io::{self, BufRead, BufWriter, Read, Write},
+    os::unix::fs::MetadataExt,
     path::PathBuf,
 };
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::PROJECT_NAME;
+use plib::i18n;
+use plib::locale::{self, Class, Ctype};
 
 /// Sort, merge, or sequence check text files
 #[derive(Parser, Debug)]
@@ -74,10 +75,14 @@ struct Args {
     #[arg(short = 't')]
     field_separator: Option<char>,
 
-    /// Specify the key definition for sorting
+    /// Specify a sort key as field_start[type][,field_end[type]]; may be repeated, and later keys break ties in earlier ones
     #[arg(short = 'k')]
     key_definition: Vec<String>,
 
+    /// Stable sort: keep lines with equal keys in their input order instead of comparing whole lines
+    #[arg(short = 's')]
+    stable: bool,
+
     /// Input files
     filenames: Vec<PathBuf>,
 }
@@ -122,677 +127,484 @@ impl Args {
     }
 }
 
-/// A struct representing a range field with various sorting and comparison options.
-#[derive(Clone)]
-struct RangeField {
-    /// The number of the field to be considered in the range.
-    field_number: usize,
+/// Comparison options that apply to one sort key.
+///
+/// A key given with `-k` takes these from its own modifier letters when it
+/// has any, and from the global options otherwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct KeyOptions {
+    /// Skip leading blanks when locating the start of the key (`b` on the start position).
+    blank_start: bool,
 
-    /// The position of the first character in the field to start comparison.
-    first_character: usize,
+    /// Skip leading blanks when locating the end of the key (`b` on the end position).
+    blank_end: bool,
 
-    /// A boolean flag to indicate if the field should be sorted numerically.
-    numeric_sort: bool,
-
-    /// A boolean flag to indicate if leading blanks should be ignored during comparison.
-    ignore_leading_blanks: bool,
+    /// Compare only blanks and alphanumeric characters (`d`).
+    dictionary_order: bool,
 
-    /// A boolean flag to indicate if the sort order should be reversed.
-    reverse: bool,
+    /// Fold lowercase to uppercase (`f`).
+    fold_case: bool,
 
-    /// A boolean flag to indicate if non-printable characters should be ignored during comparison.
+    /// Ignore non-printable characters (`i`).
     ignore_nonprintable: bool,
 
-    /// A boolean flag to indicate if the case should be folded (case-insensitive comparison).
-    fold_case: bool,
+    /// Compare as numbers (`n`).
+    numeric_sort: bool,
 
-    /// A boolean flag to indicate if the field should be compared in dictionary order
-    /// (considers only alphanumeric characters and blanks).
-    dictionary_order: bool,
+    /// Reverse the sense of the comparison (`r`).
+    reverse: bool,
 }
 
-impl RangeField {
-    fn new() -> RangeField {
-        Self {
-            field_number: 0,
-            first_character: 0,
-            numeric_sort: false,
-            ignore_leading_blanks: false,
-            reverse: false,
-            ignore_nonprintable: false,
-            fold_case: false,
-            dictionary_order: false,
+impl KeyOptions {
+    fn from_args(args: &Args) -> KeyOptions {
+        KeyOptions {
+            blank_start: args.ignore_leading_blanks,
+            blank_end: args.ignore_leading_blanks,
+            dictionary_order: args.dictionary_order,
+            fold_case: args.fold_case,
+            ignore_nonprintable: args.ignore_nonprintable,
+            numeric_sort: args.numeric_sort,
+            reverse: args.reverse,
+        }
+    }
+
+    /// Applies one modifier letter from a `-k` position, returning false
+    /// if `c` is not a modifier.
+    fn set_modifier(&mut self, c: char, start: bool) -> bool {
+        match c {
+            'b' if start => self.blank_start = true,
+            'b' => self.blank_end = true,
+            'd' => self.dictionary_order = true,
+            'f' => self.fold_case = true,
+            'i' => self.ignore_nonprintable = true,
+            'n' => self.numeric_sort = true,
+            'r' => self.reverse = true,
+            _ => return false,
         }
+        true
     }
 }
 
-/// Updates two RangeField objects based on their comparison options.
-///
-/// This function takes two RangeField objects, compares their fields, and updates the fields
-/// according to the comparison options. If any of the comparison options (`dictionary_order`,
-/// `fold_case`, `ignore_nonprintable`, or `numeric_sort`) is true in either of the objects,
-/// it sets the same option to true in both objects.
-///
-/// # Arguments
-///
-/// * `field1` - The first RangeField object to compare and update.
-/// * `field2` - The second RangeField object to compare and update.
+/// A compiled `-k` sort key.
 ///
-/// # Returns
-///
-/// A tuple containing the updated RangeField objects.
-///
-fn update_range_field(mut field1: RangeField, mut field2: RangeField) -> (RangeField, RangeField) {
-    if field1.dictionary_order || field2.dictionary_order {
-        field1.dictionary_order = true;
-        field2.dictionary_order = true;
-    }
+/// Fields and characters are counted from zero here, while the command
+/// line counts from one.
+#[derive(Clone, Debug)]
+struct SortKey {
+    /// The field in which the key starts.
+    start_field: usize,
 
-    if field1.fold_case || field2.fold_case {
-        field1.fold_case = true;
-        field2.fold_case = true;
-    }
+    /// The character within `start_field` at which the key starts.
+    start_char: usize,
 
-    if field1.ignore_nonprintable || field2.ignore_nonprintable {
-        field1.ignore_nonprintable = true;
-        field2.ignore_nonprintable = true;
+    /// The field and, optionally, the last character within it at which
+    /// the key ends; `None` extends the key to the end of the line, and a
+    /// `None` character to the end of the field.
+    end: Option<(usize, Option<usize>)>,
+
+    options: KeyOptions,
+}
+
+impl SortKey {
+    /// The key used when no `-k` is given: the whole line.
+    fn whole_line(options: KeyOptions) -> SortKey {
+        SortKey {
+            start_field: 0,
+            start_char: 0,
+            end: None,
+            options,
+        }
     }
+}
 
-    if field1.numeric_sort || field2.numeric_sort {
-        field1.numeric_sort = true;
-        field2.numeric_sort = true;
+/// Splits a position such as `2.3bn` into its numeric part and its
+/// modifier letters, applying the letters to `options`.
+fn split_modifiers<'a>(
+    pos: &'a str,
+    options: &mut KeyOptions,
+    start: bool,
+) -> Result<&'a str, Box<dyn std::error::Error>> {
+    let digits_end = pos
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(pos.len());
+
+    for c in pos[digits_end..].chars() {
+        if !options.set_modifier(c, start) {
+            return Err(format!("invalid key modifier: {}", c).into());
+        }
     }
 
-    (field1, field2)
+    Ok(&pos[..digits_end])
 }
 
-/// Compares two RangeField objects based on their field numbers and first characters.
-///
-/// This function takes references to two RangeField objects and compares them based on their
-/// field numbers and first characters. If the field number of the first object is less than
-/// the field number of the second object, it returns true. If the field numbers are equal,
-/// it compares the first characters, returning true if the first character of the first object
-/// is less than or equal to the first character of the second object. Otherwise, it returns false.
-///
-/// # Arguments
-///
-/// * `field1` - A reference to the first RangeField object to compare.
-/// * `field2` - A reference to the second RangeField object to compare.
+/// Parses a `field_start[type][,field_end[type]]` key definition.
 ///
-/// # Returns
-///
-/// A boolean value indicating whether the first RangeField object is less than or equal to
-/// the second RangeField object based on their field numbers and first characters.
-///
-fn compare_range_fields(field1: &RangeField, field2: &RangeField) -> bool {
-    if field1.field_number < field2.field_number {
-        true
-    } else if field1.field_number == field2.field_number {
-        field1.first_character <= field2.first_character
-    } else {
-        false
+/// If either position carries modifier letters, the key uses only
+/// those; otherwise it inherits the global ordering options.
+fn parse_key(key_def: &str, args: &Args) -> Result<SortKey, Box<dyn std::error::Error>> {
+    if key_def.is_empty() {
+        return Err("key must be non-empty".into());
     }
-}
 
-/// Trims and concatenates substrings from a vector of strings based on a specified range.
-///
-/// This function takes a vector of string slices and a key range, then extracts and concatenates
-/// the substrings from the specified range. The range is defined by a tuple of `RangeField`
-/// structs, where the first element defines the start of the range, and the optional second element
-/// defines the end of the range.
-///
-/// # Arguments
-///
-/// * `line` - A vector of string slices representing the fields to be processed.
-/// * `key_range` - A tuple containing two elements:
-///     * The first `RangeField` specifies the starting field and character position.
-///     * An optional `RangeField` that specifies the ending field and character position.
-///
-/// # Returns
-///
-/// A `String` that contains the concatenated result of the specified range of substrings.
-///
-fn cut_line_by_range(line: Vec<&str>, key_range: &(RangeField, Option<RangeField>)) -> String {
-    let mut result = String::new();
+    let (start_pos, end_pos) = match key_def.split_once(',') {
+        Some((start, end)) => (start, Some(end)),
+        None => (key_def, None),
+    };
 
-    let start_field = key_range.0.field_number;
-    let start_char = key_range.0.first_character;
+    let mut options = KeyOptions::default();
+    let start_pos = split_modifiers(start_pos, &mut options, true)?;
+    let end_pos = match end_pos {
+        Some(end) => Some(split_modifiers(end, &mut options, false)?),
+        None => None,
+    };
+    if options == KeyOptions::default() {
+        options = KeyOptions::from_args(args);
+    }
 
-    let end_field = match &key_range.1 {
-        Some(range) => range.field_number,
-        None => line.len() - 1,
+    let mut parts = start_pos.split('.');
+    let start_field: usize = parts.next().unwrap().parse()?;
+    if start_field == 0 {
+        return Err("the key can't be zero.".into());
+    }
+    let start_char: usize = match parts.next() {
+        Some(c) => c.parse()?,
+        None => 1,
     };
-    let end_char = key_range.1.as_ref().map(|range| range.first_character);
-
-    for (i, field) in line.iter().skip(start_field).enumerate() {
-        let i = i + start_field;
-        if i >= start_field && i <= end_field {
-            let start = if i == start_field {
-                if key_range.0.ignore_leading_blanks {
-                    start_char + (field.len() - field.trim_start().len())
-                } else {
-                    start_char
-                }
-            } else {
-                0
-            };
-            let mut end = if i == end_field {
-                if let Some(char) = end_char {
-                    if char == usize::MAX - 1 {
-                        field.len() - 1
-                    } else if key_range.clone().1.unwrap().ignore_leading_blanks {
-                        char + (field.len() - field.trim_start().len())
-                    } else {
-                        char
-                    }
-                } else {
-                    field.len() - 1
-                }
-            } else {
-                field.len() - 1
+    if start_char == 0 {
+        return Err("the key can't be zero.".into());
+    }
+
+    let end = match end_pos {
+        Some(end_pos) => {
+            let mut parts = end_pos.split('.');
+            let end_field: usize = parts.next().unwrap().parse()?;
+            if end_field == 0 {
+                return Err("the key can't be zero.".into());
+            }
+            // a character position of 0 means the end of the field
+            let end_char = match parts.next() {
+                Some(c) => match c.parse::<usize>()? {
+                    0 => None,
+                    c => Some(c - 1),
+                },
+                None => None,
             };
-            if end >= field.len() {
-                end = field.len() - 1;
+
+            if end_field < start_field
+                || (end_field == start_field && end_char.is_some_and(|c| c + 1 < start_char))
+            {
+                return Err("keys fields with end position before start!".into());
             }
+            Some((end_field - 1, end_char))
+        }
+        None => None,
+    };
 
-            result.push_str(&field[start..=end]);
+    Ok(SortKey {
+        start_field: start_field - 1,
+        start_char: start_char - 1,
+        end,
+        options,
+    })
+}
+
+/// Returns the byte ranges of the fields of `line`.
+///
+/// With a separator, fields lie between separator characters.  Without
+/// one, each field is a run of blanks followed by a run of non-blanks,
+/// so the leading blanks belong to the field.
+fn split_fields(line: &str, field_separator: Option<char>) -> Vec<(usize, usize)> {
+    let mut fields = Vec::new();
+
+    match field_separator {
+        Some(sep) => {
+            let mut start = 0;
+            for (i, c) in line.char_indices() {
+                if c == sep {
+                    fields.push((start, i));
+                    start = i + c.len_utf8();
+                }
+            }
+            fields.push((start, line.len()));
+        }
+        None => {
+            let mut start = 0;
+            let mut in_word = false;
+            for (i, c) in line.char_indices() {
+                let blank = c == ' ' || c == '\t';
+                if blank && in_word {
+                    fields.push((start, i));
+                    start = i;
+                }
+                in_word = !blank;
+            }
+            fields.push((start, line.len()));
         }
     }
 
-    result
+    fields
 }
 
-/// Extracts a number from a string, ignoring other characters.
-///
-/// This function processes an input string and extracts the first sequence of characters that can
-/// be part of a number (including digits, a minus sign, a decimal point, and an asterisk). If no
-/// such sequence is found, it returns `None`.
-///
-/// # Arguments
-///
-/// * `input` - A string slice (`&str`) containing the input to be processed.
-///
-/// # Returns
-///
-/// An `Option<String>` containing the extracted number if found, or `None` if no numeric sequence
-/// is found.
-///
-fn numeric_sort_filter(input: &str) -> Option<String> {
-    let mut result = String::new();
-    let mut found_number = false;
-
-    for c in input.chars() {
-        if c.is_ascii_digit() || c == '-' || c == '.' || c == '*' {
-            found_number = true;
-            result.push(c);
-        } else if found_number {
-            return Some(result);
-        }
+/// Advances `pos` past leading blanks and then `n` characters, without
+/// going beyond `limit`.
+fn advance(line: &str, mut pos: usize, limit: usize, skip_blanks: bool, n: usize) -> usize {
+    if skip_blanks {
+        pos += line[pos..limit]
+            .find(|c: char| c != ' ' && c != '\t')
+            .unwrap_or(limit - pos);
+    }
+    match line[pos..limit].char_indices().nth(n) {
+        Some((i, _)) => pos + i,
+        None => limit,
     }
+}
+
+/// Extracts the part of `line` that `key` selects.
+fn key_slice<'a>(line: &'a str, key: &SortKey, field_separator: Option<char>) -> &'a str {
+    if key.start_field == 0 && key.start_char == 0 && key.end.is_none() && !key.options.blank_start
+    {
+        return line;
+    }
+
+    let fields = split_fields(line, field_separator);
 
-    if found_number {
-        Some(result)
+    let start = match fields.get(key.start_field) {
+        Some(&(fs, fe)) => advance(line, fs, fe, key.options.blank_start, key.start_char),
+        None => line.len(),
+    };
+
+    let end = match key.end {
+        None => line.len(),
+        Some((field, end_char)) => match fields.get(field) {
+            Some(&(fs, fe)) => match end_char {
+                None => fe,
+                Some(c) => advance(line, fs, fe, key.options.blank_end, c + 1),
+            },
+            None => line.len(),
+        },
+    };
+
+    if end > start {
+        &line[start..end]
     } else {
-        None
+        ""
     }
 }
 
-/// Compares two strings numerically, extracting numbers and performing a numeric comparison.
-///
-/// This function extracts the first numeric sequence from each input string using
-/// `numeric_sort_filter` and then compares these sequences as floating-point numbers (`f64`).
-/// If no valid numeric sequence is found, it defaults to comparing the strings lexicographically.
-///
-/// # Arguments
-///
-/// * `line1` - A string slice (`&str`) representing the first input string.
-/// * `line2` - A string slice (`&str`) representing the second input string.
-///
-/// # Returns
-///
-/// An `Ordering` value (`Ordering::Less`, `Ordering::Greater`, or `Ordering::Equal`) indicating
-/// the result of the comparison:
-/// * `Ordering::Less` if the first number is less than the second.
-/// * `Ordering::Greater` if the first number is greater than the second.
-/// * `Ordering::Equal` if the numbers are equal or if both strings lack numeric sequences and are
-///   equal lexicographically.
-///
-fn compare_numeric(line1: &str, line2: &str) -> Ordering {
-    let line1 = numeric_sort_filter(line1).unwrap_or("0".to_string());
-    let line2 = numeric_sort_filter(line2).unwrap_or("0".to_string());
-    let a_num = line1.parse::<f64>().ok();
-    let b_num = line2.parse::<f64>().ok();
+/// Parses the leading numeric string of `key` (optional blanks, an
+/// optional minus sign, digits and a decimal point) as used by `-n`.
+/// A key with no digits compares as zero.
+fn numeric_value(key: &str) -> f64 {
+    let key = key.trim_start_matches([' ', '\t']);
+    let bytes = key.as_bytes();
+
+    let mut end = 0;
+    if bytes.first() == Some(&b'-') {
+        end = 1;
+    }
+    let mut seen_point = false;
+    while end < bytes.len() {
+        match bytes[end] {
+            b'0'..=b'9' => {}
+            b'.' if !seen_point => seen_point = true,
+            _ => break,
+        }
+        end += 1;
+    }
 
-    a_num.partial_cmp(&b_num).unwrap_or(Ordering::Equal)
+    key[..end].parse::<f64>().unwrap_or(0.0)
 }
 
-/// Filters a string to include only alphanumeric characters and whitespace.
-///
-/// This function processes an input string and retains only the alphanumeric characters
-/// (`a-z`, `A-Z`, `0-9`) and whitespace characters, effectively removing all other characters.
-/// It is useful for preparing strings for dictionary-order comparisons.
-///
-/// # Arguments
-///
-/// * `line` - A string slice (`&str`) representing the input string to be filtered.
-///
-/// # Returns
-///
-/// A `String` containing only the alphanumeric and whitespace characters from the input string.
-///
-fn dictionary_order_filter(line: &str) -> String {
-    line.chars()
-        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
-        .collect::<String>()
+/// A sort key extracted from one line and prepared for comparison.
+enum KeyValue<'a> {
+    Number(f64),
+    /// The text of the key, or its collation key when `LC_COLLATE` does
+    /// not order by bytes.
+    Text(Cow<'a, [u8]>),
 }
 
-/// Filters a string to include only printable ASCII characters.
-///
-/// This function processes an input string and retains only the ASCII characters that are
-/// considered printable and graphic (i.e., not control characters or whitespace).
-///
-/// # Arguments
-///
-/// * `line` - A string slice (`&str`) representing the input string to be filtered.
-///
-/// # Returns
-///
-/// A `String` containing only the printable ASCII characters from the input string.
-///
-fn ignore_nonprintable_filter(line: &str) -> String {
-    line.chars()
-        .filter(|&c| c.is_ascii() && c.is_ascii_graphic())
-        .collect()
+impl KeyValue<'_> {
+    fn into_owned(self) -> KeyValue<'static> {
+        match self {
+            KeyValue::Number(n) => KeyValue::Number(n),
+            KeyValue::Text(text) => KeyValue::Text(Cow::Owned(text.into_owned())),
+        }
+    }
 }
 
-/// Generates a `RangeField` struct based on the specified key range and arguments.
-///
-/// This function constructs a `RangeField` struct based on the provided key range string
-/// and the arguments specified in the `Args` struct. It parses the key range string to
-/// determine various sorting and comparison options.
-///
-/// # Arguments
-///
-/// * `key_range` - A string slice (`&str`) representing the key range configuration.
-/// * `args` - A reference to an `Args` struct containing additional configuration options.
-/// * `first` - A boolean indicating whether this is the first part of a range.
-///
-/// # Returns
-///
-/// A `Result` containing the constructed `RangeField` if successful, or a `Box` containing
-/// a dynamic `Error` trait object if parsing or construction fails.
-///
-fn generate_range(
-    key_range: &str,
-    args: &Args,
-    first: bool,
-) -> Result<RangeField, Box<dyn std::error::Error>> {
-    let mut numeric_sort = args.numeric_sort;
-    let mut ignore_leading_blanks = args.ignore_leading_blanks;
-    let mut reverse = args.reverse;
-    let mut ignore_nonprintable = args.ignore_nonprintable;
-    let mut fold_case = args.fold_case;
-    let mut dictionary_order = args.dictionary_order;
-
-    if key_range.contains('n')
-        || key_range.contains('b')
-        || key_range.contains('r')
-        || key_range.contains('i')
-        || key_range.contains('f')
-        || key_range.contains('d')
-    {
-        numeric_sort = false;
-        ignore_leading_blanks = false;
-        ignore_nonprintable = false;
-        fold_case = false;
-        dictionary_order = false;
+fn compare_values(a: &KeyValue, b: &KeyValue) -> Ordering {
+    match (a, b) {
+        (KeyValue::Number(a), KeyValue::Number(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+        (KeyValue::Text(a), KeyValue::Text(b)) => a.cmp(b),
+        _ => Ordering::Equal,
     }
+}
 
-    let mut key_range = key_range.to_string();
-    if key_range.contains('n') {
-        key_range = key_range.replace('n', "");
-        numeric_sort = true;
-    }
-    if key_range.contains('b') {
-        key_range = key_range.replace('b', "");
-        ignore_leading_blanks = true;
-    }
-    if key_range.contains('r') {
-        key_range = key_range.replace('r', "");
-        reverse = true;
-    }
-    if key_range.contains('i') {
-        key_range = key_range.replace('i', "");
-        ignore_nonprintable = true;
-    }
-    if key_range.contains('f') {
-        key_range = key_range.replace('f', "");
-        fold_case = true;
-    }
-    if key_range.contains('d') {
-        key_range = key_range.replace('d', "");
-        dictionary_order = true;
-    }
-    let mut parts = key_range.split('.');
-    let start_1: usize = parts
-        .next()
-        .unwrap()
-        .parse()
-        .map_err(|err| Box::new(Error::new(ErrorKind::Other, err)))?;
-
-    let start_2 = parts.next();
-    let mut start_2: usize = match first {
-        true => start_2
-            .unwrap_or("1")
-            .parse()
-            .map_err(|err| Box::new(Error::new(ErrorKind::Other, err)))?,
-        false => start_2
-            .unwrap_or(&usize::MAX.to_string())
-            .parse()
-            .map_err(|err| Box::new(Error::new(ErrorKind::Other, err)))?,
-    };
+/// The ordering rules compiled from the command line.
+///
+/// Keys are parsed once, and each line's key values are extracted once
+/// before sorting rather than on every comparison.
+struct Comparator {
+    keys: Vec<SortKey>,
+    field_separator: Option<char>,
 
-    if !first && start_2 == 0 {
-        start_2 = usize::MAX;
-    }
+    /// Skip the last-resort comparison of whole lines (`-s`, and also
+    /// `-u`, so that the first of several equal lines is the one kept).
+    stable: bool,
 
-    Ok(RangeField {
-        field_number: start_1 - 1,
-        first_character: start_2 - 1,
-        numeric_sort,
-        ignore_leading_blanks,
-        reverse,
-        ignore_nonprintable,
-        fold_case,
-        dictionary_order,
-    })
+    /// Reverse the last-resort comparison (`-r`).
+    reverse: bool,
+
+    /// Character classes and case for `-d`, `-i` and `-f`.
+    ctype: Ctype,
+
+    /// Compare text in the collating sequence of `LC_COLLATE` rather
+    /// than by bytes.
+    collate: bool,
 }
 
-/// Cuts a line into fields based on the provided RangeField object(s) and field separator.
-///
-/// This function takes a line of text, a RangeField object or a tuple of two RangeField objects
-/// representing the key range(s), and an optional field separator character. It then splits the line
-/// into fields according to the provided separator or space character, and returns a string containing
-/// the selected fields based on the key range(s).
-///
-/// If a field separator character is provided, the line is split using that separator.
-/// If no separator is provided, the line is split using space (' ') as the separator.
-///
-/// # Arguments
-///
-/// * `line` - A reference to the input line of text to be cut into fields.
-/// * `key_range` - A tuple containing one or two RangeField objects representing the key range(s).
-///   The first RangeField object represents the primary key range, and the second one represents
-///   the secondary key range. If only one key range is provided, the second element of the tuple
-///   should be None.
-/// * `field_separator` - An optional character used to separate fields in the line. If None, space
-///   (' ') is used as the default separator.
-///
-/// # Returns
-///
-/// A string containing the selected fields based on the key range(s).
-///
-fn cut_line(
-    line: &str,
-    key_range: &(RangeField, Option<RangeField>),
-    field_separator: Option<char>,
-) -> String {
-    if let Some(separator) = field_separator {
-        let split = line.split(separator);
-        cut_line_by_range(split.collect(), key_range)
-    } else {
-        let split: Vec<&str> = line.split(' ').collect();
+/// A line together with its extracted key values.  Lines that are
+/// sorted borrow from the input; lines that are merged or checked are
+/// read one at a time and own their text.
+struct SortLine<'a> {
+    line: Cow<'a, str>,
+    keys: Vec<KeyValue<'a>>,
+}
 
-        cut_line_by_range(
-            merge_empty_lines(split)
+impl Comparator {
+    fn new(args: &Args) -> Result<Comparator, Box<dyn std::error::Error>> {
+        let keys = if args.key_definition.is_empty() {
+            vec![SortKey::whole_line(KeyOptions::from_args(args))]
+        } else {
+            args.key_definition
                 .iter()
-                .map(|s| s.as_str())
-                .collect(),
-            key_range,
-        )
-    }
-}
+                .map(|key_def| parse_key(key_def, args))
+                .collect::<Result<Vec<_>, _>>()?
+        };
 
-/// Compares two strings based on a specified key range and optional field separator.
-///
-/// This function compares two strings (`line1` and `line2`) based on a specified key range,
-/// taking into account optional field separation if provided. It applies sorting and comparison
-/// rules defined in the `RangeField` struct associated with the key range.
-///
-/// # Arguments
-///
-/// * `line1` - A string slice (`&str`) representing the first input string to be compared.
-/// * `line2` - A string slice (`&str`) representing the second input string to be compared.
-/// * `key_range` - A tuple containing two elements:
-///     * The first `RangeField` specifies the key range configuration for both strings.
-///     * An optional `RangeField` specifies the end of the key range if different from the start.
-/// * `field_separator` - An optional character specifying the field separator for splitting
-///   strings into fields before comparison. If `None`, whitespace is used as the separator.
-///
-/// # Returns
-///
-/// An `Ordering` value (`Ordering::Less`, `Ordering::Greater`, or `Ordering::Equal`) indicating
-/// the result of the comparison:
-/// * `Ordering::Less` if `line1` is less than `line2` according to the specified key range.
-/// * `Ordering::Greater` if `line1` is greater than `line2` according to the specified key range.
-/// * `Ordering::Equal` if `line1` and `line2` are equal within the specified key range.
-///
-fn compare_key(
-    line1: &str,
-    line2: &str,
-    key_range: &(RangeField, Option<RangeField>),
-    field_separator: Option<char>,
-) -> Ordering {
-    let mut line1 = cut_line(line1, key_range, field_separator);
-    let mut line2 = cut_line(line2, key_range, field_separator);
-
-    // Compare keys
-    if key_range.0.numeric_sort {
-        // If the keys are represented by numbers, compare them as numbers
-        let mut result = compare_numeric(&line1, &line2);
-        if key_range.0.reverse {
-            match result {
-                Ordering::Less => result = Ordering::Greater,
-                Ordering::Greater => result = Ordering::Less,
-                _ => (),
-            }
-        }
-        return result;
-    } else if key_range.0.dictionary_order {
-        line1 = dictionary_order_filter(&line1);
-        line2 = dictionary_order_filter(&line2);
-    } else if key_range.0.ignore_nonprintable {
-        line1 = ignore_nonprintable_filter(&line1);
-        line2 = ignore_nonprintable_filter(&line2);
+        Ok(Comparator {
+            keys,
+            field_separator: args.field_separator,
+            stable: args.stable || args.unique,
+            reverse: args.reverse,
+            ctype: Ctype::current(),
+            collate: !locale::collates_bytewise(),
+        })
     }
 
-    let result;
+    /// Extracts and prepares the value of `key` for `line`.
+    fn key_value<'a>(&self, line: &'a str, key: &SortKey) -> KeyValue<'a> {
+        let slice = key_slice(line, key, self.field_separator);
+        let opts = &key.options;
+
+        if opts.numeric_sort {
+            return KeyValue::Number(numeric_value(slice));
+        }
 
-    if key_range.0.fold_case {
-        let cmp = line1.to_uppercase().cmp(&line2.to_uppercase());
-        if cmp == std::cmp::Ordering::Equal {
-            result = line1.cmp(&line2);
+        let text = if opts.dictionary_order || opts.ignore_nonprintable || opts.fold_case {
+            let ctype = &self.ctype;
+            Cow::Owned(
+                slice
+                    .chars()
+                    .filter(|&c| {
+                        !opts.dictionary_order
+                            || ctype.is_class(Class::Alnum, c)
+                            || ctype.is_class(Class::Blank, c)
+                    })
+                    .filter(|&c| !opts.ignore_nonprintable || ctype.is_class(Class::Print, c))
+                    .map(|c| {
+                        if opts.fold_case {
+                            ctype.to_case(c, true)
+                        } else {
+                            c
+                        }
+                    })
+                    .collect::<String>(),
+            )
         } else {
-            result = cmp;
+            Cow::Borrowed(slice)
+        };
+
+        if self.collate {
+            return KeyValue::Text(Cow::Owned(locale::collation_key(&text)));
         }
-    } else {
-        result = line1.cmp(&line2);
-    }
-    if key_range.0.reverse {
-        match result {
-            Ordering::Less => Ordering::Greater,
-            Ordering::Greater => Ordering::Less,
-            _ => Ordering::Equal,
+        match text {
+            Cow::Borrowed(text) => KeyValue::Text(Cow::Borrowed(text.as_bytes())),
+            Cow::Owned(text) => KeyValue::Text(Cow::Owned(text.into_bytes())),
         }
-    } else {
-        result
     }
-}
 
-/// Compares two lines of text based on specified sorting and comparison options.
-///
-/// This function compares two lines of text (`line1` and `line2`) based on specified
-/// sorting and comparison options. It supports options for numeric sorting, dictionary
-/// ordering, case folding, and ignoring non-printable characters.
-///
-/// # Arguments
-///
-/// * `line1` - A string slice (`&str`) representing the first line of text to be compared.
-/// * `line2` - A string slice (`&str`) representing the second line of text to be compared.
-/// * `dictionary_order` - A boolean indicating whether to use dictionary ordering.
-/// * `fold_case` - A boolean indicating whether to fold case during comparison.
-/// * `ignore_nonprintable` - A boolean indicating whether to ignore non-printable characters.
-/// * `numeric_sort` - A boolean indicating whether to perform numeric sorting.
-///
-/// # Returns
-///
-/// An `Ordering` value (`Ordering::Less`, `Ordering::Greater`, or `Ordering::Equal`)
-/// indicating the result of the comparison:
-/// * `Ordering::Less` if `line1` is less than `line2` according to the specified options.
-/// * `Ordering::Greater` if `line1` is greater than `line2` according to the specified options.
-/// * `Ordering::Equal` if `line1` and `line2` are equal within the specified options.
-///
-fn compare_lines(
-    line1: &str,
-    line2: &str,
-    dictionary_order: bool,
-    fold_case: bool,
-    ignore_nonprintable: bool,
-    numeric_sort: bool,
-) -> Ordering {
-    let mut line1 = line1.to_string();
-    let mut line2 = line2.to_string();
-
-    if numeric_sort {
-        return compare_numeric(&line1, &line2);
-    } else if dictionary_order {
-        line1 = dictionary_order_filter(&line1);
-        line2 = dictionary_order_filter(&line2);
-    } else if ignore_nonprintable {
-        line1 = ignore_nonprintable_filter(&line1);
-        line2 = ignore_nonprintable_filter(&line2);
-    }
-
-    if fold_case {
-        let cmp = line1.to_uppercase().cmp(&line2.to_uppercase());
-        if cmp == std::cmp::Ordering::Equal {
-            line1.cmp(&line2)
-        } else {
-            cmp
+    fn prepare<'a>(&self, line: &'a str) -> SortLine<'a> {
+        SortLine {
+            line: Cow::Borrowed(line),
+            keys: self
+                .keys
+                .iter()
+                .map(|key| self.key_value(line, key))
+                .collect(),
         }
-    } else {
-        line1.cmp(&line2)
     }
-}
 
-/// Finds the first differing line between two slices of strings.
-///
-/// This function iterates over two slices of strings (`lines_1` and `lines_2`)
-/// and finds the index and content of the first line where they differ.
-/// If the slices have different lengths, it returns the index and content
-/// of the first line from `lines_1` that doesn't have a corresponding line
-/// in `lines_2`.
-///
-/// # Arguments
-///
-/// * `lines_1` - A slice of strings (`&[String]`) representing the first set of lines.
-/// * `lines_2` - A slice of strings (`&[String]`) representing the second set of lines.
-///
-/// # Returns
-///
-/// An `Option` containing a tuple:
-/// - If differing lines are found, it returns the index of the differing line and its content.
-/// - If the slices have different lengths, it returns the index and content of the first
-///   additional line from `lines_1` compared to `lines_2`.
-/// - If the slices are identical, it returns `None`.
-///
-fn find_first_difference(lines_1: &[String], lines_2: &[String]) -> Option<(usize, String)> {
-    let min_length = std::cmp::min(lines_1.len(), lines_2.len());
-
-    for i in 0..min_length {
-        if lines_1[i] != lines_2[i] {
-            return Some((i, lines_1[i].clone()));
+    fn prepare_owned(&self, line: String) -> SortLine<'static> {
+        let keys = self
+            .keys
+            .iter()
+            .map(|key| self.key_value(&line, key).into_owned())
+            .collect();
+        SortLine {
+            line: Cow::Owned(line),
+            keys,
         }
     }
 
-    if lines_1.len() != lines_2.len() {
-        return Some((min_length, lines_1.get(min_length).unwrap().to_string()));
+    /// Compares two lines by their keys only.
+    fn compare_keys(&self, a: &SortLine, b: &SortLine) -> Ordering {
+        for (key, (ka, kb)) in self.keys.iter().zip(a.keys.iter().zip(b.keys.iter())) {
+            let ord = compare_values(ka, kb);
+            let ord = if key.options.reverse {
+                ord.reverse()
+            } else {
+                ord
+            };
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+        Ordering::Equal
     }
 
-    None
-}
-
-/// Creates range fields based on the specified key range and arguments.
-///
-/// This function takes a key range string (`key_range`) and a reference to an `Args` struct (`args`)
-/// containing additional configuration options. It splits the key range string into individual
-/// range components separated by commas and generates corresponding `RangeField` structs for each
-/// component using the `generate_range` function.
-///
-/// # Arguments
-///
-/// * `key_range` - A string slice (`&str`) representing the key range configuration.
-/// * `args` - A reference to an `Args` struct containing additional configuration options.
-///
-/// # Returns
-///
-/// A `Result` containing a tuple `(RangeField, Option<RangeField>)` if successful, or a `Box`
-/// containing a dynamic `Error` trait object if parsing or construction fails.
-///
-fn create_ranges(
-    key_range: &str,
-    args: &Args,
-) -> Result<(RangeField, Option<RangeField>), Box<dyn std::error::Error>> {
-    // Split the key range with commas
-    let key_ranges: Vec<&str> = key_range.split(',').collect();
-    let mut key_ranges = key_ranges.iter();
-
-    // Convert key ranges to numeric representations
-    let mut ranges: (RangeField, Option<RangeField>) = (RangeField::new(), None);
-
-    ranges.0 = {
-        let key_range = key_ranges.next().unwrap().to_string();
-        if key_range == "0" {
-            return Err(Box::new(Error::new(
-                ErrorKind::Other,
-                "the key can't be zero.",
-            )));
+    /// Compares two lines, falling back to comparing the whole lines when
+    /// the keys are equal, unless `-s` or `-u` was given.
+    fn compare(&self, a: &SortLine, b: &SortLine) -> Ordering {
+        let ord = self.compare_keys(a, b);
+        if ord != Ordering::Equal || self.stable {
+            return ord;
         }
-        generate_range(&key_range, args, true)?
-    };
-    ranges.1 = {
-        if let Some(key_range) = key_ranges.next() {
-            Some(generate_range(key_range, args, false)?)
+
+        let ord = if self.collate {
+            locale::strcoll(&a.line, &b.line)
         } else {
-            None
-        }
-    };
-    if let Some(range_2) = ranges.1 {
-        let (range_1, range_2) = update_range_field(ranges.0, range_2);
-        if !compare_range_fields(&range_1, &range_2) {
-            return Err(Box::new(Error::new(
-                ErrorKind::Other,
-                "keys fields with end position before start!",
-            )));
+            a.line.as_bytes().cmp(b.line.as_bytes())
+        };
+        if self.reverse {
+            ord.reverse()
+        } else {
+            ord
         }
-
-        ranges = (range_1, Some(range_2));
     }
-
-    Ok(ranges)
 }
 
 /// Sorts strings based on specified sorting criteria and writes the result to the output.
 ///
-/// This function reads lines from the provided input reader and sorts them based on the
-/// specified sorting criteria in the `Args` struct. It supports sorting by key ranges,
-/// dictionary ordering, case folding, numeric sorting, and other options. The sorted
-/// lines are then written to the output, which can be a file or the standard output.
+/// The lines are sorted by the compiled keys, falling back to a byte-wise
+/// comparison of whole lines unless `-s` is given.  With `-u`, only the
+/// first of each run of lines with equal keys is kept.  The sorted lines
+/// are then written to the output, which can be a file or the standard output.
 ///
 /// # Arguments
 ///
 /// * `args` - A reference to an `Args` struct containing sorting and configuration options.
-/// * `reader` - A boxed reader implementing the `Read` trait, representing the input source.
+/// * `lines` - The input lines.
 ///
 /// # Returns
 ///
@@ -801,179 +613,227 @@ fn create_ranges(
 /// * `Err(Box<dyn Error>)` if an error occurs during sorting, reading, or writing.
 ///
 fn sort_lines(args: &Args, lines: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
-    let mut result_lines = lines.clone();
-    let mut duplicates = vec![];
+    let comparator = Comparator::new(args)?;
 
-    if !args.key_definition.is_empty() {
-        let key_range = &args.key_definition[0];
+    let mut prepared: Vec<SortLine> = lines.iter().map(|line| comparator.prepare(line)).collect();
+    prepared.sort_by(|a, b| comparator.compare(a, b));
 
-        if key_range.is_empty() {
-            return Err(Box::new(Error::new(
-                ErrorKind::Other,
-                "key must be non-empty",
-            )));
-        }
-
-        let ranges = create_ranges(key_range, args)?;
-        let ranges_2 = match args.key_definition.get(1) {
-            Some(key_range_2) => Some(create_ranges(key_range_2, args)?),
-            None => None,
-        };
-
-        // Sort strings by keys
-        result_lines.sort_by(|a, b| {
-            let mut ordering = compare_key(a, b, &ranges, args.field_separator);
-            if let Ordering::Equal = ordering {
-                if let Some(ranges_2) = &ranges_2 {
-                    let ordering_2 = compare_key(a, b, ranges_2, args.field_separator);
-                    if let Ordering::Equal = ordering_2 {
-                        duplicates.push(a.to_string());
-                    }
-                    ordering = ordering_2
-                }
-            }
-            ordering
-        });
-        if args.unique {
-            result_lines.retain(|line| !duplicates.contains(line));
-        }
-    } else {
-        result_lines.sort_by(|a, b| {
-            let ord = compare_lines(
-                a,
-                b,
-                args.dictionary_order,
-                args.fold_case,
-                args.ignore_nonprintable,
-                args.numeric_sort,
-            );
-            if let Ordering::Equal = ord {
-                duplicates.push(a.to_string());
-            }
-            ord
-        });
-
-        if args.unique {
-            result_lines.retain(|line| !duplicates.contains(line));
-        }
+    // the first line of each run of equal keys, in sorted order
+    if args.unique {
+        prepared.dedup_by(|b, a| comparator.compare_keys(a, b) == Ordering::Equal);
     }
 
-    if args.reverse {
-        result_lines.reverse();
-    }
+    write_lines(&args.output_file, prepared.iter().map(|l| l.line.as_ref()))
+}
 
-    if args.check_order_without_war_mess {
-        if find_first_difference(&lines, &result_lines).is_some() {
-            return Err(Box::new(Error::new(
-                ErrorKind::Other,
-                "The order of the lines is not correct",
-            )));
-        } else {
-            return Ok(());
-        }
-    } else if args.check_order {
-        if args.unique && !duplicates.is_empty() {
-            let message = format!("Duplicate key was found! `{}`", duplicates.first().unwrap());
-            return Err(Box::new(Error::new(ErrorKind::Other, message)));
-        }
-        if let Some((index, line)) = find_first_difference(&lines, &result_lines) {
-            let message = format!(
-                "The order of the lines is not correct on line {}:`{}`",
-                index + 1,
-                line
-            );
-            return Err(Box::new(Error::new(ErrorKind::Other, message)));
-        }
-        return Ok(());
-    } else if let Some(file_path) = &args.output_file {
+/// Opens the output file given with `-o`, or the standard output.
+fn open_output(output_path: &Option<PathBuf>) -> io::Result<Box<dyn Write>> {
+    Ok(match output_path {
         // Open the file for writing
-        let file_out = File::create(file_path)?;
-        let mut writer = BufWriter::new(file_out);
+        Some(file_path) => Box::new(BufWriter::new(File::create(file_path)?)),
+        None => Box::new(plib::io::StdoutWriter::new()),
+    })
+}
 
-        // Write the sorted strings to a file
-        for line in result_lines {
-            writeln!(writer, "{}", line)?;
-        }
-    } else {
-        let result = result_lines.join("\n");
-        println!("{result}");
+/// Writes lines to the output file given with `-o`, or to the standard output.
+fn write_lines<'a>(
+    output_path: &Option<PathBuf>,
+    lines: impl Iterator<Item = &'a str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = open_output(output_path)?;
+
+    for line in lines {
+        writeln!(writer, "{}", line)?;
     }
+    writer.flush()?;
 
     Ok(())
 }
 
-/// Merges contents from multiple sorted files into a single output.
+/// Merges input files that are each already sorted.
 ///
-/// This function takes a vector of mutable references to readers (`paths`) representing
-/// sorted input files and an optional output file path (`output_path`). It reads from each
-/// input file sequentially and writes the contents to the output file or the standard output.
+/// Only the first unmerged line of each file is held in memory.  The next
+/// output line is always the least of them, taking the earliest file when
+/// lines compare equal.  With `-u`, a line whose keys equal those of the
+/// last line written is dropped.
 ///
 /// # Arguments
 ///
-/// * `paths` - A mutable reference to a vector of readers (`Vec<Box<dyn Read>>`) representing
-///             sorted input files.
-/// * `output_path` - An optional string (`Option<String>`) representing the output file path.
-///                   If `Some`, the merged contents are written to the specified file; if `None`,
-///                   the contents are written to the standard output.
+/// * `args` - A reference to an `Args` struct containing sorting and configuration options.
+/// * `filenames` - The input files.
 ///
 /// # Returns
 ///
-/// An `io::Result` indicating success or failure:
+/// A `Result` indicating success or failure:
 /// * `Ok(())` if the merging process completes successfully.
-/// * `Err(io::Error)` if an error occurs during file I/O or copying.
+/// * `Err(Box<dyn Error>)` if an error occurs while reading or writing.
 ///
-fn merge_files(paths: &mut Vec<Box<dyn Read>>, output_path: &Option<PathBuf>) -> io::Result<()> {
-    let mut output_file: Box<dyn Write> = match output_path {
-        Some(path) => Box::new(File::create(path)?),
-        None => Box::new(io::stdout()),
-    };
+fn merge_files(args: &Args, filenames: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+    let comparator = Comparator::new(args)?;
 
-    for path in paths {
-        let mut input_file = path;
+    let mut inputs = Vec::with_capacity(filenames.len());
+    for path in filenames {
+        inputs.push(LineReader::open(path, &args.output_file)?);
+    }
+    let mut pending = Vec::with_capacity(inputs.len());
+    for input in &mut inputs {
+        pending.push(
+            input
+                .next_line()?
+                .map(|line| comparator.prepare_owned(line)),
+        );
+    }
+
+    let mut writer = open_output(&args.output_file)?;
+    let mut last: Option<SortLine> = None;
 
-        // Copy the contents of the input file to the output file or stdout
-        io::copy(&mut input_file, &mut output_file)?;
+    loop {
+        let mut least: Option<usize> = None;
+        for (i, line) in pending.iter().enumerate() {
+            let Some(line) = line else {
+                continue;
+            };
+            match least.and_then(|j| pending[j].as_ref()) {
+                Some(other) if comparator.compare(line, other) != Ordering::Less => {}
+                _ => least = Some(i),
+            }
+        }
+        let Some(i) = least else {
+            break;
+        };
+
+        let next = inputs[i]
+            .next_line()?
+            .map(|line| comparator.prepare_owned(line));
+        let line = std::mem::replace(&mut pending[i], next).unwrap();
+        if args.unique {
+            if let Some(last) = &last {
+                if comparator.compare_keys(last, &line) == Ordering::Equal {
+                    continue;
+                }
+            }
+        }
+        writeln!(writer, "{}", line.line)?;
+        last = Some(line);
     }
+    writer.flush()?;
 
     Ok(())
 }
 
-/// Merges consecutive empty strings in the input vector with the nearest non-empty string.
-/// Spaces are added to empty strings to align them with the nearest non-empty string.
+/// Checks that the lines of an input file are in order.
+///
+/// Only the previous line is held in memory.  With `-u`, lines with equal
+/// keys are also out of order.  Unless `-C` was given, the first line
+/// found out of order is reported on standard error.
 ///
 /// # Arguments
 ///
-/// * `vec` - A vector of string references (`&str`).
+/// * `args` - A reference to an `Args` struct containing sorting and configuration options.
+/// * `path` - The input file.
 ///
 /// # Returns
 ///
-/// A vector of strings (`Vec<String>`) where consecutive empty strings are merged with the nearest non-empty string.
-///
-fn merge_empty_lines(vec: Vec<&str>) -> Vec<String> {
-    let mut empty_count = 0;
-    let mut result = vec![];
-
-    for i in 0..vec.len() {
-        if vec[i].is_empty() {
-            empty_count += 1;
-        } else if empty_count > 0 {
-            let spaces = " ".repeat(empty_count);
-            result.push(format!("{}{}", spaces, vec[i]));
-            empty_count = 0;
-        } else {
-            result.push(vec[i].to_string());
+/// A `Result` with `true` if the file is in order, or an error if the
+/// sort keys are not valid or the file cannot be read.
+///
+fn check_order(args: &Args, path: &PathBuf) -> Result<bool, Box<dyn std::error::Error>> {
+    let comparator = Comparator::new(args)?;
+    let mut input = LineReader::open(path, &None)?;
+
+    let mut prev: Option<SortLine> = None;
+    let mut index = 0;
+    while let Some(line) = input.next_line()? {
+        index += 1;
+        let line = comparator.prepare_owned(line);
+        if let Some(prev) = &prev {
+            let disorder = match comparator.compare(prev, &line) {
+                Ordering::Greater => true,
+                _ => args.unique && comparator.compare_keys(prev, &line) == Ordering::Equal,
+            };
+            if disorder {
+                if args.check_order {
+                    eprintln!(
+                        "sort: {}:{}: disorder: {}",
+                        path.display(),
+                        index,
+                        line.line
+                    );
+                }
+                return Ok(false);
+            }
+        }
+        prev = Some(line);
+    }
+
+    Ok(true)
+}
+
+/// Reads the lines of an input file, or of standard input for "-", one
+/// at a time.
+struct LineReader {
+    path: PathBuf,
+    lines: io::Lines<io::BufReader<Box<dyn Read>>>,
+}
+
+impl LineReader {
+    /// Opens `path`.  An input that is also the output file is read into
+    /// memory at once, before the output truncates it.
+    fn open(
+        path: &PathBuf,
+        output_path: &Option<PathBuf>,
+    ) -> Result<LineReader, Box<dyn std::error::Error>> {
+        let error = |e: io::Error| format!("sort: {}: {}", path.display(), e);
+        let mut reader = plib::io::input_reader(path, true).map_err(error)?;
+
+        let is_output = match output_path {
+            Some(output) if path.as_os_str() != "-" => {
+                match (fs::metadata(path), fs::metadata(output)) {
+                    (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+                    _ => false,
+                }
+            }
+            _ => false,
+        };
+        if is_output {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).map_err(error)?;
+            reader = io::BufReader::new(Box::new(io::Cursor::new(data)));
         }
+
+        Ok(LineReader {
+            path: path.clone(),
+            lines: reader.lines(),
+        })
     }
 
-    result
+    fn next_line(&mut self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        match self.lines.next() {
+            Some(Ok(line)) => Ok(Some(line)),
+            Some(Err(e)) => Err(format!("sort: {}: {}", self.path.display(), e).into()),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Reads the lines of an input file, or of standard input for "-".
+fn read_lines(path: &PathBuf) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let reader = plib::io::input_reader(path, true)
+        .map_err(|e| format!("sort: {}: {}", path.display(), e))?;
+
+    let lines = reader
+        .lines()
+        .collect::<io::Result<Vec<String>>>()
+        .map_err(|e| format!("sort: {}: {}", path.display(), e))?;
+    Ok(lines)
 }
 
 /// Sorts the contents of input files or standard input based on specified criteria.
 ///
 /// This function takes an `Args` struct containing sorting options and configuration and sorts
 /// the contents of input files or standard input accordingly. It supports sorting by key ranges,
-/// dictionary ordering, case folding, numeric sorting, and other options. The sorted contents
-/// can be written to the output or merged directly if specified.
+/// dictionary ordering, case folding, numeric sorting, and other options. With `-m` the
+/// already sorted inputs are merged, and with `-c` or `-C` the single input is only checked.
 ///
 /// # Arguments
 ///
@@ -982,54 +842,59 @@ fn merge_empty_lines(vec: Vec<&str>) -> Vec<String> {
 /// # Returns
 ///
 /// A `Result` indicating success or failure:
-/// * `Ok(())` if the sorting process completes successfully.
+/// * `Ok(true)` if the sorting process completes successfully, or the checked input is in order.
+/// * `Ok(false)` if the checked input is not in order.
 /// * `Err(Box<dyn Error>)` if an error occurs during sorting or merging.
 ///
-fn sort(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
-    let mut readers: Vec<Box<dyn Read>> = if (args.filenames.len() == 1
-        && args.filenames[0] == PathBuf::from("-"))
-        || args.filenames.is_empty()
-    {
-        vec![Box::new(io::stdin().lock())]
+fn sort(args: &Args) -> Result<bool, Box<dyn std::error::Error>> {
+    let filenames = if args.filenames.is_empty() {
+        vec![PathBuf::from("-")]
     } else {
-        let mut bufs: Vec<Box<dyn Read>> = vec![];
-        for file in &args.filenames {
-            bufs.push(Box::new(std::fs::File::open(file)?))
-        }
-        bufs
+        args.filenames.clone()
     };
 
+    if args.check_order || args.check_order_without_war_mess {
+        if filenames.len() > 1 {
+            return Err("sort: only one file may be checked for order".into());
+        }
+        return check_order(args, &filenames[0]);
+    }
+
     if args.merge_only {
-        merge_files(&mut readers, &args.output_file)?;
-        return Ok(());
+        merge_files(args, &filenames)?;
+        return Ok(true);
     }
-    let mut all_lines: Vec<String> = Vec::new();
-    for reader in readers {
-        let reader = io::BufReader::new(reader);
-        let lines: Vec<String> = reader.lines().map(|l| l.unwrap()).collect();
-        all_lines.extend(lines);
+
+    // all input is read before the output is created, as the output file
+    // may also be an input
+    let mut files = Vec::with_capacity(filenames.len());
+    for path in &filenames {
+        files.push(read_lines(path)?);
     }
-    sort_lines(args, all_lines)?;
+    sort_lines(args, files.concat())?;
 
-    Ok(())
+    Ok(true)
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // parse command line arguments
     let args = Args::parse();
 
-    args.validate_args()?;
-
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain(PROJECT_NAME)?;
-    bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
-
-    let mut exit_code = 0;
+    i18n::init();
 
-    if let Err(err) = sort(&args) {
-        exit_code = 1;
-        eprintln!("{}", err);
-    }
+    // 1 is reserved for input found out of order by -c or -C
+    let exit_code = match args
+        .validate_args()
+        .map_err(|e| e.into())
+        .and_then(|_| sort(&args))
+    {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(err) => {
+            eprintln!("{}", err);
+            2
+        }
+    };
 
     std::process::exit(exit_code)
 }
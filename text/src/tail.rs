@@ -1,15 +1,20 @@
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use notify_debouncer_full::new_debouncer;
-use notify_debouncer_full::notify::event::{ModifyKind, RemoveKind};
-use notify_debouncer_full::notify::{EventKind, RecursiveMode, Watcher};
+use notify_debouncer_full::notify::{self, RecursiveMode, Watcher};
 use plib::PROJECT_NAME;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::fs::{self, File};
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::time::Duration;
 
+/// How often followed files are checked when no change notification
+/// arrives, or when notification is not available at all.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 /// A wrapper type for `isize` that defaults to negative values if no sign is provided.
 ///
 /// The `SignedIsize` struct is a simple wrapper around the `isize` type, designed to parse
@@ -57,8 +62,13 @@ struct Args {
     #[arg(short = 'f')]
     follow: bool,
 
-    /// The file to read
-    file: Option<PathBuf>,
+    /// Like -f, but follow the file by name: reopen it when it is
+    /// rotated, replaced or recreated
+    #[arg(short = 'F')]
+    follow_name: bool,
+
+    /// The files to read
+    files: Vec<PathBuf>,
 }
 
 impl Args {
@@ -293,53 +303,263 @@ fn print_last_n_bytes<R: Read>(
 
 /// Prints a slice of bytes to standard output.
 ///
-/// This function attempts to interpret the byte slice as a UTF-8 string and prints it.
-/// If the byte slice is not valid UTF-8, it prints each byte as a character.
+/// The bytes are written as they are, so that input which is not valid
+/// UTF-8 passes through unchanged.
 ///
 /// # Arguments
 /// * `bytes` - A slice of bytes to print.
 ///
 fn print_bytes(bytes: &[u8]) {
-    match std::str::from_utf8(bytes) {
-        Ok(valid_str) => print!("{}", valid_str),
-        Err(_) => {
-            for byte in bytes {
-                print!("{}", *byte as char);
+    let _ = io::stdout().write_all(bytes);
+}
+
+/// A file being followed with `-f` or `-F`.
+struct Followed {
+    path: PathBuf,
+    file: Option<File>,
+    /// Device and inode of the open file, to notice replacement under `-F`.
+    id: Option<(u64, u64)>,
+    /// Whether the open file is a regular file, whose length tells
+    /// whether there is new data; anything else is read until EOF.
+    regular: bool,
+    /// Offset up to which the file has been printed.
+    pos: u64,
+    /// Set once the file is reported missing, so that it is reported once.
+    missing: bool,
+}
+
+impl Followed {
+    /// Starts following `path` where the initial output stopped reading
+    /// `file`, so that nothing appended in between is lost.  Without a
+    /// file, `path` could not be opened.
+    fn new(path: &Path, file: Option<File>) -> Followed {
+        let mut followed = Followed {
+            path: path.to_path_buf(),
+            file: None,
+            id: None,
+            regular: true,
+            pos: 0,
+            missing: file.is_none(),
+        };
+
+        if let Some(mut file) = file {
+            if let Ok(pos) = file.stream_position() {
+                followed.pos = pos;
+            }
+            followed.open(file);
+        }
+
+        followed
+    }
+
+    /// Makes `file` the followed file.
+    fn open(&mut self, file: File) {
+        match file.metadata() {
+            Ok(md) => {
+                self.id = Some((md.dev(), md.ino()));
+                self.regular = md.is_file();
+                // a FIFO with a writer but no data must not block the others
+                if md.file_type().is_fifo() || md.file_type().is_char_device() {
+                    unsafe {
+                        let flags = libc::fcntl(file.as_raw_fd(), libc::F_GETFL);
+                        libc::fcntl(file.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK);
+                    }
+                }
+            }
+            Err(_) => self.id = None,
+        }
+        self.file = Some(file);
+    }
+
+    /// Reads whatever was appended to the open file since the last check,
+    /// starting over if the file was truncated.
+    fn read_new_data(&mut self, data: &mut Vec<u8>) -> io::Result<()> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+
+        if !self.regular {
+            return match file.read_to_end(data) {
+                // what was read before running out is kept in data
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(()),
+                result => result.map(|_| ()),
+            };
+        }
+
+        let len = file.metadata()?.len();
+        if len < self.pos {
+            eprintln!("\ntail: {}: file truncated", self.path.display());
+            self.pos = 0;
+        }
+        if len > self.pos {
+            file.seek(SeekFrom::Start(self.pos))?;
+            self.pos += file.read_to_end(data)? as u64;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the data appended since the last check.  With `by_name`,
+    /// also notices the path being removed, renamed away or replaced;
+    /// the old file is drained before the new one is opened.
+    fn check(&mut self, by_name: bool) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.read_new_data(&mut data)?;
+
+        if !by_name {
+            return Ok(data);
+        }
+
+        match fs::metadata(&self.path) {
+            Ok(md) if self.id != Some((md.dev(), md.ino())) => {
+                let file = match File::open(&self.path) {
+                    Ok(file) => file,
+                    // raced with another rename; try again next time
+                    Err(_) => return Ok(data),
+                };
+                if self.file.is_some() {
+                    eprintln!(
+                        "tail: '{}' has been replaced; following new file",
+                        self.path.display()
+                    );
+                } else {
+                    eprintln!(
+                        "tail: '{}' has appeared; following new file",
+                        self.path.display()
+                    );
+                }
+                self.open(file);
+                self.pos = 0;
+                self.missing = false;
+                self.read_new_data(&mut data)?;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                if !self.missing {
+                    eprintln!(
+                        "tail: '{}' has become inaccessible: {}",
+                        self.path.display(),
+                        e
+                    );
+                    self.missing = true;
+                }
+                self.file = None;
+                self.id = None;
             }
         }
+
+        Ok(data)
     }
 }
 
-/// The main logic for the `tail` command.
+/// Prints the `==> name <==` header used when several files are shown.
+fn print_header(path: &Path, first: bool) {
+    let name = if path.as_os_str() == "-" {
+        "standard input".into()
+    } else {
+        path.display().to_string()
+    };
+    if first {
+        println!("==> {} <==", name);
+    } else {
+        println!("\n==> {} <==", name);
+    }
+}
+
+/// Follows `files` until killed, printing data as it is appended.
 ///
-/// This function processes the command-line arguments to determine how many lines or bytes
-/// to print from the end of a specified file or standard input. It supports an option to
-/// follow the file, printing new data as it is appended to the file.
+/// Change notification (inotify, kqueue, ...) is used to wake up as
+/// soon as something happens; the files are also checked every
+/// `POLL_INTERVAL`, which is all that happens when no watcher can be
+/// set up.  Parent directories are watched rather than the files
+/// themselves, so that `-F` sees files being recreated.
 ///
 /// # Arguments
-/// * `args` - The command-line arguments parsed into an `Args` struct.
-///
-/// # Returns
-/// * `Ok(())` - If the operation completes successfully.
-/// * `Err(Box<dyn std::error::Error>)` - If an error occurs during the operation.
+/// * `files` - The files to follow; standard input is not followed.
+/// * `opened` - For each of `files`, the file as left by the initial
+///   output, if it could be opened.
+/// * `by_name` - Follow by name (`-F`) rather than by descriptor (`-f`).
+/// * `want_header` - Print a header whenever output switches files.
 ///
-/// # Errors
-/// This function will return an error if:
-/// - The specified file cannot be opened.
-/// - An error occurs while reading from the file or stdin.
-/// - An error occurs while watching the file for changes.
+fn follow(
+    files: &[PathBuf],
+    opened: Vec<Option<File>>,
+    by_name: bool,
+    want_header: bool,
+) -> io::Result<()> {
+    let mut followed: Vec<Followed> = files
+        .iter()
+        .zip(opened)
+        .filter(|(path, _)| path.as_os_str() != "-")
+        .map(|(path, file)| Followed::new(path, file))
+        .collect();
+    if followed.is_empty() {
+        return Ok(());
+    }
+
+    // the initial output ended with the last file operand
+    let mut last_shown = if files.last().is_some_and(|p| p.as_os_str() != "-") {
+        Some(followed.len() - 1)
+    } else {
+        None
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let watcher = notify::recommended_watcher(move |res| {
+        let _ = tx.send(res);
+    });
+    let _watcher = watcher.ok().map(|mut watcher| {
+        for f in &followed {
+            let dir = match f.path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            let _ = watcher.watch(dir, RecursiveMode::NonRecursive);
+        }
+        watcher
+    });
+
+    loop {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+            // no watcher: plain polling
+            Err(RecvTimeoutError::Disconnected) => std::thread::sleep(POLL_INTERVAL),
+        }
+
+        for (idx, f) in followed.iter_mut().enumerate() {
+            let data = f.check(by_name)?;
+            if data.is_empty() {
+                continue;
+            }
+
+            if want_header && last_shown != Some(idx) {
+                print_header(&f.path, false);
+                last_shown = Some(idx);
+            }
+            print_bytes(&data);
+            io::stdout().flush()?;
+        }
+    }
+}
+
+/// Prints the requested part of one file operand.
 ///
-fn tail(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+/// Returns the file, read to its end, so that `-f` can carry on from
+/// there; standard input is not returned.
+fn tail_file(args: &Args, path: &Path) -> Result<Option<File>, Box<dyn std::error::Error>> {
     // open file, or stdin
-    let file: Box<dyn Read> = {
-        if args.file == Some(PathBuf::from("-")) || args.file.is_none() {
-            Box::new(io::stdin().lock())
-        } else {
-            Box::new(File::open(args.file.as_ref().unwrap())?)
-        }
+    let file = if path.as_os_str() == "-" {
+        None
+    } else {
+        Some(File::open(path)?)
+    };
+    let input: Box<dyn Read + '_> = match &file {
+        Some(file) => Box::new(file),
+        None => Box::new(io::stdin().lock()),
     };
 
-    let mut reader = io::BufReader::new(file);
+    let mut reader = io::BufReader::new(input);
 
     if let Some(bytes) = &args.bytes {
         print_last_n_bytes(&mut reader, bytes.0)?;
@@ -347,64 +567,55 @@ fn tail(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
         print_last_n_lines(&mut reader, args.lines.as_ref().unwrap().0)?;
     }
 
-    // If follow option is specified, continue monitoring the file
-    if args.follow && !(args.file == Some(PathBuf::from("-")) || args.file.is_none()) {
-        let file_path = args.file.as_ref().unwrap();
-
-        // Opening a file and placing the cursor at the end of the file
-        let mut file = File::open(file_path)?;
-        file.seek(SeekFrom::End(0))?;
-        let mut reader = BufReader::new(&file);
-
-        let (tx, rx) = std::sync::mpsc::channel();
-        // Automatically select the best implementation for your platform.
-        let mut debouncer = new_debouncer(Duration::from_millis(1), None, tx).unwrap();
-
-        // Add a path to be watched.
-        // below will be monitored for changes.
-        debouncer
-            .watcher()
-            .watch(Path::new(file_path), RecursiveMode::NonRecursive)?;
-
-        for res in rx {
-            match res {
-                Ok(events) => {
-                    let event = events.first().unwrap();
-                    match event.kind {
-                        EventKind::Modify(ModifyKind::Any)
-                        | EventKind::Modify(ModifyKind::Data(_))
-                        | EventKind::Modify(ModifyKind::Other) => {
-                            // If the file has been modified, check if the file was truncated
-                            let metadata = file.metadata()?;
-                            let current_size = metadata.len();
-
-                            if current_size < reader.stream_position()? {
-                                eprintln!("\ntail: {}: file truncated", file_path.display());
-                                reader.seek(SeekFrom::Start(0))?;
-                            }
-
-                            // Read the new lines and output them
-                            let mut new_data = vec![];
-                            let bytes_read = reader.read_to_end(&mut new_data)?;
-                            if bytes_read > 0 {
-                                print_bytes(&new_data);
-                                io::stdout().flush()?;
-                            }
-                        }
-                        EventKind::Remove(RemoveKind::File) => {
-                            debouncer.watcher().unwatch(Path::new(file_path))?
-                        }
-                        _ => {}
-                    }
-                }
-                Err(e) => {
-                    eprintln!("watch error: {:?}", e);
-                }
+    drop(reader);
+    Ok(file)
+}
+
+/// The main logic for the `tail` command.
+///
+/// This function processes the command-line arguments to determine how many lines or bytes
+/// to print from the end of each specified file or standard input. It supports options to
+/// follow the files, printing new data as it is appended.
+///
+/// # Arguments
+/// * `args` - The command-line arguments parsed into an `Args` struct.
+///
+/// # Returns
+/// * `Ok(true)` - If every file was processed successfully.
+/// * `Ok(false)` - If some file could not be read; the error has been reported.
+/// * `Err(Box<dyn std::error::Error>)` - If an error occurs while following.
+///
+fn tail(args: &Args) -> Result<bool, Box<dyn std::error::Error>> {
+    let files = if args.files.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        args.files.clone()
+    };
+    let want_header = files.len() > 1;
+    let mut success = true;
+    let mut opened = Vec::with_capacity(files.len());
+
+    for (i, path) in files.iter().enumerate() {
+        if want_header {
+            print_header(path, i == 0);
+        }
+        match tail_file(args, path) {
+            Ok(file) => opened.push(file),
+            Err(e) => {
+                eprintln!("tail: {}: {}", path.display(), e);
+                success = false;
+                opened.push(None);
             }
         }
     }
+    io::stdout().flush()?;
 
-    Ok(())
+    // If follow option is specified, continue monitoring the files
+    if args.follow || args.follow_name {
+        follow(&files, opened, args.follow_name, want_header)?;
+    }
+
+    Ok(success)
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -413,12 +624,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
     let mut args = Args::parse();
     args.validate_args()?;
-    let mut exit_code = 0;
 
-    if let Err(err) = tail(&args) {
-        exit_code = 1;
-        eprint!("{}", err);
-    }
+    let exit_code = match tail(&args) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(err) => {
+            eprintln!("tail: {}", err);
+            1
+        }
+    };
 
     std::process::exit(exit_code)
 }
@@ -1,10 +1,10 @@
 use clap::Parser;
 use deunicode::deunicode_char;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::PROJECT_NAME;
-use regex::Regex;
-use std::collections::{HashMap, HashSet};
-use std::io::{self, Read};
+use plib::i18n;
+use plib::io::StdoutWriter;
+use plib::locale::{Class, Ctype};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
 
 /// tr - translate or delete characters
 #[derive(Parser, Debug)]
@@ -39,713 +39,619 @@ impl Args {
         if self.complement_char && self.complement_val {
             return Err("Options '-c' and '-C' cannot be used together".to_string());
         }
-        if self.squeeze_repeats
-            && self.string2.is_none()
-            && (self.complement_char || self.complement_val)
-            && !self.delete
-        {
-            return Err("Option '-c' or '-C' may only be used with 2 strings".to_string());
-        }
 
         if !self.squeeze_repeats && !self.delete && self.string2.is_none() {
             return Err("Need two strings operand".to_string());
         }
 
+        if self.delete && !self.squeeze_repeats && self.string2.is_some() {
+            return Err("Only one string may be given when deleting".to_string());
+        }
+
         if self.string1.is_empty() {
             return Err("At least 1 string operand is required".to_string());
         }
 
         Ok(())
     }
-}
 
-// The Char struct represents a character along with its repetition count.
-#[derive(Debug, Clone)]
-struct Char {
-    // The character.
-    char: char,
-    // The number of times the character is repeated
-    repeated: usize,
+    fn complement(&self) -> bool {
+        self.complement_char || self.complement_val
+    }
 }
 
-// The Equiv struct represents a character equivalent.
-#[derive(Debug, Clone)]
-struct Equiv {
-    // The character equivalent.
-    char: char,
+/// One element of a string operand.
+#[derive(Debug, Clone, PartialEq)]
+enum Element {
+    /// A single character.
+    Char(char),
+    /// `c-c`: the characters from the first to the second, inclusive.
+    Range(char, char),
+    /// `[:class:]`
+    Class(Class),
+    /// `[=equiv=]`: the characters that collate the same as this one.
+    Equiv(char),
+    /// `[x*n]`, or `[x*]` (`None`), which fills string2 to the length
+    /// of string1.
+    Repeat(char, Option<usize>),
 }
 
-// The Operand enum can be either a Char or an Equiv
-#[derive(Debug, Clone)]
-enum Operand {
-    Char(Char),
-    Equiv(Equiv),
+/// Checks whether `a` and `b` are in the same equivalence class.
+///
+/// In a UTF-8 locale, characters are equivalent when they transliterate
+/// to the same ASCII text, so `e`, `é` and `è` form one class.
+fn is_equiv(locale: &Ctype, a: char, b: char) -> bool {
+    if a == b {
+        return true;
+    }
+    if !locale.utf8 {
+        return false;
+    }
+    match (deunicode_char(a), deunicode_char(b)) {
+        (Some(a), Some(b)) => !a.is_empty() && a == b,
+        _ => false,
+    }
 }
 
-impl Operand {
-    /// Checks if a target character exists in a vector of `Operand` elements.
-    ///
-    /// # Arguments
-    ///
-    /// * `operands` - A reference to a vector of `Operand` elements.
-    /// * `target` - A reference to the target character to search for.
-    ///
-    /// # Returns
-    ///
-    /// `true` if the target character is found, `false` otherwise.
-    fn contains(operands: &Vec<Operand>, target: &char) -> bool {
-        for operand in operands {
-            match operand {
-                Operand::Equiv(e) => {
-                    if compare_deunicoded_chars(e.char, *target) {
-                        return true;
-                    }
-                }
-                Operand::Char(c) => {
-                    if c.char == *target {
-                        return true;
-                    }
-                }
+/// Reads the byte value of an octal escape whose first digit is `first`,
+/// taking up to two more digits while the value fits in a byte.
+fn read_octal(raw: &[char], pos: &mut usize, first: char) -> u8 {
+    let mut value = first.to_digit(8).unwrap();
+    for _ in 0..2 {
+        match raw.get(*pos).and_then(|c| c.to_digit(8)) {
+            Some(digit) if value * 8 + digit <= 0xff => {
+                value = value * 8 + digit;
+                *pos += 1;
             }
+            _ => break,
         }
-        false
     }
+    value as u8
 }
 
-/// Filters out `Char` elements from a vector of `Operand` elements.
-///
-/// # Arguments
+/// Reads one character of a string operand, decoding a backslash escape.
 ///
-/// * `operands` - A vector of `Operand` elements.
+/// Besides `\\`, the escapes are `\a`, `\b`, `\f`, `\n`, `\r`, `\t`,
+/// `\v` and `\` followed by one to three octal digits. A backslash
+/// before any other character stands for that character, and a trailing
+/// backslash for itself.
 ///
-/// # Returns
-///
-/// A vector of `Char` elements.
-fn filter_chars(operands: Vec<Operand>) -> Vec<Char> {
-    operands
-        .into_iter()
-        .filter_map(|operand| {
-            if let Operand::Char(c) = operand {
-                Some(c)
-            } else {
-                None
-            }
-        })
-        .collect()
-}
-
-/// Creates a minimal string with a given size using a vector of `Char` elements.
+/// An octal escape is always one byte. In a UTF-8 locale, a character
+/// of several bytes is written as consecutive escapes, one per byte.
 ///
 /// # Arguments
 ///
-/// * `chars` - A vector of `Char` elements.
-/// * `size` - The desired size of the resulting string.
+/// * `raw` - The characters of the operand.
+/// * `pos` - The index of the character to read, advanced past it.
+/// * `locale` - The locale that defines how bytes form characters.
 ///
 /// # Returns
 ///
-/// A vector of characters representing the minimal string.
-fn create_minimal_string(chars: Vec<Char>, size: usize) -> Vec<char> {
-    let mut result = vec![];
-    let mut remaining_space = size;
-    let mut overflow_chars: Vec<(usize, Char)> = vec![];
-
-    // Add chars with repeated == 1 to the result
-    for ch in &chars {
-        if ch.repeated == 1 {
-            if remaining_space > 0 {
-                result.push(ch.char);
-                remaining_space -= 1;
-            }
-        } else if remaining_space >= ch.repeated {
-            for _ in 0..ch.repeated {
-                result.push(ch.char);
-            }
-
-            remaining_space -= ch.repeated;
-        } else {
-            overflow_chars.push((result.len(), ch.clone()));
-        }
+/// * `char` - The decoded character.
+///
+fn read_char(raw: &[char], pos: &mut usize, locale: &Ctype) -> char {
+    let ch = raw[*pos];
+    *pos += 1;
+    if ch != '\\' || *pos >= raw.len() {
+        return ch;
     }
 
-    // Add remaining chars from overflow_chars if there's still space
-    if !overflow_chars.is_empty() {
-        for (insert_position, char) in overflow_chars.iter().rev() {
-            if remaining_space > 0 {
-                let chars_to_add = remaining_space.min(char.repeated);
-                let replace_with = vec![char.char; chars_to_add];
-                result.splice(insert_position..insert_position, replace_with);
-
-                remaining_space -= chars_to_add;
+    let escaped = raw[*pos];
+    *pos += 1;
+    match escaped {
+        'a' => '\x07',
+        'b' => '\x08',
+        'f' => '\x0c',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'v' => '\x0b',
+        '0'..='7' => {
+            let byte = read_octal(raw, pos, escaped);
+            if !locale.utf8 || byte.is_ascii() {
+                return byte as char;
             }
-        }
-    }
 
-    if result.len() < size {
-        let last = *result.last().unwrap();
-        for _ in 0..size - result.len() {
-            result.push(last);
+            let mut bytes = vec![byte];
+            loop {
+                match std::str::from_utf8(&bytes) {
+                    Ok(s) => return s.chars().next().unwrap(),
+                    // the character continues in the next escape
+                    Err(e) if e.error_len().is_none() => {}
+                    Err(_) => return char::REPLACEMENT_CHARACTER,
+                }
+                match (raw.get(*pos), raw.get(*pos + 1)) {
+                    (Some('\\'), Some(&digit @ '0'..='7')) => {
+                        *pos += 2;
+                        bytes.push(read_octal(raw, pos, digit));
+                    }
+                    _ => return char::REPLACEMENT_CHARACTER,
+                }
+            }
         }
+        other => other,
     }
-
-    result
 }
 
-/// Parses a sequence in the format `[=equiv=]` from the given character iterator.
-///
-/// The function expects the iterator to be positioned just before the first `=`
-/// character. It reads the equivalent characters between the `=` symbols and
-/// creates a list of `Operand::Equiv` entries, one for each character.
+/// Tries to parse a bracketed construct, `[:class:]`, `[=equiv=]` or
+/// `[x*n]`, starting at the `[` at `pos`.
 ///
 /// # Arguments
 ///
-/// * `chars` - A mutable reference to a peekable character iterator.
+/// * `raw` - The characters of the operand.
+/// * `pos` - The index of the `[`; advanced past the construct on success.
+/// * `locale` - The locale that defines how bytes form characters.
 ///
 /// # Returns
 ///
-/// A `Result` containing a vector of `Operand::Equiv` entries if successful, or a
-/// `String` describing the error if parsing fails.
+/// * `Result<Option<Element>, String>` - The element, or `None` when the
+///   `[` does not start a construct and so stands for itself.
 ///
 /// # Errors
 ///
-/// This function will return an error if:
-/// - The sequence does not contain a closing `=` before `]`.
-/// - The sequence does not contain a closing `]`.
-/// - The sequence contains no characters between the `=` symbols.
+/// Returns an error for an unknown class name.
 ///
-fn parse_equiv(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Vec<Operand>, String> {
-    chars.next(); // Skip '='
-    let mut equiv = String::new();
+fn parse_bracket(raw: &[char], pos: &mut usize, locale: &Ctype) -> Result<Option<Element>, String> {
+    let start = *pos + 1;
 
-    while let Some(&next_ch) = chars.peek() {
-        if next_ch == '=' {
-            break;
+    // [:class:]
+    if raw.get(start) == Some(&':') {
+        let rest: String = raw[start + 1..].iter().collect();
+        if let Some(end) = rest.find(":]") {
+            let name = &rest[..end];
+            let class = Class::from_name(name)
+                .ok_or_else(|| format!("invalid character class '{}'", name))?;
+            *pos = start + 1 + name.chars().count() + 2;
+            return Ok(Some(Element::Class(class)));
         }
-        equiv.push(next_ch);
-        chars.next();
     }
 
-    if equiv.is_empty() {
-        return Err("Error: Missing equiv symbol after '[='".to_string());
+    // [=equiv=]
+    if raw.get(start) == Some(&'=') && start + 1 < raw.len() {
+        let mut next = start + 1;
+        let ch = read_char(raw, &mut next, locale);
+        if raw.get(next) == Some(&'=') && raw.get(next + 1) == Some(&']') {
+            *pos = next + 2;
+            return Ok(Some(Element::Equiv(ch)));
+        }
     }
 
-    // Skip '='
-    let Some('=') = chars.next() else {
-        return Err("Error: Missing '=' before ']' for '[=equiv=]'".to_string());
-    };
-
-    // Skip ']'
-    let Some(']') = chars.next() else {
-        return Err("Error: Missing closing ']' for '[=equiv=]'".to_string());
-    };
-
-    let mut operands = Vec::new();
-    for equiv_char in equiv.chars() {
-        operands.push(Operand::Equiv(Equiv { char: equiv_char }));
+    // [x*n]: a count with a leading zero is octal, and an empty or zero
+    // count fills string2
+    if start < raw.len() {
+        let mut next = start;
+        let ch = read_char(raw, &mut next, locale);
+        if raw.get(next) == Some(&'*') {
+            let digits: String = raw[next + 1..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .collect();
+            let close = next + 1 + digits.len();
+            if raw.get(close) == Some(&']') {
+                let radix = if digits.starts_with('0') { 8 } else { 10 };
+                let count = match digits.as_str() {
+                    "" => None,
+                    _ => match usize::from_str_radix(&digits, radix) {
+                        Ok(0) => None,
+                        Ok(n) => Some(n),
+                        Err(_) => return Err(format!("invalid repeat count '{}'", digits)),
+                    },
+                };
+                *pos = close + 1;
+                return Ok(Some(Element::Repeat(ch, count)));
+            }
+        }
     }
 
-    Ok(operands)
+    Ok(None)
 }
 
-/// Parses a sequence in the format `[x*n]` from the given character iterator.
-///
-/// The function expects the iterator to be positioned just before the symbol `x`.
-/// It reads the symbol, the repetition count `n`, and creates an `Operand::Char`
-/// entry with the specified number of repetitions.
+/// Parses a string operand into its elements.
 ///
 /// # Arguments
 ///
-/// * `chars` - A mutable reference to a peekable character iterator.
-/// * `symbol` - The symbol that is being repeated.
+/// * `input` - The operand as given on the command line.
+/// * `locale` - The locale that defines how bytes form characters.
 ///
 /// # Returns
 ///
-/// A `Result` containing an `Operand::Char` entry if successful, or a `String`
-/// describing the error if parsing fails.
+/// * `Result<Vec<Element>, String>` - The elements, in order.
 ///
 /// # Errors
 ///
-/// This function will return an error if:
-/// - The sequence does not contain a `*` after the symbol.
-/// - The sequence does not contain a closing `]`.
-/// - The repetition count `n` is not a valid positive integer.
-///
-fn parse_repeated_char(
-    chars: &mut std::iter::Peekable<std::str::Chars>,
-    symbol: char,
-) -> Result<Operand, String> {
-    // Skip '*'
-    let Some('*') = chars.next() else {
-        return Err(format!(
-            "Error: Missing '*' after '[' for symbol '{}'",
-            symbol
-        ));
+/// Returns an error for an unknown class name, a bad repeat count, or a
+/// range whose end comes before its start.
+///
+fn parse_operand(input: &str, locale: &Ctype) -> Result<Vec<Element>, String> {
+    // outside a UTF-8 locale every byte is a character
+    let raw: Vec<char> = if locale.utf8 {
+        input.chars().collect()
+    } else {
+        input.bytes().map(char::from).collect()
     };
+    let mut elements = Vec::new();
+    let mut pos = 0;
 
-    let mut repeat_str = String::new();
-    while let Some(&digit) = chars.peek() {
-        if !digit.is_ascii_digit() {
-            break;
+    while pos < raw.len() {
+        if raw[pos] == '[' {
+            if let Some(element) = parse_bracket(&raw, &mut pos, locale)? {
+                elements.push(element);
+                continue;
+            }
         }
-        repeat_str.push(digit);
-        chars.next();
-    }
 
-    // Skip ']'
-    let Some(']') = chars.next() else {
-        return Err("Error: Missing closing ']'".to_string());
-    };
-
-    let repeated = match repeat_str.parse::<usize>() {
-        Ok(n) if n > 0 => n,
-        _ => usize::MAX,
-    };
+        let ch = read_char(&raw, &mut pos, locale);
+        if raw.get(pos) == Some(&'-') && pos + 1 < raw.len() {
+            let mut next = pos + 1;
+            let end = read_char(&raw, &mut next, locale);
+            if end < ch {
+                return Err(format!(
+                    "range-endpoints of '{}-{}' are in reverse collating sequence order",
+                    ch, end
+                ));
+            }
+            elements.push(Element::Range(ch, end));
+            pos = next;
+        } else {
+            elements.push(Element::Char(ch));
+        }
+    }
 
-    Ok(Operand::Char(Char {
-        char: symbol,
-        repeated,
-    }))
+    Ok(elements)
 }
 
-/// Parses an input string and converts it into a vector of `Operand` entries.
-///
-/// This function processes the input string, looking for sequences in the formats
-/// `[=equiv=]` and `[x*n]`, as well as regular characters. It delegates the parsing
-/// of the specific formats to helper functions `parse_equiv` and `parse_repeated_char`.
+/// Checks whether `c` is one of the characters of a parsed operand.
 ///
 /// # Arguments
 ///
-/// * `input` - A string slice containing the input to be parsed.
+/// * `elements` - The parsed operand.
+/// * `c` - The character to look for.
+/// * `locale` - The locale that defines classes and equivalence classes.
 ///
 /// # Returns
 ///
-/// A `Result` containing a vector of `Operand` entries if successful, or a `String`
-/// describing the error if parsing fails.
+/// * `bool` - `true` if any element matches `c`.
 ///
-/// # Errors
-///
-/// This function will return an error if:
-/// - It encounters an invalid format.
-/// - It encounters any specific error from `parse_equiv` or `parse_repeated_char`.
-///
-fn parse_symbols(input: &str) -> Result<Vec<Operand>, String> {
-    let mut operands: Vec<Operand> = Vec::new();
-    let mut chars = input.chars().peekable();
-
-    while let Some(&ch) = chars.peek() {
-        if ch == '[' {
-            chars.next(); // Skip '['
-            let Some(&'=') = chars.peek() else {
-                let symbol = chars
-                    .next()
-                    .ok_or("Error: Missing symbol after '['".to_string())?;
-                operands.push(parse_repeated_char(&mut chars, symbol)?);
-                continue;
-            };
-
-            operands.extend(parse_equiv(&mut chars)?);
-        } else {
-            // Add a regular character with a repetition of 1
-            operands.push(Operand::Char(Char {
-                char: ch,
-                repeated: 1,
-            }));
-            chars.next();
-        }
-    }
-
-    Ok(operands)
+fn set_contains(elements: &[Element], c: char, locale: &Ctype) -> bool {
+    elements.iter().any(|element| match *element {
+        Element::Char(ch) | Element::Repeat(ch, _) => ch == c,
+        Element::Range(start, end) => (start..=end).contains(&c),
+        Element::Class(class) => locale.is_class(class, c),
+        Element::Equiv(ch) => is_equiv(locale, ch, c),
+    })
 }
 
-/// Represents the case sensitivity of character classes.
-#[derive(Debug, PartialEq)]
-enum CaseSensitive {
-    UpperCase,
-    LowerCase,
-    None,
-}
+/// The expanded string1: its characters, and where each case class
+/// starts in them.
+type ExpandedString1 = (Vec<char>, Vec<(usize, Class)>);
 
-/// Compares two characters after normalizing them.
-/// This function uses the hypothetical `deunicode_char` function to normalize
-/// the input characters and then compares them for equality.
-/// # Arguments
-///
-/// * `char1` - The first character to compare.
-/// * `char2` - The second character to compare.
+/// Expands string1 into the ordered list of characters to translate.
 ///
-/// # Returns
-///
-/// * `true` if the normalized characters are equal.
-/// * `false` otherwise.
-fn compare_deunicoded_chars(char1: char, char2: char) -> bool {
-    let normalized_char1 = deunicode_char(char1);
-    let normalized_char2 = deunicode_char(char2);
-    normalized_char1 == normalized_char2
-}
-
-/// Expands a character class name into a vector of `Operand` elements and determines the case sensitivity.
-/// This function expands the given character class name into its corresponding characters
-/// and wraps them in `Operand::Char`. It also determines the case sensitivity of the class
-/// based on the class name. If the class name is invalid, it returns an error message.
 /// # Arguments
 ///
-/// * `class` - A string slice representing the character class name.
+/// * `elements` - The parsed string1.
+/// * `locale` - The locale that defines classes and equivalence classes.
 ///
 /// # Returns
 ///
-/// * `Result<(Vec<Operand>, CaseSensitive), String>` - On success, returns a tuple containing a vector of `Operand` elements and a `CaseSensitive` value.
-///   On failure, returns an error message.
-fn expand_character_class(class: &str) -> Result<(Vec<Operand>, CaseSensitive), String> {
-    let mut case_sensitive = CaseSensitive::None;
-    let result = match class {
-        "alnum" => ('0'..='9').chain('A'..='Z').chain('a'..='z').collect(),
-        "alpha" => ('A'..='Z').chain('a'..='z').collect(),
-        "digit" => ('0'..='9').collect(),
-        "lower" => {
-            case_sensitive = CaseSensitive::LowerCase;
-            ('a'..='z').collect()
-        }
-        "upper" => {
-            case_sensitive = CaseSensitive::UpperCase;
-            ('A'..='Z').collect()
-        }
-        "space" => vec![' ', '\t', '\n', '\r', '\x0b', '\x0c'],
-        "blank" => vec![' ', '\t'],
-        "cntrl" => (0..=31)
-            .chain(std::iter::once(127))
-            .map(|c| c as u8 as char)
-            .collect(),
-        "graph" => (33..=126).map(|c| c as u8 as char).collect(),
-        "print" => (32..=126).map(|c| c as u8 as char).collect(),
-        "punct" => (33..=47)
-            .chain(58..=64)
-            .chain(91..=96)
-            .chain(123..=126)
-            .map(|c| c as u8 as char)
-            .collect(),
-        "xdigit" => ('0'..='9').chain('A'..='F').chain('a'..='f').collect(),
-        _ => return Err("Error: Invalid class name ".to_string()),
-    };
-    Ok((
-        result
-            .into_iter()
-            .map(|c| {
-                Operand::Char(Char {
-                    char: c,
-                    repeated: 1,
-                })
-            })
-            .collect(),
-        case_sensitive,
-    ))
-}
-
-/// Parses character classes from a string input and expands them into a vector of `Operand` elements.
-///
-/// This function parses the input string for character classes in the `[:class:]` format and expands them
-/// using the `expand_character_class` function. It collects the resulting `Operand` elements and determines
-/// the case sensitivity of the character classes encountered.
+/// * `Result<ExpandedString1, String>` - The characters,
+///   and where each `[:upper:]` or `[:lower:]` class starts in them, so
+///   that a matching class in string2 can convert case.
 ///
-/// If an invalid format is detected, the function returns an error message.
-/// # Arguments
-///
-/// * `input` - A string slice containing the input to parse.
+/// # Errors
 ///
-/// # Returns
+/// Returns an error for a `[x*]` without a count, which only string2 may
+/// use.
 ///
-/// * `Result<(Vec<Operand>, CaseSensitive), String>` - On success, returns a tuple containing a vector of `Operand` elements and a `CaseSensitive` value.
-///   On failure, returns an error message.
-fn parse_classes(input: &str) -> Result<(Vec<Operand>, CaseSensitive), String> {
-    let mut classes: Vec<Operand> = Vec::new();
-    let mut chars = input.chars().peekable();
-    let mut case_sensitive = CaseSensitive::None;
-    while let Some(ch) = chars.next() {
-        if ch == '[' {
-            let Some(':') = chars.next() else {
-                continue;
-            };
-            // Processing the [:class:] format
-
-            let mut class = String::new();
-            while let Some(&next_ch) = chars.peek() {
-                if next_ch == ':' {
-                    break;
+fn expand_string1(elements: &[Element], locale: &Ctype) -> Result<ExpandedString1, String> {
+    let mut chars = Vec::new();
+    let mut case_classes = Vec::new();
+
+    for element in elements {
+        match *element {
+            Element::Char(ch) => chars.push(ch),
+            Element::Range(start, end) => chars.extend(start..=end),
+            Element::Class(class) => {
+                if class == Class::Upper || class == Class::Lower {
+                    case_classes.push((chars.len(), class));
                 }
-                class.push(next_ch);
-                chars.next();
+                chars.extend(locale.universe().filter(|&c| locale.is_class(class, c)));
             }
-            if class.is_empty() {
-                return Err("Error: Missing class name after '[:'".to_string());
+            Element::Equiv(ch) => {
+                chars.push(ch);
+                if locale.utf8 {
+                    chars.extend(
+                        locale
+                            .universe()
+                            .filter(|&c| c != ch && is_equiv(locale, ch, c)),
+                    );
+                }
+            }
+            Element::Repeat(ch, Some(count)) => chars.extend(std::iter::repeat_n(ch, count)),
+            Element::Repeat(_, None) => {
+                return Err("the [c*] construct may only appear in string2".to_string())
             }
-            // Skip ':'
-            let Some(':') = chars.next() else {
-                return Err("Error: Missing ':' before ']' for '[:class:]'".to_string());
-            };
-            // Skip ']'
-            let Some(']') = chars.next() else {
-                return Err("Error: Missing closing ']' for '[:class:]'".to_string());
-            };
-
-            let res = expand_character_class(&class)?;
-            case_sensitive = res.1;
-            classes.extend(res.0);
         }
     }
 
-    Ok((classes, case_sensitive))
+    Ok((chars, case_classes))
 }
 
-/// Parses an octal string and returns the corresponding character, if valid.
-///
-/// # Arguments
-///
-/// * `s` - A string slice that holds the octal representation of the character.
-///
-/// # Returns
-///
-/// * `Option<char>` - Returns `Some(char)` if the input string is a valid octal
-///   representation of a Unicode character. Returns `None` if the string is
-///   not a valid octal number or if the resulting number does not correspond
-///   to a valid Unicode character.
-///
-fn parse_octal(s: &str) -> Option<char> {
-    u32::from_str_radix(s, 8).ok().and_then(char::from_u32)
+/// A run-length encoded list of characters, for string2, whose `[x*]`
+/// may expand to more than a million characters when string1 is
+/// complemented.
+struct Sequence {
+    /// Each character with the number of times it repeats.
+    runs: Vec<(char, usize)>,
+    /// The cumulative length at the end of each run.
+    ends: Vec<usize>,
+}
+
+impl Sequence {
+    fn new(runs: Vec<(char, usize)>) -> Sequence {
+        let runs: Vec<(char, usize)> = runs.into_iter().filter(|&(_, n)| n > 0).collect();
+        let mut ends = Vec::with_capacity(runs.len());
+        let mut total: usize = 0;
+        for &(_, count) in &runs {
+            total = total.saturating_add(count);
+            ends.push(total);
+        }
+        Sequence { runs, ends }
+    }
+
+    fn len(&self) -> usize {
+        self.ends.last().copied().unwrap_or(0)
+    }
+
+    fn last(&self) -> Option<char> {
+        self.runs.last().map(|&(ch, _)| ch)
+    }
+
+    /// The character at `index`, or the last character past the end,
+    /// which pads string2 to the length of string1.
+    fn get(&self, index: usize) -> Option<char> {
+        let run = self.ends.partition_point(|&end| end <= index);
+        match self.runs.get(run) {
+            Some(&(ch, _)) => Some(ch),
+            None => self.last(),
+        }
+    }
 }
 
-/// Parses a string representing a range of characters or octal values and returns a vector of `Operand`s.
+/// Expands string2 to translate the `len1` characters of string1.
 ///
-/// This function handles ranges specified in square brackets, such as `[a-z]` or `[\\141-\\172]`.
-/// It supports ranges of plain characters and ranges of octal-encoded characters. The function
-/// trims the square brackets from the input string, splits the range into start and end parts,
-/// and then expands the range into a list of `Operand`s.
+/// `[x*]` repeats `x` as often as needed to make string2 as long as
+/// string1. A `[:upper:]` or `[:lower:]` class that lines up with the
+/// opposite class in string1 holds the case-converted characters of that
+/// class, in the same order.
 ///
 /// # Arguments
 ///
-/// * `input` - A string slice containing the range to be parsed. The range can be in the form of
-///   `[a-z]`, `[\\141-\\172]`, etc.
+/// * `elements` - The parsed string2.
+/// * `len1` - The number of characters in the expanded string1.
+/// * `case_classes` - Where the case classes of string1 start.
+/// * `chars1` - The expanded string1, when it is not complemented.
+/// * `locale` - The locale that defines classes and case conversion.
 ///
 /// # Returns
 ///
-/// * `Result<Vec<Operand>, String>` - Returns `Ok(Vec<Operand>)` if the input string represents
-///   a valid range. Returns `Err(String)` with an error message if the input is invalid.
+/// * `Result<Sequence, String>` - The expanded string2.
 ///
 /// # Errors
 ///
-/// This function returns an error if:
-/// - The input string does not contain a valid range.
-/// - The octal values in the range cannot be parsed into valid characters.
-///
-fn parse_ranges(input: &str) -> Result<Vec<Operand>, String> {
-    let mut chars = Vec::new();
-    let s = input.trim_matches(|c| c == '[' || c == ']'); // Remove square brackets
-    let parts: Vec<&str> = s.split('-').collect();
-
-    let start = parts[0];
-    let end = parts[1];
-
-    if start.starts_with('\\') && end.starts_with('\\') {
-        // Processing the \octal-\octal range
-        if let (Some(start_char), Some(end_char)) =
-            (parse_octal(&start[1..]), parse_octal(&end[1..]))
-        {
-            let start_u32 = start_char as u32;
-            let end_u32 = end_char as u32;
-
-            for code in start_u32..=end_u32 {
-                if let Some(c) = char::from_u32(code) {
-                    chars.push(c);
+/// Returns an error for a class other than `[:upper:]` and `[:lower:]`,
+/// or for more than one `[x*]`.
+///
+fn expand_string2(
+    elements: &[Element],
+    len1: usize,
+    case_classes: &[(usize, Class)],
+    chars1: &[char],
+    locale: &Ctype,
+) -> Result<Sequence, String> {
+    let mut runs: Vec<(char, usize)> = Vec::new();
+    let mut fill_at = None;
+
+    for element in elements {
+        let offset: usize = runs.iter().map(|&(_, n)| n).sum();
+        match *element {
+            Element::Char(ch) | Element::Equiv(ch) => runs.push((ch, 1)),
+            Element::Range(start, end) => runs.extend((start..=end).map(|c| (c, 1))),
+            Element::Repeat(ch, Some(count)) => runs.push((ch, count)),
+            Element::Repeat(ch, None) => {
+                if fill_at.is_some() {
+                    return Err("only one [c*] construct may appear in string2".to_string());
                 }
+                fill_at = Some(runs.len());
+                runs.push((ch, 0));
+            }
+            Element::Class(class @ (Class::Upper | Class::Lower)) => {
+                let upper = class == Class::Upper;
+                let opposite = if upper { Class::Lower } else { Class::Upper };
+                let aligned = case_classes
+                    .iter()
+                    .any(|&(start, c)| start == offset && c == opposite);
+                if aligned {
+                    let count = locale
+                        .universe()
+                        .filter(|&c| locale.is_class(opposite, c))
+                        .count();
+                    runs.extend(
+                        chars1[offset..offset + count]
+                            .iter()
+                            .map(|&c| (locale.to_case(c, upper), 1)),
+                    );
+                } else {
+                    runs.extend(
+                        locale
+                            .universe()
+                            .filter(|&c| locale.is_class(class, c))
+                            .map(|c| (c, 1)),
+                    );
+                }
+            }
+            Element::Class(_) => {
+                return Err(
+                    "only [:upper:] and [:lower:] may appear in string2 when translating"
+                        .to_string(),
+                )
             }
         }
-    } else if !start.starts_with('\\') && !end.starts_with('\\') {
-        // Processing the c-c range
-        let start_char = start.chars().next().unwrap();
-        let end_char = end.chars().next().unwrap();
+    }
 
-        for c in start_char..=end_char {
-            chars.push(c);
-        }
+    if let Some(index) = fill_at {
+        let others: usize = runs.iter().map(|&(_, n)| n).sum();
+        runs[index].1 = len1.saturating_sub(others);
     }
 
-    Ok(chars
-        .into_iter()
-        .map(|c| {
-            Operand::Char(Char {
-                char: c,
-                repeated: 1,
-            })
-        })
-        .collect())
+    Ok(Sequence::new(runs))
 }
 
-/// Parses a set expression and returns a vector of `Operand`s and a `CaseSensitive` flag.
-///
-/// The function determines the type of set based on its format and delegates
-/// the parsing to the appropriate helper function. The supported formats are:
-/// - Character classes in the form of `[:class:]`
-/// - Single ranges such as `[a-z]` or `[\\141-\\172]`
-/// - Symbols and characters not fitting the above categories
-///
-/// # Arguments
-///
-/// * `set` - A string slice representing the set expression to be parsed.
-///
-/// # Returns
-///
-/// * `Result<(Vec<Operand>, CaseSensitive), String>` - Returns `Ok((Vec<Operand>, CaseSensitive))`
-///   on successful parsing. Returns `Err(String)` with an error message if the input is invalid.
-///
-fn parse_set(set: &str) -> Result<(Vec<Operand>, CaseSensitive), String> {
-    if set.starts_with("[:") && set.ends_with(":]") {
-        Ok(parse_classes(set)?)
-    } else if contains_single_range(set) {
-        Ok((parse_ranges(set)?, CaseSensitive::None))
-    } else {
-        Ok((parse_symbols(set)?, CaseSensitive::None))
-    }
+/// Maps each input character to its translation.
+enum Translation {
+    /// The characters of string1 and their replacements.
+    Map(HashMap<char, char>),
+    /// With `-c` or `-C`, the characters not in string1, in code point
+    /// order, are replaced by those of string2. `set1` is sorted.
+    Complement { set1: Vec<char>, set2: Sequence },
 }
 
-/// Determines if a string contains a single valid range expression.
-///
-/// This function uses a regular expression to check if the input string matches any
-/// of the following range formats:
-/// - `[a-z]` or `[A-Z]` or `[0-9]`: Character ranges enclosed in square brackets
-/// - `\\octal-\\octal`: Ranges of octal-encoded characters
-/// - `a-z` or `A-Z` or `0-9`: Simple character-symbol ranges
-///
-/// # Arguments
-///
-/// * `s` - A string slice to be checked for containing a single valid range.
-///
-/// # Returns
-///
-/// * `bool` - Returns `true` if the input string matches any of the valid range formats.
-///   Returns `false` otherwise.
-///
-fn contains_single_range(s: &str) -> bool {
-    // Regular expression for a range of characters or \octal
-    let re = Regex::new(
-        r"(?x)
-        ^ \[ [a-zA-Z0-9\\]+ - [a-zA-Z0-9\\]+ \] $ |   # Range in square brackets
-        ^ \\ [0-7]{1,3} - \\ [0-7]{1,3} $ |           # Range \octal-\octal
-        ^ [a-zA-Z0-9] - [a-zA-Z0-9] $                 # Character-symbol range
-    ",
-    )
-    .unwrap();
-
-    re.is_match(s)
+impl Translation {
+    /// Builds the translation from string1 to string2.
+    ///
+    /// # Arguments
+    ///
+    /// * `elements1` - The parsed string1.
+    /// * `elements2` - The parsed string2.
+    /// * `complement` - Whether string1 is complemented.
+    /// * `locale` - The locale that defines classes and case conversion.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Translation, String>` - The translation.
+    ///
+    fn new(
+        elements1: &[Element],
+        elements2: &[Element],
+        complement: bool,
+        locale: &Ctype,
+    ) -> Result<Translation, String> {
+        let (chars1, case_classes) = expand_string1(elements1, locale)?;
+
+        if complement {
+            let mut set1 = chars1;
+            set1.sort_unstable();
+            set1.dedup();
+            let len1 = locale.universe().count() - set1.len();
+            let set2 = expand_string2(elements2, len1, &[], &[], locale)?;
+            if set2.len() == 0 {
+                return Err("string2 must not be empty".to_string());
+            }
+            return Ok(Translation::Complement { set1, set2 });
+        }
+
+        let set2 = expand_string2(elements2, chars1.len(), &case_classes, &chars1, locale)?;
+        if set2.len() == 0 && !chars1.is_empty() {
+            return Err("string2 must not be empty".to_string());
+        }
+
+        // a character listed more than once takes its last translation
+        let mut map = HashMap::new();
+        for (i, &ch) in chars1.iter().enumerate() {
+            map.insert(ch, set2.get(i).unwrap());
+        }
+        Ok(Translation::Map(map))
+    }
+
+    fn translate(&self, c: char) -> char {
+        match self {
+            Translation::Map(map) => map.get(&c).copied().unwrap_or(c),
+            Translation::Complement { set1, set2 } => {
+                let below = match set1.binary_search(&c) {
+                    Ok(_) => return c,
+                    Err(below) => below,
+                };
+                // the position of c among all characters, skipping the
+                // surrogate code points, which are not characters
+                let mut ordinal = c as usize;
+                if ordinal > 0xdfff {
+                    ordinal -= 0x800;
+                }
+                set2.get(ordinal - below).unwrap_or(c)
+            }
+        }
+    }
 }
 
-/// Computes the complement of a string with respect to two sets of characters.
+/// Translates, deletes and squeezes `input` according to the arguments.
 ///
-/// This function takes an input string and two sets of characters (`chars1` and `chars2`)
-/// and computes the complement of the input string with respect to the characters in `chars1`.
-/// For each character in the input string:
-/// - If the character is present in `chars1`, it remains unchanged in the result.
-/// - If the character is not present in `chars1`, it is replaced with characters from `chars2`
-///   sequentially until all characters in `chars2` are exhausted, and then the process repeats.
+/// Deletion (`-d`) removes the characters of string1, or with `-c` or
+/// `-C` those not in it. Otherwise, if string2 is given, characters are
+/// translated. Then `-s` replaces each run of a repeated character with
+/// one occurrence, if the character is in the last string given (the
+/// complement of string1 when that is the only one).
 ///
 /// # Arguments
 ///
-/// * `input` - A string slice representing the input string.
-/// * `chars1` - A vector of `Operand` representing the first set of characters.
-/// * `chars2` - A vector of `Operand` representing the second set of characters.
+/// * `args` - The command-line arguments.
+/// * `input` - The characters to process.
+/// * `locale` - The locale that defines classes and equivalence classes.
 ///
 /// # Returns
 ///
-/// * `String` - Returns a string representing the complement of the input string.
-///
-fn complement_chars(input: &str, chars1: Vec<Operand>, mut chars2: Vec<Operand>) -> String {
-    // Create a variable to store the result
-    let mut result = String::new();
-    let chars_2_const = chars2.clone();
+/// * `Result<Vec<char>, String>` - The processed characters, or an error
+///   in an operand.
+///
+fn tr_chars(
+    args: &Args,
+    input: impl Iterator<Item = char>,
+    locale: &Ctype,
+) -> Result<Vec<char>, String> {
+    let complement = args.complement();
+    let set1 = parse_operand(&args.string1, locale)?;
+    let set2 = match &args.string2 {
+        Some(string2) => Some(parse_operand(string2, locale)?),
+        None => None,
+    };
 
-    // Initialize the index for the chars2 vector
-    let mut chars2_index = 0;
+    let translation = match (&set2, args.delete) {
+        (Some(set2), false) => Some(Translation::new(&set1, set2, complement, locale)?),
+        _ => None,
+    };
 
-    // Convert the input string to a character vector for easy processing
-    let input_chars: Vec<char> = input.chars().collect();
+    let in_set1 = |c: char| set_contains(&set1, c, locale) != complement;
+    let in_squeeze_set = |c: char| match &set2 {
+        Some(set2) => set_contains(set2, c, locale),
+        None => in_set1(c),
+    };
 
-    // Go through each character in the input string
-    for &ch in &input_chars {
-        // Check if the character is in the chars1 vector
-        if Operand::contains(&chars1, &ch) {
-            // If the character is in the chars1 vector, add it to the result without changing it
-            result.push(ch);
+    let mut output = Vec::new();
+    let mut last = None;
+    for c in input {
+        if args.delete && in_set1(c) {
             continue;
         }
-        // If the character is not in the chars1 vector, replace it with a character from the chars2 vector
-        // Add the character from the chars2 vector to the result
-        let operand = &mut chars2[chars2_index];
-        match operand {
-            Operand::Char(char) => {
-                result.push(char.char);
-                char.repeated -= 1;
-
-                if char.repeated > 0 {
-                    continue;
-                }
-            }
-            Operand::Equiv(equiv) => {
-                result.push(equiv.char);
-            }
-        }
 
-        // Increase the index for the chars2 vector
-        chars2_index += 1;
+        let c = match &translation {
+            Some(translation) => translation.translate(c),
+            None => c,
+        };
 
-        // If the index has reached the end of the chars2 vector, reset it to zero
-        if chars2_index >= chars2.len() {
-            chars2_index = 0;
-            chars2.clone_from(&chars_2_const);
+        if args.squeeze_repeats && last == Some(c) && in_squeeze_set(c) {
+            continue;
         }
-    }
-
-    result
-}
 
-/// Checks if a character is repeatable based on certain conditions.
-///
-/// This function determines if a character `c` is repeatable based on the following conditions:
-/// - The character occurs more than once in the input string.
-/// - The character is present in the set `set2`.
-///
-/// If the conditions are met and the character has not been seen before, it is considered repeatable.
-///
-/// # Arguments
-///
-/// * `c` - A character to be checked for repeatability.
-/// * `char_counts` - A reference to a hashmap containing character counts in the input string.
-/// * `seen` - A mutable reference to a hash set to keep track of characters seen so far.
-/// * `set2` - A reference to a vector of `Operand` representing the second set of characters.
-///
-/// # Returns
-///
-/// * `bool` - Returns `true` if the character is repeatable based on the conditions specified above.
-///            Returns `false` otherwise.
-///
-fn check_repeatable(
-    c: char,
-    char_counts: &HashMap<char, usize>,
-    seen: &mut HashSet<char>,
-    set2: &Vec<Operand>,
-) -> bool {
-    if char_counts[&c] > 1 && Operand::contains(set2, &c) {
-        if seen.contains(&c) {
-            false
-        } else {
-            seen.insert(c);
-            true
-        }
-    } else {
-        true
+        output.push(c);
+        last = Some(c);
     }
+
+    Ok(output)
 }
 
 /// Translates or deletes characters from standard input, according to specified arguments.
 ///
 /// This function reads from standard input, processes the input string based on the specified arguments,
-/// and prints the result to standard output. It supports translation of characters, deletion of characters,
-/// and squeezing repeated characters.
+/// and prints the result to standard output.
 ///
 /// # Arguments
 ///
@@ -757,192 +663,43 @@ fn check_repeatable(
 ///   if there is an error reading from standard input or processing the input string.
 ///
 fn tr(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
-    let mut input = String::new();
-    io::stdin()
-        .read_to_string(&mut input)
-        .expect("Failed to read input");
-
-    let (set1, set_1_collection) = parse_set(&args.string1)?;
-    let (mut set2, mut set_2_collection) = (None, CaseSensitive::None);
-    if let Some(string2) = &args.string2 {
-        let result = parse_set(string2)?;
-        set2 = Some(result.0);
-        set_2_collection = result.1;
-    }
+    let locale = Ctype::current();
 
-    if args.delete {
-        let mut filtered_string: String;
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input)?;
 
-        if args.complement_char || args.complement_val {
-            filtered_string = input
-                .chars()
-                .filter(|c| Operand::contains(&set1, c))
-                .collect();
-        } else {
-            filtered_string = input
-                .chars()
-                .filter(|c| !Operand::contains(&set1, c))
-                .collect();
-        }
-
-        if args.squeeze_repeats && set2.is_some() {
-            // Counting the frequency of characters in the chars vector
-            let mut char_counts = HashMap::new();
-            for c in filtered_string.chars() {
-                *char_counts.entry(c).or_insert(0) += 1;
-            }
-
-            let mut seen = HashSet::new();
-            filtered_string = filtered_string
-                .chars()
-                .filter(|&c| check_repeatable(c, &char_counts, &mut seen, set2.as_ref().unwrap()))
-                .collect();
-        }
-
-        print!("{filtered_string}");
-        Ok(())
-    } else if args.squeeze_repeats && set2.is_none() {
-        let mut char_counts = HashMap::new();
-        for c in input.chars() {
-            *char_counts.entry(c).or_insert(0) += 1;
-        }
-
-        let mut seen = HashSet::new();
-        let filtered_string: String = input
-            .chars()
-            .filter(|&c| {
-                if char_counts[&c] > 1 && Operand::contains(&set1, &c) {
-                    if seen.contains(&c) {
-                        false
-                    } else {
-                        seen.insert(c);
-                        true
-                    }
-                } else {
-                    true
-                }
-            })
-            .collect();
-        print!("{filtered_string}");
-        return Ok(());
+    // outside a UTF-8 locale every byte is a character, and is written
+    // back unchanged unless it is translated
+    let output: Vec<u8> = if locale.utf8 {
+        let input = String::from_utf8_lossy(&input);
+        let output = tr_chars(args, input.chars(), &locale)?;
+        output.into_iter().collect::<String>().into_bytes()
     } else {
-        let mut result_string: String;
-
-        if args.complement_char || args.complement_val {
-            if args.complement_char {
-                result_string = complement_chars(&input, set1, set2.clone().unwrap());
-            } else {
-                let mut set2 = set2.clone().unwrap();
-                set2.sort_by(|a, b| match (a, b) {
-                    (Operand::Char(char1), Operand::Char(char2)) => char1.char.cmp(&char2.char),
-                    (Operand::Equiv(equiv1), Operand::Equiv(equiv2)) => {
-                        equiv1.char.cmp(&equiv2.char)
-                    }
-                    (Operand::Char(char1), Operand::Equiv(equiv2)) => char1.char.cmp(&equiv2.char),
-                    (Operand::Equiv(equiv1), Operand::Char(char2)) => equiv1.char.cmp(&char2.char),
-                });
-
-                result_string = complement_chars(&input, set1, set2);
-            }
-        } else {
-            if set_1_collection != CaseSensitive::None
-                && set_2_collection != CaseSensitive::None
-                && set_1_collection != set_2_collection
-            {
-                match set_1_collection {
-                    CaseSensitive::UpperCase => print!("{}", input.to_lowercase()),
-
-                    CaseSensitive::LowerCase => print!("{}", input.to_uppercase()),
-                    _ => (),
-                }
-                return Ok(());
-            }
-
-            let set_2 = set2.clone().unwrap();
-            let input_chars: Vec<char> = input.chars().collect();
-
-            let mut result_chars = input_chars.clone();
-            let input_len = input_chars.len();
-
-            let mut start = 0;
-            let end_loop = input_len;
-
-            while start < end_loop {
-                let mut match_len = 0;
-                let mut j = 0;
-                let mut end = start;
-
-                while j < set1.len() && end < input_len {
-                    let mut count = 0;
-
-                    if let Operand::Equiv(equiv) = &set1[j] {
-                        if end < input_len && compare_deunicoded_chars(equiv.char, input_chars[end])
-                        {
-                            j += 1;
-                            end += 1;
-                            match_len = end - start;
-                        }
-                    } else if let Operand::Char(char_struct) = &set1[j] {
-                        while end < input_len && input_chars[end] == char_struct.char {
-                            count += 1;
-                            end += 1;
-                        }
-                        if count != 0 && count <= char_struct.repeated {
-                            j += 1;
-                            match_len = end - start;
-                        } else {
-                            break;
-                        }
-                    }
-                }
-
-                if match_len > 0 {
-                    let set_2_chars = filter_chars(set_2.clone());
-                    let string_for_replace = create_minimal_string(set_2_chars, match_len);
-
-                    result_chars.splice(start..start + match_len, string_for_replace);
-
-                    start += match_len;
-                    continue;
-                }
-
-                start += 1;
-            }
-
-            result_string = result_chars.into_iter().collect();
-        }
-
-        if args.squeeze_repeats {
-            // Counting the frequency of characters in the chars vector
-            let mut char_counts = HashMap::new();
-            for c in result_string.chars() {
-                *char_counts.entry(c).or_insert(0) += 1;
-            }
+        let output = tr_chars(args, input.iter().map(|&b| char::from(b)), &locale)?;
+        output.into_iter().map(|c| c as u8).collect()
+    };
 
-            let mut seen = HashSet::new();
-            result_string = result_string
-                .chars()
-                .filter(|&c| check_repeatable(c, &char_counts, &mut seen, set2.as_ref().unwrap()))
-                .collect();
-        }
+    let mut out = StdoutWriter::new();
+    out.write_all(&output)?;
+    out.flush()?;
 
-        print!("{result_string}");
-        return Ok(());
-    }
+    Ok(())
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain(PROJECT_NAME)?;
-    bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
+    i18n::init();
 
     let args = Args::parse();
-    args.validate_args()?;
     let mut exit_code = 0;
 
+    if let Err(err) = args.validate_args() {
+        eprintln!("tr: {}", err);
+        std::process::exit(1);
+    }
+
     if let Err(err) = tr(&args) {
         exit_code = 1;
-        eprint!("{}", err);
+        eprintln!("tr: {}", err);
     }
 
     std::process::exit(exit_code)
@@ -1,5 +1,7 @@
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use plib::io::StdoutWriter;
+use plib::tabs::TabStops;
 use plib::PROJECT_NAME;
 use std::io::{self, BufRead, Write};
 use std::path::PathBuf;
@@ -19,164 +21,123 @@ struct Args {
     files: Vec<PathBuf>,
 }
 
-fn parse_tablist(s: &str) -> Result<Vec<usize>, std::num::ParseIntError> {
-    s.split(',').map(|item| item.parse::<usize>()).collect()
-}
-
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     setlocale(LocaleCategory::LcAll, "");
     textdomain(PROJECT_NAME)?;
     bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
-    let args = Args::parse();
-
-    let mut exit_code = 0;
-
-    if let Err(err) = unexpand(&args) {
-        exit_code = 1;
-        eprintln!("{}", err);
-    }
+    let mut args = Args::parse();
 
-    std::process::exit(exit_code)
-}
-
-fn unexpand(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
-    let tablist = match &args.tablist {
-        Some(s) => parse_tablist(s)?,
-        None => vec![8],
-    };
-    let mut stdout = io::stdout();
-
-    if (args.files.len() == 1 && args.files[0] == PathBuf::from("-")) || args.files.is_empty() {
-        let reader = io::stdin();
-        let lines = io::BufReader::new(reader).lines();
-        for line in lines {
-            let line = line?;
-            let converted_line = if args.all_spaces && args.tablist.is_none() {
-                convert_all_blanks(&line, &tablist)
-            } else {
-                convert_leading_blanks(&line, &tablist)
-            };
-            writeln!(stdout, "{}", converted_line)?;
-        }
-    } else {
-        for file in &args.files {
-            let reader = io::BufReader::new(std::fs::File::open(file)?);
-            for line in reader.lines() {
-                let line = line?;
-                let converted_line = if args.all_spaces && args.tablist.is_none() {
-                    convert_all_blanks(&line, &tablist)
-                } else {
-                    convert_leading_blanks(&line, &tablist)
-                };
-                writeln!(stdout, "{}", converted_line)?;
-            }
+    let tabstops = match args.tablist.as_deref().map(TabStops::parse) {
+        Some(Ok(tabstops)) => tabstops,
+        Some(Err(e)) => {
+            eprintln!("unexpand: {}", e);
+            std::process::exit(1);
         }
+        None => TabStops::default(),
     };
 
-    Ok(())
-}
-
-fn convert_leading_blanks(line: &str, tablist: &[usize]) -> String {
-    let mut result = String::new();
-    let mut space_count = 0;
-    let mut chars = line.chars().peekable();
-
-    while let Some(&ch) = chars.peek() {
-        if ch == ' ' {
-            space_count += 1;
-            chars.next();
-        } else {
-            break;
-        }
+    // if no files, read from stdin
+    if args.files.is_empty() {
+        args.files.push(PathBuf::from("-"));
     }
 
-    let mut col = 0;
-    for &tabstop in tablist {
-        while space_count > 0 && col < tabstop {
-            let spaces_to_next_tabstop = tabstop - col;
-            if space_count >= spaces_to_next_tabstop {
-                result.push('\t');
-                space_count -= spaces_to_next_tabstop;
-                col = tabstop;
-            } else {
-                col += space_count;
-                break;
-            }
+    let mut exit_code = 0;
+    let mut out = StdoutWriter::new();
+
+    for file in &args.files {
+        if let Err(e) = unexpand_file(&args, &tabstops, &mut out, file) {
+            exit_code = 1;
+            eprintln!("unexpand: {}: {}", file.display(), e);
         }
     }
 
-    for _ in 0..space_count {
-        result.push(' ');
+    if let Err(e) = out.flush() {
+        eprintln!("unexpand: {}", e);
+        exit_code = 1;
     }
 
-    result.push_str(&chars.collect::<String>());
-    result
+    std::process::exit(exit_code)
 }
 
-fn split_whitespaces(line: &str) -> Vec<String> {
-    let mut parts = Vec::new();
-    let mut current_part = String::new();
-    let mut in_word = false;
-
-    for c in line.chars() {
-        if c.is_whitespace() {
-            if in_word {
-                parts.push(current_part.clone());
-                current_part.clear();
-                in_word = false;
-            }
-        } else if !in_word {
-            in_word = true;
+fn unexpand_file(
+    args: &Args,
+    tabstops: &TabStops,
+    out: &mut StdoutWriter,
+    pathname: &PathBuf,
+) -> io::Result<()> {
+    let mut reader = plib::io::input_reader(pathname, true)?;
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        if line.ends_with(b"\n") {
+            line.pop();
         }
 
-        current_part.push(c);
-    }
-
-    if !current_part.is_empty() {
-        parts.push(current_part);
-    }
-    parts
-}
-
-fn convert_all_blanks(line: &str, tablist: &[usize]) -> String {
-    let mut result = String::new();
-
-    let split_parts: Vec<String> = split_whitespaces(line);
-
-    for part in &split_parts {
-        result.push_str(&convert_spaces_to_tabs(part, tablist[0]));
+        out.write_all(&unexpand_line(&line, tabstops, args.all_spaces))?;
+        out.write_all(b"\n")?;
     }
 
-    result
+    Ok(())
 }
 
-fn convert_spaces_to_tabs(line: &str, tabstop: usize) -> String {
-    let mut result = String::new();
-    let mut space_count = 0;
-    let mut chars = line.chars().peekable();
-
-    while let Some(&ch) = chars.peek() {
-        if ch == ' ' {
-            space_count += 1;
-            chars.next();
-        } else {
+/// Replace the blanks in `line` that reach a tab stop with tabs: the
+/// leading ones, or with `all` every run of two or more.
+fn unexpand_line(line: &[u8], tabstops: &TabStops, all: bool) -> Vec<u8> {
+    let mut result = Vec::with_capacity(line.len());
+    // the blanks not yet written, since the last tab stop or other character
+    let mut blanks: Vec<u8> = Vec::new();
+    let mut column = 0;
+    let mut converting = true;
+
+    for (i, &byte) in line.iter().enumerate() {
+        if !converting {
+            result.extend_from_slice(&line[i..]);
             break;
         }
-    }
 
-    while space_count > 0 {
-        if space_count >= tabstop {
-            result.push('\t');
-            space_count -= tabstop;
-        } else {
-            break;
+        match byte {
+            b' ' | b'\t' => {
+                let next_stop = tabstops.next(column);
+                column = match (byte, next_stop) {
+                    (b' ', _) => column + 1,
+                    (_, Some(stop)) => stop,
+                    // a tab past the last tab stop, the columns are unknown
+                    (_, None) => {
+                        result.append(&mut blanks);
+                        result.push(byte);
+                        converting = false;
+                        continue;
+                    }
+                };
+                blanks.push(byte);
+
+                if next_stop == Some(column) {
+                    // a single space reaching a tab stop is left alone
+                    if blanks.len() > 1 || byte == b'\t' {
+                        result.push(b'\t');
+                    } else {
+                        result.push(b' ');
+                    }
+                    blanks.clear();
+                }
+            }
+            _ => {
+                result.append(&mut blanks);
+                result.push(byte);
+                column = if byte == 0x8 {
+                    column.saturating_sub(1)
+                } else {
+                    column + 1
+                };
+                converting = all;
+            }
         }
     }
 
-    for _ in 0..space_count {
-        result.push(' ');
-    }
-
-    result.push_str(&chars.collect::<String>());
+    result.append(&mut blanks);
     result
 }
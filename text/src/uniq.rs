@@ -21,11 +21,11 @@ struct Args {
     #[arg(short = 'u')]
     unique: bool,
 
-    /// Ignore the first fields fields on each input line
+    /// Ignore the first fields fields on each input line when comparing; a field is a string of blanks followed by non-blanks
     #[arg(short = 'f')]
     fields: Option<usize>,
 
-    /// Ignore the first chars characters on each input line
+    /// Ignore the first chars characters when comparing; with -f, these are counted after the skipped fields
     #[arg(short = 's')]
     chars: Option<usize>,
 
@@ -47,12 +47,6 @@ impl Args {
         if self.unique && self.repeated {
             return Err("Options '-u' and '-d' cannot be used together".to_string());
         }
-        if self.count && self.repeated {
-            return Err("Options '-c' and '-d' cannot be used together".to_string());
-        }
-        if self.count && self.unique {
-            return Err("Options '-c' and '-u' cannot be used together".to_string());
-        }
         Ok(())
     }
 }
@@ -85,32 +79,34 @@ fn uniq(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
 
     let lines: Vec<String> = input.lines().collect::<Result<_, _>>()?;
 
-    let mut last_line: Option<String> = None;
+    let mut last_line: Option<&str> = None;
     let mut current_count = 0;
 
     for line in &lines {
-        let processed_line = process_line(line, args.fields, args.chars);
-
-        if let Some(last_line) = &last_line {
-            let processed_last_line = process_line(last_line, args.fields, args.chars);
-            if processed_line == processed_last_line {
+        if let Some(last) = last_line {
+            if compare_key(line, args.fields, args.chars)
+                == compare_key(last, args.fields, args.chars)
+            {
                 current_count += 1;
                 continue;
-            } else {
-                output_result(&mut output, last_line, current_count, args)?;
             }
+            output_result(&mut output, last, current_count, args)?;
         }
-        last_line = Some(line.to_string());
+        last_line = Some(line);
         current_count = 1;
     }
 
     if let Some(last) = last_line {
-        output_result(&mut output, &last, current_count, args)?;
+        output_result(&mut output, last, current_count, args)?;
     }
     Ok(())
 }
 
-/// Processes a line according to the specified field and character options.
+/// Returns the part of a line that is compared, after skipping fields and characters.
+///
+/// A field is a maximal string of blanks followed by non-blanks, so the
+/// blanks before the next field are part of what is compared.  When the
+/// line has fewer fields or characters than are skipped, the key is empty.
 ///
 /// # Arguments
 ///
@@ -120,44 +116,24 @@ fn uniq(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
 ///
 /// # Returns
 ///
-/// Returns the processed line as a `String`.
-fn process_line(line: &str, fields: Option<usize>, chars: Option<usize>) -> String {
-    let mut processed_line = line.to_string();
-    if line.is_empty() {
-        return line.to_string();
-    }
-    if let Some(f) = fields {
-        if f == 0 {
-            processed_line = line.to_string();
-        } else {
-            let mut field_count = 0;
-
-            let chars = line.chars().skip_while(|c| {
-                if c.is_whitespace() {
-                    if field_count >= f - 1 {
-                        return false;
-                    }
-                    field_count += 1;
-                }
-                true
-            });
-            processed_line = chars.collect::<String>();
-        }
+/// Returns the remainder of the line that takes part in the comparison.
+fn compare_key(line: &str, fields: Option<usize>, chars: Option<usize>) -> &str {
+    let is_blank = |c: char| c == ' ' || c == '\t';
+    let mut key = line;
+
+    for _ in 0..fields.unwrap_or(0) {
+        key = key.trim_start_matches(is_blank);
+        key = key.trim_start_matches(|c: char| !is_blank(c));
     }
 
-    if let Some(c) = chars {
-        if c < processed_line.len() {
-            processed_line = processed_line[c..].to_string();
-        } else {
-            processed_line.clear();
-        }
+    if let Some(n) = chars {
+        key = match key.char_indices().nth(n) {
+            Some((i, _)) => &key[i..],
+            None => "",
+        };
     }
 
-    if processed_line.is_empty() {
-        line.to_string()
-    } else {
-        processed_line
-    }
+    key
 }
 
 /// Writes the result to the output according to the specified arguments.
@@ -178,13 +154,14 @@ fn output_result<W: Write>(
     count: usize,
     args: &Args,
 ) -> Result<(), io::Error> {
+    // -d and -u select lines; -c only changes how they are written
+    if (args.repeated && count == 1) || (args.unique && count > 1) {
+        return Ok(());
+    }
+
     if args.count {
         writeln!(output, "{} {}", count, line)?;
-    } else if args.repeated && count > 1 {
-        writeln!(output, "{}", line)?;
-    } else if args.unique && count == 1 {
-        writeln!(output, "{}", line)?;
-    } else if !args.repeated && !args.unique {
+    } else {
         writeln!(output, "{}", line)?;
     }
     Ok(())
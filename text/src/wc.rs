@@ -8,13 +8,13 @@
 //
 
 extern crate clap;
+extern crate libc;
 extern crate plib;
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::PROJECT_NAME;
+use plib::{i18n, locale};
 use std::ffi::OsStr;
-use std::io::{self, BufRead, Read};
+use std::io::{self, Read};
 use std::path::PathBuf;
 
 /// wc - word, line, and byte or character count
@@ -30,10 +30,10 @@ struct Args {
     lines: bool,
 
     /// Count number of characters in each file
-    #[arg(short = 'm', long)]
+    #[arg(short = 'm', long, conflicts_with = "bytes")]
     chars: bool,
 
-    /// Count number of lines in each file
+    /// Count number of words in each file
     #[arg(short, long)]
     words: bool,
 
@@ -43,6 +43,7 @@ struct Args {
 
 struct CountInfo {
     words: usize,
+    bytes: usize,
     chars: usize,
     nl: usize,
 }
@@ -51,6 +52,7 @@ impl CountInfo {
     fn new() -> CountInfo {
         CountInfo {
             words: 0,
+            bytes: 0,
             chars: 0,
             nl: 0,
         }
@@ -58,11 +60,101 @@ impl CountInfo {
 
     fn accum(&mut self, count: &CountInfo) {
         self.words = self.words + count.words;
+        self.bytes = self.bytes + count.bytes;
         self.chars = self.chars + count.chars;
         self.nl = self.nl + count.nl;
     }
 }
 
+/// Counts a stream that arrives in chunks, which may split a multibyte
+/// character.
+struct Counter {
+    utf8: bool,
+    in_word: bool,
+    // the start of a character cut off at the end of the last chunk
+    partial: Vec<u8>,
+}
+
+impl Counter {
+    fn new(utf8: bool) -> Counter {
+        Counter {
+            utf8,
+            in_word: false,
+            partial: Vec::new(),
+        }
+    }
+
+    fn word_char(&mut self, count: &mut CountInfo, space: bool) {
+        if space {
+            self.in_word = false;
+        } else if !self.in_word {
+            self.in_word = true;
+            count.words = count.words + 1;
+        }
+    }
+
+    fn count(&mut self, count: &mut CountInfo, buf: &[u8]) {
+        count.bytes = count.bytes + buf.len();
+        count.nl = count.nl + buf.iter().filter(|&&b| b == b'\n').count();
+
+        if !self.utf8 {
+            count.chars = count.chars + buf.len();
+            for &b in buf {
+                self.word_char(count, b == b' ' || (b'\t'..=b'\r').contains(&b));
+            }
+            return;
+        }
+
+        let mut joined;
+        let mut data = buf;
+        if !self.partial.is_empty() {
+            joined = std::mem::take(&mut self.partial);
+            joined.extend_from_slice(buf);
+            data = &joined;
+        }
+
+        loop {
+            let (valid, rest) = match std::str::from_utf8(data) {
+                Ok(text) => (text, None),
+                Err(e) => {
+                    let (valid, rest) = data.split_at(e.valid_up_to());
+                    (
+                        std::str::from_utf8(valid).unwrap(),
+                        Some((rest, e.error_len())),
+                    )
+                }
+            };
+
+            for ch in valid.chars() {
+                count.chars = count.chars + 1;
+                self.word_char(count, ch.is_whitespace());
+            }
+
+            match rest {
+                None => break,
+                // an invalid sequence is not a character, but it is not
+                // white space either
+                Some((rest, Some(len))) => {
+                    self.word_char(count, false);
+                    data = &rest[len..];
+                }
+                Some((rest, None)) => {
+                    self.partial = rest.to_vec();
+                    break;
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, count: &mut CountInfo) {
+        // a truncated character at the end of the input
+        if !self.partial.is_empty() {
+            self.partial.clear();
+            self.word_char(count, false);
+        }
+    }
+}
+
 fn build_display_str(args: &Args, count: &CountInfo, filename: &OsStr) -> String {
     let mut output = String::with_capacity(filename.len() + (3 * 10));
 
@@ -92,9 +184,10 @@ fn build_display_str(args: &Args, count: &CountInfo, filename: &OsStr) -> String
         if output.len() > 0 {
             output.push(' ');
         }
+        let n = if args.chars { count.chars } else { count.bytes };
         let numstr = match only_bytechars {
-            true => format!("{}", count.chars),
-            false => format!("{:>8}", count.chars),
+            true => format!("{}", n),
+            false => format!("{:>8}", n),
         };
         output.push_str(&numstr);
     }
@@ -112,11 +205,11 @@ fn build_display_str(args: &Args, count: &CountInfo, filename: &OsStr) -> String
     output
 }
 
-fn wc_file_bytes(count: &mut CountInfo, pathname: &PathBuf) -> io::Result<()> {
+fn wc_file(args: &Args, utf8: bool, pathname: &PathBuf, count: &mut CountInfo) -> io::Result<()> {
     let mut file = plib::io::input_stream(pathname, false)?;
 
     let mut buffer = [0; plib::BUFSZ];
-    let mut in_word = false;
+    let mut counter = Counter::new(utf8);
 
     loop {
         let n_read = file.read(&mut buffer[..])?;
@@ -124,87 +217,9 @@ fn wc_file_bytes(count: &mut CountInfo, pathname: &PathBuf) -> io::Result<()> {
             break;
         }
 
-        count.chars = count.chars + n_read;
-
-        let bufslice = &buffer[0..n_read];
-
-        for ch_u8 in bufslice {
-            let ch = *ch_u8 as char;
-
-            if ch == '\n' {
-                count.nl = count.nl + 1;
-                if in_word {
-                    in_word = false;
-                    count.words = count.words + 1;
-                }
-            } else if ch.is_whitespace() {
-                if in_word {
-                    in_word = false;
-                    count.words = count.words + 1;
-                }
-            } else {
-                if !in_word {
-                    in_word = true;
-                }
-            }
-        }
-    }
-
-    if in_word {
-        count.words = count.words + 1;
-    }
-
-    Ok(())
-}
-
-fn wc_file_chars(args: &Args, count: &mut CountInfo, pathname: &PathBuf) -> io::Result<()> {
-    let mut reader = plib::io::input_reader(pathname, false)?;
-
-    loop {
-        let mut buffer = String::new();
-        let n_read = reader.read_line(&mut buffer)?;
-        if n_read == 0 {
-            break;
-        }
-
-        count.nl = count.nl + 1;
-        count.chars = count.chars + n_read;
-
-        if args.words {
-            let mut in_word = false;
-
-            for ch in buffer.chars() {
-                if ch.is_whitespace() {
-                    if in_word {
-                        in_word = false;
-                        count.words = count.words + 1;
-                    }
-                } else {
-                    if !in_word {
-                        in_word = true;
-                    }
-                }
-            }
-            if in_word {
-                count.words = count.words + 1;
-            }
-        }
-    }
-
-    Ok(())
-}
-
-fn wc_file(
-    args: &Args,
-    chars_mode: bool,
-    pathname: &PathBuf,
-    count: &mut CountInfo,
-) -> io::Result<()> {
-    if chars_mode {
-        wc_file_chars(args, count, pathname)?;
-    } else {
-        wc_file_bytes(count, pathname)?;
+        counter.count(count, &buffer[0..n_read]);
     }
+    counter.finish(count);
 
     let output = build_display_str(&args, count, pathname.as_os_str());
 
@@ -217,21 +232,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // parse command line arguments
     let mut args = Args::parse();
 
-    let mut chars_mode = false;
-
     // Assign defaults, per POSIX
     if !args.bytes && !args.lines && !args.chars && !args.words {
         args.bytes = true;
         args.lines = true;
         args.words = true;
-    } else if args.chars {
-        args.bytes = false;
-        chars_mode = true;
     }
 
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain(PROJECT_NAME)?;
-    bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
+    i18n::init();
+
+    let utf8 = locale::is_utf8();
 
     let mut exit_code = 0;
     let mut totals = CountInfo::new();
@@ -240,7 +250,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     if args.files.is_empty() {
         let mut count = CountInfo::new();
 
-        if let Err(e) = wc_file(&args, chars_mode, &PathBuf::new(), &mut count) {
+        if let Err(e) = wc_file(&args, utf8, &PathBuf::new(), &mut count) {
             exit_code = 1;
             eprintln!("stdin: {}", e);
         }
@@ -250,7 +260,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         for filename in &args.files {
             let mut count = CountInfo::new();
 
-            if let Err(e) = wc_file(&args, chars_mode, filename, &mut count) {
+            if let Err(e) = wc_file(&args, utf8, filename, &mut count) {
                 exit_code = 1;
                 eprintln!("{}: {}", filename.display(), e);
             }
@@ -16,24 +16,48 @@ use std::io::Read;
 const PR_DATE_TIME_FORMAT: &str = "%b %d %H:%M %Y";
 
 fn expand_test_noargs(test_data: &str, expected_output: &str) {
+    expand_test(&[], test_data, expected_output);
+}
+
+fn expand_test(args: &[&str], test_data: &str, expected_output: &str) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
     run_test(TestPlan {
         cmd: String::from("expand"),
-        args: Vec::new(),
+        args: str_args,
         stdin_data: String::from(test_data),
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
-fn head_test(test_data: &str, expected_output: &str) {
+fn fold_test(args: &[&str], test_data: &str, expected_output: &str) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("fold"),
+        args: str_args,
+        stdin_data: String::from(test_data),
+        expected_out: String::from(expected_output),
+        expected_err: String::from(""),
+        expected_exit_code: 0,
+        ..Default::default()
+    });
+}
+
+fn head_test(args: &[&str], test_data: &str, expected_output: &str) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
     run_test(TestPlan {
         cmd: String::from("head"),
-        args: Vec::new(),
+        args: str_args,
         stdin_data: String::from(test_data),
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -47,6 +71,7 @@ fn wc_test(args: &[&str], test_data: &str, expected_output: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -60,6 +85,7 @@ fn csplit_test(args: &[&str], test_data: &str, expected_output: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -73,6 +99,7 @@ fn tr_test(args: &[&str], test_data: &str, expected_output: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -86,6 +113,7 @@ fn nl_test(args: &[&str], test_data: &str, expected_output: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -99,10 +127,11 @@ fn pr_test(args: &[&str], test_data: &str, expected_output: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
-fn diff_test(args: &[&str], expected_output: &str) {
+fn diff_test(args: &[&str], expected_output: &str, expected_exit_code: i32) {
     let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
 
     run_test(TestPlan {
@@ -111,7 +140,8 @@ fn diff_test(args: &[&str], expected_output: &str) {
         stdin_data: String::from(""),
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
-        expected_exit_code: 0,
+        expected_exit_code,
+        ..Default::default()
     });
 }
 
@@ -125,6 +155,21 @@ fn cut_test(args: &[&str], test_data: &str, expected_output: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
+    });
+}
+
+fn paste_test(args: &[&str], test_data: &str, expected_output: &str) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("paste"),
+        args: str_args,
+        stdin_data: String::from(test_data),
+        expected_out: String::from(expected_output),
+        expected_err: String::from(""),
+        expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -138,6 +183,7 @@ fn unexpand_test(args: &[&str], test_data: &str, expected_output: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -157,6 +203,7 @@ fn sort_test(
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_err),
         expected_exit_code,
+        ..Default::default()
     });
 }
 
@@ -170,6 +217,7 @@ fn uniq_test(args: &[&str], test_data: &str, expected_output: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -183,6 +231,35 @@ fn tail_test(args: &[&str], test_data: &str, expected_output: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
+    });
+}
+
+fn ed_test(args: &[&str], test_data: &str, expected_output: &str) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("ed"),
+        args: str_args,
+        stdin_data: String::from(test_data),
+        expected_out: String::from(expected_output),
+        expected_err: String::from(""),
+        expected_exit_code: 0,
+        ..Default::default()
+    });
+}
+
+fn comm_test(args: &[&str], test_data: &str, expected_output: &str) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("comm"),
+        args: str_args,
+        stdin_data: String::from(test_data),
+        expected_out: String::from(expected_output),
+        expected_err: String::from(""),
+        expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -223,19 +300,57 @@ fn test_expand_basic() {
     expand_test_noargs("a\tb\tc\n", "a       b       c\n");
 }
 
+#[test]
+fn test_expand_tablist() {
+    expand_test(&["-t", "4"], "a\tb\tc\n", "a   b   c\n");
+    // past the last stop, a tab becomes a single space; stops restart on
+    // each line
+    expand_test(&["-t", "4,6"], "a\tb\tc\td\n\tx\n", "a   b c d\n    x\n");
+    expand_test(&["-t", "2 5"], "\ta\tb\n", "  a  b\n");
+}
+
 #[test]
 fn test_head_basic() {
-    head_test("a\nb\nc\nd\n", "a\nb\nc\nd\n");
+    head_test(&[], "a\nb\nc\nd\n", "a\nb\nc\nd\n");
     head_test(
+        &[],
         "1\n2\n3\n4\n5\n6\n7\n8\n9\n0\n",
         "1\n2\n3\n4\n5\n6\n7\n8\n9\n0\n",
     );
     head_test(
+        &[],
         "1\n2\n3\n4\n5\n6\n7\n8\n9\n0\na\n",
         "1\n2\n3\n4\n5\n6\n7\n8\n9\n0\n",
     );
 }
 
+#[test]
+fn test_head_bytes() {
+    head_test(&["-c", "3"], "abcdef\n", "abc");
+    head_test(&["-c", "100"], "a\nb\n", "a\nb\n");
+    head_test(&["-n", "2"], "a\nb\nc\n", "a\nb\n");
+}
+
+#[test]
+fn test_head_headers() {
+    head_test(
+        &["-n", "1", "tests/assets/test_file.txt", "-"],
+        "",
+        "==> tests/assets/test_file.txt <==\n1sdfghnm\n\n==> standard input <==\n",
+    );
+    head_test(
+        &[
+            "-q",
+            "-c",
+            "2",
+            "tests/assets/test_file.txt",
+            "tests/assets/test_file.txt",
+        ],
+        "",
+        "1s1s",
+    );
+}
+
 #[test]
 fn test_wc_empty() {
     wc_test(&["-c"], "", "0\n");
@@ -257,10 +372,78 @@ fn test_wc_two() {
     wc_test(&["-w"], "x y\n", "2\n");
 }
 
+fn wc_test_utf8(args: &[&str], test_data: &str, expected_output: &str) {
+    run_test(TestPlan {
+        cmd: String::from("wc"),
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        stdin_data: String::from(test_data),
+        expected_out: String::from(expected_output),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+        env: vec![(String::from("LC_ALL"), String::from("C.UTF-8"))],
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_wc_chars() {
+    // without a multibyte locale, characters are bytes
+    wc_test(&["-m"], "h\u{e9}llo\n", "7\n");
+    wc_test_utf8(&["-m"], "h\u{e9}llo\n", "6\n");
+    wc_test_utf8(&["-c"], "h\u{e9}llo\n", "7\n");
+    wc_test_utf8(
+        &["-lwm"],
+        "\u{3b1}\u{3b2} \u{3b3}\n",
+        "       1        2        5\n",
+    );
+}
+
+#[test]
+fn test_wc_invalid_sequence() {
+    // the invalid bytes are not characters, but they do not split words
+    wc_test_utf8(
+        &["-lwm", "tests/assets/wc_invalid_utf8.txt"],
+        "",
+        "       0        2        4\n",
+    );
+}
+
+#[test]
+fn test_fold_width() {
+    fold_test(&["-w", "4"], "abcdefghij\n", "abcd\nefgh\nij\n");
+    // the last line need not end with a newline
+    fold_test(&["-w", "4"], "abcdefghij", "abcd\nefgh\nij");
+}
+
+#[test]
+fn test_fold_spaces() {
+    fold_test(
+        &["-s", "-w", "12"],
+        "aaaa bbbb cccc dddd\n",
+        "aaaa bbbb \ncccc dddd\n",
+    );
+    // a word longer than the line is still broken
+    fold_test(&["-s", "-w", "4"], "abcdefg h\n", "abcd\nefg \nh\n");
+}
+
+#[test]
+fn test_fold_columns() {
+    fold_test(&["-w", "10"], "a\tb\tc\n", "a\tb\n\tc\n");
+    fold_test(&["-w", "4"], "abc\x08def\n", "abc\x08de\nf\n");
+    fold_test(&["-w", "4"], "abc\rdefg\n", "abc\rdefg\n");
+    fold_test(&["-w", "4"], "h\u{e9}llo\n", "h\u{e9}ll\no\n");
+}
+
+#[test]
+fn test_fold_bytes() {
+    fold_test(&["-b", "-w", "4"], "a\tb\tc\n", "a\tb\t\nc\n");
+    fold_test(&["-b", "-w", "4"], "abc\x08def\n", "abc\x08\ndef\n");
+}
+
 #[test]
 fn test_csplit_text_by_lines() {
     csplit_test(
-        &["-f", "text", "-", "5", "{3}"],
+        &["-f", "text", "-", "5", "{2}"],
         "1sdfghnm
 2sadsgdhjmf
 3zcxbncvm vbm
@@ -278,7 +461,7 @@ fn test_csplit_text_by_lines() {
 15
 16
 17",
-        "43\n\n57\n\n31\n\n14\n\n",
+        "44\n77\n19\n8\n",
     );
     std::fs::remove_file("text00").unwrap();
     std::fs::remove_file("text01").unwrap();
@@ -289,9 +472,9 @@ fn test_csplit_text_by_lines() {
 #[test]
 fn test_csplit_text_by_lines_from_file() {
     csplit_test(
-        &["-f", "text_f", "tests/assets/test_file.txt", "5", "{3}"],
+        &["-f", "text_f", "tests/assets/test_file.txt", "5", "{2}"],
         "",
-        "43\n\n57\n\n31\n\n14\n\n",
+        "44\n77\n19\n8\n",
     );
     std::fs::remove_file("text_f00").unwrap();
     std::fs::remove_file("text_f01").unwrap();
@@ -306,17 +489,18 @@ fn test_csplit_c_code_by_regex() {
             "-f",
             "code_c",
             "tests/assets/test_file_c",
-            r"%main\(%",
+            "%main(%",
             "/^}/+1",
             "{3}",
         ],
         "",
-        "59\n\n53\n\n53\n\n54\n\n",
+        "60\n54\n54\n54\n0\n",
     );
     std::fs::remove_file("code_c00").unwrap();
     std::fs::remove_file("code_c01").unwrap();
     std::fs::remove_file("code_c02").unwrap();
     std::fs::remove_file("code_c03").unwrap();
+    std::fs::remove_file("code_c04").unwrap();
 }
 
 #[test]
@@ -326,17 +510,18 @@ fn test_csplit_c_code_by_regex_negative_offset() {
             "-f",
             "code_c_neg",
             "tests/assets/test_file_c",
-            r"%main\(%",
+            "%main(%",
             "/^}/-2",
             "{3}",
         ],
         "",
-        "12\n\n46\n\n52\n\n107\n\n",
+        "13\n48\n54\n54\n53\n",
     );
     std::fs::remove_file("code_c_neg00").unwrap();
     std::fs::remove_file("code_c_neg01").unwrap();
     std::fs::remove_file("code_c_neg02").unwrap();
     std::fs::remove_file("code_c_neg03").unwrap();
+    std::fs::remove_file("code_c_neg04").unwrap();
 }
 
 #[test]
@@ -347,7 +532,7 @@ fn test_csplit_c_code_by_regex_suppress() {
             "-f",
             "code_c_s",
             "tests/assets/test_file_c",
-            r"%main\(%",
+            "%main(%",
             "/^}/+1",
             "{3}",
         ],
@@ -358,6 +543,7 @@ fn test_csplit_c_code_by_regex_suppress() {
     std::fs::remove_file("code_c_s01").unwrap();
     std::fs::remove_file("code_c_s02").unwrap();
     std::fs::remove_file("code_c_s03").unwrap();
+    std::fs::remove_file("code_c_s04").unwrap();
 }
 
 #[test]
@@ -369,17 +555,18 @@ fn test_csplit_c_code_by_regex_with_number() {
             "-n",
             "3",
             "tests/assets/test_file_c",
-            r"%main\(%",
+            "%main(%",
             "/^}/+1",
             "{3}",
         ],
         "",
-        "59\n\n53\n\n53\n\n54\n\n",
+        "60\n54\n54\n54\n0\n",
     );
     std::fs::remove_file("code_c_n000").unwrap();
     std::fs::remove_file("code_c_n001").unwrap();
     std::fs::remove_file("code_c_n002").unwrap();
     std::fs::remove_file("code_c_n003").unwrap();
+    std::fs::remove_file("code_c_n004").unwrap();
 }
 
 #[test]
@@ -387,7 +574,7 @@ fn test_csplit_regex_by_empty_lines() {
     csplit_test(
         &["-f", "empty_lines", "tests/assets/empty_line.txt", "/^$/"],
         "",
-        "6\n\n7\n\n",
+        "7\n7\n",
     );
     std::fs::remove_file("empty_lines00").unwrap();
     std::fs::remove_file("empty_lines01").unwrap();
@@ -404,9 +591,10 @@ fn test_csplit_regex_would_infloop() {
             "{*}",
         ],
         "",
-        "2\n\n",
+        "0\n2\n",
     );
     std::fs::remove_file("would_infloop00").unwrap();
+    std::fs::remove_file("would_infloop01").unwrap();
 }
 
 #[test]
@@ -414,7 +602,7 @@ fn test_csplit_regex_in_uniq() {
     csplit_test(
         &["-f", "in_uniq", "tests/assets/in_uniq", "/^$/", "{*}"],
         "",
-        "6\n\n10\n\n8\n\n9\n\n",
+        "7\n11\n9\n9\n",
     );
     std::fs::remove_file("in_uniq00").unwrap();
     std::fs::remove_file("in_uniq01").unwrap();
@@ -427,7 +615,7 @@ fn test_csplit_regex_in_uniq_2() {
     csplit_test(
         &["-f", "in_uniq_2_", "tests/assets/in_uniq", "/^$/-1", "{*}"],
         "",
-        "3\n\n10\n\n8\n\n12\n\n",
+        "4\n11\n9\n12\n",
     );
     std::fs::remove_file("in_uniq_2_00").unwrap();
     std::fs::remove_file("in_uniq_2_01").unwrap();
@@ -440,7 +628,7 @@ fn test_csplit_regex_in_uniq_3() {
     csplit_test(
         &["-f", "in_uniq_3_", "tests/assets/in_uniq", "/^$/1", "{*}"],
         "",
-        "7\n\n10\n\n8\n\n8\n\n",
+        "8\n11\n9\n8\n",
     );
     std::fs::remove_file("in_uniq_3_00").unwrap();
     std::fs::remove_file("in_uniq_3_01").unwrap();
@@ -453,7 +641,7 @@ fn test_csplit_regex_in_seq() {
     csplit_test(
         &["-f", "in_seq", "tests/assets/in_seq", "/2/", "/4/", "/6/"],
         "",
-        "1\n\n3\n\n3\n\n1\n\n",
+        "2\n4\n4\n1\n",
     );
     std::fs::remove_file("in_seq00").unwrap();
     std::fs::remove_file("in_seq01").unwrap();
@@ -461,6 +649,69 @@ fn test_csplit_regex_in_seq() {
     std::fs::remove_file("in_seq03").unwrap();
 }
 
+#[test]
+fn test_csplit_skip_and_offset() {
+    csplit_test(
+        &["-f", "skip_offset", "-", "%x%", "/b/-1"],
+        "a\nx\na\nb\na\n",
+        "2\n6\n",
+    );
+    std::fs::remove_file("skip_offset00").unwrap();
+    std::fs::remove_file("skip_offset01").unwrap();
+}
+
+#[test]
+fn test_csplit_basic_regex() {
+    // '+' is literal in a basic regular expression
+    csplit_test(
+        &["-f", "basic_re", "-", "/a+/", "/x\\{2\\}/"],
+        "aa\na+\nx\nxx\n",
+        "3\n5\n3\n",
+    );
+    std::fs::remove_file("basic_re00").unwrap();
+    std::fs::remove_file("basic_re01").unwrap();
+    std::fs::remove_file("basic_re02").unwrap();
+}
+
+#[test]
+fn test_csplit_out_of_range() {
+    // the files created so far are removed
+    run_test(TestPlan {
+        cmd: String::from("csplit"),
+        args: vec![
+            String::from("-f"),
+            String::from("out_of_range"),
+            String::from("-"),
+            String::from("3"),
+            String::from("{1}"),
+        ],
+        stdin_data: String::from("a\nb\nc\nd\n"),
+        expected_out: String::from("4\n"),
+        expected_err: String::from("csplit: 3: line number out of range on repetition 1\n"),
+        expected_exit_code: 1,
+        ..Default::default()
+    });
+    assert!(!std::path::Path::new("out_of_range00").exists());
+
+    run_test(TestPlan {
+        cmd: String::from("csplit"),
+        args: vec![
+            String::from("-k"),
+            String::from("-f"),
+            String::from("keep_files"),
+            String::from("-"),
+            String::from("3"),
+            String::from("/x/"),
+        ],
+        stdin_data: String::from("a\nb\nc\nd\n"),
+        expected_out: String::from("4\n"),
+        expected_err: String::from("csplit: /x/: match not found\n"),
+        expected_exit_code: 1,
+        ..Default::default()
+    });
+    std::fs::remove_file("keep_files00").unwrap();
+}
+
 #[test]
 fn test_nl_justification() {
     nl_test(&["-n", "ln"], "a", "1     \ta\n");
@@ -521,10 +772,6 @@ fn test_nl_delimiter() {
 
 #[test]
 fn test_nl_regex() {
-    // NOTE: The implementation has better regex support than the reference.
-    // `nl -b p.+ng` would fail to match the words ending with "ng" in the
-    // original whereas it would in this Rust implementation. Might be
-    // considered a bug?
     nl_test(
         &["-b", "p.*ng"],
         "something\nanything\neverything\ncat\ndog",
@@ -532,6 +779,44 @@ fn test_nl_regex() {
     );
 }
 
+#[test]
+fn test_nl_basic_regex() {
+    // the pattern is a basic regular expression, so '+' and '(' are literal
+    nl_test(
+        &["-b", "pa+(b)"],
+        "aab\na+(b)\n",
+        "       aab\n     1\ta+(b)\n",
+    );
+    nl_test(&["-b", "pa\\{2\\}"], "a\naa\n", "       a\n     1\taa\n");
+}
+
+#[test]
+fn test_nl_join_blank_lines() {
+    nl_test(
+        &["-b", "a", "-l", "2"],
+        "a\n\n\n\n\nb\n",
+        "     1\ta\n       \n     2\t\n       \n     3\t\n     4\tb\n",
+    );
+}
+
+#[test]
+fn test_nl_width_separator() {
+    nl_test(
+        &["-w", "3", "-s", ": ", "-n", "rz", "-v", "-5"],
+        "a\n\nb\n",
+        "-05: a\n     \n-04: b\n",
+    );
+}
+
+#[test]
+fn test_nl_section_styles() {
+    nl_test(
+        &["-h", "t", "-b", "n", "-f", "a"],
+        "\\:\\:\\:\nh\n\n\\:\\:\nb\n\\:\nf\n\n",
+        "\n     1\th\n       \n\n       b\n\n     1\tf\n     2\t\n",
+    );
+}
+
 #[test]
 fn test_pr_single_column() {
     let input = "tests/pr/lorem_ipsum.txt";
@@ -586,6 +871,7 @@ fn test_pr_multi_column_merge() {
         expected_out: String::from(""),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     };
 
     run_test_with_checker(test_plan, |_, output| {
@@ -699,6 +985,30 @@ fn test_pr_expand_and_replace() {
     pr_test(&["-i?3", "-e", "-t", &input], "", &output);
 }
 
+#[test]
+fn test_pr_multi_column_tabs() {
+    // tabs are expanded so that the columns line up
+    pr_test(
+        &["-2", "-t", "-w", "20"],
+        "a\tb\nc\nd\te\nf\n",
+        "a       b d       e \nc         f         \n",
+    );
+}
+
+#[test]
+fn test_pr_across_omit_header() {
+    pr_test(
+        &["-3", "-a", "-t", "-w", "15"],
+        "a\nb\nc\nd\ne\n",
+        "a    b    c   \nd    e        \n",
+    );
+}
+
+#[test]
+fn test_pr_double_space_number() {
+    pr_test(&["-d", "-n", "-t"], "a\nb\n", "    1\ta\n\n    2\tb\n\n");
+}
+
 #[cfg(test)]
 mod cut_tests {
     use crate::cut_test;
@@ -1025,6 +1335,87 @@ mod cut_tests {
     fn test_od_overlap5() {
         cut_test(&["-d", ":", "-b", "1-3,1-4", "-"], "abcde\n", "abcd\n");
     }
+
+    #[test]
+    fn test_no_split_multibyte() {
+        // "aé€b" is a, é (2 bytes), € (3 bytes), b
+        cut_test(&["-n", "-b", "1-2", "-"], "a\u{e9}\u{20ac}b\n", "a\n");
+        cut_test(&["-n", "-b", "3-4", "-"], "a\u{e9}\u{20ac}b\n", "\u{e9}\n");
+        cut_test(
+            &["-n", "-b", "5-", "-"],
+            "a\u{e9}\u{20ac}b\n",
+            "\u{20ac}b\n",
+        );
+        cut_test(&["-n", "-b", "-5", "-"], "a\u{e9}\u{20ac}b\n", "a\u{e9}\n");
+        cut_test(&["-n", "-b", "5", "-"], "a\u{e9}\u{20ac}b\n", "\n");
+    }
+
+    #[test]
+    fn test_open_ended_ranges() {
+        cut_test(&["-c", "-3,10-"], "abcdefghijkl\n", "abcjkl\n");
+        cut_test(&["-b", "12"], "abcdefghijkl\n", "l\n");
+        cut_test(&["-d", ",", "-f", "-2,4-"], "a,b,c,d,e\n", "a,b,d,e\n");
+    }
+
+    #[test]
+    fn test_overlapping_ranges() {
+        cut_test(&["-c", "2-4,1-3,3"], "abcdef\n", "abcd\n");
+        cut_test(&["-d", ",", "-f", "3-,2-4"], "a,b,c,d,e\n", "b,c,d,e\n");
+    }
+}
+
+#[cfg(test)]
+mod paste_tests {
+    use crate::paste_test;
+
+    #[test]
+    fn test_paste_unequal_files() {
+        paste_test(
+            &["tests/paste/letters.txt", "tests/paste/numbers.txt"],
+            "",
+            "a\t1\nb\t2\nc\t\n",
+        );
+    }
+
+    #[test]
+    fn test_paste_stdin_columns() {
+        paste_test(&["-d", ",", "-", "-"], "x\ny\nz", "x,y\nz,\n");
+    }
+
+    #[test]
+    fn test_paste_delimiter_cycle() {
+        paste_test(
+            &["-d", ":\\n", "-", "-", "-", "-"],
+            "1\n2\n3\n4\n5\n6\n7\n8\n",
+            "1:2\n3:4\n5:6\n7:8\n",
+        );
+        paste_test(
+            &[
+                "-d",
+                "\\0",
+                "tests/paste/letters.txt",
+                "tests/paste/letters.txt",
+            ],
+            "",
+            "aa\nbb\ncc\n",
+        );
+    }
+
+    #[test]
+    fn test_paste_serial() {
+        paste_test(
+            &[
+                "-s",
+                "-d",
+                ",\\t",
+                "tests/paste/letters.txt",
+                "tests/paste/numbers.txt",
+            ],
+            "",
+            "a,b\tc\n1,2\n",
+        );
+        paste_test(&["-s"], "x\ny\n", "x\ty\n");
+    }
 }
 
 #[cfg(test)]
@@ -1068,42 +1459,78 @@ mod sort_tests {
 
     #[test]
     fn test_n8a() {
-        sort_test(&["-n", "-k1,1"], ".0a\n.0b\n", ".0a\n.0b\n", 0, "");
+        sort_test(&["-s", "-n", "-k1,1"], ".0a\n.0b\n", ".0a\n.0b\n", 0, "");
     }
 
     #[test]
     fn test_n8b() {
-        sort_test(&["-n", "-k1,1"], ".0b\n.0a\n", ".0b\n.0a\n", 0, "");
+        sort_test(&["-s", "-n", "-k1,1"], ".0b\n.0a\n", ".0b\n.0a\n", 0, "");
     }
 
     #[test]
     fn test_n9a() {
-        sort_test(&["-n", "-k1,1"], ".000a\n.000b\n", ".000a\n.000b\n", 0, "");
+        sort_test(
+            &["-s", "-n", "-k1,1"],
+            ".000a\n.000b\n",
+            ".000a\n.000b\n",
+            0,
+            "",
+        );
     }
 
     #[test]
     fn test_n9b() {
-        sort_test(&["-n", "-k1,1"], ".000b\n.000a\n", ".000b\n.000a\n", 0, "");
+        sort_test(
+            &["-s", "-n", "-k1,1"],
+            ".000b\n.000a\n",
+            ".000b\n.000a\n",
+            0,
+            "",
+        );
     }
 
     #[test]
     fn test_n10a() {
-        sort_test(&["-n", "-k1,1"], ".00a\n.000b\n", ".00a\n.000b\n", 0, "");
+        sort_test(
+            &["-s", "-n", "-k1,1"],
+            ".00a\n.000b\n",
+            ".00a\n.000b\n",
+            0,
+            "",
+        );
     }
 
     #[test]
     fn test_n10b() {
-        sort_test(&["-n", "-k1,1"], ".00b\n.000a\n", ".00b\n.000a\n", 0, "");
+        sort_test(
+            &["-s", "-n", "-k1,1"],
+            ".00b\n.000a\n",
+            ".00b\n.000a\n",
+            0,
+            "",
+        );
     }
 
     #[test]
     fn test_n11a() {
-        sort_test(&["-n", "-k1,1"], ".01a\n.010\n", ".01a\n.010\n", 0, "");
+        sort_test(
+            &["-s", "-n", "-k1,1"],
+            ".01a\n.010\n",
+            ".01a\n.010\n",
+            0,
+            "",
+        );
     }
 
     #[test]
     fn test_n11b() {
-        sort_test(&["-n", "-k1,1"], ".010\n.01a\n", ".010\n.01a\n", 0, "");
+        sort_test(
+            &["-s", "-n", "-k1,1"],
+            ".010\n.01a\n",
+            ".010\n.01a\n",
+            0,
+            "",
+        );
     }
 
     #[test]
@@ -1113,13 +1540,7 @@ mod sort_tests {
 
     #[test]
     fn test_02b() {
-        sort_test(
-            &["-c"],
-            "A\nC\nB\n",
-            "",
-            1,
-            "The order of the lines is not correct on line 2:`C`\n",
-        );
+        sort_test(&["-c"], "A\nC\nB\n", "", 1, "sort: -:3: disorder: B\n");
     }
 
     #[test]
@@ -1134,18 +1555,12 @@ mod sort_tests {
 
     #[test]
     fn test_02e() {
-        sort_test(
-            &["-C"],
-            "A\nC\nB\n",
-            "",
-            1,
-            "The order of the lines is not correct\n",
-        );
+        sort_test(&["-C"], "A\nC\nB\n", "", 1, "");
     }
 
     #[test]
     fn test_02m() {
-        sort_test(&["-cu"], "A\nA\n", "", 1, "Duplicate key was found! `A`\n");
+        sort_test(&["-cu"], "A\nA\n", "", 1, "sort: -:2: disorder: A\n");
     }
 
     #[test]
@@ -1155,24 +1570,12 @@ mod sort_tests {
 
     #[test]
     fn test_02o() {
-        sort_test(
-            &["-cu"],
-            "A\nB\nB\n",
-            "",
-            1,
-            "Duplicate key was found! `B`\n",
-        );
+        sort_test(&["-cu"], "A\nB\nB\n", "", 1, "sort: -:3: disorder: B\n");
     }
 
     #[test]
     fn test_02p() {
-        sort_test(
-            &["-cu"],
-            "B\nA\nB\n",
-            "",
-            1,
-            "Duplicate key was found! `B`\n",
-        );
+        sort_test(&["-cu"], "B\nA\nB\n", "", 1, "sort: -:2: disorder: A\n");
     }
 
     #[test]
@@ -1193,7 +1596,7 @@ mod sort_tests {
     #[test]
     fn test_03d() {
         // Fail with a diagnostic when -k specifies field == 0.
-        sort_test(&["-k0", "-"], "", "", 1, "the key can't be zero.\n");
+        sort_test(&["-k0", "-"], "", "", 2, "the key can't be zero.\n");
     }
 
     #[test]
@@ -1291,7 +1694,7 @@ mod sort_tests {
             &["-n", "-k1.3,1.1", "-"],
             "a 2\nb 1\n",
             "",
-            1,
+            2,
             "keys fields with end position before start!\n",
         );
     }
@@ -1303,7 +1706,7 @@ mod sort_tests {
             &["-k", "2.,3", "-"],
             "",
             "",
-            1,
+            2,
             "cannot parse integer from empty string\n",
         );
     }
@@ -1315,7 +1718,7 @@ mod sort_tests {
             &["-k", "2,", "-"],
             "",
             "",
-            1,
+            2,
             "cannot parse integer from empty string\n",
         );
     }
@@ -1579,6 +1982,47 @@ mod sort_tests {
         );
     }
 
+    #[test]
+    fn test_multiple_keys_per_key_options() {
+        // the first key sorts numerically in reverse, the second breaks ties
+        sort_test(
+            &["-t", ",", "-k2,2nr", "-k1,1"],
+            "x,1\ny,3\na,3\n",
+            "a,3\ny,3\nx,1\n",
+            0,
+            "",
+        );
+    }
+
+    #[test]
+    fn test_key_modifiers_override_globals() {
+        // -k1,1 inherits -f, -k2,2n does not
+        sort_test(
+            &["-f", "-k1,1", "-k2,2n"],
+            "b 10\nB 9\na 1\n",
+            "a 1\nB 9\nb 10\n",
+            0,
+            "",
+        );
+    }
+
+    #[test]
+    fn test_stable() {
+        sort_test(
+            &["-s", "-k1,1"],
+            "b 2\na 9\nb 1\n",
+            "a 9\nb 2\nb 1\n",
+            0,
+            "",
+        );
+        sort_test(&["-k1,1"], "b 2\na 9\nb 1\n", "a 9\nb 1\nb 2\n", 0, "");
+    }
+
+    #[test]
+    fn test_invalid_key_modifier() {
+        sort_test(&["-k1x"], "", "", 2, "invalid key modifier: x\n");
+    }
+
     #[test]
     fn test_neg_nls() {
         sort_test(&["-n", "-"], "-1\n-9\n", "-9\n-1\n", 0, "");
@@ -1615,11 +2059,89 @@ mod sort_tests {
             "",
         );
     }
+    #[test]
+    fn test_merge() {
+        // comm_file2.txt is "b\nc\nd\ne\n"
+        let file = "tests/assets/comm_file2.txt";
+        sort_test(
+            &["-m", "-", file],
+            "a\nc\nf\n",
+            "a\nb\nc\nc\nd\ne\nf\n",
+            0,
+            "",
+        );
+        sort_test(
+            &["-mu", "-", file],
+            "a\nc\nf\n",
+            "a\nb\nc\nd\ne\nf\n",
+            0,
+            "",
+        );
+        sort_test(&["-mr", "-"], "c\nb\na\n", "c\nb\na\n", 0, "");
+    }
+
+    #[test]
+    fn test_check_too_many_files() {
+        sort_test(
+            &["-c", "-", "tests/assets/comm_file2.txt"],
+            "",
+            "",
+            2,
+            "sort: only one file may be checked for order\n",
+        );
+    }
+
+    #[test]
+    fn test_merge_into_input() {
+        let dir = std::env::temp_dir().join(format!("sort-merge-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("merged");
+        std::fs::write(&file, "b\nd\n").unwrap();
+
+        // the output may be one of the inputs
+        let file = file.to_str().unwrap();
+        sort_test(&["-m", "-o", file, "-", file], "a\nc\n", "", 0, "");
+        assert_eq!(std::fs::read_to_string(file).unwrap(), "a\nb\nc\nd\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_check_stops_at_disorder() {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+        use std::time::{Duration, Instant};
+
+        // the input never ends, so the disorder must be found while reading
+        let mut child = Command::new(env!("CARGO_BIN_EXE_sort"))
+            .arg("-C")
+            .stdin(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(b"b\na\n").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let status = loop {
+            if let Some(status) = child.try_wait().unwrap() {
+                break Some(status);
+            }
+            if Instant::now() > deadline {
+                child.kill().unwrap();
+                child.wait().unwrap();
+                break None;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        };
+        assert_eq!(status.and_then(|status| status.code()), Some(1));
+    }
 }
 
 #[cfg(test)]
 mod tr_tests {
     use crate::tr_test;
+    use plib::{run_test, TestPlan};
 
     #[test]
     fn test_tr_1() {
@@ -1799,10 +2321,9 @@ mod tr_tests {
 
     #[test]
     fn test_tr_w() {
-        tr_test(
-            &["-ds", "\u{350}", "\u{345}"],
-            "\u{300}\u{301}\u{377}\u{345}\u{345}\u{350}\u{345}",
-            "\u{300}\u{301}\u{377}\u{345}",
+        assert_eq!(
+            tr_bytes(&["-ds", "\\350", "\\345"], b"\xc0\xc1\xff\xe5\xe5\xe8\xe5"),
+            b"\xc0\xc1\xff\xe5"
         );
     }
 
@@ -1867,7 +2388,11 @@ mod tr_tests {
 
     #[test]
     fn test_tr_ross_2() {
-        tr_test(&["-dcs", "[:lower:]", "n-rs-z"], "amzAMZ123.-+amz", "amzam");
+        tr_test(
+            &["-dcs", "[:lower:]", "n-rs-z"],
+            "amzAMZ123.-+amz",
+            "amzamz",
+        );
     }
 
     #[test]
@@ -1918,6 +2443,76 @@ mod tr_tests {
     fn test_tr_no_abort_1() {
         tr_test(&["-c", "a", "[b*256]"], "abc", "abb");
     }
+
+    #[test]
+    fn test_tr_octal_escapes() {
+        tr_test(&["\\141-\\143", "\\101-\\103"], "abcd", "ABCd");
+        tr_test(&["\\n\\t", "[_*]"], "a\tb\n", "a_b_");
+    }
+
+    #[test]
+    fn test_tr_squeeze_runs_only() {
+        tr_test(&["-s", "ab"], "aabbaab", "abab");
+    }
+
+    #[test]
+    fn test_tr_squeeze_complement() {
+        tr_test(&["-cs", "[:alnum:]"], "a  b,,c\n", "a b,c\n");
+    }
+
+    #[test]
+    fn test_tr_class_and_range() {
+        tr_test(&["[:digit:]a-c", "0-9A-C"], "5 abc", "5 ABC");
+    }
+
+    #[test]
+    fn test_tr_equiv_translate() {
+        tr_test(&["[=e=]", "E"], "bee", "bEE");
+    }
+
+    #[test]
+    fn test_tr_reverse_range() {
+        run_test(TestPlan {
+            cmd: String::from("tr"),
+            args: vec![String::from("z-a"), String::from("x")],
+            stdin_data: String::from("abc"),
+            expected_out: String::new(),
+            expected_err: String::from(
+                "tr: range-endpoints of 'z-a' are in reverse collating sequence order\n",
+            ),
+            expected_exit_code: 1,
+            ..Default::default()
+        });
+    }
+
+    // Runs tr in the C locale on input that need not be UTF-8.
+    fn tr_bytes(args: &[&str], input: &[u8]) -> Vec<u8> {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_tr"))
+            .args(args)
+            .env("LC_ALL", "C")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(input).unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+        output.stdout
+    }
+
+    #[test]
+    fn test_tr_c_locale_bytes() {
+        // bytes that are not UTF-8 pass through unchanged
+        assert_eq!(tr_bytes(&["a", "b"], b"caf\xe9\n"), b"cbf\xe9\n");
+        assert_eq!(tr_bytes(&["-d", "\\200-\\377"], b"a\x80b\xffc\n"), b"abc\n");
+        // an octal escape is one byte
+        assert_eq!(tr_bytes(&["a", "\\351"], b"xay\n"), b"x\xe9y\n");
+        // the complement includes the bytes above 0177
+        assert_eq!(tr_bytes(&["-c", "a\n", "[x*]"], b"a\xe9\n"), b"ax\n");
+    }
 }
 
 #[cfg(test)]
@@ -1938,7 +2533,7 @@ mod unexpand_tests {
         unexpand_test(
             &["-"],
             "    Apple\n        Banana\n            Cherry\n                Date",
-            "    Apple\n\tBanana\n\t    Cherry\n\t        Date\n",
+            "    Apple\n\tBanana\n\t    Cherry\n\t\tDate\n",
         );
     }
 
@@ -1970,9 +2565,24 @@ mod unexpand_tests {
         unexpand_test(
             &["-a"],
             "text        with                spaces",
-            "text\twith\t\tspaces\n",
+            "text\t    with\t\tspaces\n",
         );
     }
+
+    #[test]
+    fn unexpand_test_all_tablist() {
+        unexpand_test(
+            &["-a", "-t", "4,8,12"],
+            "ab  cd  e x    y\n",
+            "ab\tcd\te x    y\n",
+        );
+    }
+
+    #[test]
+    fn unexpand_test_single_space() {
+        // a single space before a tab stop is not worth a tab
+        unexpand_test(&["-a"], "1234567 x       y\n", "1234567 x\ty\n");
+    }
 }
 
 mod uniq_tests {
@@ -2156,12 +2766,43 @@ mod uniq_tests {
     fn test_uniq_43() {
         uniq_test(&["-c"], "a\na\n", "2 a\n");
     }
+
+    #[test]
+    fn test_uniq_skip_fields_log() {
+        // deduplicate log lines that differ only in their timestamps
+        let log = "10:00:01  disk full\n10:00:02  disk full\n10:00:05  link up\n";
+        uniq_test(
+            &["-f", "1"],
+            log,
+            "10:00:01  disk full\n10:00:05  link up\n",
+        );
+        uniq_test(
+            &["-c", "-f", "1"],
+            log,
+            "2 10:00:01  disk full\n1 10:00:05  link up\n",
+        );
+        uniq_test(&["-c", "-d", "-f", "1"], log, "2 10:00:01  disk full\n");
+        uniq_test(&["-u", "-f", "1"], log, "10:00:05  link up\n");
+    }
+
+    #[test]
+    fn test_uniq_skip_past_end() {
+        // lines with no fields or characters left compare equal
+        uniq_test(&["-f", "3"], "a b\nc d\n", "a b\n");
+        uniq_test(&["-s", "5"], "abc\nxyz\n", "abc\n");
+    }
+
+    #[test]
+    fn test_uniq_skip_chars_multibyte() {
+        uniq_test(&["-s", "1"], "ööa\näöa\nööb\n", "ööa\nööb\n");
+        uniq_test(&["-f", "1", "-s", "3"], "x  éa\ny \tüa\n", "x  éa\n");
+    }
 }
 
 #[cfg(test)]
 mod diff_tests {
     use crate::diff_test;
-    use std::{path::PathBuf, process::Stdio};
+    use std::{fs, path::PathBuf, process::Stdio};
 
     fn diff_base_path() -> PathBuf {
         PathBuf::from("tests").join("diff")
@@ -2207,6 +2848,22 @@ mod diff_tests {
             .to_string()
     }
 
+    fn context1_txt_path() -> String {
+        diff_base_path()
+            .join("context1.txt")
+            .to_str()
+            .expect("Could not unwrap context1_txt_path")
+            .to_string()
+    }
+
+    fn context2_txt_path() -> String {
+        diff_base_path()
+            .join("context2.txt")
+            .to_str()
+            .expect("Could not unwrap context2_txt_path")
+            .to_string()
+    }
+
     struct DiffTestHelper {
         pub key: String,
         content: String,
@@ -2355,7 +3012,7 @@ mod diff_tests {
     #[test]
     fn test_diff_normal() {
         let data = input_by_key("test_diff_normal");
-        diff_test(&[data.file1_path(), data.file2_path()], data.content());
+        diff_test(&[data.file1_path(), data.file2_path()], data.content(), 1);
     }
 
     #[test]
@@ -2365,6 +3022,7 @@ mod diff_tests {
         diff_test(
             &["-c", data.file1_path(), data.file2_path()],
             data.content(),
+            1,
         );
     }
 
@@ -2375,6 +3033,7 @@ mod diff_tests {
         diff_test(
             &["-C", "1", data.file1_path(), data.file2_path()],
             data.content(),
+            1,
         );
     }
 
@@ -2385,6 +3044,7 @@ mod diff_tests {
         diff_test(
             &["-C", "10", data.file1_path(), data.file2_path()],
             data.content(),
+            1,
         );
     }
 
@@ -2395,6 +3055,7 @@ mod diff_tests {
         diff_test(
             &["-e", data.file1_path(), data.file2_path()],
             data.content(),
+            1,
         );
     }
 
@@ -2405,6 +3066,7 @@ mod diff_tests {
         diff_test(
             &["-f", data.file1_path(), data.file2_path()],
             data.content(),
+            1,
         );
     }
 
@@ -2415,6 +3077,7 @@ mod diff_tests {
         diff_test(
             &["-u", data.file1_path(), data.file2_path()],
             data.content(),
+            1,
         );
     }
 
@@ -2425,6 +3088,7 @@ mod diff_tests {
         diff_test(
             &["-U", "0", data.file1_path(), data.file2_path()],
             data.content(),
+            1,
         );
     }
 
@@ -2435,19 +3099,20 @@ mod diff_tests {
         diff_test(
             &["-U", "10", data.file1_path(), data.file2_path()],
             data.content(),
+            1,
         );
     }
 
     #[test]
     fn test_diff_file_directory() {
         let data = input_by_key("test_diff_file_directory");
-        diff_test(&[data.file1_path(), data.file2_path()], data.content());
+        diff_test(&[data.file1_path(), data.file2_path()], data.content(), 1);
     }
 
     #[test]
     fn test_diff_directories() {
         let data = input_by_key("test_diff_directories");
-        diff_test(&[data.file1_path(), data.file2_path()], data.content());
+        diff_test(&[data.file1_path(), data.file2_path()], data.content(), 1);
     }
 
     #[test]
@@ -2457,6 +3122,7 @@ mod diff_tests {
         diff_test(
             &["-r", data.file1_path(), data.file2_path()],
             data.content(),
+            1,
         );
     }
 
@@ -2467,6 +3133,7 @@ mod diff_tests {
         diff_test(
             &["-r", "-c", data.file1_path(), data.file2_path()],
             data.content(),
+            1,
         );
     }
 
@@ -2477,6 +3144,7 @@ mod diff_tests {
         diff_test(
             &["-r", "-e", data.file1_path(), data.file2_path()],
             data.content(),
+            1,
         );
     }
 
@@ -2487,6 +3155,7 @@ mod diff_tests {
         diff_test(
             &["-r", "-f", data.file1_path(), data.file2_path()],
             data.content(),
+            1,
         );
     }
 
@@ -2497,13 +3166,14 @@ mod diff_tests {
         diff_test(
             &["-r", "-u", data.file1_path(), data.file2_path()],
             data.content(),
+            1,
         );
     }
 
     #[test]
     fn test_diff_counting_eol_spaces() {
         let data = input_by_key("test_diff_counting_eol_spaces");
-        diff_test(&[data.file1_path(), data.file2_path()], data.content());
+        diff_test(&[data.file1_path(), data.file2_path()], data.content(), 1);
     }
 
     #[test]
@@ -2513,6 +3183,7 @@ mod diff_tests {
         diff_test(
             &["-b", data.file1_path(), data.file2_path()],
             data.content(),
+            0,
         );
     }
 
@@ -2531,13 +3202,90 @@ mod diff_tests {
                 data.file2_path(),
             ],
             data.content(),
+            1,
         );
     }
+
+    #[test]
+    fn test_diff_unified_hunks() {
+        diff_test(
+            &[
+                "--label",
+                "A",
+                "--label2",
+                "B",
+                "-u",
+                &context1_txt_path(),
+                &context2_txt_path(),
+            ],
+            "--- A\n+++ B\n@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n@@ -9,3 +9,4 @@\n i\n j\n k\n+l\n\\ No newline at end of file\n", 1);
+    }
+
+    #[test]
+    fn test_diff_unified0_empty_range() {
+        diff_test(
+            &[
+                "--label",
+                "A",
+                "--label2",
+                "B",
+                "-U",
+                "0",
+                &context2_txt_path(),
+                &context1_txt_path(),
+            ],
+            "--- A\n+++ B\n@@ -2 +2 @@\n-B\n+b\n@@ -12 +11,0 @@\n-l\n\\ No newline at end of file\n", 1);
+    }
+
+    #[test]
+    fn test_diff_context_hunks() {
+        diff_test(
+            &[
+                "--label",
+                "A",
+                "--label2",
+                "B",
+                "-c",
+                &context1_txt_path(),
+                &context2_txt_path(),
+            ],
+            "*** A\n--- B\n***************\n*** 1,5 ****\n  a\n! b\n  c\n  d\n  e\n--- 1,5 ----\n  a\n! B\n  c\n  d\n  e\n***************\n*** 9,11 ****\n--- 9,12 ----\n  i\n  j\n  k\n+ l\n\\ No newline at end of file\n", 1);
+    }
+
+    #[test]
+    fn test_diff_unified_identical() {
+        diff_test(&["-u", &context1_txt_path(), &context1_txt_path()], "", 0);
+    }
+
+    #[test]
+    fn test_diff_directories_only_in() {
+        let base = std::env::temp_dir().join(format!("diff_only_in_{}", std::process::id()));
+        let (dir1, dir2) = (base.join("a"), base.join("b"));
+        fs::create_dir_all(dir1.join("sub")).unwrap();
+        fs::create_dir_all(dir2.join("sub")).unwrap();
+        fs::write(dir1.join("sub").join("f"), "1\n2\n").unwrap();
+        fs::write(dir2.join("sub").join("f"), "1\n3\n").unwrap();
+        fs::write(dir1.join("only_a"), "x\n").unwrap();
+        fs::write(dir2.join("sub").join("only_b"), "y\n").unwrap();
+
+        let (dir1, dir2) = (dir1.to_str().unwrap(), dir2.to_str().unwrap());
+        diff_test(
+            &["-r", dir1, dir2],
+            &format!(
+                "Only in {dir1}: only_a\ndiff -r {dir1}/sub/f {dir2}/sub/f\n2c2\n< 2\n---\n> 3\nOnly in {dir2}/sub: only_b\n"
+            ), 1);
+
+        fs::remove_dir_all(base).unwrap();
+    }
 }
 
 #[cfg(test)]
 mod tail_tests {
     use crate::tail_test;
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    use std::time::Duration;
+    use std::{env, fs, thread};
 
     #[test]
     fn test_tail() {
@@ -2669,4 +3417,173 @@ mod tail_tests {
     fn test_tail_20() {
         tail_test(&["-n-1"], &("y\n".repeat(5)), "y\n");
     }
+
+    #[test]
+    fn test_tail_multiple_files() {
+        tail_test(
+            &["-n1", "tests/assets/test_file.txt", "-"],
+            "a\nb\n",
+            "==> tests/assets/test_file.txt <==\n17\n\n==> standard input <==\nb\n",
+        );
+    }
+
+    // Waits long enough for a change to be seen even by the polling
+    // fallback, which checks once a second.
+    fn settle() {
+        thread::sleep(Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_tail_follow_name_rotation() {
+        let dir = env::temp_dir().join(format!("tail-follow-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("log");
+        fs::write(&log, "a\nb\n").unwrap();
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_tail"))
+            .args(["-F", "-n1"])
+            .arg(&log)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        settle();
+
+        // appended data is picked up
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&log)
+            .unwrap()
+            .write_all(b"c\n")
+            .unwrap();
+        settle();
+
+        // rotation: the old file is renamed away and a new one created
+        fs::rename(&log, dir.join("log.1")).unwrap();
+        fs::write(&log, "new\n").unwrap();
+        settle();
+
+        child.kill().unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "b\nc\nnew\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_tail_follow_fifo() {
+        let dir = env::temp_dir().join(format!("tail-fifo-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let fifo = dir.join("fifo");
+        let cpath = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(cpath.as_ptr(), 0o600) }, 0);
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_tail"))
+            .arg("-f")
+            .arg(&fifo)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        // the first writer's data is the initial output; a later writer's
+        // data is followed, although a FIFO never has a length
+        fs::write(&fifo, "a\n").unwrap();
+        settle();
+        fs::write(&fifo, "b\n").unwrap();
+        settle();
+
+        child.kill().unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "a\nb\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn test_ed_append_print() {
+    ed_test(
+        &["-s"],
+        "a\none\ntwo\nthree\n.\n2p\n,n\n1,2l\n$=\nQ\n",
+        "two\n1\tone\n2\ttwo\n3\tthree\none$\ntwo$\n3\n",
+    );
+}
+
+#[test]
+fn test_ed_substitute() {
+    ed_test(
+        &["-s"],
+        "a\nfoo bar foo\n.\ns/foo/[&]/gp\ns/\\(\\[[a-z]*]\\) \\(bar\\)/\\2 \\1/p\ns/o/0/3p\ns/ /\\\n/p\n,p\nQ\n",
+        "[foo] bar [foo]\nbar [foo] [foo]\nbar [foo] [f0o]\n[foo] [f0o]\nbar\n[foo] [f0o]\n",
+    );
+}
+
+#[test]
+fn test_ed_global_undo() {
+    ed_test(
+        &["-s"],
+        "a\napple\nbanana\navocado\n.\ng/^a/s/a/A/\\\nm0\n,p\nu\n,p\nv/an/d\n,p\nQ\n",
+        "Avocado\nApple\nbanana\napple\nbanana\navocado\nbanana\n",
+    );
+}
+
+#[test]
+fn test_ed_addresses() {
+    ed_test(
+        &["-s"],
+        "a\n1\n2\n3\n4\n5\n.\n/3/;+1p\n?2?p\n2kx\n$p\n'x,'x+1p\n-,.p\n;p\nQ\n",
+        "3\n4\n2\n5\n2\n3\n2\n3\n3\n4\n5\n",
+    );
+}
+
+#[test]
+fn test_ed_write_file() {
+    run_test(TestPlan {
+        cmd: String::from("ed"),
+        args: vec![String::from("ed_write_file.txt")],
+        stdin_data: String::from("a\nfirst\nsecond\n.\nq\nw\n1d\nq\nq\n"),
+        expected_out: String::from("?\n13\n?\n"),
+        expected_err: String::from("ed_write_file.txt: No such file or directory (os error 2)\n"),
+        expected_exit_code: 1,
+        ..Default::default()
+    });
+
+    let contents = fs::read_to_string("ed_write_file.txt").unwrap();
+    fs::remove_file("ed_write_file.txt").unwrap();
+    assert_eq!(contents, "first\nsecond\n");
+}
+
+#[test]
+fn test_comm_columns() {
+    let file2 = "tests/assets/comm_file2.txt";
+    let input = "a\nb\nd\nf";
+
+    comm_test(&["-", file2], input, "a\n\t\tb\n\tc\n\t\td\n\te\nf\n");
+    comm_test(&["-1", "-", file2], input, "\tb\nc\n\td\ne\n");
+    comm_test(&["-2", "-", file2], input, "a\n\tb\n\td\nf\n");
+    comm_test(&["-3", "-", file2], input, "a\n\tc\n\te\nf\n");
+    comm_test(&["-12", "-", file2], input, "b\nd\n");
+    comm_test(&["-13", "-", file2], input, "c\ne\n");
+    comm_test(&["-123", "-", file2], input, "");
+}
+
+#[test]
+fn test_comm_unsorted() {
+    run_test(TestPlan {
+        cmd: String::from("comm"),
+        args: vec![
+            String::from("-"),
+            String::from("tests/assets/comm_file2.txt"),
+        ],
+        stdin_data: String::from("c\na\n"),
+        expected_out: String::from("\tb\n\t\tc\na\n\td\n\te\n"),
+        expected_err: String::from(
+            "comm: file 1 is not in sorted order\ncomm: input is not in sorted order\n",
+        ),
+        expected_exit_code: 1,
+        ..Default::default()
+    });
 }
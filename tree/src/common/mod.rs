@@ -42,7 +42,10 @@ pub fn error_string(e: &io::Error) -> String {
 /// Copy the metadata in `source_md` to `target`.
 ///
 /// This copies the last access time, last modification time, user ownership,
-/// group ownership, and permissions.
+/// group ownership, and permissions. A symbolic link `target` is changed
+/// itself rather than the file it points to, and has no permissions to copy.
+/// If the ownership cannot be copied, the set-user-ID and set-group-ID bits
+/// are cleared instead of being copied to a file owned by someone else.
 pub fn copy_characteristics(source_md: &fs::Metadata, target: &Path) -> io::Result<()> {
     let target_cstr = CString::new(target.as_os_str().as_bytes())?;
 
@@ -71,16 +74,33 @@ pub fn copy_characteristics(source_md: &fs::Metadata, target: &Path) -> io::Resu
         }
 
         // Copy user and group
-        let ret = libc::chown(target_cstr.as_ptr(), source_md.uid(), source_md.gid());
-        if ret != 0 {
-            return Err(io::Error::last_os_error());
+        let ret = libc::lchown(target_cstr.as_ptr(), source_md.uid(), source_md.gid());
+        let chown_err = if ret != 0 {
+            Some(io::Error::last_os_error())
+        } else {
+            None
+        };
+
+        if source_md.file_type().is_symlink() {
+            return match chown_err {
+                Some(e) => Err(e),
+                None => Ok(()),
+            };
         }
 
         // Copy permissions
-        let ret = libc::chmod(target_cstr.as_ptr(), source_md.mode() as libc::mode_t);
+        let mut mode = source_md.mode() as libc::mode_t;
+        if chown_err.is_some() {
+            mode &= !(libc::S_ISUID | libc::S_ISGID);
+        }
+        let ret = libc::chmod(target_cstr.as_ptr(), mode);
         if ret != 0 {
             return Err(io::Error::last_os_error());
         }
+
+        if let Some(e) = chown_err {
+            return Err(e);
+        }
     }
     Ok(())
 }
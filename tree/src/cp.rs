@@ -19,10 +19,12 @@ use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleC
 use plib::PROJECT_NAME;
 use std::collections::HashSet;
 use std::ffi::CString;
+use std::io::{Read, Seek, SeekFrom};
 use std::os::unix::{
     self,
     ffi::OsStrExt,
-    fs::{DirBuilderExt, FileTypeExt, MetadataExt, OpenOptionsExt},
+    fs::{DirBuilderExt, FileTypeExt, MetadataExt, OpenOptionsExt, PermissionsExt},
+    io::AsRawFd,
 };
 use std::path::{Path, PathBuf};
 use std::{fs, io};
@@ -57,8 +59,7 @@ struct Args {
             "dereference",
             "no_dereference"
         ],
-        requires = "recursive",
-        default_value_t = true,
+        requires = "recursive"
     )]
     dereference: bool,
 
@@ -82,7 +83,7 @@ struct Args {
     #[arg(short, long)]
     preserve: bool,
 
-    /// Remove file hierarchies.
+    /// Copy file hierarchies.
     #[arg(short = 'R', visible_short_alias = 'r', long)]
     recursive: bool,
 
@@ -97,19 +98,32 @@ struct Config {
     interactive: bool,
     preserve: bool,
     recursive: bool,
+    umask: libc::mode_t,
 }
 
 impl Config {
     fn new(args: &Args) -> Self {
         // `args.no_dereference` serves only to disable `args.dereference` or
         // `follow_cli`
+
+        // There is no way to read the umask without setting it
+        let umask = unsafe {
+            let mask = libc::umask(0);
+            libc::umask(mask);
+            mask
+        };
+
         Config {
             force: args.force,
             follow_cli: args.follow_cli,
-            dereference: args.dereference,
+            // Without -R, symlinks are followed unless -P is given. With -R,
+            // -H follows those on the command line and -L all of them; by
+            // default the links themselves are copied.
+            dereference: args.dereference || (!args.recursive && !args.no_dereference),
             interactive: args.interactive,
             preserve: args.preserve,
             recursive: args.recursive,
+            umask,
         }
     }
 }
@@ -142,7 +156,7 @@ fn copy_special_file(
         // "In general, it is strongly suggested that the permissions,
         // owner, and group be the same as if the user had run the
         // historical mknod, ln, or other utility to create the file"""
-        0o644
+        (source_md.mode() as libc::mode_t & libc::S_IFMT) | 0o644
     };
 
     if target.exists() {
@@ -167,11 +181,69 @@ fn copy_special_file(
     }
 }
 
+/// Copy a file that may contain holes, seeking over them in the target
+/// instead of writing zeros. Returns false, having copied nothing, if the
+/// file system cannot report where the holes are.
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+fn copy_sparse(source: &mut fs::File, target: &mut fs::File, size: u64) -> io::Result<bool> {
+    let source_fd = source.as_raw_fd();
+    let size = size as libc::off_t;
+
+    let mut offset = 0;
+    while offset < size {
+        let data = unsafe { libc::lseek(source_fd, offset, libc::SEEK_DATA) };
+        if data < 0 {
+            let e = io::Error::last_os_error();
+            match e.raw_os_error() {
+                // Only a hole is left
+                Some(libc::ENXIO) => break,
+                Some(libc::EINVAL) if offset == 0 => return Ok(false),
+                _ => return Err(e),
+            }
+        }
+
+        let hole = unsafe { libc::lseek(source_fd, data, libc::SEEK_HOLE) };
+        if hole < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        source.seek(SeekFrom::Start(data as u64))?;
+        target.seek(SeekFrom::Start(data as u64))?;
+        io::copy(&mut source.by_ref().take((hole - data) as u64), target)?;
+
+        offset = hole;
+    }
+
+    // Extending the file leaves a trailing hole
+    target.set_len(size as u64)?;
+
+    Ok(true)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "freebsd")))]
+fn copy_sparse(_source: &mut fs::File, _target: &mut fs::File, _size: u64) -> io::Result<bool> {
+    Ok(false)
+}
+
+/// Copy the contents of `source` to `target`, keeping holes in sparse files.
+fn copy_data(source: &mut fs::File, target: &mut fs::File) -> io::Result<()> {
+    let md = source.metadata()?;
+
+    // Fewer blocks than the size needs means the file has holes
+    if md.is_file() && md.blocks() * 512 < md.len() && copy_sparse(source, target, md.len())? {
+        return Ok(());
+    }
+
+    io::copy(source, target)?;
+    Ok(())
+}
+
 fn copy_file(
     cfg: &Config,
     source: &Path,
     target: &Path,
     created_files: &mut HashSet<PathBuf>,
+    ancestors: &mut Vec<(u64, u64)>,
     init_call: bool,
 ) -> io::Result<()> {
     let err_cannot_stat_source = |e: &io::Error| -> io::Error {
@@ -200,18 +272,26 @@ fn copy_file(
         );
     }
 
-    let source_deref_md = fs::metadata(source);
+    let follow = cfg.dereference || (cfg.follow_cli && init_call);
+
+    // Resolving a symlink updates its access time, so it is only done when
+    // the link is to be followed; this keeps -p faithful for copied links
+    let source_deref_md = if follow {
+        fs::metadata(source)
+    } else {
+        Ok(source_symlink_md.clone())
+    };
     let source_is_dangling_symlink = source_deref_md.is_err();
 
-    let (source_is_dir, source_md) =
-        if (cfg.dereference || (cfg.follow_cli && init_call)) && !source_is_dangling_symlink {
-            let md = source_deref_md.as_ref().map_err(err_cannot_stat_source)?;
-            // Metadata of the file that `source` points to
-            (md.is_dir(), md)
-        } else {
-            // Metadata of `source` itself
-            (source_symlink_md.is_dir(), source_symlink_md)
-        };
+    let (source_is_dir, source_md) = if follow && !source_is_dangling_symlink {
+        let md = source_deref_md.as_ref().map_err(err_cannot_stat_source)?;
+        // Metadata of the file that `source` points to
+        (md.is_dir(), md)
+    } else {
+        // Metadata of `source` itself
+        (source_symlink_md.is_dir(), source_symlink_md)
+    };
+    let copy_as_symlink = source_md.file_type().is_symlink();
 
     let target_deref_md = fs::metadata(target);
     let target_symlink_md = fs::symlink_metadata(target);
@@ -274,8 +354,22 @@ fn copy_file(
         }
 
         // 2.e
-        if !target_exists {
-            if target.starts_with(source) {
+        if init_call {
+            // Compare real paths, so that neither relative paths nor symlinks
+            // can hide that the target is inside the source
+            let source_real = fs::canonicalize(source)?;
+            let target_real = match (target.parent(), target.file_name()) {
+                (Some(parent), Some(name)) if !target_exists => {
+                    let parent = if parent.as_os_str().is_empty() {
+                        Path::new(".")
+                    } else {
+                        parent
+                    };
+                    fs::canonicalize(parent).map(|p| p.join(name))
+                }
+                _ => fs::canonicalize(target),
+            };
+            if matches!(target_real, Ok(p) if p.starts_with(&source_real)) {
                 let err_str = gettext!(
                     "cannot copy a directory, '{}', into itself, '{}'",
                     source.display(),
@@ -283,25 +377,59 @@ fn copy_file(
                 );
                 return Err(io::Error::other(err_str));
             }
+        }
+
+        // Following symlinks can lead back into a directory that is already
+        // being copied
+        let source_id = (source_md.dev(), source_md.ino());
+        if ancestors.contains(&source_id) {
+            let err_str = gettext!(
+                "cannot copy '{}': directory causes a cycle",
+                source.display()
+            );
+            return Err(io::Error::other(err_str));
+        }
 
+        if !target_exists {
+            // The owner needs full access to fill in the directory, the
+            // final mode is set once its contents are copied
             fs::DirBuilder::new()
                 .recursive(true)
-                .mode(source_md.mode())
+                .mode(source_md.mode() | 0o700)
                 .create(target)?;
-
-            if cfg.preserve {
-                let _ = copy_characteristics(&source_md, target);
-            }
         }
 
         // 2.f
-        for entry in fs::read_dir(source)? {
-            let entry = entry?;
-
-            let new_source = entry.path();
-            let new_target = target.join(entry.file_name());
+        ancestors.push(source_id);
+        let mut copy_entries = || -> io::Result<()> {
+            for entry in fs::read_dir(source)? {
+                let entry = entry?;
+
+                let new_source = entry.path();
+                let new_target = target.join(entry.file_name());
+
+                copy_file(
+                    cfg,
+                    &new_source,
+                    &new_target,
+                    created_files,
+                    ancestors,
+                    false,
+                )?;
+            }
+            Ok(())
+        };
+        let res = copy_entries();
+        ancestors.pop();
+        res?;
 
-            copy_file(cfg, &new_source, &new_target, created_files, false)?;
+        // 2.g Done last, so that copying the entries does not change the
+        // timestamps
+        if cfg.preserve {
+            let _ = copy_characteristics(source_md, target);
+        } else if !target_exists {
+            let mode = source_md.mode() as libc::mode_t & !cfg.umask & 0o7777;
+            fs::set_permissions(target, fs::Permissions::from_mode(mode as u32))?;
         }
     } else {
         // 3. If source_file is of type regular file
@@ -341,7 +469,7 @@ fn copy_file(
                 })?;
 
             // 3.d
-            io::copy(&mut source_file, &mut target_file)?;
+            copy_data(&mut source_file, &mut target_file)?;
 
             Ok(())
         };
@@ -415,7 +543,7 @@ fn copy_file(
             }
 
             // 4.c
-            if source.is_symlink() {
+            if copy_as_symlink {
                 fs::remove_file(target)?;
                 let source_ref = fs::read_link(source)?;
                 unix::fs::symlink(source_ref, target)?;
@@ -435,7 +563,7 @@ fn copy_file(
                             );
                             io::Error::other(err_str)
                         })?;
-                        io::copy(&mut source_file, &mut target_file)?;
+                        copy_data(&mut source_file, &mut target_file)?;
                     }
                     Err(e) => {
                         // 3.a.iii
@@ -460,7 +588,7 @@ fn copy_file(
         // 3.b
         } else {
             // 4.c
-            if source.is_symlink() {
+            if copy_as_symlink {
                 let source_ref = fs::read_link(source)?;
                 unix::fs::symlink(source_ref, target)?;
             } else {
@@ -469,7 +597,14 @@ fn copy_file(
         }
 
         if cfg.preserve {
-            let _ = copy_characteristics(&source_md, target);
+            if copy_as_symlink {
+                // Reading the link has updated its access time, so the
+                // copy gets the times the source has now
+                let link_md = fs::symlink_metadata(source)?;
+                let _ = copy_characteristics(&link_md, target);
+            } else {
+                let _ = copy_characteristics(source_md, target);
+            }
         }
         created_files.insert(target.to_path_buf());
     }
@@ -510,7 +645,14 @@ fn copy_files(cfg: &Config, sources: &[PathBuf], target: &Path) -> Option<()> {
             }
         };
 
-        match copy_file(cfg, source, &new_target, &mut created_files, true) {
+        match copy_file(
+            cfg,
+            source,
+            &new_target,
+            &mut created_files,
+            &mut Vec::new(),
+            true,
+        ) {
             Ok(_) => (),
             Err(e) => {
                 eprintln!("cp: {}", error_string(&e));
@@ -565,7 +707,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     } else {
         let mut created_files = HashSet::new();
-        match copy_file(&cfg, &sources[0], target, &mut created_files, true) {
+        match copy_file(
+            &cfg,
+            &sources[0],
+            target,
+            &mut created_files,
+            &mut Vec::new(),
+            true,
+        ) {
             Ok(_) => Ok(()),
             Err(e) => {
                 eprintln!("cp: {}", error_string(&e));
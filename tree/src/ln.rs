@@ -13,7 +13,9 @@ extern crate plib;
 use clap::Parser;
 use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
 use plib::PROJECT_NAME;
-use std::path::{Path, PathBuf};
+use std::ffi::CString;
+use std::os::unix::{ffi::OsStrExt, fs::MetadataExt};
+use std::path::{Component, Path, PathBuf};
 use std::{fs, io};
 
 /// ln - link files
@@ -28,26 +30,143 @@ struct Args {
     #[arg(short, long)]
     symlink: bool,
 
+    /// If a source operand names a symbolic link, create a hard link to the file it refers to.
+    #[arg(short = 'L', overrides_with_all = ["logical", "physical"])]
+    logical: bool,
+
+    /// If a source operand names a symbolic link, create a hard link to the link itself (default).
+    #[arg(short = 'P', overrides_with_all = ["logical", "physical"])]
+    physical: bool,
+
+    /// Make symbolic links relative to the directory that contains them.
+    #[arg(short, long, requires = "symlink")]
+    relative: bool,
+
     /// Source(s) and target of link(s).
-    files: Vec<String>,
+    files: Vec<PathBuf>,
+}
+
+/// Normalize `path` into an absolute path without "." or ".." components.
+/// The directory part is resolved through symlinks when it exists, the
+/// final component is kept as is since it may itself be a link.
+fn absolute_path(path: &Path) -> io::Result<PathBuf> {
+    let path = std::env::current_dir()?.join(path);
+
+    if let (Some(parent), Some(name)) = (path.parent(), path.file_name()) {
+        if let Ok(parent) = fs::canonicalize(parent) {
+            return Ok(parent.join(name));
+        }
+    }
+
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            _ => result.push(component),
+        }
+    }
+    Ok(result)
+}
+
+/// Compute the path of `target` as seen from the directory that will
+/// contain `link`.
+fn relative_target(target: &Path, link: &Path) -> io::Result<PathBuf> {
+    let target = absolute_path(target)?;
+    let link = absolute_path(link)?;
+    let link_dir = link.parent().unwrap_or(Path::new("/"));
+
+    let target_parts: Vec<Component> = target.components().collect();
+    let dir_parts: Vec<Component> = link_dir.components().collect();
+    let common = target_parts
+        .iter()
+        .zip(&dir_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut result = PathBuf::new();
+    for _ in common..dir_parts.len() {
+        result.push("..");
+    }
+    for part in &target_parts[common..] {
+        result.push(part);
+    }
+    if result.as_os_str().is_empty() {
+        result.push(".");
+    }
+
+    Ok(result)
+}
+
+fn hard_link(src: &Path, dest: &Path, follow: bool) -> io::Result<()> {
+    let src_cstr = CString::new(src.as_os_str().as_bytes())?;
+    let dest_cstr = CString::new(dest.as_os_str().as_bytes())?;
+    let flags = if follow { libc::AT_SYMLINK_FOLLOW } else { 0 };
+
+    let ret = unsafe {
+        libc::linkat(
+            libc::AT_FDCWD,
+            src_cstr.as_ptr(),
+            libc::AT_FDCWD,
+            dest_cstr.as_ptr(),
+            flags,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
 }
 
-#[allow(deprecated)] // for soft_link()
-fn do_link(args: &Args, file1: &str, file2: &str) -> io::Result<()> {
+fn do_link(args: &Args, src: &Path, dest: &Path) -> io::Result<()> {
+    if let Ok(dest_md) = fs::symlink_metadata(dest) {
+        if !args.force {
+            return Err(io::Error::from_raw_os_error(libc::EEXIST));
+        }
+
+        // Removing the destination would lose the source if they are one file
+        if !args.symlink {
+            let src_md = if args.logical {
+                fs::metadata(src)
+            } else {
+                fs::symlink_metadata(src)
+            };
+            if let Ok(src_md) = src_md {
+                if src_md.dev() == dest_md.dev() && src_md.ino() == dest_md.ino() {
+                    return Err(io::Error::other(gettext(
+                        "source and destination are the same file",
+                    )));
+                }
+            }
+        }
+
+        if dest_md.is_dir() {
+            return Err(io::Error::from_raw_os_error(libc::EISDIR));
+        }
+        fs::remove_file(dest)?;
+    }
+
     if args.symlink {
-        fs::soft_link(file1, file2)
+        let src = if args.relative {
+            relative_target(src, dest)?
+        } else {
+            src.to_path_buf()
+        };
+        std::os::unix::fs::symlink(src, dest)
     } else {
-        fs::hard_link(file1, file2)
+        hard_link(src, dest, args.logical)
     }
 }
 
-fn do_link_into(args: &Args, src: &str, target_dir: &str) -> io::Result<()> {
-    let mut path = PathBuf::from(target_dir);
-    path.push(Path::new(src).file_name().expect("Invalid source name"));
-
-    let target_name = path.to_str().expect("Unicode filenames required");
+fn do_link_into(args: &Args, src: &Path, target_dir: &Path) -> io::Result<()> {
+    let name = match src.file_name() {
+        Some(name) => name,
+        None => return Err(io::Error::other(gettext("invalid source name"))),
+    };
 
-    do_link(args, src, target_name)
+    do_link(args, src, &target_dir.join(name))
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -68,17 +187,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut exit_code = 0;
 
-    if sources.len() == 1 {
+    if sources.len() == 1 && !target.is_dir() {
         let src = &sources[0];
         if let Err(e) = do_link(&args, src, target) {
             exit_code = 1;
-            eprintln!("{} -> {}: {}", src, target, e);
+            eprintln!("{} -> {}: {}", src.display(), target.display(), e);
         }
+    } else if !target.is_dir() {
+        exit_code = 1;
+        eprintln!(
+            "{}: {}",
+            target.display(),
+            io::Error::from_raw_os_error(libc::ENOTDIR)
+        );
     } else {
         for src in sources {
             if let Err(e) = do_link_into(&args, src, target) {
                 exit_code = 1;
-                eprintln!("{} -> {}: {}", src, target, e);
+                eprintln!("{} -> {}: {}", src.display(), target.display(), e);
             }
         }
     }
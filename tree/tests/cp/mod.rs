@@ -29,6 +29,7 @@ fn cp_test(args: &[&str], expected_output: &str, expected_error: &str, expected_
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code,
+        ..Default::default()
     });
 }
 
@@ -48,6 +49,7 @@ fn cp_test_with_stdin(
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code,
+        ..Default::default()
     });
 }
 
@@ -938,3 +940,132 @@ fn test_cp_special_bits() {
 
     fs::remove_dir_all(test_dir).unwrap();
 }
+
+#[test]
+fn test_cp_r_preserve_tree() {
+    let test_dir = &format!("{}/test_cp_r_preserve_tree", env!("CARGO_TARGET_TMPDIR"));
+    let src = &format!("{test_dir}/src");
+    let sub = &format!("{test_dir}/src/sub");
+    let file = &format!("{test_dir}/src/sub/file");
+    let link = &format!("{test_dir}/src/link");
+    let fifo = &format!("{test_dir}/src/fifo");
+    let dst = &format!("{test_dir}/dst");
+
+    fs::create_dir(test_dir).unwrap();
+    fs::create_dir_all(sub).unwrap();
+    fs::write(file, "data\n").unwrap();
+    unix::fs::symlink("sub/file", link).unwrap();
+    unsafe {
+        let fifo_cstr = CString::new(fifo.as_bytes()).unwrap();
+        let ret = libc::mkfifo(fifo_cstr.as_ptr(), 0o600);
+        if ret != 0 {
+            panic!("{}", io::Error::last_os_error());
+        }
+    }
+
+    // A read-only directory must still be filled in
+    fs::set_permissions(file, fs::Permissions::from_mode(0o640)).unwrap();
+    fs::set_permissions(sub, fs::Permissions::from_mode(0o555)).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    cp_test(&["-R", "-p", src, dst], "", "", 0);
+
+    for (orig, copy) in [
+        (sub.as_str(), format!("{dst}/sub")),
+        (file.as_str(), format!("{dst}/sub/file")),
+    ] {
+        let orig_md = fs::metadata(orig).unwrap();
+        let copy_md = fs::metadata(&copy).unwrap();
+        assert_eq!(orig_md.mode(), copy_md.mode());
+        assert_eq!(orig_md.mtime(), copy_md.mtime());
+        assert_eq!(orig_md.mtime_nsec(), copy_md.mtime_nsec());
+    }
+
+    let link_copy = format!("{dst}/link");
+    assert!(Path::new(&link_copy).is_symlink());
+    assert_eq!(
+        fs::read_link(&link_copy).unwrap(),
+        Path::new("sub/file").to_path_buf()
+    );
+    assert!(fs::symlink_metadata(format!("{dst}/fifo"))
+        .unwrap()
+        .file_type()
+        .is_fifo());
+
+    fs::set_permissions(sub, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::set_permissions(format!("{dst}/sub"), fs::Permissions::from_mode(0o755)).unwrap();
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_cp_into_self_existing_target() {
+    let test_dir = &format!(
+        "{}/test_cp_into_self_existing_target",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    let a = &format!("{test_dir}/a");
+    let a_b = &format!("{test_dir}/a/b");
+    let a_b_via_dot = &format!("{test_dir}/a/../a/b");
+
+    fs::create_dir(test_dir).unwrap();
+    fs::create_dir_all(a_b).unwrap();
+
+    cp_test(
+        &["-R", a, a_b_via_dot],
+        "",
+        &format!("cp: cannot copy a directory, '{a}', into itself, '{a_b_via_dot}/a'\n"),
+        1,
+    );
+    assert!(!Path::new(&format!("{a_b}/a")).exists());
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_cp_symlink_cycle() {
+    let test_dir = &format!("{}/test_cp_symlink_cycle", env!("CARGO_TARGET_TMPDIR"));
+    let a = &format!("{test_dir}/a");
+    let a_loop = &format!("{test_dir}/a/loop");
+    let b = &format!("{test_dir}/b");
+
+    fs::create_dir(test_dir).unwrap();
+    fs::create_dir(a).unwrap();
+    unix::fs::symlink(".", a_loop).unwrap();
+
+    cp_test(
+        &["-RL", a, b],
+        "",
+        &format!("cp: cannot copy '{a_loop}': directory causes a cycle\n"),
+        1,
+    );
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+#[cfg_attr(not(target_os = "linux"), ignore)]
+fn test_cp_sparse() {
+    let test_dir = &format!("{}/test_cp_sparse", env!("CARGO_TARGET_TMPDIR"));
+    let sparse = &format!("{test_dir}/sparse");
+    let copy = &format!("{test_dir}/copy");
+    const SIZE: u64 = 64 * 1024 * 1024;
+
+    fs::create_dir(test_dir).unwrap();
+    {
+        let mut file = fs::File::create(sparse).unwrap();
+        file.write_all(b"start").unwrap();
+        file.set_len(SIZE).unwrap();
+    }
+
+    cp_test(&[sparse, copy], "", "", 0);
+
+    let md = fs::metadata(copy).unwrap();
+    assert_eq!(md.len(), SIZE);
+    assert!(md.blocks() * 512 < SIZE);
+
+    let mut buf = [0; 5];
+    fs::File::open(copy).unwrap().read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"start");
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
@@ -8,6 +8,7 @@
 //
 
 mod cp;
+mod ln;
 mod ls;
 mod mv;
 mod rm;
@@ -44,6 +44,7 @@ fn cp_test(args: &[&str], expected_output: &str, expected_error: &str, expected_
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code,
+        ..Default::default()
     });
 }
 
@@ -57,6 +58,7 @@ fn rm_test(args: &[&str], expected_output: &str, expected_error: &str, expected_
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code,
+        ..Default::default()
     });
 }
 
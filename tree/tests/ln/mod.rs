@@ -0,0 +1,137 @@
+//
+// Copyright (c) 2024 Hemi Labs, Inc.
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::{run_test, TestPlan};
+use std::fs;
+use std::os::unix::{self, fs::MetadataExt};
+use std::path::Path;
+
+fn ln_test(args: &[&str], expected_output: &str, expected_error: &str, expected_exit_code: i32) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from("ln"),
+        args: str_args,
+        stdin_data: String::new(),
+        expected_out: String::from(expected_output),
+        expected_err: String::from(expected_error),
+        expected_exit_code,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_ln_logical_physical() {
+    let test_dir = &format!("{}/test_ln_logical_physical", env!("CARGO_TARGET_TMPDIR"));
+    let f = &format!("{test_dir}/f");
+    let slink = &format!("{test_dir}/slink");
+    let logical = &format!("{test_dir}/logical");
+    let physical = &format!("{test_dir}/physical");
+
+    fs::create_dir(test_dir).unwrap();
+    fs::write(f, "f\n").unwrap();
+    unix::fs::symlink("f", slink).unwrap();
+
+    ln_test(&["-L", slink, logical], "", "", 0);
+    let md = fs::symlink_metadata(logical).unwrap();
+    assert!(md.is_file());
+    assert_eq!(md.ino(), fs::metadata(f).unwrap().ino());
+
+    // The last of -L and -P wins
+    ln_test(&["-L", "-P", slink, physical], "", "", 0);
+    let md = fs::symlink_metadata(physical).unwrap();
+    assert!(md.file_type().is_symlink());
+    assert_eq!(md.ino(), fs::symlink_metadata(slink).unwrap().ino());
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_ln_relative() {
+    let test_dir = &format!("{}/test_ln_relative", env!("CARGO_TARGET_TMPDIR"));
+    let a_f = &format!("{test_dir}/a/f");
+    let b_c = &format!("{test_dir}/b/c");
+    let link = &format!("{test_dir}/b/c/link");
+    let link_via_dot = &format!("{test_dir}/b/./c/../link");
+
+    fs::create_dir(test_dir).unwrap();
+    fs::create_dir(format!("{test_dir}/a")).unwrap();
+    fs::create_dir_all(b_c).unwrap();
+    fs::write(a_f, "f\n").unwrap();
+
+    ln_test(&["-s", "-r", a_f, link], "", "", 0);
+    assert_eq!(fs::read_link(link).unwrap(), Path::new("../../a/f"));
+    assert_eq!(fs::read_to_string(link).unwrap(), "f\n");
+
+    ln_test(&["-sr", a_f, link_via_dot], "", "", 0);
+    assert_eq!(
+        fs::read_link(format!("{test_dir}/b/link")).unwrap(),
+        Path::new("../a/f")
+    );
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_ln_into_directory() {
+    let test_dir = &format!("{}/test_ln_into_directory", env!("CARGO_TARGET_TMPDIR"));
+    let f = &format!("{test_dir}/f");
+    let g = &format!("{test_dir}/g");
+    let d = &format!("{test_dir}/d");
+
+    fs::create_dir(test_dir).unwrap();
+    fs::create_dir(d).unwrap();
+    fs::write(f, "f\n").unwrap();
+    fs::write(g, "g\n").unwrap();
+
+    // A single source is linked into an existing directory too
+    ln_test(&[f, d], "", "", 0);
+    ln_test(&["-s", "-r", g, d], "", "", 0);
+    assert_eq!(fs::read_to_string(format!("{d}/f")).unwrap(), "f\n");
+    assert_eq!(fs::read_link(format!("{d}/g")).unwrap(), Path::new("../g"));
+
+    ln_test(
+        &[f, g, f],
+        "",
+        &format!("{f}: Not a directory (os error 20)\n"),
+        1,
+    );
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
+
+#[test]
+fn test_ln_force() {
+    let test_dir = &format!("{}/test_ln_force", env!("CARGO_TARGET_TMPDIR"));
+    let f = &format!("{test_dir}/f");
+    let g = &format!("{test_dir}/g");
+
+    fs::create_dir(test_dir).unwrap();
+    fs::write(f, "f\n").unwrap();
+    fs::write(g, "g\n").unwrap();
+
+    ln_test(
+        &[f, g],
+        "",
+        &format!("{f} -> {g}: File exists (os error 17)\n"),
+        1,
+    );
+    ln_test(&["-f", f, g], "", "", 0);
+    assert_eq!(fs::read_to_string(g).unwrap(), "f\n");
+
+    ln_test(
+        &["-f", f, g],
+        "",
+        &format!("{f} -> {g}: source and destination are the same file\n"),
+        1,
+    );
+    assert!(Path::new(f).exists());
+
+    fs::remove_dir_all(test_dir).unwrap();
+}
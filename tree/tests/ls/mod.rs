@@ -88,6 +88,7 @@ fn ls_test(args: &[&str], expected_output: &str, expected_error: &str, expected_
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code: expected_exit_code,
+        ..Default::default()
     });
 }
 
@@ -101,6 +102,7 @@ fn ls_test_with_checker<F: FnMut(&TestPlan, &std::process::Output)>(args: &[&str
         expected_out: String::new(),
         expected_err: String::new(),
         expected_exit_code: 0,
+        ..Default::default()
     };
 
     run_test_with_checker(test_plan, checker);
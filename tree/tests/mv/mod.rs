@@ -29,6 +29,7 @@ fn mv_test(args: &[&str], expected_output: &str, expected_error: &str, expected_
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code,
+        ..Default::default()
     });
 }
 
@@ -48,6 +49,7 @@ fn mv_test_with_stdin(
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code,
+        ..Default::default()
     });
 }
 
@@ -28,6 +28,7 @@ fn rm_test(args: &[&str], expected_output: &str, expected_error: &str, expected_
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code,
+        ..Default::default()
     });
 }
 
@@ -47,6 +48,7 @@ fn rm_test_with_stdin(
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code,
+        ..Default::default()
     });
 }
 
@@ -96,6 +98,7 @@ fn rm_test_with_checker<F: FnMut(&TestPlan, &std::process::Output)>(
         expected_out: String::new(),
         expected_err: String::new(),
         expected_exit_code: 0,
+        ..Default::default()
     };
 
     run_test_with_checker(test_plan, checker);
@@ -0,0 +1,122 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+extern crate clap;
+extern crate libc;
+extern crate plib;
+
+use clap::Parser;
+use plib::i18n::{self, gettext};
+use std::ffi::{CStr, CString};
+use std::io;
+
+/// groups - display the groups a user is in
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about)]
+struct Args {
+    /// Users whose groups are shown, instead of the invoking process.
+    users: Vec<String>,
+}
+
+fn group_name(gid: libc::gid_t) -> String {
+    let group = unsafe { libc::getgrgid(gid) };
+    if group.is_null() {
+        return gid.to_string();
+    }
+    unsafe { CStr::from_ptr((*group).gr_name) }
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Push `gid` unless it is already listed.
+fn add_group(groups: &mut Vec<libc::gid_t>, gid: libc::gid_t) {
+    if !groups.contains(&gid) {
+        groups.push(gid);
+    }
+}
+
+/// The effective, real and supplementary groups of this process.
+fn process_groups() -> io::Result<Vec<libc::gid_t>> {
+    let mut groups = Vec::new();
+    add_group(&mut groups, unsafe { libc::getegid() });
+    add_group(&mut groups, unsafe { libc::getgid() });
+
+    let count = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+    if count < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut supplementary = vec![0; count as usize];
+    let count = unsafe { libc::getgroups(count, supplementary.as_mut_ptr()) };
+    if count < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    supplementary.truncate(count as usize);
+
+    for gid in supplementary {
+        add_group(&mut groups, gid);
+    }
+    Ok(groups)
+}
+
+/// The primary group of `user`, then the groups listing it as a member.
+fn user_groups(user: &str) -> Option<Vec<libc::gid_t>> {
+    let user_str = CString::new(user).ok()?;
+    let passwd = unsafe { libc::getpwnam(user_str.as_ptr()) };
+    if passwd.is_null() {
+        return None;
+    }
+
+    let mut groups = vec![unsafe { (*passwd).pw_gid }];
+    for group in plib::group::load() {
+        if group.members.iter().any(|member| member == user) {
+            add_group(&mut groups, group.gid);
+        }
+    }
+    Some(groups)
+}
+
+fn format_groups(groups: &[libc::gid_t]) -> String {
+    let names: Vec<String> = groups.iter().map(|&gid| group_name(gid)).collect();
+    names.join(" ")
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // parse command line arguments
+    let args = Args::parse();
+
+    i18n::init();
+
+    if args.users.is_empty() {
+        match process_groups() {
+            Ok(groups) => println!("{}", format_groups(&groups)),
+            Err(e) => {
+                eprintln!("groups: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    let mut exit_code = 0;
+    for user in &args.users {
+        match user_groups(user) {
+            // several users are told apart by name
+            Some(groups) if args.users.len() > 1 => {
+                println!("{} : {}", user, format_groups(&groups))
+            }
+            Some(groups) => println!("{}", format_groups(&groups)),
+            None => {
+                eprintln!("groups: {}", gettext!("{}: no such user", user));
+                exit_code = 1;
+            }
+        }
+    }
+
+    std::process::exit(exit_code)
+}
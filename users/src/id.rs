@@ -6,18 +6,15 @@
 // file in the root directory of this project.
 // SPDX-License-Identifier: MIT
 //
-// TODO:
-// - bug: only one group is returned, in group list (MacOS-only?)
-//
 
 extern crate clap;
 extern crate libc;
 extern crate plib;
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::PROJECT_NAME;
+use plib::i18n::{self, gettext};
 use std::collections::HashMap;
+use std::ffi::{CStr, CString};
 use std::io::Error;
 
 /// id - return user identity
@@ -37,11 +34,11 @@ struct Args {
     e_user: bool,
 
     /// Output the real ID instead of the effective ID.
-    #[arg(short, long)]
+    #[arg(short, long, requires = "output")]
     real: bool,
 
     /// Output the name in string format, instead of the numeric
-    #[arg(short, long)]
+    #[arg(short, long, requires = "output")]
     name: bool,
 
     /// The login name for which information is to be written.
@@ -55,8 +52,33 @@ struct UserInfo {
     egid: libc::gid_t,
     groups: Vec<libc::gid_t>,
 
-    username: String,
-    group_names: HashMap<libc::gid_t, String>,
+    username: Option<String>,
+    user_names: HashMap<libc::uid_t, Option<String>>,
+    group_names: HashMap<libc::gid_t, Option<String>>,
+}
+
+fn user_name(uid: libc::uid_t) -> Option<String> {
+    let passwd = unsafe { libc::getpwuid(uid) };
+    if passwd.is_null() {
+        return None;
+    }
+    Some(
+        unsafe { CStr::from_ptr((*passwd).pw_name) }
+            .to_string_lossy()
+            .to_string(),
+    )
+}
+
+fn group_name(gid: libc::gid_t) -> Option<String> {
+    let group = unsafe { libc::getgrgid(gid) };
+    if group.is_null() {
+        return None;
+    }
+    Some(
+        unsafe { CStr::from_ptr((*group).gr_name) }
+            .to_string_lossy()
+            .to_string(),
+    )
 }
 
 fn userinfo_process(userinfo: &mut UserInfo) -> Result<(), Box<dyn std::error::Error>> {
@@ -64,30 +86,29 @@ fn userinfo_process(userinfo: &mut UserInfo) -> Result<(), Box<dyn std::error::E
     userinfo.gid = unsafe { libc::getgid() };
     userinfo.euid = unsafe { libc::geteuid() };
     userinfo.egid = unsafe { libc::getegid() };
+    userinfo.username = user_name(userinfo.uid);
 
-    let passwd = unsafe { libc::getpwuid(userinfo.uid) };
-    if passwd.is_null() {
-        let err = Error::last_os_error();
-        eprintln!("getpwuid: {}", err);
-        return Err(Box::new(err));
+    // supplementary groups of the process, which may differ from the group database
+    let count = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+    if count < 0 {
+        return Err(Box::new(Error::last_os_error()));
     }
-
-    userinfo.username = unsafe {
-        std::ffi::CStr::from_ptr((*passwd).pw_name)
-            .to_string_lossy()
-            .to_string()
-    };
+    let mut groups = vec![0; count as usize];
+    let count = unsafe { libc::getgroups(count, groups.as_mut_ptr()) };
+    if count < 0 {
+        return Err(Box::new(Error::last_os_error()));
+    }
+    groups.truncate(count as usize);
+    userinfo.groups = groups;
 
     Ok(())
 }
 
 fn userinfo_name(userinfo: &mut UserInfo, user: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let user_str = std::ffi::CString::new(user).unwrap();
+    let user_str = CString::new(user)?;
     let passwd = unsafe { libc::getpwnam(user_str.as_ptr()) };
     if passwd.is_null() {
-        let err = Error::last_os_error();
-        eprintln!("getpwnam: {}", err);
-        return Err(Box::new(err));
+        return Err(gettext!("{}: no such user", user).into());
     }
 
     unsafe {
@@ -96,11 +117,17 @@ fn userinfo_name(userinfo: &mut UserInfo, user: &str) -> Result<(), Box<dyn std:
     }
     userinfo.euid = userinfo.uid;
     userinfo.egid = userinfo.gid;
-    userinfo.username = unsafe {
-        std::ffi::CStr::from_ptr((*passwd).pw_name)
-            .to_string_lossy()
-            .to_string()
-    };
+    let username = unsafe { CStr::from_ptr((*passwd).pw_name) }
+        .to_string_lossy()
+        .to_string();
+
+    // groups that list the user as a member
+    for group in plib::group::load() {
+        if group.members.contains(&username) {
+            userinfo.groups.push(group.gid);
+        }
+    }
+    userinfo.username = Some(username);
 
     Ok(())
 }
@@ -112,11 +139,12 @@ fn get_user_info(args: &Args) -> Result<UserInfo, Box<dyn std::error::Error>> {
         euid: 0,
         egid: 0,
         groups: Vec::new(),
-        username: String::new(),
+        username: None,
+        user_names: HashMap::new(),
         group_names: HashMap::new(),
     };
 
-    // fill in uid, gid
+    // fill in uid, gid and supplementary groups
     match args.user {
         None => userinfo_process(&mut userinfo)?,
         Some(ref user) => userinfo_name(&mut userinfo, user)?,
@@ -125,89 +153,141 @@ fn get_user_info(args: &Args) -> Result<UserInfo, Box<dyn std::error::Error>> {
     Ok(userinfo)
 }
 
-fn get_group_info(userinfo: &mut UserInfo) -> Result<(), Box<dyn std::error::Error>> {
-    let groups = plib::group::load();
-
-    for group in &groups {
-        // skip groups that the user is not a member of
-        let mut found = false;
-        for member in &group.members {
-            if *member == userinfo.username {
-                found = true;
-                break;
+impl UserInfo {
+    /// The effective (or with `real`, the real) group ID, followed by
+    /// the other group IDs, without repeats.
+    fn all_groups(&self, real: bool) -> Vec<libc::gid_t> {
+        let mut groups = Vec::new();
+        let first = if real { self.gid } else { self.egid };
+        for gid in [first, self.gid]
+            .into_iter()
+            .chain(self.groups.iter().copied())
+        {
+            if !groups.contains(&gid) {
+                groups.push(gid);
             }
         }
-        if !found && group.gid != userinfo.gid {
-            continue;
+        groups
+    }
+
+    fn user_name(&mut self, uid: libc::uid_t) -> Option<String> {
+        if uid == self.uid && self.username.is_some() {
+            return self.username.clone();
         }
+        self.user_names
+            .entry(uid)
+            .or_insert_with(|| user_name(uid))
+            .clone()
+    }
 
-        // add group to user's group list
-        userinfo.groups.push(group.gid);
-        userinfo.group_names.insert(group.gid, group.name.clone());
+    fn group_name(&mut self, gid: libc::gid_t) -> Option<String> {
+        self.group_names
+            .entry(gid)
+            .or_insert_with(|| group_name(gid))
+            .clone()
     }
 
-    Ok(())
+    /// "uid(name)", or just the number if the ID has no name.
+    fn fmt_user(&mut self, uid: libc::uid_t) -> String {
+        match self.user_name(uid) {
+            Some(name) => format!("{}({})", uid, name),
+            None => uid.to_string(),
+        }
+    }
+
+    fn fmt_group(&mut self, gid: libc::gid_t) -> String {
+        match self.group_name(gid) {
+            Some(name) => format!("{}({})", gid, name),
+            None => gid.to_string(),
+        }
+    }
 }
 
-fn display_user_info(args: &Args, userinfo: &UserInfo) {
+fn display_user_info(args: &Args, userinfo: &mut UserInfo) {
+    // with -n, an ID without a name is written as a number
     if args.e_user {
-        println!("{}", userinfo.euid);
+        let uid = if args.real {
+            userinfo.uid
+        } else {
+            userinfo.euid
+        };
+        match userinfo.user_name(uid).filter(|_| args.name) {
+            Some(name) => println!("{}", name),
+            None => println!("{}", uid),
+        }
         return;
     }
 
     if args.group {
-        println!("{}", userinfo.egid);
+        let gid = if args.real {
+            userinfo.gid
+        } else {
+            userinfo.egid
+        };
+        match userinfo.group_name(gid).filter(|_| args.name) {
+            Some(name) => println!("{}", name),
+            None => println!("{}", gid),
+        }
         return;
     }
 
     if args.groups {
-        for gid in &userinfo.groups {
-            print!("{} ", gid);
-        }
-        println!();
+        let groups = userinfo.all_groups(args.real);
+        let list: Vec<String> = groups
+            .iter()
+            .map(
+                |&gid| match userinfo.group_name(gid).filter(|_| args.name) {
+                    Some(name) => name,
+                    None => gid.to_string(),
+                },
+            )
+            .collect();
+        println!("{}", list.join(" "));
         return;
     }
 
-    if args.name {
-        let group_name = {
-            match userinfo.group_names.get(&userinfo.egid) {
-                None => "unknown",
-                Some(name) => name,
-            }
-        };
-        println!(
-            "uid={}({}) gid={}({}) groups={}",
-            userinfo.uid, userinfo.username, userinfo.gid, group_name, userinfo.egid
-        );
-        for gid in &userinfo.groups {
-            print!("{}({}),", gid, userinfo.group_names[gid]);
-        }
-        println!();
-        return;
+    let mut out = format!(
+        "uid={} gid={}",
+        userinfo.fmt_user(userinfo.uid),
+        userinfo.fmt_group(userinfo.gid)
+    );
+    if userinfo.euid != userinfo.uid {
+        out.push_str(&format!(" euid={}", userinfo.fmt_user(userinfo.euid)));
+    }
+    if userinfo.egid != userinfo.gid {
+        out.push_str(&format!(" egid={}", userinfo.fmt_group(userinfo.egid)));
     }
 
-    println!(
-        "uid={} gid={} groups={}",
-        userinfo.uid, userinfo.gid, userinfo.egid
-    );
-    for gid in &userinfo.groups {
-        print!("{},", gid);
+    let mut groups = userinfo.groups.clone();
+    if !groups.contains(&userinfo.gid) {
+        groups.insert(0, userinfo.gid);
+    }
+    if !groups.is_empty() {
+        let list: Vec<String> = groups
+            .into_iter()
+            .map(|gid| userinfo.fmt_group(gid))
+            .collect();
+        out.push_str(&format!(" groups={}", list.join(",")));
     }
-    println!();
+
+    println!("{}", out);
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // parse command line arguments
     let args = Args::parse();
 
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain(PROJECT_NAME)?;
-    bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
+    i18n::init();
 
-    let mut userinfo = get_user_info(&args)?;
-    get_group_info(&mut userinfo)?;
+    let mut userinfo = match get_user_info(&args) {
+        Ok(userinfo) => userinfo,
+        Err(e) => {
+            eprintln!("id: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    display_user_info(&args, &userinfo);
+    display_user_info(&args, &mut userinfo);
 
     Ok(())
 }
@@ -9,14 +9,11 @@
 
 extern crate plib;
 
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::PROJECT_NAME;
+use plib::i18n::{self, gettext};
 use syslog::{Facility, Formatter3164};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain(PROJECT_NAME)?;
-    bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
+    i18n::init();
 
     let mut args: Vec<String> = std::env::args().collect();
     args.remove(0);
@@ -30,7 +27,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     };
 
     match syslog::unix(formatter) {
-        Err(e) => eprintln!("Unable to connect to syslog: {:?}", e),
+        Err(e) => eprintln!("logger: {}", gettext!("unable to connect to syslog: {}", e)),
         Ok(mut writer) => {
             writer.err(&log_str).expect("could not write error message");
         }
@@ -8,7 +8,6 @@
 //
 // TODO:
 // - use .metadata() and std::os::unix::fs::PermissionsExt if possible
-// - set process exit code according to spec
 //
 
 extern crate clap;
@@ -16,10 +15,10 @@ extern crate libc;
 extern crate plib;
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, gettext, setlocale, textdomain, LocaleCategory};
-use plib::PROJECT_NAME;
-use std::io::{self, Error, ErrorKind};
+use plib::i18n::{self, gettext};
+use std::io::{self, Error};
 use std::mem;
+use std::process;
 
 /// mesg - permit or deny messages
 #[derive(Parser, Debug)]
@@ -36,13 +35,7 @@ const STREAMS: [atty::Stream; 3] = [
 ];
 
 fn find_tty() -> Option<atty::Stream> {
-    for stream in STREAMS {
-        if atty::is(stream) {
-            return Some(stream);
-        }
-    }
-
-    None
+    STREAMS.into_iter().find(|&stream| atty::is(stream))
 }
 
 fn tty_to_fd(tty: atty::Stream) -> i32 {
@@ -54,11 +47,10 @@ fn tty_to_fd(tty: atty::Stream) -> i32 {
 }
 
 fn stat_tty() -> io::Result<(i32, libc::stat)> {
-    let tty_res = find_tty();
-    if tty_res.is_none() {
-        return Err(Error::new(ErrorKind::Other, "tty not found"));
-    }
-    let fd = tty_to_fd(tty_res.unwrap());
+    let fd = match find_tty() {
+        Some(tty) => tty_to_fd(tty),
+        None => return Err(Error::other(gettext("tty not found"))),
+    };
 
     unsafe {
         let mut st: libc::stat = mem::zeroed();
@@ -72,13 +64,20 @@ fn stat_tty() -> io::Result<(i32, libc::stat)> {
     }
 }
 
-fn show_mesg(st: libc::stat) -> io::Result<()> {
-    if (st.st_mode & (libc::S_IWGRP | libc::S_IWOTH)) != 0 {
+/// Whether other users may write to the terminal. write and talkd check
+/// the same group write bit.
+fn is_enabled(st: &libc::stat) -> bool {
+    (st.st_mode & libc::S_IWGRP) != 0
+}
+
+fn show_mesg(st: &libc::stat) -> bool {
+    let enabled = is_enabled(st);
+    if enabled {
         println!("is y");
     } else {
         println!("is n");
     }
-    Ok(())
+    enabled
 }
 
 fn parse_setting(setting: &str) -> Result<bool, &'static str> {
@@ -89,27 +88,14 @@ fn parse_setting(setting: &str) -> Result<bool, &'static str> {
     }
 }
 
-fn set_mesg(fd: i32, st: libc::stat, setting: &str) -> io::Result<()> {
-    let res = parse_setting(setting);
-    if let Err(e) = res {
-        return Err(Error::new(ErrorKind::Other, e));
-    }
-    let affirm = res.unwrap();
-
-    let mut mode = st.st_mode;
-
-    if affirm {
-        if (mode & (libc::S_IWGRP | libc::S_IWOTH)) != 0 {
-            return Ok(());
-        }
-
-        mode = mode | libc::S_IWGRP | libc::S_IWOTH;
+fn set_mesg(fd: i32, st: &libc::stat, affirm: bool) -> io::Result<()> {
+    let mode = if affirm {
+        st.st_mode | libc::S_IWGRP
     } else {
-        if (mode & (libc::S_IWGRP | libc::S_IWOTH)) == 0 {
-            return Ok(());
-        }
-
-        mode = mode & !(libc::S_IWGRP | libc::S_IWOTH);
+        st.st_mode & !(libc::S_IWGRP | libc::S_IWOTH)
+    };
+    if mode == st.st_mode {
+        return Ok(());
     }
 
     let chres = unsafe { libc::fchmod(fd, mode) };
@@ -125,16 +111,36 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // parse command line arguments
     let args = Args::parse();
 
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain(PROJECT_NAME)?;
-    bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
-
-    let (fd, stat) = stat_tty()?;
+    i18n::init();
 
-    match args.operand {
-        None => show_mesg(stat)?,
-        Some(op) => set_mesg(fd, stat, &op)?,
-    }
+    // exit status: 0 if messages are allowed, 1 if not, 2 on error
+    let affirm = match args.operand.as_deref().map(parse_setting) {
+        Some(Err(e)) => {
+            eprintln!("mesg: {}", gettext(e));
+            process::exit(2);
+        }
+        Some(Ok(affirm)) => Some(affirm),
+        None => None,
+    };
+
+    let (fd, stat) = match stat_tty() {
+        Ok(res) => res,
+        Err(e) => {
+            eprintln!("mesg: {}", e);
+            process::exit(2);
+        }
+    };
+
+    let enabled = match affirm {
+        None => show_mesg(&stat),
+        Some(affirm) => {
+            if let Err(e) = set_mesg(fd, &stat, affirm) {
+                eprintln!("mesg: {}", e);
+                process::exit(2);
+            }
+            affirm
+        }
+    };
 
-    Ok(())
+    process::exit(if enabled { 0 } else { 1 })
 }
@@ -14,8 +14,7 @@ extern crate clap;
 extern crate plib;
 
 use clap::Parser;
-use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
-use plib::PROJECT_NAME;
+use plib::i18n;
 use std::ffi::OsStr;
 use std::path::{Component, Path};
 
@@ -59,9 +58,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // parse command line arguments
     let args = Args::parse();
 
-    setlocale(LocaleCategory::LcAll, "");
-    textdomain(PROJECT_NAME)?;
-    bind_textdomain_codeset(PROJECT_NAME, "UTF-8")?;
+    i18n::init();
 
     let mut cwd = std::env::current_dir()?.into_os_string();
     if args.env {
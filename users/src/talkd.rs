@@ -0,0 +1,508 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+// TODO:
+// - log requests to syslog
+//
+
+extern crate clap;
+extern crate libc;
+extern crate plib;
+
+use clap::Parser;
+use plib::i18n::{self, gettext};
+use std::ffi::CStr;
+use std::fs;
+use std::io::{self, Write};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::os::unix::io::FromRawFd;
+use std::time::{Duration, Instant};
+
+/// talkd - remote user communication server
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about)]
+struct Args {
+    /// Use the datagram socket on standard input, as started by inetd.
+    #[arg(short, long)]
+    inetd: bool,
+
+    /// UDP port to listen on.
+    #[arg(short, long, default_value_t = NTALK_PORT, conflicts_with = "inetd")]
+    port: u16,
+
+    /// Print each request and response on standard error.
+    #[arg(short, long)]
+    debug: bool,
+}
+
+const NTALK_PORT: u16 = 518;
+const TALK_VERSION: u8 = 1;
+
+// how long an invitation is kept
+const MAX_LIFE: Duration = Duration::from_secs(60);
+// under inetd, exit after this long without requests
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+// message types
+const LEAVE_INVITE: u8 = 0;
+const LOOK_UP: u8 = 1;
+const DELETE: u8 = 2;
+const ANNOUNCE: u8 = 3;
+
+// answers
+const SUCCESS: u8 = 0;
+const NOT_HERE: u8 = 1;
+const FAILED: u8 = 2;
+const PERMISSION_DENIED: u8 = 4;
+const UNKNOWN_REQUEST: u8 = 5;
+const BADVERSION: u8 = 6;
+const BADADDR: u8 = 7;
+const BADCTLADDR: u8 = 8;
+
+const CTL_MSG_LEN: usize = 84;
+const CTL_RESPONSE_LEN: usize = 24;
+const NAME_SIZE: usize = 12;
+const TTY_SIZE: usize = 16;
+
+/// An IPv4 address in the BSD `osockaddr` layout: a big-endian address
+/// family, then the port and address, padded to 16 bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TalkAddr {
+    family: u16,
+    addr: SocketAddrV4,
+}
+
+impl TalkAddr {
+    fn decode(buf: &[u8]) -> TalkAddr {
+        TalkAddr {
+            family: u16::from_be_bytes([buf[0], buf[1]]),
+            addr: SocketAddrV4::new(
+                Ipv4Addr::new(buf[4], buf[5], buf[6], buf[7]),
+                u16::from_be_bytes([buf[2], buf[3]]),
+            ),
+        }
+    }
+
+    fn encode(&self, buf: &mut [u8]) {
+        buf[0..2].copy_from_slice(&self.family.to_be_bytes());
+        buf[2..4].copy_from_slice(&self.addr.port().to_be_bytes());
+        buf[4..8].copy_from_slice(&self.addr.ip().octets());
+        buf[8..16].fill(0);
+    }
+
+    fn empty() -> TalkAddr {
+        TalkAddr {
+            family: 0,
+            addr: SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0),
+        }
+    }
+}
+
+/// A request from a talk client (`CTL_MSG`).
+#[derive(Debug, Clone)]
+struct CtlMsg {
+    vers: u8,
+    typ: u8,
+    id_num: u32,
+    /// Where the caller accepts the talk connection
+    addr: TalkAddr,
+    /// Where the caller receives responses
+    ctl_addr: TalkAddr,
+    pid: i32,
+    l_name: String,
+    r_name: String,
+    r_tty: String,
+}
+
+fn c_field(buf: &[u8]) -> String {
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..end]).into_owned()
+}
+
+impl CtlMsg {
+    fn decode(buf: &[u8]) -> Option<CtlMsg> {
+        if buf.len() < CTL_MSG_LEN {
+            return None;
+        }
+
+        Some(CtlMsg {
+            vers: buf[0],
+            typ: buf[1],
+            id_num: u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]),
+            addr: TalkAddr::decode(&buf[8..24]),
+            ctl_addr: TalkAddr::decode(&buf[24..40]),
+            pid: i32::from_be_bytes([buf[40], buf[41], buf[42], buf[43]]),
+            l_name: c_field(&buf[44..44 + NAME_SIZE]),
+            r_name: c_field(&buf[56..56 + NAME_SIZE]),
+            r_tty: c_field(&buf[68..68 + TTY_SIZE]),
+        })
+    }
+
+    /// True if `self` is an invitation for the same call as `other`.
+    fn same_call(&self, other: &CtlMsg) -> bool {
+        self.l_name == other.l_name
+            && self.r_name == other.r_name
+            && self.pid == other.pid
+            && self.ctl_addr == other.ctl_addr
+    }
+}
+
+/// A reply to a talk client (`CTL_RESPONSE`).
+#[derive(Debug)]
+struct CtlResponse {
+    typ: u8,
+    answer: u8,
+    id_num: u32,
+    addr: TalkAddr,
+}
+
+impl CtlResponse {
+    fn encode(&self) -> [u8; CTL_RESPONSE_LEN] {
+        let mut buf = [0; CTL_RESPONSE_LEN];
+        buf[0] = TALK_VERSION;
+        buf[1] = self.typ;
+        buf[2] = self.answer;
+        buf[4..8].copy_from_slice(&self.id_num.to_be_bytes());
+        self.addr.encode(&mut buf[8..24]);
+        buf
+    }
+}
+
+struct Invitation {
+    msg: CtlMsg,
+    created: Instant,
+}
+
+/// Pending invitations, keyed by their id.
+struct Table {
+    invitations: Vec<Invitation>,
+    next_id: u32,
+}
+
+impl Table {
+    fn new() -> Table {
+        Table {
+            invitations: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    fn expire(&mut self) {
+        self.invitations
+            .retain(|invite| invite.created.elapsed() < MAX_LIFE);
+    }
+
+    /// An invitation left by the caller of `msg` for the same call.
+    fn find_request(&mut self, msg: &CtlMsg, typ: u8) -> Option<&mut Invitation> {
+        self.invitations
+            .iter_mut()
+            .find(|invite| invite.msg.typ == typ && invite.msg.same_call(msg))
+    }
+
+    /// An invitation left for the sender of `msg`, by the user it wants to talk to.
+    fn find_match(&self, msg: &CtlMsg) -> Option<&Invitation> {
+        self.invitations.iter().find(|invite| {
+            invite.msg.typ == LEAVE_INVITE
+                && invite.msg.l_name == msg.r_name
+                && invite.msg.r_name == msg.l_name
+        })
+    }
+
+    fn insert(&mut self, msg: &CtlMsg) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1).max(1);
+
+        let mut msg = msg.clone();
+        msg.id_num = id;
+        self.invitations.push(Invitation {
+            msg,
+            created: Instant::now(),
+        });
+        id
+    }
+
+    fn delete(&mut self, id: u32) -> bool {
+        let len = self.invitations.len();
+        self.invitations.retain(|invite| invite.msg.id_num != id);
+        self.invitations.len() != len
+    }
+}
+
+/// Find the terminal of a logged-in user that accepts messages. Without
+/// a requested terminal, the one with the most recent input is chosen.
+fn find_user(name: &str, tty: &str) -> Result<String, u8> {
+    let mut answer = NOT_HERE;
+    let mut best: Option<(String, i64)> = None;
+
+    for entry in plib::utmpx::entries() {
+        if !entry.is_user_session() || entry.user != name {
+            continue;
+        }
+        if !tty.is_empty() && entry.line != tty {
+            continue;
+        }
+
+        let md = match fs::metadata(format!("/dev/{}", entry.line)) {
+            Ok(md) => md,
+            Err(_) => continue,
+        };
+        // mesg n removes the group write permission
+        if md.mode() & 0o020 == 0 {
+            answer = PERMISSION_DENIED;
+            continue;
+        }
+
+        if best.as_ref().is_none_or(|(_, atime)| md.atime() > *atime) {
+            best = Some((entry.line.clone(), md.atime()));
+        }
+    }
+
+    best.map(|(line, _)| line).ok_or(answer)
+}
+
+fn hostname() -> String {
+    let mut buf = [0 as libc::c_char; 256];
+    let ret = unsafe { libc::gethostname(buf.as_mut_ptr(), buf.len()) };
+    if ret != 0 {
+        return String::from("localhost");
+    }
+    buf[buf.len() - 1] = 0;
+    unsafe { CStr::from_ptr(buf.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Name of the host at `addr`, or its address if it has none.
+fn remote_hostname(addr: &Ipv4Addr) -> String {
+    let sin = libc::sockaddr_in {
+        #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+        sin_len: std::mem::size_of::<libc::sockaddr_in>() as u8,
+        sin_family: libc::AF_INET as libc::sa_family_t,
+        sin_port: 0,
+        sin_addr: libc::in_addr {
+            s_addr: u32::from_ne_bytes(addr.octets()),
+        },
+        sin_zero: [0; 8],
+    };
+
+    let mut host = [0 as libc::c_char; 1025];
+    let ret = unsafe {
+        libc::getnameinfo(
+            &sin as *const libc::sockaddr_in as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+            host.as_mut_ptr(),
+            host.len() as libc::socklen_t,
+            std::ptr::null_mut(),
+            0,
+            libc::NI_NAMEREQD,
+        )
+    };
+    if ret != 0 {
+        return addr.to_string();
+    }
+    unsafe { CStr::from_ptr(host.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Make `s` safe to print on a terminal: control characters are shown
+/// as ^X. The caller's name comes from the network, and its host name
+/// from a lookup that the caller may control.
+fn sanitize(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+
+    for ch in s.chars() {
+        match ch {
+            c if (c as u32) < 0x20 => {
+                out.push('^');
+                out.push((c as u8 + b'@') as char);
+            }
+            '\x7f' => out.push_str("^?"),
+            c if c.is_control() => {
+                out.push_str("M-^");
+                out.push(((c as u32 - 0x80) as u8 + b'@') as char);
+            }
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Write the invitation in `msg` to the callee's terminal.
+fn announce(msg: &CtlMsg, tty: &str, caller_host: &str) -> io::Result<()> {
+    let now = unsafe {
+        let t = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&t, &mut tm);
+        tm
+    };
+
+    let caller = format!("{}@{}", sanitize(&msg.l_name), sanitize(caller_host));
+    let text = format!(
+        "\x07\r\n{} Talk_Daemon@{} {} {:02}:{:02} ...\r\n{} {}.\r\n{}  talk {}\r\n\r\n",
+        gettext("Message from"),
+        hostname(),
+        gettext("at"),
+        now.tm_hour,
+        now.tm_min,
+        gettext("talk: connection requested by"),
+        caller,
+        gettext("talk: respond with:"),
+        caller
+    );
+
+    // never block on, or become controlled by, the user's terminal
+    let mut term = fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+        .open(format!("/dev/{}", tty))?;
+    term.write_all(text.as_bytes())
+}
+
+fn process_request(table: &mut Table, msg: &CtlMsg, sender: &SocketAddr) -> CtlResponse {
+    let mut response = CtlResponse {
+        typ: msg.typ,
+        answer: SUCCESS,
+        id_num: 0,
+        addr: TalkAddr::empty(),
+    };
+
+    if msg.vers != TALK_VERSION {
+        response.answer = BADVERSION;
+        return response;
+    }
+    if msg.addr.family != libc::AF_INET as u16 {
+        response.answer = BADADDR;
+        return response;
+    }
+    if msg.ctl_addr.family != libc::AF_INET as u16 {
+        response.answer = BADCTLADDR;
+        return response;
+    }
+
+    table.expire();
+
+    match msg.typ {
+        ANNOUNCE => {
+            let tty = match find_user(&msg.r_name, &msg.r_tty) {
+                Ok(tty) => tty,
+                Err(answer) => {
+                    response.answer = answer;
+                    return response;
+                }
+            };
+
+            // a repeated announcement is only written again if it is a new call
+            if let Some(invite) = table.find_request(msg, ANNOUNCE) {
+                if msg.id_num <= invite.msg.id_num {
+                    response.id_num = invite.msg.id_num;
+                    return response;
+                }
+            }
+
+            let caller_host = match sender {
+                SocketAddr::V4(addr) => remote_hostname(addr.ip()),
+                SocketAddr::V6(addr) => addr.ip().to_string(),
+            };
+            if announce(msg, &tty, &caller_host).is_err() {
+                response.answer = FAILED;
+                return response;
+            }
+            response.id_num = table.insert(msg);
+        }
+        LEAVE_INVITE => {
+            response.id_num = match table.find_request(msg, LEAVE_INVITE) {
+                Some(invite) => {
+                    invite.created = Instant::now();
+                    invite.msg.id_num
+                }
+                None => table.insert(msg),
+            };
+        }
+        LOOK_UP => match table.find_match(msg) {
+            Some(invite) => {
+                response.id_num = invite.msg.id_num;
+                response.addr = invite.msg.addr;
+            }
+            None => response.answer = NOT_HERE,
+        },
+        DELETE => {
+            if !table.delete(msg.id_num) {
+                response.answer = NOT_HERE;
+            }
+        }
+        _ => response.answer = UNKNOWN_REQUEST,
+    }
+
+    response
+}
+
+fn serve(socket: &UdpSocket, inetd: bool, debug: bool) -> io::Result<()> {
+    let mut table = Table::new();
+    let mut buf = [0; 1024];
+
+    if inetd {
+        socket.set_read_timeout(Some(IDLE_TIMEOUT))?;
+    }
+
+    loop {
+        let (len, sender) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e)
+                if inetd
+                    && matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+            {
+                return Ok(());
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        let msg = match CtlMsg::decode(&buf[..len]) {
+            Some(msg) => msg,
+            None => {
+                if debug {
+                    eprintln!("talkd: {} {}", gettext("short request from"), sender);
+                }
+                continue;
+            }
+        };
+
+        let response = process_request(&mut table, &msg, &sender);
+        if debug {
+            eprintln!("talkd: {:?} -> {:?}", msg, response);
+        }
+
+        if let Err(e) = socket.send_to(&response.encode(), sender) {
+            eprintln!("talkd: {}: {}", sender, e);
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // parse command line arguments
+    let args = Args::parse();
+
+    i18n::init();
+
+    let socket = if args.inetd {
+        // inetd passes the bound socket as standard input
+        unsafe { UdpSocket::from_raw_fd(libc::STDIN_FILENO) }
+    } else {
+        UdpSocket::bind((Ipv4Addr::UNSPECIFIED, args.port))?
+    };
+
+    serve(&socket, args.inetd, args.debug)?;
+
+    Ok(())
+}
@@ -9,12 +9,17 @@
 
 extern crate atty;
 extern crate libc;
+extern crate plib;
+
+use plib::i18n::{self, gettext};
 use std::ffi::CStr;
 
 fn main() {
+    i18n::init();
+
     let is_tty = atty::is(atty::Stream::Stdin);
     if !is_tty {
-        println!("not a tty");
+        println!("{}", gettext("not a tty"));
         std::process::exit(1);
     }
 
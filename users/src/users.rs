@@ -0,0 +1,53 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+extern crate clap;
+extern crate plib;
+
+use clap::Parser;
+use plib::i18n;
+use std::path::PathBuf;
+
+/// users - list the users currently logged in
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about)]
+struct Args {
+    /// Read the entries from FILE, such as /var/log/wtmp, instead of the utmpx database.
+    file: Option<PathBuf>,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // parse command line arguments
+    let args = Args::parse();
+
+    i18n::init();
+
+    let entries = match &args.file {
+        Some(file) => match plib::utmpx::load_file(file) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("users: {}: {}", file.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => plib::utmpx::load(),
+    };
+
+    // one name per session, so a user logged in twice is listed twice
+    let mut users: Vec<&str> = plib::utmpx::user_sessions(&entries)
+        .map(|entry| entry.user.as_str())
+        .collect();
+    users.sort_unstable();
+
+    if !users.is_empty() {
+        println!("{}", users.join(" "));
+    }
+
+    Ok(())
+}
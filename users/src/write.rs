@@ -0,0 +1,255 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+extern crate clap;
+extern crate libc;
+extern crate plib;
+
+use chrono::Local;
+use clap::Parser;
+use plib::i18n::{self, gettext};
+use std::ffi::CStr;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Write};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::os::unix::io::AsRawFd;
+use std::process::{self, Command};
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// write - write to another user
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about)]
+struct Args {
+    /// Login name of the person to whom the message shall be written.
+    user_name: String,
+
+    /// Terminal to which the message shall be written.
+    terminal: Option<String>,
+}
+
+const DEF_TIMESTR: &str = "%a %b %e %H:%M:%S %Y";
+const EOF_MARK: &[u8] = b"EOF\r\n";
+
+// the recipient's terminal, for the interrupt handler
+static TTY_FD: AtomicI32 = AtomicI32::new(-1);
+
+extern "C" fn interrupt_handler(_sig: libc::c_int) {
+    let fd = TTY_FD.load(Ordering::SeqCst);
+    unsafe {
+        if fd >= 0 {
+            libc::write(fd, EOF_MARK.as_ptr() as *const libc::c_void, EOF_MARK.len());
+        }
+        libc::_exit(0);
+    }
+}
+
+/// Whether the terminal's owner accepts messages, as set by mesg.
+fn messages_enabled(md: &fs::Metadata) -> bool {
+    md.mode() & 0o020 != 0
+}
+
+/// Choose the recipient's terminal, returning its name relative to /dev.
+fn find_terminal(user: &str, terminal: Option<&str>) -> Result<String, String> {
+    let mut lines = Vec::new();
+    for entry in plib::utmpx::entries() {
+        if entry.is_user_session() && entry.user == user {
+            lines.push(entry.line);
+        }
+    }
+
+    if lines.is_empty() {
+        return Err(gettext!("{} is not logged in", user));
+    }
+
+    if let Some(terminal) = terminal {
+        let terminal = terminal.strip_prefix("/dev/").unwrap_or(terminal);
+        if !lines.iter().any(|line| line == terminal) {
+            return Err(gettext!("{} is not logged in on {}", user, terminal));
+        }
+        return Ok(terminal.to_string());
+    }
+
+    // of the terminals accepting messages, use the one most recently typed on
+    let mut best: Option<(&String, i64)> = None;
+    for line in &lines {
+        if let Ok(md) = fs::metadata(format!("/dev/{}", line)) {
+            if messages_enabled(&md) && best.is_none_or(|(_, atime)| md.atime() > atime) {
+                best = Some((line, md.atime()));
+            }
+        }
+    }
+
+    match best {
+        Some((line, _)) => {
+            if lines.len() > 1 {
+                eprintln!(
+                    "write: {}",
+                    gettext!("{} is logged in more than once; writing to {}", user, line)
+                );
+            }
+            Ok(line.clone())
+        }
+        None => Err(gettext!("{} has messages disabled", user)),
+    }
+}
+
+/// The sender's login name and terminal.
+fn sender() -> (String, String) {
+    let login = unsafe {
+        let name = libc::getlogin();
+        if !name.is_null() {
+            Some(CStr::from_ptr(name).to_string_lossy().into_owned())
+        } else {
+            let pw = libc::getpwuid(libc::getuid());
+            if pw.is_null() {
+                None
+            } else {
+                Some(CStr::from_ptr((*pw).pw_name).to_string_lossy().into_owned())
+            }
+        }
+    };
+
+    let mut tty = None;
+    for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        let name = unsafe { libc::ttyname(fd) };
+        if !name.is_null() {
+            let name = unsafe { CStr::from_ptr(name) }.to_string_lossy();
+            tty = Some(name.strip_prefix("/dev/").unwrap_or(&name).to_string());
+            break;
+        }
+    }
+
+    (
+        login.unwrap_or_else(|| String::from("???")),
+        tty.unwrap_or_else(|| String::from("???")),
+    )
+}
+
+/// Make `line` safe to print on a terminal: control characters are
+/// shown as ^X, and the line ends with a carriage return and newline.
+fn sanitize(line: &[u8]) -> Vec<u8> {
+    let text = String::from_utf8_lossy(line);
+    let mut out = Vec::with_capacity(line.len() + 2);
+
+    for ch in text.chars() {
+        match ch {
+            '\t' | '\x07' => out.push(ch as u8),
+            c if (c as u32) < 0x20 => {
+                out.push(b'^');
+                out.push(c as u8 + b'@');
+            }
+            '\x7f' => out.extend_from_slice(b"^?"),
+            c if c.is_control() => out.extend_from_slice(
+                format!("M-^{}", ((c as u32 - 0x80) as u8 + b'@') as char).as_bytes(),
+            ),
+            c => {
+                let mut buf = [0; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+
+    out.extend_from_slice(b"\r\n");
+    out
+}
+
+fn write_message(tty: &mut File, user: &str) -> io::Result<()> {
+    let (login, sender_tty) = sender();
+    let date = Local::now().format(DEF_TIMESTR);
+    let banner = format!(
+        "\r\n\x07\x07\x07{} {} ({}) [{}]...\r\n",
+        gettext("Message from"),
+        login,
+        sender_tty,
+        date
+    );
+    tty.write_all(banner.as_bytes())?;
+
+    let stdin = io::stdin();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if stdin.lock().read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        let text = line.strip_suffix(b"\n").unwrap_or(&line);
+
+        // "!command" runs the command instead of sending the line
+        if let Some(command) = text.strip_prefix(b"!") {
+            let command = String::from_utf8_lossy(command).into_owned();
+            if let Err(e) = Command::new("sh").arg("-c").arg(&command).status() {
+                eprintln!("write: sh: {}", e);
+            }
+            println!("!");
+            continue;
+        }
+
+        tty.write_all(&sanitize(text)).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                gettext!("cannot write to {}'s terminal: {}", user, e),
+            )
+        })?;
+    }
+
+    tty.write_all(EOF_MARK)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // parse command line arguments
+    let args = Args::parse();
+
+    i18n::init();
+
+    let line = match find_terminal(&args.user_name, args.terminal.as_deref()) {
+        Ok(line) => line,
+        Err(e) => {
+            eprintln!("write: {}", e);
+            process::exit(1);
+        }
+    };
+    let path = format!("/dev/{}", line);
+
+    // the superuser may write to any terminal
+    let md = fs::metadata(&path)?;
+    if !messages_enabled(&md) && unsafe { libc::geteuid() } != 0 {
+        eprintln!(
+            "write: {}",
+            gettext!("{} has messages disabled on {}", args.user_name, line)
+        );
+        process::exit(1);
+    }
+
+    let mut tty = match fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_NOCTTY)
+        .open(&path)
+    {
+        Ok(tty) => tty,
+        Err(e) => {
+            eprintln!("write: {}: {}", path, e);
+            process::exit(1);
+        }
+    };
+
+    TTY_FD.store(tty.as_raw_fd(), Ordering::SeqCst);
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            interrupt_handler as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+
+    if let Err(e) = write_message(&mut tty, &args.user_name) {
+        eprintln!("write: {}", e);
+        process::exit(1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,189 @@
+//
+// Copyright (c) 2024 Jeff Garzik
+//
+// This file is part of the posixutils-rs project covered under
+// the MIT License.  For the full license text, please see the LICENSE
+// file in the root directory of this project.
+// SPDX-License-Identifier: MIT
+//
+
+use plib::{run_test, TestPlan};
+use std::ffi::CStr;
+
+fn users_test(cmd: &str, args: &[&str], expected_output: &str) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
+    run_test(TestPlan {
+        cmd: String::from(cmd),
+        args: str_args,
+        stdin_data: String::new(),
+        expected_out: String::from(expected_output),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+        ..Default::default()
+    });
+}
+
+// The fixture is in the glibc utmpx record layout.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[test]
+fn test_users_file() {
+    users_test("users", &["../sys/tests/who/utmp"], "alice bob\n");
+}
+
+#[test]
+fn test_id_user_and_group() {
+    let uid = unsafe { libc::getuid() };
+    let gid = unsafe { libc::getgid() };
+    users_test("id", &["-u"], &format!("{}\n", uid));
+    users_test("id", &["-g"], &format!("{}\n", gid));
+}
+
+#[test]
+fn test_groups_of_process() {
+    // the effective and real groups, then the supplementary ones
+    let mut gids = unsafe { vec![libc::getegid(), libc::getgid()] };
+    let count = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+    let mut supplementary = vec![0; count as usize];
+    let count = unsafe { libc::getgroups(count, supplementary.as_mut_ptr()) };
+    supplementary.truncate(count as usize);
+    gids.extend(supplementary);
+
+    let mut names: Vec<String> = Vec::new();
+    let mut seen = Vec::new();
+    for gid in gids {
+        if seen.contains(&gid) {
+            continue;
+        }
+        seen.push(gid);
+        let group = unsafe { libc::getgrgid(gid) };
+        names.push(if group.is_null() {
+            gid.to_string()
+        } else {
+            unsafe { CStr::from_ptr((*group).gr_name) }
+                .to_string_lossy()
+                .into_owned()
+        });
+    }
+
+    users_test("groups", &[], &format!("{}\n", names.join(" ")));
+}
+
+mod talkd_tests {
+    use std::net::{Ipv4Addr, UdpSocket};
+    use std::process::{Child, Command};
+    use std::time::Duration;
+
+    const LEAVE_INVITE: u8 = 0;
+    const LOOK_UP: u8 = 1;
+    const DELETE: u8 = 2;
+
+    const SUCCESS: u8 = 0;
+    const NOT_HERE: u8 = 1;
+
+    // A talkd on a local port, stopped when dropped.
+    struct Talkd {
+        child: Child,
+        port: u16,
+    }
+
+    impl Talkd {
+        fn start() -> Talkd {
+            // an unused port, freed again for talkd to bind
+            let port = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port();
+            let child = Command::new(env!("CARGO_BIN_EXE_talkd"))
+                .args(["-p", &port.to_string()])
+                .spawn()
+                .unwrap();
+            Talkd { child, port }
+        }
+    }
+
+    impl Drop for Talkd {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+
+    // A CTL_MSG from `l_name` to `r_name`, whose talk connection is
+    // accepted on `addr_port`.
+    fn ctl_msg(typ: u8, id_num: u32, l_name: &str, r_name: &str, addr_port: u16) -> [u8; 84] {
+        let mut buf = [0; 84];
+        buf[0] = 1;
+        buf[1] = typ;
+        buf[4..8].copy_from_slice(&id_num.to_be_bytes());
+        for start in [8, 24] {
+            let addr = &mut buf[start..start + 16];
+            addr[0..2].copy_from_slice(&(libc::AF_INET as u16).to_be_bytes());
+            addr[2..4].copy_from_slice(&addr_port.to_be_bytes());
+            addr[4..8].copy_from_slice(&Ipv4Addr::LOCALHOST.octets());
+        }
+        buf[40..44].copy_from_slice(&1234i32.to_be_bytes());
+        buf[44..44 + l_name.len()].copy_from_slice(l_name.as_bytes());
+        buf[56..56 + r_name.len()].copy_from_slice(r_name.as_bytes());
+        buf
+    }
+
+    // Sends `msg` and returns the answer, id and address port of the
+    // response, retrying while talkd starts up.
+    fn request(talkd: &Talkd, msg: &[u8]) -> (u8, u32, u16) {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .unwrap();
+
+        let mut buf = [0; 24];
+        for _ in 0..50 {
+            socket
+                .send_to(msg, (Ipv4Addr::LOCALHOST, talkd.port))
+                .unwrap();
+            if let Ok(len) = socket.recv(&mut buf) {
+                assert_eq!(len, buf.len());
+                assert_eq!(buf[1], msg[1]);
+                return (
+                    buf[2],
+                    u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]),
+                    u16::from_be_bytes([buf[10], buf[11]]),
+                );
+            }
+        }
+        panic!("no response from talkd");
+    }
+
+    #[test]
+    fn test_talkd_invite_look_up_delete() {
+        let talkd = Talkd::start();
+
+        // nothing to find before an invitation is left
+        let (answer, _, _) = request(&talkd, &ctl_msg(LOOK_UP, 0, "bob", "alice", 0));
+        assert_eq!(answer, NOT_HERE);
+
+        let (answer, id, _) = request(&talkd, &ctl_msg(LEAVE_INVITE, 0, "alice", "bob", 4000));
+        assert_eq!(answer, SUCCESS);
+        assert_ne!(id, 0);
+
+        // leaving the same invitation again keeps its id
+        let (answer, again, _) = request(&talkd, &ctl_msg(LEAVE_INVITE, 0, "alice", "bob", 4000));
+        assert_eq!(answer, SUCCESS);
+        assert_eq!(again, id);
+
+        // the callee finds where the caller waits
+        let (answer, found, port) = request(&talkd, &ctl_msg(LOOK_UP, 0, "bob", "alice", 0));
+        assert_eq!(answer, SUCCESS);
+        assert_eq!(found, id);
+        assert_eq!(port, 4000);
+
+        let (answer, _, _) = request(&talkd, &ctl_msg(DELETE, id, "alice", "bob", 0));
+        assert_eq!(answer, SUCCESS);
+        let (answer, _, _) = request(&talkd, &ctl_msg(DELETE, id, "alice", "bob", 0));
+        assert_eq!(answer, NOT_HERE);
+
+        let (answer, _, _) = request(&talkd, &ctl_msg(LOOK_UP, 0, "bob", "alice", 0));
+        assert_eq!(answer, NOT_HERE);
+    }
+}
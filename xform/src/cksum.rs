@@ -20,53 +20,206 @@ extern crate plib;
 
 mod crc32;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use gettextrs::{bind_textdomain_codeset, setlocale, textdomain, LocaleCategory};
+use md5::Md5;
 use plib::PROJECT_NAME;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::BTreeMap;
 use std::io::{self, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+// files are read in large chunks; the default plib::BUFSZ is tuned
+// for line-oriented work and costs too many syscalls here
+const READ_BUFSZ: usize = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Algorithm {
+    /// POSIX CRC-32 checksum and byte count (default)
+    Crc,
+    /// MD5 digest (extension)
+    Md5,
+    /// SHA-256 digest (extension)
+    Sha256,
+    /// SHA-512 digest (extension)
+    Sha512,
+}
 
 /// cksum - write file checksums and sizes
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about)]
 struct Args {
+    /// Checksum algorithm to use.
+    #[arg(short, long, value_enum, default_value_t = Algorithm::Crc)]
+    algorithm: Algorithm,
+
     /// Files to read as input.  Use "-" or no-args for stdin.
     files: Vec<PathBuf>,
 }
 
-fn cksum_file(filename: &PathBuf) -> io::Result<()> {
+enum Hasher {
+    Crc { crc: u32, n_bytes: u64 },
+    Md5(Md5),
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl Hasher {
+    fn new(algorithm: Algorithm) -> Hasher {
+        match algorithm {
+            Algorithm::Crc => Hasher::Crc { crc: 0, n_bytes: 0 },
+            Algorithm::Md5 => Hasher::Md5(Md5::new()),
+            Algorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            Algorithm::Sha512 => Hasher::Sha512(Sha512::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Crc { crc, n_bytes } => {
+                *n_bytes += data.len() as u64;
+                *crc = crc32::update(*crc, data);
+            }
+            Hasher::Md5(h) => h.update(data),
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Sha512(h) => h.update(data),
+        }
+    }
+
+    /// Format the output line for `filename`, which is empty for stdin.
+    fn finish(self, filename: &Path) -> String {
+        let (tag, digest) = match self {
+            Hasher::Crc { crc, n_bytes } => {
+                let filename_prefix = if is_stdin(filename) { "" } else { " " };
+                return format!(
+                    "{} {}{}{}",
+                    crc32::finalize(crc, n_bytes as usize),
+                    n_bytes,
+                    filename_prefix,
+                    filename.display()
+                );
+            }
+            Hasher::Md5(h) => ("MD5", h.finalize().to_vec()),
+            Hasher::Sha256(h) => ("SHA256", h.finalize().to_vec()),
+            Hasher::Sha512(h) => ("SHA512", h.finalize().to_vec()),
+        };
+
+        // the digests use the tagged format of BSD and GNU cksum
+        let name = if is_stdin(filename) {
+            String::from("-")
+        } else {
+            filename.display().to_string()
+        };
+        let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("{} ({}) = {}", tag, name, hex)
+    }
+}
+
+fn cksum_file(filename: &PathBuf, algorithm: Algorithm) -> io::Result<String> {
     let mut file = plib::io::input_stream(filename, false)?;
 
-    let mut buffer = [0; plib::BUFSZ];
-    let mut n_bytes: u64 = 0;
-    let mut crc: u32 = 0;
+    let mut buffer = vec![0; READ_BUFSZ];
+    let mut hasher = Hasher::new(algorithm);
 
     loop {
-        let n_read = file.read(&mut buffer[..])?;
-        if n_read == 0 {
-            break;
-        }
+        let n_read = match file.read(&mut buffer[..]) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        hasher.update(&buffer[0..n_read]);
+    }
 
-        n_bytes = n_bytes + n_read as u64;
-        crc = crc32::update(crc, &buffer[0..n_read]);
+    Ok(hasher.finish(filename))
+}
+
+// Stdin is named by an empty path.
+fn is_stdin(filename: &Path) -> bool {
+    filename.as_os_str() == ""
+}
+
+/// Print the results that are next in operand order, and forget them.
+/// Returns false if any of them was a read error.
+fn print_ready(
+    files: &[PathBuf],
+    pending: &mut BTreeMap<usize, io::Result<String>>,
+    next_output: &mut usize,
+) -> bool {
+    let mut success = true;
+    while let Some(res) = pending.remove(next_output) {
+        match res {
+            Ok(line) => println!("{}", line),
+            Err(e) => {
+                success = false;
+                let filename = &files[*next_output];
+                if is_stdin(filename) {
+                    eprintln!("-: {}", e);
+                } else {
+                    eprintln!("{}: {}", filename.display(), e);
+                }
+            }
+        }
+        *next_output += 1;
     }
+    success
+}
 
-    let filename_prefix = {
-        if filename.as_os_str() == "" {
-            ""
-        } else {
-            " "
+/// Checksum every file, spreading the work over a pool of threads.
+/// Stdin is read by the calling thread, once per operand in operand
+/// order, so that later "-" operands find it at end-of-file.
+/// Results are printed in operand order as soon as they are ready.
+/// Returns true if all files were read successfully.
+fn cksum_files(files: &[PathBuf], algorithm: Algorithm) -> bool {
+    let n_files = files.iter().filter(|f| !is_stdin(f)).count();
+    let n_workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(n_files);
+    let next_file = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+
+    let mut success = true;
+
+    thread::scope(|scope| {
+        for _ in 0..n_workers {
+            let tx = tx.clone();
+            let next_file = &next_file;
+            scope.spawn(move || loop {
+                let idx = next_file.fetch_add(1, Ordering::Relaxed);
+                if idx >= files.len() {
+                    break;
+                }
+                if is_stdin(&files[idx]) {
+                    continue;
+                }
+                if tx.send((idx, cksum_file(&files[idx], algorithm))).is_err() {
+                    break;
+                }
+            });
         }
-    };
-    println!(
-        "{} {}{}{}",
-        crc32::finalize(crc, n_bytes as usize),
-        n_bytes,
-        filename_prefix,
-        filename.display()
-    );
-
-    Ok(())
+        drop(tx);
+
+        let mut pending = BTreeMap::new();
+        let mut next_output = 0;
+        for (idx, filename) in files.iter().enumerate() {
+            if is_stdin(filename) {
+                pending.insert(idx, cksum_file(filename, algorithm));
+                success &= print_ready(files, &mut pending, &mut next_output);
+            }
+        }
+
+        for (idx, res) in rx {
+            pending.insert(idx, res);
+            success &= print_ready(files, &mut pending, &mut next_output);
+        }
+    });
+
+    success
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -82,14 +235,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         args.files.push(PathBuf::new());
     }
 
-    let mut exit_code = 0;
-
-    for filename in &args.files {
-        if let Err(e) = cksum_file(filename) {
-            exit_code = 1;
-            eprintln!("{}: {}", filename.display(), e);
+    // "-" is stdin as well
+    for filename in &mut args.files {
+        if filename.as_os_str() == "-" {
+            *filename = PathBuf::new();
         }
     }
 
+    let exit_code = if cksum_files(&args.files, args.algorithm) {
+        0
+    } else {
+        1
+    };
+
     std::process::exit(exit_code)
 }
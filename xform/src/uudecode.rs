@@ -21,7 +21,7 @@ use std::path::PathBuf;
 
 macro_rules! reduce {
     ($e : expr) => {
-        $e - 0x20
+        ($e - 0x20)
     };
 }
 
@@ -30,6 +30,7 @@ macro_rules! reduce {
 #[command(author, version, about, long_about)]
 struct Args {
     /// A pathname of a file that shall be used instead of any pathname contained in the input data.
+    /// /dev/stdout writes the decoded data to standard output.
     #[arg(short, long)]
     outfile: Option<PathBuf>,
 
@@ -53,39 +54,52 @@ struct Header {
     out: PathBuf,
 }
 
+fn invalid_data(msg: &str) -> Error {
+    Error::new(io::ErrorKind::InvalidData, msg)
+}
+
 impl Header {
-    fn parse(line: &str) -> Self {
-        // split with spaces
-        let split: Vec<&str> = line.split(' ').collect();
-        let dec_type = if split[0] == "begin" {
-            DecodingType::Historical
-        } else if split[0] == "begin-base64" {
-            DecodingType::Base64
+    /// Parse a "begin" line: the encoding, the octal mode and the
+    /// pathname, which may contain blanks. None if `line` is not one.
+    fn parse(line: &[u8]) -> Option<io::Result<Self>> {
+        let line = String::from_utf8_lossy(line);
+        let (dec_type, rest) = if let Some(rest) = line.strip_prefix("begin-base64 ") {
+            (DecodingType::Base64, rest)
+        } else if let Some(rest) = line.strip_prefix("begin ") {
+            (DecodingType::Historical, rest)
         } else {
-            panic!("Invalid encoding type");
+            return None;
         };
 
-        let lower_perm_bits = u32::from_str_radix(split[1], 8).expect("Invalid permission value");
-        let out = PathBuf::from(split[2]);
+        let Some((mode, out)) = rest.split_once(' ') else {
+            return Some(Err(invalid_data("invalid header line")));
+        };
+        let Ok(lower_perm_bits) = u32::from_str_radix(mode, 8) else {
+            return Some(Err(invalid_data("invalid mode in header line")));
+        };
+        if out.is_empty() {
+            return Some(Err(invalid_data("invalid header line")));
+        }
 
-        Self {
+        Some(Ok(Self {
             dec_type,
-            lower_perm_bits,
-            out,
-        }
+            lower_perm_bits: lower_perm_bits & 0o777,
+            out: PathBuf::from(out),
+        }))
     }
 }
 
-fn decode_historical_line(line: &str) -> Vec<u8> {
+fn decode_historical_line(line: &[u8]) -> Vec<u8> {
     let mut out = Vec::new();
 
-    for chunk in line.as_bytes().chunks(4) {
-        let chunk = chunk.to_vec();
+    for chunk in line.chunks(4) {
+        // a short line is padded out, as if with spaces
+        let c = |i: usize| reduce!(*chunk.get(i).unwrap_or(&0x20)) & 0x3F;
 
         let out_chunk = [
-            reduce!(chunk[0]) << 2 | (reduce!(chunk[1])) >> 4,
-            reduce!(chunk[1]) << 4 | reduce!(chunk[2]) >> 2,
-            reduce!(chunk[2]) << 6 | reduce!(chunk[3]),
+            c(0) << 2 | c(1) >> 4,
+            c(1) << 4 | c(2) >> 2,
+            c(2) << 6 | c(3),
         ];
 
         out.extend_from_slice(&out_chunk);
@@ -94,63 +108,81 @@ fn decode_historical_line(line: &str) -> Vec<u8> {
     out
 }
 
-fn decode_base64_line(line: &str) -> io::Result<Vec<u8>> {
+fn decode_base64_line(line: &[u8]) -> io::Result<Vec<u8>> {
     BASE64_STANDARD
-        .decode(&line)
-        .map_err(|_| Error::from(io::ErrorKind::InvalidInput))
+        .decode(line)
+        .map_err(|_| invalid_data("invalid base64 data"))
 }
 
 fn decode_file(args: &Args) -> io::Result<()> {
     let mut buf: Vec<u8> = Vec::new();
     let mut out: Vec<u8> = Vec::new();
 
-    let file_p = args
-        .file
-        .as_ref()
-        .unwrap_or(&PathBuf::from("/dev/stdin"))
-        .clone();
-
-    if file_p == PathBuf::from("/dev/stdin") {
-        io::stdin().lock().read_to_end(&mut buf)?;
-    } else {
-        let mut file = File::open(&file_p)?;
-        file.read_to_end(&mut buf)?;
+    match &args.file {
+        None => {
+            io::stdin().lock().read_to_end(&mut buf)?;
+        }
+        Some(file_p) => {
+            File::open(file_p)?.read_to_end(&mut buf)?;
+        }
     }
 
-    let buf = String::from_utf8(buf).unwrap();
-    let mut lines = buf.lines();
-    let header = Header::parse(lines.next().expect("No header line"));
+    let mut lines = buf.split(|&b| b == b'\n').map(|line| match line {
+        [line @ .., b'\r'] => line,
+        line => line,
+    });
+
+    // anything before the header is skipped
+    let header = loop {
+        match lines.next() {
+            Some(line) => {
+                if let Some(header) = Header::parse(line) {
+                    break header?;
+                }
+            }
+            None => return Err(invalid_data("no 'begin' line")),
+        }
+    };
 
+    let mut ended = false;
     match header.dec_type {
         DecodingType::Historical => {
             while let Some(line) = lines.next() {
-                let line = line.replace("`", " ");
-                if line.len() == 1 && line == " " {
-                    let end_line = lines.next().expect("No end line");
-                    if end_line == "end" || end_line == "end\r" {
-                        break;
-                    } else {
-                        panic!("Invalid ending")
-                    }
+                if line.is_empty() {
+                    continue;
+                }
+
+                // the length character; a line of length zero ends the data
+                let len = (reduce!(line[0]) & 0x3F) as usize;
+                if len == 0 {
+                    ended = lines.next() == Some(b"end");
+                    break;
                 }
 
-                let len = (line[..1].as_bytes()[0] - 32) as usize;
                 let mut dec_out = decode_historical_line(&line[1..]);
+                if dec_out.len() < len {
+                    return Err(invalid_data("short line in encoded data"));
+                }
                 dec_out.truncate(len);
                 out.extend_from_slice(&dec_out);
             }
         }
 
         DecodingType::Base64 => {
-            while let Some(line) = lines.next() {
-                if line == "====" || line == "====\n" {
+            for line in lines {
+                if line == b"====" {
+                    ended = true;
                     break;
                 }
-                out.extend_from_slice(&decode_base64_line(line)?);
+                out.extend_from_slice(&decode_base64_line(line.trim_ascii())?);
             }
         }
     }
 
+    if !ended {
+        return Err(invalid_data("no 'end' line"));
+    }
+
     let out_path = args.outfile.as_ref().unwrap_or(&header.out);
 
     if out_path == &PathBuf::from("/dev/stdout") {
@@ -192,7 +224,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     if let Err(e) = decode_file(&args) {
         exit_code = 1;
-        eprintln!("{:?}: {}", pathname_display(&args.file), e);
+        eprintln!("uudecode: {}: {}", pathname_display(&args.file), e);
     }
 
     std::process::exit(exit_code)
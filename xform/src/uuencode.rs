@@ -30,11 +30,23 @@ struct Args {
     #[arg(short = 'm', long)]
     base64: bool,
 
-    /// File to read as input.
-    file: Option<PathBuf>,
+    /// The file to encode, then the pathname to decode it to. With only
+    /// the pathname, standard input is encoded.
+    #[arg(required = true, num_args = 1..=2, value_names = ["FILE", "DECODE_PATHNAME"])]
+    operands: Vec<String>,
+}
+
+impl Args {
+    fn file(&self) -> Option<PathBuf> {
+        match self.operands.len() {
+            2 => Some(PathBuf::from(&self.operands[0])),
+            _ => None,
+        }
+    }
 
-    /// Decode pathname
-    decode_path: Option<String>,
+    fn decode_path(&self) -> &str {
+        self.operands.last().unwrap()
+    }
 }
 
 enum EncodingType {
@@ -102,10 +114,7 @@ fn encode_historical_line(line: &[u8]) -> Vec<u8> {
 
 /// encodes the file(it can be standard input too) and outputs on standard output
 fn encode_file(args: &Args) -> io::Result<()> {
-    let decode_path = match &args.decode_path {
-        None => String::from("/dev/stdout"),
-        Some(path) => String::from(path),
-    };
+    let decode_path = args.decode_path();
 
     let encoding_type = if args.base64 {
         EncodingType::Base64
@@ -117,38 +126,35 @@ fn encode_file(args: &Args) -> io::Result<()> {
     let mut out: Vec<u8> = Vec::new();
 
     let header_init = encoding_type.get_header();
-    let file_p = args
-        .file
-        .as_ref()
-        .unwrap_or(&PathBuf::from("/dev/stdin"))
-        .clone();
-
-    if file_p == PathBuf::from("/dev/stdin") {
-        let mode = {
-            #[cfg(target_os = "macos")]
-            {
-                RW & (!unsafe { libc::umask(RW as u16) } as u32)
-            }
 
-            #[cfg(not(target_os = "macos"))]
-            {
-                RW & (!unsafe { libc::umask(RW) })
-            }
-        };
-
-        let perm = get_permission_values(Permissions::from_mode(mode));
-        let header = format!("{header_init} {perm} {decode_path}\n");
-
-        io::stdout().write_all(header.as_bytes())?;
-        io::stdin().lock().read_to_end(&mut buf)?;
-    } else {
-        let mut file = File::open(&file_p)?;
-        let perm = get_permission_values(file.metadata()?.permissions());
+    let perm = match args.file() {
+        None => {
+            // standard input has no mode of its own, so use the one a new
+            // file would get
+            let mode = {
+                #[cfg(target_os = "macos")]
+                {
+                    RW & (!unsafe { libc::umask(RW as u16) } as u32)
+                }
+
+                #[cfg(not(target_os = "macos"))]
+                {
+                    RW & (!unsafe { libc::umask(RW) })
+                }
+            };
+
+            io::stdin().lock().read_to_end(&mut buf)?;
+            get_permission_values(Permissions::from_mode(mode))
+        }
+        Some(file_p) => {
+            let mut file = File::open(&file_p)?;
+            file.read_to_end(&mut buf)?;
+            get_permission_values(file.metadata()?.permissions())
+        }
+    };
 
-        let header = format!("{header_init} {perm} {decode_path}\n");
-        out.extend_from_slice(header.as_bytes());
-        file.read_to_end(&mut buf)?;
-    }
+    let header = format!("{header_init} {perm} {decode_path}\n");
+    out.extend_from_slice(header.as_bytes());
 
     match encoding_type {
         EncodingType::Historical => {
@@ -174,7 +180,7 @@ fn encode_file(args: &Args) -> io::Result<()> {
 
 fn pathname_display(path: &Option<PathBuf>) -> String {
     match path {
-        None => String::from("/dev/stdin"),
+        None => String::from("stdin"),
         Some(p) => p.display().to_string(),
     }
 }
@@ -190,7 +196,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     if let Err(e) = encode_file(&args) {
         exit_code = 1;
-        eprintln!("{:?}: {}", pathname_display(&args.file), e);
+        eprintln!("uuencode: {}: {}", pathname_display(&args.file()), e);
     }
 
     std::process::exit(exit_code)
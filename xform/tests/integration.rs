@@ -27,14 +27,17 @@ fn get_permission_values(perm: Permissions) -> String {
     format!("{user_perm}{group_perm}{others_perm}")
 }
 
-fn cksum_test(test_data: &str, expected_output: &str) {
+fn cksum_test(args: &[&str], test_data: &str, expected_output: &str) {
+    let str_args: Vec<String> = args.iter().map(|s| String::from(*s)).collect();
+
     run_test(TestPlan {
         cmd: String::from("cksum"),
-        args: Vec::new(),
+        args: str_args,
         stdin_data: String::from(test_data),
         expected_out: String::from(expected_output),
         expected_err: String::from(""),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -48,6 +51,7 @@ fn compress_test(args: &[&str], expected_output: &str, expected_error: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -61,6 +65,7 @@ fn uncompress_test(args: &[&str], expected_output: &str, expected_error: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
@@ -74,6 +79,7 @@ fn uuencode_test(args: &[&str], expected_output: &str, expected_error: &str) {
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code: 0,
+        ..Default::default()
     })
 }
 
@@ -87,12 +93,61 @@ fn uudecode_test(args: &[&str], stdin_data: &str, expected_output: &str, expecte
         expected_out: String::from(expected_output),
         expected_err: String::from(expected_error),
         expected_exit_code: 0,
+        ..Default::default()
     });
 }
 
 #[test]
 fn test_cksum() {
-    cksum_test("foo\n", "3915528286 4\n");
+    cksum_test(&[], "foo\n", "3915528286 4\n");
+}
+
+#[test]
+fn test_cksum_algorithms() {
+    cksum_test(
+        &["-a", "sha256"],
+        "foo\n",
+        "SHA256 (-) = b5bb9d8014a0f9b1d61e21e796d78dccdf1352f23cd32812f4850b878ae4944c\n",
+    );
+    cksum_test(
+        &["-a", "md5", "tests/compress/lorem_ipsum.txt"],
+        "",
+        "MD5 (tests/compress/lorem_ipsum.txt) = 3cace8d120518433a11ee53d06639637\n",
+    );
+}
+
+#[test]
+fn test_cksum_multiple_files_in_order() {
+    // the files are hashed in parallel but must be reported in operand order
+    cksum_test(
+        &[
+            "tests/compress/lorem_ipsum.txt",
+            "-",
+            "tests/compress/lorem_ipsum.txt",
+        ],
+        "foo\n",
+        "2119704109 3901 tests/compress/lorem_ipsum.txt\n\
+         3915528286 4\n\
+         2119704109 3901 tests/compress/lorem_ipsum.txt\n",
+    );
+}
+
+#[test]
+fn test_cksum_stdin_twice() {
+    // stdin is read once; the second "-" finds it at end-of-file
+    cksum_test(&["-", "-"], "foo\n", "3915528286 4\n4294967295 0\n");
+}
+
+#[test]
+fn test_cksum_stdin_read_error() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_cksum"))
+        .arg("-")
+        .stdin(File::open("tests").unwrap())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("-: "));
 }
 
 #[test]
@@ -341,3 +396,69 @@ fn test_uuencode_uudecode_with_base64_encoding_jpg_file() {
 
     uudecode_test(&[], &encoded_file_content, &source_file_content, "");
 }
+
+#[test]
+fn test_uuencode_stdin() {
+    // with one operand, it names the decoded file and stdin is encoded
+    run_test(TestPlan {
+        cmd: String::from("uuencode"),
+        args: vec![String::from("hello.txt")],
+        stdin_data: String::from("hello\n"),
+        expected_out: String::from("begin 640 hello.txt\n&:&5L;&\\*\n`\nend\n"),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+        umask: Some(0o027),
+        ..Default::default()
+    });
+
+    run_test(TestPlan {
+        cmd: String::from("uuencode"),
+        args: vec![String::from("-m"), String::from("hello.txt")],
+        stdin_data: String::from("hello\n"),
+        expected_out: String::from("begin-base64 600 hello.txt\naGVsbG8K\n====\n"),
+        expected_err: String::new(),
+        expected_exit_code: 0,
+        umask: Some(0o077),
+        ..Default::default()
+    });
+}
+
+#[test]
+fn test_uudecode_to_stdout() {
+    // text before the header is skipped, and -o overrides its pathname
+    uudecode_test(
+        &["-o", "/dev/stdout"],
+        "From: someone\n\nbegin 644 a name with spaces\n&:&5L;&\\*\n`\nend\n",
+        "hello\n",
+        "",
+    );
+    uudecode_test(
+        &["-o", "/dev/stdout"],
+        "begin-base64 600 out.txt\r\naGVsbG8K\r\n====\r\n",
+        "hello\n",
+        "",
+    );
+}
+
+#[test]
+fn test_uudecode_invalid() {
+    run_test(TestPlan {
+        cmd: String::from("uudecode"),
+        args: Vec::new(),
+        stdin_data: String::from("begin 644 /dev/stdout\n&:&5L;&\\*\n"),
+        expected_out: String::new(),
+        expected_err: String::from("uudecode: stdin: no 'end' line\n"),
+        expected_exit_code: 1,
+        ..Default::default()
+    });
+
+    run_test(TestPlan {
+        cmd: String::from("uudecode"),
+        args: Vec::new(),
+        stdin_data: String::from("hello\n"),
+        expected_out: String::new(),
+        expected_err: String::from("uudecode: stdin: no 'begin' line\n"),
+        expected_exit_code: 1,
+        ..Default::default()
+    });
+}